@@ -1,2 +1,2 @@
-// Linux activity capture — not in v1.
-// Stub: X11 XEvents, Wayland protocols as backup.
+// Linux activity capture — not in v1.
+// Stub: X11 XEvents, Wayland protocols as backup.
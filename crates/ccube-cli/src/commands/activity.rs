@@ -1,101 +1,412 @@
-use anyhow::Result;
-use ccube_core::db;
-
-use crate::daemon_client;
-use crate::paths::DataRoot;
-
-/// Show recent activity events as a readable table.
-pub async fn handle_recent(root: &DataRoot, hours: f64) -> Result<()> {
-    // Try daemon HTTP first
-    let rows =
-        match daemon_client::get_json::<Vec<db::EventRow>>(&format!("/activity?hours={hours}"))
-            .await
-        {
-            Ok(rows) => rows,
-            Err(_) => {
-                // Fallback: direct DB access
-                db::init_databases(&root.data_dir)?;
-                let conn = db::open_events_db(&root.data_dir)?;
-                let now = chrono::Utc::now().timestamp_millis();
-                let since_ts = now - (hours * 3_600_000.0) as i64;
-                db::query_recent_events(&conn, since_ts)?
-            }
-        };
-
-    if rows.is_empty() {
-        println!("No events in the last {hours} hour(s).");
-        return Ok(());
-    }
-
-    render_events_table(&rows);
-
-    println!(
-        "\nShowing {} events from the last {:.1} hour(s).",
-        rows.len(),
-        hours
-    );
-
-    Ok(())
-}
-
-fn render_events_table(rows: &[db::EventRow]) {
-    println!(
-        "{:<12} {:<14} {:<22} {:<40} Mode",
-        "Time", "Kind", "App", "Title"
-    );
-    println!("{}", "-".repeat(100));
-
-    for row in rows {
-        let time_str = format_time_ms(row.ts);
-        let kind = &row.kind;
-        let app = row.app.as_deref().unwrap_or("");
-        let title = row.title.as_deref().unwrap_or("");
-        let mode = row.mode.as_deref().unwrap_or("");
-
-        let title_display = truncate(title, 38);
-
-        println!(
-            "{:<12} {:<14} {:<22} {:<40} {}",
-            time_str,
-            kind,
-            truncate(app, 20),
-            title_display,
-            mode
-        );
-    }
-}
-
-/// Delete events older than 14 days.
-pub fn handle_prune(root: &DataRoot) -> Result<()> {
-    db::init_databases(&root.data_dir)?;
-    let conn = db::open_events_db(&root.data_dir)?;
-
-    let now = chrono::Utc::now().timestamp_millis();
-    let cutoff = now - (14 * 24 * 3_600_000);
-
-    let deleted = db::prune_events(&conn, cutoff)?;
-
-    if deleted == 0 {
-        println!("No events older than 14 days to prune.");
-    } else {
-        println!("Pruned {deleted} events older than 14 days.");
-    }
-
-    Ok(())
-}
-
-fn format_time_ms(ts: i64) -> String {
-    use chrono::{DateTime, Utc};
-    let dt = DateTime::from_timestamp_millis(ts).unwrap_or_else(Utc::now);
-    let local = dt.with_timezone(&chrono::Local);
-    local.format("%H:%M:%S").to_string()
-}
-
-fn truncate(s: &str, max: usize) -> String {
-    if s.chars().count() > max {
-        let truncated: String = s.chars().take(max - 3).collect();
-        format!("{truncated}...")
-    } else {
-        s.to_string()
-    }
-}
+use anyhow::{Context, Result};
+use ccube_core::{backfill, db, export, ingest, stats};
+use serde::Deserialize;
+use std::io::Write;
+use std::path::Path;
+
+use crate::daemon_client;
+use crate::paths::DataRoot;
+
+/// Show recent activity events as a readable table.
+pub async fn handle_recent(root: &DataRoot, hours: f64) -> Result<()> {
+    // Try daemon HTTP first
+    let rows =
+        match daemon_client::get_json::<Vec<db::EventRow>>(&format!("/activity?hours={hours}"))
+            .await
+        {
+            Ok(rows) => rows,
+            Err(_) => {
+                // Fallback: direct DB access
+                db::init_databases(&root.data_dir)?;
+                let conn = db::open_events_db(&root.data_dir)?;
+                let now = chrono::Utc::now().timestamp_millis();
+                let since_ts = now - (hours * 3_600_000.0) as i64;
+                db::query_recent_events(&conn, since_ts)?
+            }
+        };
+
+    if rows.is_empty() {
+        println!("No events in the last {hours} hour(s).");
+        return Ok(());
+    }
+
+    render_events_table(&rows);
+
+    println!(
+        "\nShowing {} events from the last {:.1} hour(s).",
+        rows.len(),
+        hours
+    );
+
+    Ok(())
+}
+
+fn render_events_table(rows: &[db::EventRow]) {
+    println!(
+        "{:<12} {:<14} {:<22} {:<40} Mode",
+        "Time", "Kind", "App", "Title"
+    );
+    println!("{}", "-".repeat(100));
+
+    for row in rows {
+        let time_str = format_time_ms(row.ts);
+        let kind = &row.kind;
+        let app = row.app.as_deref().unwrap_or("");
+        let title = row.title.as_deref().unwrap_or("");
+        let mode = row.mode.as_deref().unwrap_or("");
+
+        let title_display = truncate(title, 38);
+
+        println!(
+            "{:<12} {:<14} {:<22} {:<40} {}",
+            time_str,
+            kind,
+            truncate(app, 20),
+            title_display,
+            mode
+        );
+    }
+}
+
+/// ccube data decision-events <ID> — the events a detector decision was made on.
+pub async fn handle_decision_events(root: &DataRoot, id: i64, json: bool) -> Result<()> {
+    let found: db::DecisionEvents = if daemon_client::is_daemon_running().await {
+        daemon_client::get_json(&format!("/decisions/{id}/events")).await?
+    } else {
+        let conn = db::open_events_db(&root.data_dir)?;
+        db::get_decision_events(&conn, id)?
+            .ok_or_else(|| anyhow::anyhow!("decision #{id} not found (may have been pruned)"))?
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&found)?);
+        return Ok(());
+    }
+
+    println!(
+        "Decision #{} window: {} - {}\n",
+        found.decision_id,
+        format_time_ms(found.window_start),
+        format_time_ms(found.window_end)
+    );
+    if found.events.is_empty() {
+        println!("No events in this window (they may have been pruned).");
+        return Ok(());
+    }
+    render_events_table(&found.events);
+
+    Ok(())
+}
+
+/// Import activity spans from a JSON file produced by another tracker.
+pub async fn handle_ingest(root: &DataRoot, file: &Path, source: &str) -> Result<()> {
+    let raw = std::fs::read_to_string(file)
+        .with_context(|| format!("failed to read {}", file.display()))?;
+    let events: Vec<ingest::ExternalActivity> = serde_json::from_str(&raw)
+        .context("expected a JSON array of {ts, duration_ms, app, title} objects")?;
+    ingest::validate_source(source).map_err(|e| anyhow::anyhow!(e))?;
+
+    let summary: ingest::IngestSummary = if daemon_client::is_daemon_running().await {
+        let body = serde_json::json!({ "source": source, "events": events });
+        daemon_client::post_json("/activity/ingest", &body).await?
+    } else {
+        // Fallback: direct DB access
+        let conn = db::open_events_db(&root.data_dir)?;
+        let now = chrono::Utc::now().timestamp_millis();
+        let min_ms = ingest::min_duration_ms_from_env().map_err(|e| anyhow::anyhow!(e))?;
+        ingest::ingest(&conn, &events, source, min_ms, now)?
+    };
+
+    println!(
+        "Ingested {} event(s) from '{source}' ({} duplicate, {} too short, {} rejected).",
+        summary.inserted,
+        summary.duplicates,
+        summary.too_short,
+        summary.rejected.len()
+    );
+    for r in &summary.rejected {
+        println!("  #{}: {}", r.index, r.reason);
+    }
+
+    Ok(())
+}
+
+/// Import window events from an ActivityWatch JSON export.
+pub async fn handle_import_aw(root: &DataRoot, file: &Path, source: &str) -> Result<()> {
+    // Keep each request to the daemon well under its body size limit
+    const BATCH: usize = 1000;
+
+    let raw = std::fs::read_to_string(file)
+        .with_context(|| format!("failed to read {}", file.display()))?;
+    let import = ingest::parse_aw_export(&raw).map_err(|e| anyhow::anyhow!(e))?;
+    ingest::validate_source(source).map_err(|e| anyhow::anyhow!(e))?;
+
+    // Anything past the retention window would be pruned within the hour
+    let retention_days = db::retention_days_from_env().map_err(|e| anyhow::anyhow!(e))?;
+    let now = chrono::Utc::now().timestamp_millis();
+    let cutoff = now - (retention_days * 24 * 3_600_000);
+    let (events, too_old): (Vec<_>, Vec<_>) =
+        import.activities.into_iter().partition(|a| a.ts >= cutoff);
+
+    let mut summary = ingest::IngestSummary::default();
+    if daemon_client::is_daemon_running().await {
+        for (n, chunk) in events.chunks(BATCH).enumerate() {
+            let body = serde_json::json!({ "source": source, "events": chunk });
+            let part: ingest::IngestSummary =
+                daemon_client::post_json("/activity/ingest", &body).await?;
+            summary.inserted += part.inserted;
+            summary.duplicates += part.duplicates;
+            summary.too_short += part.too_short;
+            summary
+                .rejected
+                .extend(part.rejected.into_iter().map(|mut r| {
+                    r.index += n * BATCH;
+                    r
+                }));
+        }
+    } else {
+        // Fallback: direct DB access
+        let conn = db::open_events_db(&root.data_dir)?;
+        let min_ms = ingest::min_duration_ms_from_env().map_err(|e| anyhow::anyhow!(e))?;
+        summary = ingest::ingest(&conn, &events, source, min_ms, now)?;
+    }
+
+    println!(
+        "Imported {} event(s) from {} ({} duplicate, {} too short, {} rejected).",
+        summary.inserted,
+        file.display(),
+        summary.duplicates,
+        summary.too_short,
+        summary.rejected.len()
+    );
+    if import.skipped > 0 {
+        println!("Skipped {} zero-length or malformed event(s).", import.skipped);
+    }
+    if !too_old.is_empty() {
+        println!(
+            "Skipped {} event(s) older than the {}-day retention.",
+            too_old.len(),
+            retention_days
+        );
+    }
+    for r in &summary.rejected {
+        println!("  #{}: {}", r.index, r.reason);
+    }
+
+    Ok(())
+}
+
+/// Export activity between two local dates (inclusive) as CSV, one row per
+/// span or, with `hourly`, per hour. Writes to `out`, or stdout without one.
+pub fn handle_export(
+    root: &DataRoot,
+    from: Option<&str>,
+    to: Option<&str>,
+    hourly: bool,
+    out: Option<&Path>,
+) -> Result<()> {
+    let parse = |s: &str| {
+        chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d")
+            .map_err(|_| anyhow::anyhow!("invalid date '{s}', expected YYYY-MM-DD"))
+    };
+    let today = chrono::Local::now().date_naive();
+    let to_date = to.map(parse).transpose()?.unwrap_or(today);
+    let from_date = from
+        .map(parse)
+        .transpose()?
+        .unwrap_or(to_date - chrono::Duration::days(6));
+    if from_date > to_date {
+        anyhow::bail!("--from must not be after --to");
+    }
+
+    let now = chrono::Utc::now().timestamp_millis();
+    let from_ts = stats::day_bounds(from_date, &chrono::Local).0;
+    let to_ts = stats::day_bounds(to_date, &chrono::Local).1.min(now);
+
+    let conn = db::open_events_db(&root.data_dir)?;
+    let events = stats::load_events(&conn, from_ts, to_ts)?;
+    let spans = stats::active_spans(&events, from_ts, to_ts, now);
+    let scoring = stats::FocusScoring::from_env().map_err(|e| anyhow::anyhow!(e))?;
+    let write = |w: &mut dyn Write| {
+        let mut w = std::io::BufWriter::new(w);
+        let rows = if hourly {
+            export::write_hourly_csv(&mut w, &spans, from_ts, to_ts, &chrono::Local, &scoring)?
+        } else {
+            export::write_spans_csv(&mut w, &spans, &scoring)?
+        };
+        w.flush()?;
+        std::io::Result::Ok(rows)
+    };
+
+    match out {
+        Some(path) => {
+            let mut file = std::fs::File::create(path)
+                .with_context(|| format!("failed to create {}", path.display()))?;
+            let rows =
+                write(&mut file).with_context(|| format!("failed to write {}", path.display()))?;
+            println!("Exported {rows} row(s) to {}.", path.display());
+        }
+        None => {
+            write(&mut std::io::stdout().lock())?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Re-infer focus modes for all stored app_focus events.
+pub async fn handle_backfill_modes(root: &DataRoot) -> Result<()> {
+    let summary: backfill::BackfillSummary = if daemon_client::is_daemon_running().await {
+        // Large histories can take a while; allow more than the default 5s
+        daemon_client::post_empty_timeout(
+            "/activity/backfill-modes",
+            std::time::Duration::from_secs(120),
+        )
+        .await?
+    } else {
+        // Fallback: direct DB access
+        let conn = db::open_events_db(&root.data_dir)?;
+        backfill::backfill_modes(&conn, backfill::BATCH_SIZE)?
+    };
+
+    println!(
+        "Updated the focus mode of {} of {} event(s) across {} app(s).",
+        summary.updated, summary.scanned, summary.apps_updated
+    );
+    for c in &summary.mode_changes {
+        println!(
+            "  {}: {} -> {} ({} event(s))",
+            c.app,
+            c.from.as_deref().unwrap_or("(none)"),
+            c.to,
+            c.events
+        );
+    }
+
+    Ok(())
+}
+
+/// ccube data recompute-daily [--from D] [--to D] — rewrite stored daily
+/// focus rollups from events.
+pub async fn handle_recompute_daily(
+    root: &DataRoot,
+    from: Option<&str>,
+    to: Option<&str>,
+) -> Result<()> {
+    let parse = |s: &str| {
+        chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d")
+            .map_err(|_| anyhow::anyhow!("invalid date '{s}', expected YYYY-MM-DD"))
+    };
+    let retention_days = db::retention_days_from_env().map_err(|e| anyhow::anyhow!(e))?;
+    let to_date = to
+        .map(parse)
+        .transpose()?
+        .unwrap_or_else(|| chrono::Local::now().date_naive());
+    let from_date = from
+        .map(parse)
+        .transpose()?
+        .unwrap_or(to_date - chrono::Duration::days(retention_days - 1));
+    if from_date > to_date {
+        anyhow::bail!("--from must not be after --to");
+    }
+
+    let summary: stats::RecomputeSummary = if daemon_client::is_daemon_running().await {
+        daemon_client::post_empty_timeout(
+            &format!("/activity/recompute-daily?from={from_date}&to={to_date}"),
+            std::time::Duration::from_secs(120),
+        )
+        .await?
+    } else {
+        if (to_date - from_date).num_days() >= stats::MAX_RECOMPUTE_DAYS {
+            anyhow::bail!("at most {} days per recompute", stats::MAX_RECOMPUTE_DAYS);
+        }
+        let scoring = stats::FocusScoring::from_env().map_err(|e| anyhow::anyhow!(e))?;
+        let conn = db::open_events_db(&root.data_dir)?;
+        let now = chrono::Utc::now().timestamp_millis();
+        stats::recompute_daily_stats(&conn, from_date, to_date, &chrono::Local, &scoring, now)?
+    };
+
+    println!(
+        "Recomputed daily stats for {} day(s) from {from_date} to {to_date}.",
+        summary.days_updated
+    );
+    if summary.days_skipped > 0 {
+        println!(
+            "Skipped {} day(s) with missing or partly pruned events; their totals were kept.",
+            summary.days_skipped
+        );
+    }
+
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct DeclareActiveResponse {
+    active_until: Option<i64>,
+}
+
+/// ccube active [--minutes N] — count time as active while idle detection
+/// would say otherwise.
+pub async fn handle_declare_active(root: &DataRoot, minutes: i64) -> Result<()> {
+    let minutes = minutes.clamp(0, stats::MAX_MANUAL_ACTIVE_MS / 60_000);
+    let active_until = if daemon_client::is_daemon_running().await {
+        let resp: DeclareActiveResponse =
+            daemon_client::post_empty(&format!("/activity/declare-active?minutes={minutes}"))
+                .await?;
+        resp.active_until
+    } else {
+        // Fallback: direct DB access
+        let conn = db::open_events_db(&root.data_dir)?;
+        let now = chrono::Utc::now().timestamp_millis();
+        db::end_manual_active(&conn, now)?;
+        if minutes > 0 {
+            db::insert_manual_active(&conn, now, minutes * 60_000)?;
+        }
+        (minutes > 0).then_some(now + minutes * 60_000)
+    };
+
+    match active_until.and_then(chrono::DateTime::from_timestamp_millis) {
+        Some(until) => println!(
+            "Counting you as active until {}, even if idle.",
+            until.with_timezone(&chrono::Local).format("%H:%M")
+        ),
+        None => println!("Idle detection is back in effect."),
+    }
+    Ok(())
+}
+
+/// Delete events older than the retention window (CCUBE_RETENTION_DAYS).
+pub fn handle_prune(root: &DataRoot) -> Result<()> {
+    let retention_days = db::retention_days_from_env().map_err(|e| anyhow::anyhow!(e))?;
+    db::init_databases(&root.data_dir)?;
+    let conn = db::open_events_db(&root.data_dir)?;
+
+    let now = chrono::Utc::now().timestamp_millis();
+    let cutoff = now - (retention_days * 24 * 3_600_000);
+
+    let deleted = db::prune_events(&conn, cutoff)?;
+
+    if deleted == 0 {
+        println!("No events older than {retention_days} days to prune.");
+    } else {
+        println!("Pruned {deleted} events older than {retention_days} days.");
+    }
+
+    Ok(())
+}
+
+fn format_time_ms(ts: i64) -> String {
+    use chrono::{DateTime, Utc};
+    let dt = DateTime::from_timestamp_millis(ts).unwrap_or_else(Utc::now);
+    let local = dt.with_timezone(&chrono::Local);
+    local.format("%H:%M:%S").to_string()
+}
+
+fn truncate(s: &str, max: usize) -> String {
+    if s.chars().count() > max {
+        let truncated: String = s.chars().take(max - 3).collect();
+        format!("{truncated}...")
+    } else {
+        s.to_string()
+    }
+}
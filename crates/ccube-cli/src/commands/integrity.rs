@@ -0,0 +1,66 @@
+use anyhow::Result;
+use ccube_core::integrity;
+
+use crate::daemon_client;
+use crate::paths::DataRoot;
+
+/// ccube data check-db — run SQLite's integrity check on each database.
+pub fn handle_check(root: &DataRoot, json: bool) -> Result<()> {
+    let results = integrity::check_all(&root.data_dir);
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&results)?);
+        return Ok(());
+    }
+
+    if results.is_empty() {
+        println!("No databases found in {}.", root.data_dir.display());
+        return Ok(());
+    }
+
+    for r in &results {
+        if r.ok {
+            println!("{:<22} ok", r.file);
+        } else {
+            println!("{:<22} DAMAGED: {}", r.file, r.detail);
+        }
+    }
+    if results.iter().any(|r| !r.ok) {
+        println!("\nRun `ccube data repair-db <file>` with the daemon stopped to repair.");
+    }
+
+    Ok(())
+}
+
+/// ccube data repair-db <file> — move a damaged database aside and recreate it.
+/// A file that passes the integrity check is left alone unless `force`.
+pub async fn handle_repair(
+    root: &DataRoot,
+    file: &str,
+    no_recover: bool,
+    force: bool,
+) -> Result<()> {
+    if daemon_client::is_daemon_running().await {
+        anyhow::bail!("the daemon is running; stop it first with `ccube daemon stop`");
+    }
+
+    let now = chrono::Utc::now().timestamp_millis();
+    println!("Moving {file} aside and recreating its schema...");
+    let outcome = integrity::repair(&root.data_dir, file, !no_recover, force, now)?;
+    println!("Damaged copy kept at {}", outcome.backup.display());
+
+    for t in &outcome.recovered {
+        match &t.error {
+            None => println!("  recovered {} row(s) from {}", t.rows, t.table),
+            Some(e) => println!("  could not recover {}: {e}", t.table),
+        }
+    }
+
+    let check = integrity::check_file(&root.data_dir.join(file));
+    println!(
+        "{file} is now {}.",
+        if check.ok { "healthy" } else { "still damaged" }
+    );
+
+    Ok(())
+}
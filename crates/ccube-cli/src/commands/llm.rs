@@ -0,0 +1,119 @@
+use anyhow::Result;
+use ccube_core::agents::ask;
+use ccube_core::paths::DataRoot;
+use ccube_core::{db, llm, stats};
+use serde::Serialize;
+use std::time::Duration;
+
+use super::stats::parse_date;
+use crate::daemon_client;
+
+/// Mirrors the daemon's AskRequest.
+#[derive(Serialize)]
+struct AskRequest<'a> {
+    question: &'a str,
+    date: String,
+}
+
+/// ccube llm models [--raw] — models each LLM endpoint reports. `--raw`
+/// prints the full /models payloads (or the errors) for diagnostics.
+///
+/// Goes through the daemon when it's running so the result reflects the
+/// daemon's LLM configuration; otherwise queries the servers directly.
+pub async fn handle_models(raw: bool) -> Result<()> {
+    let probes: Vec<llm::ModelsProbe> = if daemon_client::is_daemon_running().await {
+        // The daemon's LLM client has a 10s timeout; leave headroom
+        daemon_client::get_json_timeout("/llm/models/raw", std::time::Duration::from_secs(15))
+            .await?
+    } else {
+        let client = llm::LlamaCppClient::from_env().map_err(|e| anyhow::anyhow!(e))?;
+        client.probe_models().await
+    };
+
+    for (i, probe) in probes.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        if raw {
+            print_raw_probe(probe)?;
+            continue;
+        }
+        let ids = probe.model_ids();
+        if ids.is_empty() {
+            println!(
+                "No models found at {} — is the LLM server running? (see --raw)",
+                probe.url
+            );
+            continue;
+        }
+        if probes.len() > 1 {
+            println!("{}:", probe.url);
+        }
+        for id in ids {
+            println!("{id}");
+        }
+    }
+
+    Ok(())
+}
+
+fn print_raw_probe(probe: &llm::ModelsProbe) -> Result<()> {
+    println!("GET {}", probe.url);
+    match probe.status {
+        Some(status) => println!("Status: {status}"),
+        None => println!("Status: no response"),
+    }
+    if let Some(ref e) = probe.error {
+        println!("Error: {e}");
+    }
+    if let Some(ref body) = probe.body {
+        println!("{}", serde_json::to_string_pretty(body)?);
+    }
+    Ok(())
+}
+
+/// ccube ask "<question>" [--date D] — answer a question about a day's
+/// activity with the LLM. Fails with a clear error when the LLM is down;
+/// there's no offline answer.
+pub async fn handle_ask(
+    root: &DataRoot,
+    question: &str,
+    date: Option<&str>,
+    json: bool,
+) -> Result<()> {
+    let date = parse_date(date)?;
+    let question = ask::validate_question(question)?;
+
+    let out: ask::AskOutput = if daemon_client::is_daemon_running().await {
+        let body = AskRequest {
+            question,
+            date: date.to_string(),
+        };
+        // The daemon's LLM client has a 120s timeout; leave headroom
+        daemon_client::post_json_timeout("/ask", &body, Duration::from_secs(130)).await?
+    } else {
+        let conn = db::open_events_db(&root.data_dir)?;
+        let now = chrono::Utc::now().timestamp_millis();
+        let spans = stats::day_spans(&conn, date, &chrono::Local, now)?;
+        let scoring = stats::FocusScoring::from_env().map_err(|e| anyhow::anyhow!(e))?;
+        let context = ask::build_context(date, &spans, &scoring);
+        let client = llm::LlamaCppClient::from_env_with_timeout(Duration::from_secs(120))
+            .map_err(|e| anyhow::anyhow!(e))?;
+        ask::run(question, &context, &client)
+            .await
+            .map_err(|e| match e {
+                ask::AskError::LlmUnavailable(msg) => {
+                    anyhow::anyhow!("LLM unavailable, is the server running? ({msg})")
+                }
+                e => e.into(),
+            })?
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&out)?);
+    } else {
+        println!("{}", out.answer);
+    }
+
+    Ok(())
+}
@@ -1,8 +1,11 @@
-pub mod activity;
-pub mod capture;
-pub mod correct;
-pub mod curate;
-pub mod daemon;
-pub mod detect;
-pub mod memory;
-pub mod reflect;
+pub mod activity;
+pub mod capture;
+pub mod correct;
+pub mod curate;
+pub mod daemon;
+pub mod detect;
+pub mod integrity;
+pub mod llm;
+pub mod memory;
+pub mod reflect;
+pub mod stats;
@@ -0,0 +1,1709 @@
+use anyhow::Result;
+use ccube_core::agents::ask;
+use ccube_core::paths::DataRoot;
+use ccube_core::{db, llm, nudge_policy, report_card, stats};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use crate::daemon_client;
+
+/// ccube stats week [--start YYYY-MM-DD] — focus score and active time per day.
+pub async fn handle_week(root: &DataRoot, start: Option<&str>, json: bool) -> Result<()> {
+    let date = parse_date(start)?;
+
+    let days: Vec<stats::DayFocus> = if daemon_client::is_daemon_running().await {
+        daemon_client::get_json(&format!("/stats/week?start={date}")).await?
+    } else {
+        // Fallback: compute directly from the local DB
+        let monday = stats::week_start_monday(date);
+        let sunday = monday + chrono::Duration::days(6);
+        let (from, _) = stats::day_bounds(monday, &chrono::Local);
+        let (_, to) = stats::day_bounds(sunday, &chrono::Local);
+        let conn = db::open_events_db(&root.data_dir)?;
+        let rollups = db::list_daily_stats(&conn, &monday.to_string(), &sunday.to_string())?;
+        let oldest = db::oldest_event_ts(&conn)?;
+        let events = stats::load_events(&conn, from, to)?;
+        let now = chrono::Utc::now().timestamp_millis();
+        let scoring = stats::FocusScoring::from_env().map_err(|e| anyhow::anyhow!(e))?;
+        stats::weekly_focus_by_day(
+            &events,
+            &rollups,
+            oldest,
+            monday,
+            &chrono::Local,
+            &scoring,
+            now,
+        )
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&days)?);
+        return Ok(());
+    }
+
+    if let Some(first) = days.first() {
+        println!("Week of {}", first.date);
+    }
+    println!("{:<12} {:>8} {:>10}", "Day", "Focus", "Active");
+    println!("{}", "-".repeat(32));
+    for d in &days {
+        let focus = d
+            .focus_score
+            .map(|s| format!("{s:.0}%"))
+            .unwrap_or_else(|| "-".to_string());
+        let active = d
+            .active_minutes
+            .map(fmt_minutes)
+            .unwrap_or_else(|| "-".to_string());
+        println!(
+            "{:<12} {:>8} {:>10}",
+            d.date.format("%a %m-%d").to_string(),
+            focus,
+            active
+        );
+    }
+
+    Ok(())
+}
+
+/// ccube stats distraction [--date YYYY-MM-DD] — top unfocused app of the day.
+pub async fn handle_distraction(root: &DataRoot, date: Option<&str>, json: bool) -> Result<()> {
+    let date = parse_date(date)?;
+
+    let top: Option<stats::AppMinutes> = if daemon_client::is_daemon_running().await {
+        daemon_client::get_json(&format!("/stats/distraction?date={date}")).await?
+    } else {
+        let conn = db::open_events_db(&root.data_dir)?;
+        let now = chrono::Utc::now().timestamp_millis();
+        let spans = stats::day_spans(&conn, date, &chrono::Local, now)?;
+        let scoring = stats::FocusScoring::from_env().map_err(|e| anyhow::anyhow!(e))?;
+        stats::top_distraction(&spans, &scoring)
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&top)?);
+        return Ok(());
+    }
+
+    match top {
+        Some(t) => println!(
+            "Biggest distraction on {date}: {} ({})",
+            t.app,
+            fmt_minutes(t.minutes)
+        ),
+        None => println!("No unfocused time on {date}."),
+    }
+
+    Ok(())
+}
+
+/// ccube stats distractions [--date] [--limit N] — the costliest stretches
+/// away from the main task.
+pub async fn handle_distractions(
+    root: &DataRoot,
+    date: Option<&str>,
+    limit: usize,
+    json: bool,
+) -> Result<()> {
+    let date = parse_date(date)?;
+    let limit = limit.clamp(1, 20);
+
+    let ranked: Vec<stats::RankedDistraction> = if daemon_client::is_daemon_running().await {
+        daemon_client::get_json(&format!("/stats/distractions?date={date}&limit={limit}")).await?
+    } else {
+        let conn = db::open_events_db(&root.data_dir)?;
+        let now = chrono::Utc::now().timestamp_millis();
+        let spans = stats::day_spans(&conn, date, &chrono::Local, now)?;
+        stats::ranked_distractions(&spans, limit)
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&ranked)?);
+        return Ok(());
+    }
+
+    if ranked.is_empty() {
+        println!("No time away from the main task on {date}.");
+        return Ok(());
+    }
+
+    let fmt = |ts: i64| {
+        chrono::DateTime::from_timestamp_millis(ts)
+            .map(|dt| dt.with_timezone(&chrono::Local).format("%H:%M").to_string())
+            .unwrap_or_default()
+    };
+    println!(
+        "{:<13} {:<30} {:>10} {:>8}",
+        "Time", "App", "Duration", "Impact"
+    );
+    println!("{}", "-".repeat(64));
+    for d in &ranked {
+        println!(
+            "{:<13} {:<30} {:>10} {:>8.1}",
+            format!("{}-{}", fmt(d.start), fmt(d.end)),
+            d.app,
+            fmt_minutes(d.minutes),
+            d.impact
+        );
+    }
+
+    Ok(())
+}
+
+/// ccube stats contribution [--date D] [--limit N] — apps by focused time and
+/// by unfocused time.
+pub async fn handle_contribution(
+    root: &DataRoot,
+    date: Option<&str>,
+    limit: usize,
+    json: bool,
+) -> Result<()> {
+    let date = parse_date(date)?;
+    let limit = limit.clamp(1, 20);
+
+    let c: stats::AppContributions = if daemon_client::is_daemon_running().await {
+        daemon_client::get_json(&format!("/stats/contribution?date={date}&limit={limit}")).await?
+    } else {
+        let conn = db::open_events_db(&root.data_dir)?;
+        let now = chrono::Utc::now().timestamp_millis();
+        let spans = stats::day_spans(&conn, date, &chrono::Local, now)?;
+        let scoring = stats::FocusScoring::from_env().map_err(|e| anyhow::anyhow!(e))?;
+        stats::app_contributions(&spans, &scoring, limit)
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&c)?);
+        return Ok(());
+    }
+
+    if c.productive.is_empty() && c.time_sinks.is_empty() {
+        println!("No activity on {date}.");
+        return Ok(());
+    }
+
+    for (heading, apps) in [
+        ("Most focused time", &c.productive),
+        ("Time sinks", &c.time_sinks),
+    ] {
+        println!("{heading}:");
+        if apps.is_empty() {
+            println!("  (none)");
+        }
+        for a in apps {
+            println!(
+                "  {:<30} {:>10} focused {:>10} unfocused",
+                a.app,
+                fmt_minutes(a.focused_minutes),
+                fmt_minutes(a.unfocused_minutes)
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// ccube stats on-topic [--date D] [--keywords a,b] — time on study material.
+pub async fn handle_on_topic(
+    root: &DataRoot,
+    date: Option<&str>,
+    keywords: Option<&str>,
+    json: bool,
+) -> Result<()> {
+    let date = parse_date(date)?;
+    let keywords = match keywords {
+        Some(v) => stats::parse_keywords(v),
+        None => stats::study_keywords_from_env(),
+    };
+
+    let topic: stats::TopicFocus = if daemon_client::is_daemon_running().await {
+        // Keywords are free text, so let Url do the query encoding
+        let mut url = reqwest::Url::parse("http://daemon/stats/on-topic")?;
+        url.query_pairs_mut().append_pair("date", &date.to_string());
+        if !keywords.is_empty() {
+            let joined = keywords.join(",");
+            url.query_pairs_mut().append_pair("keywords", &joined);
+        }
+        let path = format!("{}?{}", url.path(), url.query().unwrap_or_default());
+        daemon_client::get_json(&path).await?
+    } else {
+        if keywords.is_empty() {
+            anyhow::bail!("no keywords: pass --keywords or set CCUBE_STUDY_KEYWORDS");
+        }
+        let conn = db::open_events_db(&root.data_dir)?;
+        let now = chrono::Utc::now().timestamp_millis();
+        let spans = stats::day_spans(&conn, date, &chrono::Local, now)?;
+        stats::topic_focus(&spans, &keywords)
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&topic)?);
+        return Ok(());
+    }
+
+    let Some(share) = topic.on_topic_share else {
+        println!("No titled activity on {date}.");
+        return Ok(());
+    };
+    println!(
+        "{share:.0}% of titled time on {} ({} on topic, {} off topic, {} untitled)",
+        topic.keywords.join(", "),
+        fmt_minutes(topic.on_topic_minutes),
+        fmt_minutes(topic.off_topic_minutes),
+        fmt_minutes(topic.untitled_minutes)
+    );
+    for t in &topic.top_titles {
+        println!("  {:<50} {:>10}", t.title, fmt_minutes(t.minutes));
+    }
+
+    Ok(())
+}
+
+/// ccube stats apps --apps a,b [--date D] [--analyze] — time in the chosen
+/// apps only. `--analyze` adds the LLM's reading of it, or a note when the LLM
+/// is down.
+pub async fn handle_apps(
+    root: &DataRoot,
+    apps: &str,
+    date: Option<&str>,
+    analyze: bool,
+    json: bool,
+) -> Result<()> {
+    let date = parse_date(date)?;
+    let apps = stats::parse_keywords(apps);
+    if apps.is_empty() {
+        anyhow::bail!("--apps needs at least one app name");
+    }
+
+    let (summary, analysis) = if analyze {
+        let out: ask::AppsAnalysis = if daemon_client::is_daemon_running().await {
+            let body = AskAppsRequest {
+                apps: apps.join(","),
+                date: date.to_string(),
+            };
+            // The daemon's LLM client has a 120s timeout; leave headroom
+            daemon_client::post_json_timeout("/ask/apps", &body, Duration::from_secs(130)).await?
+        } else {
+            let summary = local_apps_summary(root, date, &apps)?;
+            let client = llm::LlamaCppClient::from_env_with_timeout(Duration::from_secs(120))
+                .map_err(|e| anyhow::anyhow!(e))?;
+            ask::analyze_apps(date, summary, &client).await?
+        };
+        if json {
+            println!("{}", serde_json::to_string_pretty(&out)?);
+            return Ok(());
+        }
+        (out.summary, Some(out.analysis))
+    } else {
+        let summary: stats::AppsSummary = if daemon_client::is_daemon_running().await {
+            let mut url = reqwest::Url::parse("http://daemon/stats/apps")?;
+            url.query_pairs_mut()
+                .append_pair("date", &date.to_string())
+                .append_pair("apps", &apps.join(","));
+            let path = format!("{}?{}", url.path(), url.query().unwrap_or_default());
+            daemon_client::get_json(&path).await?
+        } else {
+            local_apps_summary(root, date, &apps)?
+        };
+        if json {
+            println!("{}", serde_json::to_string_pretty(&summary)?);
+            return Ok(());
+        }
+        (summary, None)
+    };
+
+    let Some(share) = summary.share.filter(|_| summary.minutes > 0.0) else {
+        println!("No time in {} on {date}.", summary.apps.join(", "));
+        return Ok(());
+    };
+    println!(
+        "{} in {} ({share:.0}% of active time, {} focused, {} switches between them)",
+        fmt_minutes(summary.minutes),
+        summary.apps.join(", "),
+        fmt_minutes(summary.focused_minutes),
+        summary.switches
+    );
+    for a in &summary.sessions {
+        println!(
+            "  {:<30} {:>10} in {} sessions (median {})",
+            a.app,
+            fmt_minutes(a.total_minutes),
+            a.sessions,
+            fmt_minutes(a.median_minutes)
+        );
+    }
+    if !summary.top_titles.is_empty() {
+        println!("Top titles:");
+        for t in &summary.top_titles {
+            println!("  {:<50} {:>10}", t.title, fmt_minutes(t.minutes));
+        }
+    }
+    if let Some(analysis) = analysis {
+        println!();
+        match analysis {
+            Some(text) => println!("{text}"),
+            None => println!("(LLM unavailable, so no analysis; the numbers above are local.)"),
+        }
+    }
+
+    Ok(())
+}
+
+/// Mirrors the daemon's AskAppsRequest.
+#[derive(Serialize)]
+struct AskAppsRequest {
+    apps: String,
+    date: String,
+}
+
+fn local_apps_summary(
+    root: &DataRoot,
+    date: chrono::NaiveDate,
+    apps: &[String],
+) -> Result<stats::AppsSummary> {
+    let conn = db::open_events_db(&root.data_dir)?;
+    let now = chrono::Utc::now().timestamp_millis();
+    let spans = stats::day_spans(&conn, date, &chrono::Local, now)?;
+    let merge_gap_ms = stats::session_merge_gap_ms_from_env().map_err(|e| anyhow::anyhow!(e))?;
+    Ok(stats::apps_summary(&spans, apps, merge_gap_ms))
+}
+
+/// ccube stats breaks [--date D] — restful vs screen breaks and the focus
+/// that followed them.
+pub async fn handle_breaks(root: &DataRoot, date: Option<&str>, json: bool) -> Result<()> {
+    let date = parse_date(date)?;
+
+    let analysis: stats::BreakAnalysis = if daemon_client::is_daemon_running().await {
+        daemon_client::get_json(&format!("/stats/breaks?date={date}")).await?
+    } else {
+        let conn = db::open_events_db(&root.data_dir)?;
+        let now = chrono::Utc::now().timestamp_millis();
+        let spans = stats::day_spans(&conn, date, &chrono::Local, now)?;
+        let scoring = stats::FocusScoring::from_env().map_err(|e| anyhow::anyhow!(e))?;
+        stats::break_analysis(&spans, &scoring)
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&analysis)?);
+        return Ok(());
+    }
+
+    if analysis.breaks.is_empty() {
+        println!("No breaks between focus blocks on {date}.");
+        return Ok(());
+    }
+
+    let fmt = |ts: i64| {
+        chrono::DateTime::from_timestamp_millis(ts)
+            .map(|dt| dt.with_timezone(&chrono::Local).format("%H:%M").to_string())
+            .unwrap_or_default()
+    };
+    for b in &analysis.breaks {
+        let kind = match b.kind {
+            stats::BreakKind::Restful => "restful",
+            stats::BreakKind::Screen => "screen",
+        };
+        println!(
+            "  {}  {:<8} {:>10}, then {} focused",
+            fmt(b.start),
+            kind,
+            fmt_minutes(b.minutes),
+            fmt_minutes(b.focus_after_minutes)
+        );
+    }
+    for (label, summary) in [
+        ("Restful breaks", &analysis.restful),
+        ("Screen breaks", &analysis.screen),
+    ] {
+        let Some(after) = summary.avg_focus_after_minutes else {
+            continue;
+        };
+        println!(
+            "{label}: {} (avg focus after: {})",
+            summary.count,
+            fmt_minutes(after)
+        );
+    }
+
+    Ok(())
+}
+
+/// ccube stats hourly [--date YYYY-MM-DD] — top app per hour of the day.
+pub async fn handle_hourly(root: &DataRoot, date: Option<&str>, json: bool) -> Result<()> {
+    let date = parse_date(date)?;
+
+    let leaders: Vec<stats::HourLeader> = if daemon_client::is_daemon_running().await {
+        daemon_client::get_json(&format!("/stats/hourly-leaders?date={date}")).await?
+    } else {
+        let conn = db::open_events_db(&root.data_dir)?;
+        let now = chrono::Utc::now().timestamp_millis();
+        let spans = stats::day_spans(&conn, date, &chrono::Local, now)?;
+        stats::hourly_app_leaders(&spans, date, &chrono::Local)
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&leaders)?);
+        return Ok(());
+    }
+
+    if leaders.iter().all(|h| h.leader.is_none()) {
+        println!("No activity on {date}.");
+        return Ok(());
+    }
+
+    println!("{:<6} {:<30} {:>8}", "Hour", "Top app", "Minutes");
+    println!("{}", "-".repeat(46));
+    for h in &leaders {
+        if let Some(ref l) = h.leader {
+            println!("{:02}:00  {:<30} {:>8.0}", h.hour, l.app, l.minutes);
+        }
+    }
+
+    Ok(())
+}
+
+/// ccube stats unclassified [--days N] — apps still on the Unspecified default.
+pub async fn handle_unclassified(root: &DataRoot, days: i64, json: bool) -> Result<()> {
+    let days = days.clamp(1, 90);
+
+    let apps: Vec<stats::AppMinutes> = if daemon_client::is_daemon_running().await {
+        daemon_client::get_json(&format!("/stats/unclassified?days={days}")).await?
+    } else {
+        let now = chrono::Utc::now().timestamp_millis();
+        let from = now - days * 86_400_000;
+        let conn = db::open_events_db(&root.data_dir)?;
+        let events = stats::load_events(&conn, from, now)?;
+        stats::unclassified_apps(&stats::active_spans(&events, from, now, now))
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&apps)?);
+        return Ok(());
+    }
+
+    if apps.is_empty() {
+        println!("Every app used in the last {days} day(s) matched a focus mode.");
+        return Ok(());
+    }
+
+    println!("Apps with no focus mode match (last {days} day(s)):");
+    println!("{:<40} {:>8}", "App", "Minutes");
+    println!("{}", "-".repeat(49));
+    for a in &apps {
+        println!("{:<40} {:>8.0}", a.app, a.minutes);
+    }
+
+    Ok(())
+}
+
+/// ccube stats productive-hours [--days N] — best hours of the day by focus.
+pub async fn handle_productive_hours(root: &DataRoot, days: i64, json: bool) -> Result<()> {
+    let days = days.clamp(1, 90);
+    let hours = fetch_productive_hours(root, days).await?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&hours)?);
+        return Ok(());
+    }
+
+    let Some(hours) = hours else {
+        println!(
+            "Not enough history yet: an hour needs {} active days in the last {days} to be ranked.",
+            stats::PRODUCTIVE_HOUR_MIN_SAMPLES
+        );
+        return Ok(());
+    };
+
+    println!("Most productive hours (last {days} day(s)):");
+    println!("{:<8} {:>8} {:>8}", "Hour", "Focus", "Samples");
+    println!("{}", "-".repeat(26));
+    for h in &hours {
+        println!(
+            "{:02}:00    {:>7.0}% {:>8}",
+            h.hour, h.avg_focus_score, h.samples
+        );
+    }
+
+    Ok(())
+}
+
+/// ccube stats schedule [--days N] — when to do deep work and when to batch
+/// admin, from productive-hours history. Drawn up by the LLM, or by the
+/// rule-based split when it's unavailable.
+pub async fn handle_schedule(root: &DataRoot, days: i64, json: bool) -> Result<()> {
+    let days = days.clamp(1, 90);
+
+    let plan: Option<stats::ScheduleRecommendations> = if daemon_client::is_daemon_running().await {
+        // The daemon's LLM client has a 120s timeout; leave headroom
+        daemon_client::get_json_timeout(
+            &format!("/stats/schedule?days={days}"),
+            Duration::from_secs(130),
+        )
+        .await?
+    } else {
+        match fetch_productive_hours(root, days).await? {
+            Some(hours) => {
+                let client = llm::LlamaCppClient::from_env_with_timeout(Duration::from_secs(120))
+                    .map_err(|e| anyhow::anyhow!(e))?;
+                Some(ask::suggest_schedule(&hours, &client).await)
+            }
+            None => None,
+        }
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&plan)?);
+        return Ok(());
+    }
+
+    let Some(plan) = plan else {
+        println!("Not enough history yet for a schedule (see `ccube stats productive-hours`).");
+        return Ok(());
+    };
+
+    for (heading, blocks) in [
+        ("Deep work", &plan.deep_work),
+        ("Admin (email, chat)", &plan.admin),
+    ] {
+        println!("{heading}:");
+        if blocks.is_empty() {
+            println!("  (none)");
+        }
+        for b in blocks {
+            println!(
+                "  {:02}:00-{:02}:00  {:>4.0}% avg focus",
+                b.start_hour, b.end_hour, b.avg_focus_score
+            );
+        }
+    }
+    match (plan.source, plan.rationale) {
+        (stats::ScheduleSource::Llm, Some(rationale)) => println!("\n{rationale}"),
+        (stats::ScheduleSource::Rules, _) => {
+            println!("\n(Rule-based: best third of hours for deep work, worst third for admin.)")
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+/// Productive hours from the daemon, or straight from the database when it
+/// isn't running.
+async fn fetch_productive_hours(
+    root: &DataRoot,
+    days: i64,
+) -> Result<Option<Vec<stats::ProductiveHour>>> {
+    if daemon_client::is_daemon_running().await {
+        return daemon_client::get_json(&format!("/stats/productive-hours?days={days}")).await;
+    }
+    let now = chrono::Utc::now().timestamp_millis();
+    let from = now - days * 86_400_000;
+    let conn = db::open_events_db(&root.data_dir)?;
+    let events = stats::load_events(&conn, from, now)?;
+    let spans = stats::active_spans(&events, from, now, now);
+    let scoring = stats::FocusScoring::from_env().map_err(|e| anyhow::anyhow!(e))?;
+    Ok(stats::productive_hours(&spans, &chrono::Local, &scoring))
+}
+
+/// ccube stats review [--date YYYY-MM-DD] — how a day (default: yesterday) went.
+pub async fn handle_review(root: &DataRoot, date: Option<&str>, json: bool) -> Result<()> {
+    let date = match date {
+        Some(s) => parse_date(Some(s))?,
+        None => chrono::Local::now().date_naive() - chrono::Duration::days(1),
+    };
+
+    let review: stats::DailyReview = if daemon_client::is_daemon_running().await {
+        daemon_client::get_json(&format!("/stats/review?date={date}")).await?
+    } else {
+        let conn = db::open_events_db(&root.data_dir)?;
+        let now = chrono::Utc::now().timestamp_millis();
+        let spans = stats::day_spans(&conn, date, &chrono::Local, now)?;
+        let scoring = stats::FocusScoring::from_env().map_err(|e| anyhow::anyhow!(e))?;
+        let target = stats::focus_target_from_env().map_err(|e| anyhow::anyhow!(e))?;
+        stats::daily_review(date, &spans, &scoring, Some(target))
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&review)?);
+        return Ok(());
+    }
+
+    println!("Review of {}", review.date.format("%A %Y-%m-%d"));
+    if review.active_minutes <= 0.0 {
+        println!("No activity recorded.");
+        return Ok(());
+    }
+
+    let score = review
+        .focus_score
+        .map(|s| format!("{s:.0}%"))
+        .unwrap_or_else(|| "-".to_string());
+    println!(
+        "Active {}, focused {} (focus score {score})",
+        fmt_minutes(review.active_minutes),
+        fmt_minutes(review.focused_minutes)
+    );
+    if let Some(ref t) = review.target_progress {
+        println!(
+            "Focus target: {:.0}% of {}{}",
+            t.percent,
+            fmt_minutes(f64::from(t.target_minutes)),
+            if t.met { " (met)" } else { "" }
+        );
+    }
+
+    let fmt = |ts: i64| {
+        chrono::DateTime::from_timestamp_millis(ts)
+            .map(|dt| dt.with_timezone(&chrono::Local).format("%H:%M").to_string())
+            .unwrap_or_default()
+    };
+    if let Some(ref b) = review.longest_focus_block {
+        println!(
+            "Longest focus block: {} ({}-{})",
+            fmt_minutes(b.minutes),
+            fmt(b.start),
+            fmt(b.end)
+        );
+    }
+    if let Some(ref d) = review.top_distraction {
+        println!(
+            "Biggest distraction: {} ({})",
+            d.app,
+            fmt_minutes(d.minutes)
+        );
+    }
+
+    println!("\nTop apps:");
+    for a in &review.top_apps {
+        println!("  {:<36} {:>10}", a.app, fmt_minutes(a.minutes));
+    }
+    println!("\nModes:");
+    for m in &review.modes {
+        println!("  {:<36} {:>10}", m.mode, fmt_minutes(m.minutes));
+    }
+    if !review.sessions.is_empty() {
+        println!("\nFocus sessions:");
+        for b in &review.sessions {
+            println!(
+                "  {}-{} {:>10}",
+                fmt(b.start),
+                fmt(b.end),
+                fmt_minutes(b.minutes)
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// ccube stats report-card [--date YYYY-MM-DD] — graded summary of a day.
+pub async fn handle_report_card(root: &DataRoot, date: Option<&str>, json: bool) -> Result<()> {
+    let date = parse_date(date)?;
+
+    let card: report_card::ReportCard = if daemon_client::is_daemon_running().await {
+        daemon_client::get_json(&format!("/stats/report-card?date={date}")).await?
+    } else {
+        let thresholds =
+            report_card::ReportCardThresholds::from_env().map_err(|e| anyhow::anyhow!(e))?;
+        let conn = db::open_events_db(&root.data_dir)?;
+        let now = chrono::Utc::now().timestamp_millis();
+        let spans = stats::day_spans(&conn, date, &chrono::Local, now)?;
+        let scoring = stats::FocusScoring::from_env().map_err(|e| anyhow::anyhow!(e))?;
+        report_card::report_card(date, &spans, &scoring, &thresholds)
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&card)?);
+        return Ok(());
+    }
+
+    let Some(grade) = card.grade else {
+        println!(
+            "Not enough activity on {date} to grade ({}).",
+            fmt_minutes(card.active_minutes)
+        );
+        return Ok(());
+    };
+
+    println!(
+        "Report card for {}: {grade}",
+        card.date.format("%A %Y-%m-%d")
+    );
+    for f in &card.factors {
+        println!("  {}  {:<20} {}", f.grade, f.factor, f.note);
+    }
+
+    Ok(())
+}
+
+/// ccube stats deviation [--days N] — last hour vs the historical baseline.
+pub async fn handle_deviation(root: &DataRoot, days: i64, json: bool) -> Result<()> {
+    let days = days.clamp(1, 90);
+
+    let dev: stats::BaselineDeviation = if daemon_client::is_daemon_running().await {
+        daemon_client::get_json(&format!("/stats/baseline-deviation?days={days}")).await?
+    } else {
+        let now = chrono::Utc::now().timestamp_millis();
+        let from = now - days * 86_400_000 - 3_600_000;
+        let conn = db::open_events_db(&root.data_dir)?;
+        let events = stats::load_events(&conn, from, now)?;
+        let spans = stats::active_spans(&events, from, now, now);
+        let counting = stats::SwitchCounting::from_env().map_err(|e| anyhow::anyhow!(e))?;
+        let scoring = stats::FocusScoring::from_env().map_err(|e| anyhow::anyhow!(e))?;
+        stats::baseline_deviation(&spans, days, counting, &scoring, now)
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&dev)?);
+        return Ok(());
+    }
+
+    println!(
+        "Last hour vs baseline ({} active hour(s) over {} day(s))",
+        dev.samples, dev.baseline_days
+    );
+    println!(
+        "{:<20} {:>9} {:>9} {:>9} {:>8}",
+        "Metric", "Current", "Baseline", "Diff", "Range"
+    );
+    println!("{}", "-".repeat(59));
+    let fmt = |v: Option<f64>| v.map(|x| format!("{x:.1}")).unwrap_or_else(|| "-".into());
+    for m in &dev.metrics {
+        let diff = m
+            .percent_diff
+            .map(|p| format!("{p:+.0}%"))
+            .unwrap_or_else(|| "-".into());
+        let range = match m.in_range {
+            Some(true) => "normal",
+            Some(false) => "OUT",
+            None => "-",
+        };
+        println!(
+            "{:<20} {:>9} {:>9} {:>9} {:>8}",
+            m.metric,
+            fmt(m.current),
+            fmt(m.baseline_mean),
+            diff,
+            range
+        );
+    }
+
+    Ok(())
+}
+
+/// ccube stats pressure [--sensitivity X] — real-time switch pressure meter.
+pub async fn handle_pressure(root: &DataRoot, sensitivity: f64, json: bool) -> Result<()> {
+    if !(0.1..=5.0).contains(&sensitivity) {
+        anyhow::bail!("sensitivity must be between 0.1 and 5.0");
+    }
+
+    let p: stats::SwitchPressure = if daemon_client::is_daemon_running().await {
+        daemon_client::get_json(&format!("/stats/switch-pressure?sensitivity={sensitivity}"))
+            .await?
+    } else {
+        let now = chrono::Utc::now().timestamp_millis();
+        let from = now - 14 * 86_400_000 - 3_600_000;
+        let conn = db::open_events_db(&root.data_dir)?;
+        let events = stats::load_events(&conn, from, now)?;
+        let spans = stats::active_spans(&events, from, now, now);
+        let counting = stats::SwitchCounting::from_env().map_err(|e| anyhow::anyhow!(e))?;
+        let baseline = stats::baseline_switch_rate(&spans, 14, counting, now);
+        stats::switch_pressure(&spans, baseline, sensitivity, counting, now)
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&p)?);
+        return Ok(());
+    }
+
+    let filled = p.pressure as usize / 5;
+    println!(
+        "Switch pressure: [{}{}] {} ({})",
+        "#".repeat(filled),
+        ".".repeat(20 - filled),
+        p.pressure,
+        p.severity.label()
+    );
+    let counted = match p.counting {
+        stats::SwitchCounting::All => p.switches,
+        stats::SwitchCounting::CrossMode => p.mode_switches,
+    };
+    println!(
+        "{} switch(es) in the last 5 min ({} across modes), {:.1} expected{}",
+        counted,
+        p.mode_switches,
+        p.expected,
+        if p.has_baseline {
+            ""
+        } else {
+            " (no baseline yet)"
+        }
+    );
+
+    Ok(())
+}
+
+/// ccube stats streak [--max-break N] — how long the current focus run has lasted.
+pub async fn handle_streak(root: &DataRoot, max_break_minutes: i64, json: bool) -> Result<()> {
+    let max_break_minutes = max_break_minutes.clamp(0, 30);
+
+    let streak: Option<stats::FocusStreak> = if daemon_client::is_daemon_running().await {
+        daemon_client::get_json(&format!(
+            "/stats/streak?max_break_minutes={max_break_minutes}"
+        ))
+        .await?
+    } else {
+        let now = chrono::Utc::now().timestamp_millis();
+        let from = now - stats::STREAK_LOOKBACK_MS;
+        let conn = db::open_events_db(&root.data_dir)?;
+        let events = stats::load_events(&conn, from, now)?;
+        let idle_timeout =
+            stats::streak_idle_timeout_ms_from_env().map_err(|e| anyhow::anyhow!(e))?;
+        let scoring = stats::FocusScoring::from_env().map_err(|e| anyhow::anyhow!(e))?;
+        stats::live_focus_streak(
+            &events,
+            &scoring,
+            max_break_minutes * 60_000,
+            idle_timeout,
+            now,
+        )
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&streak)?);
+        return Ok(());
+    }
+
+    match streak {
+        Some(s) => {
+            let since = chrono::DateTime::from_timestamp_millis(s.start)
+                .map(|dt| dt.with_timezone(&chrono::Local).format("%H:%M").to_string())
+                .unwrap_or_default();
+            println!(
+                "Focused for {} straight (since {since}, mostly {}).",
+                fmt_minutes(s.minutes),
+                s.app
+            );
+        }
+        None => println!("No focus streak running right now."),
+    }
+
+    Ok(())
+}
+
+/// ccube stats modes [--date YYYY-MM-DD] [--include-idle] — time per focus mode.
+pub async fn handle_modes(
+    root: &DataRoot,
+    date: Option<&str>,
+    include_idle: bool,
+    json: bool,
+) -> Result<()> {
+    let date = parse_date(date)?;
+
+    let modes: Vec<stats::ModeMinutes> = if daemon_client::is_daemon_running().await {
+        daemon_client::get_json(&format!(
+            "/stats/modes?date={date}&include_idle={include_idle}"
+        ))
+        .await?
+    } else {
+        let (from, to) = stats::day_bounds(date, &chrono::Local);
+        let conn = db::open_events_db(&root.data_dir)?;
+        let events = stats::load_events(&conn, from, to)?;
+        let now = chrono::Utc::now().timestamp_millis();
+        stats::mode_breakdown(&events, from, to, now, include_idle)
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&modes)?);
+        return Ok(());
+    }
+
+    if modes.is_empty() {
+        println!("No activity on {date}.");
+        return Ok(());
+    }
+
+    let total: f64 = modes.iter().map(|m| m.minutes).sum();
+    println!(
+        "{:<20} {:>8} {:>6} {:>5}",
+        "Mode", "Minutes", "Share", "Apps"
+    );
+    println!("{}", "-".repeat(42));
+    for m in &modes {
+        println!(
+            "{:<20} {:>8.0} {:>5.0}% {:>5}",
+            m.mode,
+            m.minutes,
+            m.minutes * 100.0 / total,
+            m.app_count
+        );
+    }
+
+    Ok(())
+}
+
+/// ccube stats mode <MODE> [--date] — apps within one focus mode.
+pub async fn handle_mode_detail(
+    root: &DataRoot,
+    mode: &str,
+    date: Option<&str>,
+    json: bool,
+) -> Result<()> {
+    let date = parse_date(date)?;
+    // Mode names are identifiers like VideoProduction; this also keeps the
+    // daemon path free of characters that would need escaping
+    if mode.is_empty() || !mode.chars().all(|c| c.is_ascii_alphanumeric()) {
+        anyhow::bail!("invalid mode '{mode}', expected a name like Coding or Unspecified");
+    }
+
+    let detail: stats::ModeDetail = if daemon_client::is_daemon_running().await {
+        daemon_client::get_json(&format!("/stats/modes/{mode}?date={date}")).await?
+    } else {
+        let conn = db::open_events_db(&root.data_dir)?;
+        let now = chrono::Utc::now().timestamp_millis();
+        let spans = stats::day_spans(&conn, date, &chrono::Local, now)?;
+        stats::mode_detail(&spans, mode)
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&detail)?);
+        return Ok(());
+    }
+
+    if detail.apps.is_empty() {
+        println!("No {} time on {date}.", detail.mode);
+        return Ok(());
+    }
+
+    let share = detail
+        .share
+        .map(|s| format!(" ({s:.0}% of active time)"))
+        .unwrap_or_default();
+    println!("{}: {}{share}", detail.mode, fmt_minutes(detail.minutes));
+    for app in &detail.apps {
+        println!("  {:<30} {:>10}", app.app, fmt_minutes(app.minutes));
+    }
+
+    Ok(())
+}
+
+/// ccube stats mode-trend [--weeks N] — minutes per focus mode for each of the
+/// last N weeks.
+pub async fn handle_mode_trend(root: &DataRoot, weeks: usize, json: bool) -> Result<()> {
+    let weeks = weeks.clamp(1, 52);
+
+    let trend: stats::ModeWeeklyTrend = if daemon_client::is_daemon_running().await {
+        daemon_client::get_json(&format!("/stats/mode-trend?weeks={weeks}")).await?
+    } else {
+        let today = chrono::Local::now().date_naive();
+        let first_monday =
+            stats::week_start_monday(today) - chrono::Duration::weeks(weeks as i64 - 1);
+        let (from, _) = stats::day_bounds(first_monday, &chrono::Local);
+        let now = chrono::Utc::now().timestamp_millis();
+        let conn = db::open_events_db(&root.data_dir)?;
+        let oldest = db::oldest_event_ts(&conn)?;
+        let events = stats::load_events(&conn, from, now)?;
+        stats::mode_weekly_trend(&events, oldest, today, weeks, &chrono::Local, now)
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&trend)?);
+        return Ok(());
+    }
+
+    if trend.modes.is_empty() {
+        println!("No activity in the last {weeks} week(s).");
+        return Ok(());
+    }
+
+    print!("{:<16}", "Mode");
+    for w in &trend.weeks {
+        print!(" {:>7}", w.format("%m-%d").to_string());
+    }
+    println!();
+    println!("{}", "-".repeat(16 + 8 * trend.weeks.len()));
+    for m in &trend.modes {
+        print!("{:<16}", m.mode);
+        for minutes in &m.minutes {
+            let cell = minutes.map(fmt_minutes).unwrap_or_else(|| "-".to_string());
+            print!(" {cell:>7}");
+        }
+        println!();
+    }
+    if trend.modes.iter().any(|m| m.minutes.contains(&None)) {
+        println!("\n- marks weeks that began before the oldest stored event.");
+    }
+    if trend.last_week_days < 7 {
+        println!(
+            "\nThe last column is a partial week ({} of 7 days).",
+            trend.last_week_days
+        );
+    }
+
+    Ok(())
+}
+
+/// ccube stats sessions [--date] — how each app tends to be used.
+pub async fn handle_sessions(root: &DataRoot, date: Option<&str>, json: bool) -> Result<()> {
+    let date = parse_date(date)?;
+
+    let apps: Vec<stats::AppSessionStats> = if daemon_client::is_daemon_running().await {
+        daemon_client::get_json(&format!("/stats/app-sessions?date={date}")).await?
+    } else {
+        let conn = db::open_events_db(&root.data_dir)?;
+        let now = chrono::Utc::now().timestamp_millis();
+        let spans = stats::day_spans(&conn, date, &chrono::Local, now)?;
+        let merge_gap_ms =
+            stats::session_merge_gap_ms_from_env().map_err(|e| anyhow::anyhow!(e))?;
+        stats::app_session_stats(&spans, merge_gap_ms)
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&apps)?);
+        return Ok(());
+    }
+
+    if apps.is_empty() {
+        println!("No activity on {date}.");
+        return Ok(());
+    }
+
+    println!(
+        "{:<30} {:>8} {:>10} {:>10} {:>10}",
+        "App", "Sessions", "Total", "Average", "Median"
+    );
+    println!("{}", "-".repeat(72));
+    for a in &apps {
+        println!(
+            "{:<30} {:>8} {:>10} {:>10} {:>10}",
+            a.app,
+            a.sessions,
+            fmt_minutes(a.total_minutes),
+            fmt_minutes(a.avg_minutes),
+            fmt_minutes(a.median_minutes)
+        );
+    }
+
+    Ok(())
+}
+
+/// ccube stats trend [--days N] [--bucket day|hour] — focus ratio over time.
+pub async fn handle_trend(root: &DataRoot, days: i64, bucket: &str, json: bool) -> Result<()> {
+    let bucket: stats::TrendBucket = bucket.parse().map_err(|e: String| anyhow::anyhow!(e))?;
+    let days = days.clamp(1, 90);
+
+    let points: Vec<stats::TrendPoint> = if daemon_client::is_daemon_running().await {
+        let bucket = match bucket {
+            stats::TrendBucket::Hour => "hour",
+            stats::TrendBucket::Day => "day",
+        };
+        daemon_client::get_json(&format!("/stats/focus-trend?days={days}&bucket={bucket}")).await?
+    } else {
+        let first_day = chrono::Local::now().date_naive() - chrono::Duration::days(days - 1);
+        let (from, _) = stats::day_bounds(first_day, &chrono::Local);
+        let now = chrono::Utc::now().timestamp_millis();
+        let conn = db::open_events_db(&root.data_dir)?;
+        let events = stats::load_events(&conn, from, now)?;
+        let scoring = stats::FocusScoring::from_env().map_err(|e| anyhow::anyhow!(e))?;
+        stats::focus_trend(&events, from, bucket, &chrono::Local, &scoring, now)
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&points)?);
+        return Ok(());
+    }
+
+    let label_fmt = match bucket {
+        stats::TrendBucket::Hour => "%a %m-%d %H:00",
+        stats::TrendBucket::Day => "%a %m-%d",
+    };
+    println!("{:<16} {:>6} {:>10}", "Period", "Focus", "Active");
+    println!("{}", "-".repeat(56));
+    for p in &points {
+        let label = chrono::DateTime::from_timestamp_millis(p.start)
+            .map(|dt| {
+                dt.with_timezone(&chrono::Local)
+                    .format(label_fmt)
+                    .to_string()
+            })
+            .unwrap_or_default();
+        let (ratio, bar) = match p.focus_ratio {
+            Some(r) => (format!("{r:.0}%"), "#".repeat((r / 5.0).round() as usize)),
+            None => ("-".to_string(), String::new()),
+        };
+        println!(
+            "{:<16} {:>6} {:>10} {bar}",
+            label,
+            ratio,
+            fmt_minutes(p.active_minutes)
+        );
+    }
+
+    Ok(())
+}
+
+/// ccube stats daily [--days N] — stored daily focus rollups.
+pub async fn handle_daily(root: &DataRoot, days: i64, json: bool) -> Result<()> {
+    let days = days.clamp(1, 365);
+
+    let rows: Vec<db::DailyStatsRow> = if daemon_client::is_daemon_running().await {
+        daemon_client::get_json(&format!("/stats/daily?days={days}")).await?
+    } else {
+        let today = chrono::Local::now().date_naive();
+        let from = today - chrono::Duration::days(days - 1);
+        let conn = db::open_events_db(&root.data_dir)?;
+        db::list_daily_stats(&conn, &from.to_string(), &today.to_string())?
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&rows)?);
+        return Ok(());
+    }
+
+    if rows.is_empty() {
+        println!("No daily stats stored yet (the daemon rolls them up hourly).");
+        return Ok(());
+    }
+
+    println!(
+        "{:<12} {:>6} {:>10} {:>10}",
+        "Date", "Focus", "Active", "Focused"
+    );
+    println!("{}", "-".repeat(41));
+    for r in &rows {
+        let score = r
+            .focus_score
+            .map(|s| format!("{s:.0}%"))
+            .unwrap_or_else(|| "-".to_string());
+        println!(
+            "{:<12} {:>6} {:>10} {:>10}",
+            r.date,
+            score,
+            fmt_minutes(r.active_ms as f64 / 60_000.0),
+            fmt_minutes(r.focused_ms as f64 / 60_000.0)
+        );
+    }
+
+    Ok(())
+}
+
+/// ccube stats vs-average [--days N] — today so far against the recent average.
+pub async fn handle_vs_average(root: &DataRoot, days: i64, json: bool) -> Result<()> {
+    let days = days.clamp(1, 365);
+
+    let c: stats::TodayVsAverage = if daemon_client::is_daemon_running().await {
+        daemon_client::get_json(&format!("/stats/vs-average?days={days}")).await?
+    } else {
+        let today = chrono::Local::now().date_naive();
+        let from = today - chrono::Duration::days(days);
+        let yesterday = today - chrono::Duration::days(1);
+        let now = chrono::Utc::now().timestamp_millis();
+        let conn = db::open_events_db(&root.data_dir)?;
+        let rows = db::list_daily_stats(&conn, &from.to_string(), &yesterday.to_string())?;
+        let spans = stats::day_spans(&conn, today, &chrono::Local, now)?;
+        let scoring = stats::FocusScoring::from_env().map_err(|e| anyhow::anyhow!(e))?;
+        let as_of = chrono::Local::now().time();
+        stats::today_vs_average(today, scoring.totals(&spans), &rows, Some(as_of))
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&c)?);
+        return Ok(());
+    }
+
+    let score = c
+        .today_focus_score
+        .map(|s| format!("{s:.0}%"))
+        .unwrap_or_else(|| "-".to_string());
+    println!(
+        "Today: {score} focus, {} active",
+        fmt_minutes(c.today_active_minutes)
+    );
+    let (Some(avg_minutes), Some(minutes_delta)) = (c.avg_active_minutes, c.active_minutes_delta)
+    else {
+        println!("No earlier days stored to compare with yet.");
+        return Ok(());
+    };
+    if let (Some(avg), Some(delta)) = (c.avg_focus_score, c.focus_score_delta) {
+        println!("Focus: {delta:+.0} points vs the average of {avg:.0}%");
+    }
+    let sign = if minutes_delta < 0.0 { "-" } else { "+" };
+    println!(
+        "Active: {sign}{} vs the average of {}",
+        fmt_minutes(minutes_delta.abs()),
+        fmt_minutes(avg_minutes)
+    );
+    println!(
+        "(averaged over {} of the last {days} day(s) with activity)",
+        c.sample_days
+    );
+    if let Some(note) = &c.note {
+        println!("({note})");
+    }
+
+    Ok(())
+}
+
+/// ccube stats calendar [--year Y] — one cell per day, shaded by active time.
+pub async fn handle_calendar(root: &DataRoot, year: Option<i32>, json: bool) -> Result<()> {
+    use chrono::Datelike;
+
+    let today = chrono::Local::now().date_naive();
+    let year = year.unwrap_or(today.year());
+    if !(1970..=9999).contains(&year) {
+        anyhow::bail!("--year must be between 1970 and 9999");
+    }
+
+    let days: Vec<stats::CalendarDay> = if daemon_client::is_daemon_running().await {
+        daemon_client::get_json(&format!("/stats/calendar?year={year}")).await?
+    } else {
+        let conn = db::open_events_db(&root.data_dir)?;
+        let rows = db::list_daily_stats(&conn, &format!("{year}-01-01"), &format!("{year}-12-31"))?;
+        stats::activity_calendar(year, &rows, today)
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&days)?);
+        return Ok(());
+    }
+
+    let Some(first) = days.first() else {
+        println!("Nothing to show for {year} yet.");
+        return Ok(());
+    };
+
+    // Rows are weekdays, columns are weeks starting Monday
+    let offset = first.date.weekday().num_days_from_monday() as usize;
+    let mut grid = vec![vec![' '; (offset + days.len()).div_ceil(7)]; 7];
+    for (i, day) in days.iter().enumerate() {
+        let cell = offset + i;
+        grid[cell % 7][cell / 7] = heat_cell(day.active_minutes);
+    }
+    println!("{year}");
+    for (label, row) in ["Mon", "", "Wed", "", "Fri", "", "Sun"].iter().zip(&grid) {
+        println!("{label:<4}{}", row.iter().collect::<String>());
+    }
+    println!("    · none  ░ <1h  ▒ <3h  ▓ <5h  █ 5h+");
+
+    let active: Vec<&stats::CalendarDay> = days.iter().filter(|d| d.active_minutes > 0.0).collect();
+    let total: f64 = active.iter().map(|d| d.active_minutes).sum();
+    println!(
+        "{} active day(s) of {}, {} in total",
+        active.len(),
+        days.len(),
+        fmt_minutes(total)
+    );
+
+    Ok(())
+}
+
+fn heat_cell(active_minutes: f64) -> char {
+    match active_minutes {
+        m if m <= 0.0 => '·',
+        m if m < 60.0 => '░',
+        m if m < 180.0 => '▒',
+        m if m < 300.0 => '▓',
+        _ => '█',
+    }
+}
+
+/// ccube stats nudges — how often recent nudges were rated helpful.
+pub async fn handle_nudge_accuracy(root: &DataRoot, json: bool) -> Result<()> {
+    let acc: nudge_policy::NudgeAccuracy = if daemon_client::is_daemon_running().await {
+        daemon_client::get_json("/nudges/accuracy").await?
+    } else {
+        let since =
+            chrono::Utc::now().timestamp_millis() - nudge_policy::FEEDBACK_WINDOW_DAYS * 86_400_000;
+        let conn = db::open_corrections_db(&root.data_dir)?;
+        nudge_policy::NudgeAccuracy::from_feedback(&db::list_nudge_feedback(&conn, since)?)
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&acc)?);
+        return Ok(());
+    }
+
+    match acc.accuracy {
+        Some(a) => println!(
+            "Nudge accuracy (last {} days): {a:.0}% ({} helpful, {} wrong)",
+            nudge_policy::FEEDBACK_WINDOW_DAYS,
+            acc.helpful,
+            acc.unhelpful
+        ),
+        None => println!("No nudge feedback yet (rate one with `ccube feedback <id>`)."),
+    }
+    if acc.raised_bar {
+        println!("Most recent nudges were marked wrong: only direct nudges are shown.");
+    }
+
+    Ok(())
+}
+
+/// ccube stats projection — where today's focus score is heading.
+pub async fn handle_projection(root: &DataRoot, json: bool) -> Result<()> {
+    let projection: Option<stats::FocusProjection> = if daemon_client::is_daemon_running().await {
+        daemon_client::get_json("/stats/projection").await?
+    } else {
+        let today = chrono::Local::now().date_naive();
+        let conn = db::open_events_db(&root.data_dir)?;
+        let now = chrono::Utc::now().timestamp_millis();
+        let spans = stats::day_spans(&conn, today, &chrono::Local, now)?;
+        let scoring = stats::FocusScoring::from_env().map_err(|e| anyhow::anyhow!(e))?;
+        stats::project_focus(&spans, &scoring, now)
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&projection)?);
+        return Ok(());
+    }
+
+    let Some(p) = projection else {
+        println!("Not enough activity today for a projection yet (needs 30 min).");
+        return Ok(());
+    };
+
+    println!(
+        "Projected focus score today: {:.0}% (projection)",
+        p.projected_score
+    );
+    let recent = p
+        .recent_score
+        .map(|r| format!("{r:.0}%"))
+        .unwrap_or_else(|| "-".to_string());
+    println!(
+        "So far {:.0}% over {}; last hour {recent}",
+        p.so_far_score,
+        fmt_minutes(p.active_minutes)
+    );
+
+    Ok(())
+}
+
+/// ccube stats target — today's focused time against the daily target.
+pub async fn handle_target(root: &DataRoot, json: bool) -> Result<()> {
+    let progress: stats::TargetProgress = if daemon_client::is_daemon_running().await {
+        daemon_client::get_json("/stats/target").await?
+    } else {
+        let target = stats::focus_target_from_env().map_err(|e| anyhow::anyhow!(e))?;
+        let today = chrono::Local::now().date_naive();
+        let conn = db::open_events_db(&root.data_dir)?;
+        let now = chrono::Utc::now().timestamp_millis();
+        let spans = stats::day_spans(&conn, today, &chrono::Local, now)?;
+        let scoring = stats::FocusScoring::from_env().map_err(|e| anyhow::anyhow!(e))?;
+        stats::target_progress(scoring.totals(&spans), target)
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&progress)?);
+        return Ok(());
+    }
+
+    let filled = ((progress.percent / 5.0) as usize).min(20);
+    println!(
+        "Focus target: [{}{}] {:.0}%",
+        "#".repeat(filled),
+        ".".repeat(20 - filled),
+        progress.percent
+    );
+    println!(
+        "{} focused of {}{}",
+        fmt_minutes(progress.focused_minutes),
+        fmt_minutes(progress.target_minutes as f64),
+        if progress.met { " - target met" } else { "" }
+    );
+
+    Ok(())
+}
+
+/// ccube stats switching-cost [--date YYYY-MM-DD] — estimated time lost to
+/// app switching.
+pub async fn handle_switching_cost(root: &DataRoot, date: Option<&str>, json: bool) -> Result<()> {
+    let date = parse_date(date)?;
+
+    let cost: stats::SwitchingCost = if daemon_client::is_daemon_running().await {
+        daemon_client::get_json(&format!("/stats/switching-cost?date={date}")).await?
+    } else {
+        let counting = stats::SwitchCounting::from_env().map_err(|e| anyhow::anyhow!(e))?;
+        let recovery = stats::switch_recovery_secs_from_env().map_err(|e| anyhow::anyhow!(e))?;
+        let conn = db::open_events_db(&root.data_dir)?;
+        let now = chrono::Utc::now().timestamp_millis();
+        let spans = stats::day_spans(&conn, date, &chrono::Local, now)?;
+        stats::switching_cost(&spans, counting, recovery)
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&cost)?);
+        return Ok(());
+    }
+
+    let kind = match cost.counting {
+        stats::SwitchCounting::All => "app switch(es)",
+        stats::SwitchCounting::CrossMode => "cross-mode switch(es)",
+    };
+    println!(
+        "You may have lost about {} to task switching on {date}.",
+        fmt_minutes(cost.minutes_lost)
+    );
+    println!(
+        "{} {kind} x {}s to refocus{}",
+        cost.switches,
+        cost.recovery_secs,
+        cost.share_of_active
+            .map(|s| format!(" ({s:.0}% of active time)"))
+            .unwrap_or_default()
+    );
+
+    Ok(())
+}
+
+/// ccube stats meeting — whether the user is in a video call right now.
+pub async fn handle_meeting(root: &DataRoot, json: bool) -> Result<()> {
+    let status: nudge_policy::MeetingStatus = if daemon_client::is_daemon_running().await {
+        daemon_client::get_json("/stats/meeting").await?
+    } else {
+        let detection =
+            nudge_policy::MeetingDetection::from_env().map_err(|e| anyhow::anyhow!(e))?;
+        let conn = db::open_events_db(&root.data_dir)?;
+        let now = chrono::Utc::now().timestamp_millis();
+        let events = db::query_recent_events(&conn, now - nudge_policy::MEETING_LOOKBACK_MS)?;
+        detection.status(&events, now)
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&status)?);
+        return Ok(());
+    }
+
+    let (Some(app), Some(since)) = (&status.app, status.since) else {
+        println!("Not in a meeting.");
+        return Ok(());
+    };
+    let since = chrono::DateTime::from_timestamp_millis(since)
+        .map(|dt| dt.with_timezone(&chrono::Local).format("%H:%M").to_string())
+        .unwrap_or_default();
+    if status.in_meeting {
+        println!(
+            "In a meeting: {app} since {since} ({}). Nudges are held back.",
+            fmt_minutes(status.minutes)
+        );
+    } else {
+        println!(
+            "{app} in front since {since} ({}), not long enough to count as a meeting yet.",
+            fmt_minutes(status.minutes)
+        );
+    }
+
+    Ok(())
+}
+
+/// ccube stats since-return [--min-idle M] — activity since the last long break.
+pub async fn handle_since_return(root: &DataRoot, min_idle: i64, json: bool) -> Result<()> {
+    if min_idle < 1 {
+        anyhow::bail!("--min-idle must be at least 1 minute");
+    }
+
+    let summary: stats::WindowSummary = if daemon_client::is_daemon_running().await {
+        daemon_client::get_json(&format!("/stats/since-return?min_idle_minutes={min_idle}")).await?
+    } else {
+        let today = chrono::Local::now().date_naive();
+        let (midnight, _) = stats::day_bounds(today, &chrono::Local);
+        let conn = db::open_events_db(&root.data_dir)?;
+        let now = chrono::Utc::now().timestamp_millis();
+        let events = stats::load_events(&conn, midnight, now)?;
+        let scoring = stats::FocusScoring::from_env().map_err(|e| anyhow::anyhow!(e))?;
+        stats::since_return_summary(
+            &events,
+            min_idle * 60_000,
+            midnight,
+            &scoring,
+            now,
+            &chrono::Local,
+        )
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&summary)?);
+        return Ok(());
+    }
+
+    println!("Activity {}", summary.period);
+    let score = summary
+        .focus_score
+        .map(|s| format!("{s:.0}%"))
+        .unwrap_or_else(|| "-".to_string());
+    println!(
+        "Active {}, focused {}, focus score {score}",
+        fmt_minutes(summary.active_minutes),
+        fmt_minutes(summary.focused_minutes)
+    );
+    for app in &summary.top_apps {
+        println!("  {:<30} {:>10}", app.app, fmt_minutes(app.minutes));
+    }
+
+    Ok(())
+}
+
+/// ccube stats timeframes — focus score over the last 5 minutes, 30 minutes
+/// and hour, so a short window can be read against the longer ones.
+pub async fn handle_timeframes(root: &DataRoot, json: bool) -> Result<()> {
+    let scores: BTreeMap<String, Option<f64>> = if daemon_client::is_daemon_running().await {
+        daemon_client::get_json("/stats/timeframes").await?
+    } else {
+        let now = chrono::Utc::now().timestamp_millis();
+        let from = now - stats::FOCUS_TIMEFRAMES_LOOKBACK_MS;
+        let conn = db::open_events_db(&root.data_dir)?;
+        let events = stats::load_events(&conn, from, now)?;
+        let spans = stats::active_spans(&events, from, now, now);
+        let scoring = stats::FocusScoring::from_env().map_err(|e| anyhow::anyhow!(e))?;
+        stats::focus_by_timeframe(&spans, &scoring, now)
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&scores)?);
+        return Ok(());
+    }
+
+    for (label, _) in stats::FOCUS_TIMEFRAMES {
+        let score = scores
+            .get(label)
+            .copied()
+            .flatten()
+            .map(|s| format!("{s:.0}%"))
+            .unwrap_or_else(|| "-".to_string());
+        println!("  last {label:<4} {score:>5}");
+    }
+
+    Ok(())
+}
+
+/// ccube stats latency [--limit N] — LLM call latency, cold vs warm.
+pub async fn handle_latency(root: &DataRoot, limit: i64, json: bool) -> Result<()> {
+    let limit = limit.clamp(1, db::LLM_CALLS_MAX_ROWS);
+
+    let history: llm::LatencyHistory = if daemon_client::is_daemon_running().await {
+        daemon_client::get_json(&format!("/llm/latency?limit={limit}")).await?
+    } else {
+        let conn = db::open_events_db(&root.data_dir)?;
+        llm::latency_history(db::list_llm_calls(&conn, limit)?)
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&history)?);
+        return Ok(());
+    }
+
+    if history.calls.is_empty() {
+        println!("No LLM calls recorded yet.");
+        return Ok(());
+    }
+
+    let fmt_avg = |v: Option<i64>| v.map(|ms| format!("{ms} ms")).unwrap_or_else(|| "-".into());
+    println!(
+        "Cold: {} call(s), avg {}",
+        history.cold_calls,
+        fmt_avg(history.cold_avg_ms)
+    );
+    println!(
+        "Warm: {} call(s), avg {}",
+        history.warm_calls,
+        fmt_avg(history.warm_avg_ms)
+    );
+    println!(
+        "\n{:<20} {:<10} {:>10} {:<5} OK",
+        "Time", "Client", "Latency", "Cold"
+    );
+    println!("{}", "-".repeat(52));
+    for c in &history.calls {
+        let time = chrono::DateTime::from_timestamp_millis(c.ts)
+            .map(|dt| {
+                dt.with_timezone(&chrono::Local)
+                    .format("%Y-%m-%d %H:%M:%S")
+                    .to_string()
+            })
+            .unwrap_or_default();
+        println!(
+            "{:<20} {:<10} {:>7} ms {:<5} {}",
+            time,
+            c.client,
+            c.latency_ms,
+            if c.cold { "yes" } else { "" },
+            if c.ok { "yes" } else { "no" }
+        );
+    }
+
+    Ok(())
+}
+
+/// ccube stats usage [--days N] [--reset] — LLM calls and tokens per day.
+pub async fn handle_usage(root: &DataRoot, days: i64, reset: bool, json: bool) -> Result<()> {
+    if reset {
+        let removed = if daemon_client::is_daemon_running().await {
+            let resp: serde_json::Value = daemon_client::post_empty("/llm/usage/reset").await?;
+            resp["removed"].as_u64().unwrap_or(0) as usize
+        } else {
+            db::reset_llm_usage(&db::open_events_db(&root.data_dir)?)?
+        };
+        println!("LLM usage counters cleared ({removed} day/client row(s)).");
+        return Ok(());
+    }
+
+    let days = days.clamp(1, 365);
+    let usage: llm::UsageSummary = if daemon_client::is_daemon_running().await {
+        daemon_client::get_json(&format!("/llm/usage?days={days}")).await?
+    } else {
+        let today = chrono::Local::now().date_naive();
+        let from = today - chrono::Duration::days(days - 1);
+        let conn = db::open_events_db(&root.data_dir)?;
+        let rows = db::list_llm_usage(&conn, &from.to_string(), &today.to_string())?;
+        llm::usage_summary(rows)
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&usage)?);
+        return Ok(());
+    }
+
+    if usage.calls == 0 {
+        println!("No LLM calls counted in the last {days} day(s).");
+        return Ok(());
+    }
+
+    println!(
+        "{} call(s) ({} failed), {} prompt + {} completion tokens over {days} day(s)",
+        usage.calls, usage.failed, usage.prompt_tokens, usage.completion_tokens
+    );
+    println!(
+        "\n{:<12} {:<10} {:>6} {:>10} {:>10}",
+        "Date", "Client", "Calls", "Prompt", "Output"
+    );
+    println!("{}", "-".repeat(52));
+    for d in &usage.days {
+        println!(
+            "{:<12} {:<10} {:>6} {:>10} {:>10}",
+            d.date, d.client, d.calls, d.prompt_tokens, d.completion_tokens
+        );
+    }
+
+    Ok(())
+}
+
+/// Format a minute count in the unit chosen by `CCUBE_TIME_UNIT`.
+fn fmt_minutes(minutes: f64) -> String {
+    static UNIT: std::sync::OnceLock<stats::TimeUnit> = std::sync::OnceLock::new();
+    let unit = *UNIT.get_or_init(|| {
+        stats::TimeUnit::from_env().unwrap_or_else(|e| {
+            eprintln!("warning: {e}, showing minutes");
+            stats::TimeUnit::Minutes
+        })
+    });
+    stats::format_duration((minutes * 60.0).round() as i64, unit)
+}
+
+/// Parse an optional `YYYY-MM-DD` argument, defaulting to today (local time).
+pub(crate) fn parse_date(value: Option<&str>) -> Result<chrono::NaiveDate> {
+    match value {
+        Some(s) => chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d")
+            .map_err(|_| anyhow::anyhow!("invalid date '{s}', expected YYYY-MM-DD")),
+        None => Ok(chrono::Local::now().date_naive()),
+    }
+}
@@ -1,338 +1,988 @@
-mod commands;
-mod daemon_client;
-mod paths;
-
-use anyhow::Result;
-use clap::{Parser, Subcommand};
-use commands::memory::{EditTarget, MemoryTarget};
-
-#[derive(Parser)]
-#[command(
-    name = "ccube",
-    version,
-    about = "Companion Cube — ADHD focus companion"
-)]
-struct Cli {
-    #[command(subcommand)]
-    command: Option<Commands>,
-}
-
-#[derive(Subcommand)]
-enum Commands {
-    /// Run the detector once
-    Detect {
-        /// Show result without delivering a notification
-        #[arg(long)]
-        dry_run: bool,
-        /// Output as JSON
-        #[arg(long)]
-        json: bool,
-    },
-    /// Record a correction
-    Correct {
-        /// Decision ID to correct (shown in notifications and detect output)
-        decision_id: i64,
-        /// Your verdict (e.g. "wasn't drift", "should have nudged")
-        verdict: String,
-    },
-    /// Show the current briefing the detector would see
-    Briefing {
-        /// Output as JSON
-        #[arg(long)]
-        json: bool,
-    },
-    /// Show daemon status
-    Status,
-    /// Agent operations (curate, reflect)
-    Agent {
-        #[command(subcommand)]
-        command: AgentCommands,
-    },
-    /// Data inspection and management
-    Data {
-        #[command(subcommand)]
-        command: DataCommands,
-    },
-    /// Daemon lifecycle control
-    Daemon {
-        #[command(subcommand)]
-        command: DaemonCommands,
-    },
-}
-
-// ---------------------------------------------------------------------------
-// Agent subcommands
-// ---------------------------------------------------------------------------
-
-#[derive(Subcommand)]
-enum AgentCommands {
-    /// Run the curator agent
-    Curate {
-        /// Propose changes without writing to patterns.md
-        #[arg(long)]
-        dry_run: bool,
-        /// Output as JSON
-        #[arg(long)]
-        json: bool,
-    },
-    /// Run the reflector agent
-    Reflect {
-        #[command(subcommand)]
-        command: ReflectCommands,
-    },
-}
-
-#[derive(Subcommand)]
-enum ReflectCommands {
-    /// Run the reflector to consolidate patterns.md
-    Run {
-        /// Propose changes without writing to patterns.md
-        #[arg(long)]
-        dry_run: bool,
-        /// Output as JSON
-        #[arg(long)]
-        json: bool,
-    },
-    /// Accept a pending reflector rewrite
-    Accept,
-    /// Reject a pending reflector rewrite
-    Reject,
-    /// Show pending reflector output (if any)
-    Show {
-        /// Output as JSON
-        #[arg(long)]
-        json: bool,
-    },
-}
-
-// ---------------------------------------------------------------------------
-// Data subcommands
-// ---------------------------------------------------------------------------
-
-#[derive(Subcommand)]
-enum DataCommands {
-    /// Show recent activity events
-    Activity {
-        /// Number of hours to look back (default: 1)
-        #[arg(long, default_value = "1.0")]
-        hours: f64,
-    },
-    /// Delete events older than 14 days
-    Prune,
-    /// List corrections
-    Corrections {
-        /// Show only pending corrections
-        #[arg(long)]
-        pending: bool,
-        /// Maximum number of corrections to show
-        #[arg(long, default_value = "20")]
-        limit: i64,
-    },
-    /// Show full details for a correction
-    Correction {
-        /// Correction ID
-        id: i64,
-    },
-    /// Memory file management (profile, patterns)
-    Memory {
-        #[command(subcommand)]
-        command: MemoryCommands,
-    },
-}
-
-#[derive(Subcommand)]
-enum MemoryCommands {
-    /// Show memory contents (profile, patterns, or corrections)
-    Show {
-        /// Which memory layer to display
-        target: MemoryTarget,
-    },
-    /// Open a memory file in your editor
-    Edit {
-        /// Which memory file to edit
-        target: EditTarget,
-    },
-    /// List history snapshots for a memory file
-    History {
-        /// Which memory file's history to show
-        target: EditTarget,
-    },
-    /// Restore a memory file from a history snapshot
-    Restore {
-        /// Which memory file to restore
-        target: EditTarget,
-        /// Unix timestamp of the snapshot to restore
-        timestamp: i64,
-    },
-    /// Diff two history snapshots
-    Diff {
-        /// Which memory file to diff
-        target: EditTarget,
-        /// Unix timestamp of the first (older) snapshot
-        ts1: i64,
-        /// Unix timestamp of the second (newer) snapshot
-        ts2: i64,
-    },
-}
-
-// ---------------------------------------------------------------------------
-// Daemon subcommands
-// ---------------------------------------------------------------------------
-
-#[derive(Subcommand)]
-enum DaemonCommands {
-    /// Start the daemon in the background
-    Start,
-    /// Stop the running daemon
-    Stop,
-    /// Show daemon status
-    Status,
-    /// Show daemon logs
-    Logs {
-        /// Follow the log file (like tail -f)
-        #[arg(long)]
-        follow: bool,
-        /// Filter by agent (detector, curator, reflector)
-        #[arg(long)]
-        agent: Option<String>,
-    },
-    /// Run continuous activity capture (Ctrl+C to stop)
-    Capture,
-    /// Register daemon to start on logon
-    Install,
-    /// Remove daemon autostart registration
-    Uninstall,
-}
-
-// ---------------------------------------------------------------------------
-// Dispatch
-// ---------------------------------------------------------------------------
-
-#[tokio::main]
-async fn main() -> Result<()> {
-    dotenvy::dotenv().ok();
-
-    let cli = Cli::parse();
-
-    match cli.command {
-        // --- Top-level shortcuts (daily workflow) ---
-        Some(Commands::Detect { dry_run, json }) => {
-            let root = paths::DataRoot::resolve()?;
-            ccube_core::db::init_databases(&root.data_dir)?;
-            commands::detect::handle_detect(&root, dry_run, json).await?;
-        }
-        Some(Commands::Correct {
-            decision_id,
-            verdict,
-        }) => {
-            let root = paths::DataRoot::resolve()?;
-            ccube_core::db::init_databases(&root.data_dir)?;
-            commands::correct::handle_correct(&root, decision_id, &verdict).await?;
-        }
-        Some(Commands::Briefing { json }) => {
-            let root = paths::DataRoot::resolve()?;
-            ccube_core::db::init_databases(&root.data_dir)?;
-            commands::detect::handle_briefing(&root, json).await?;
-        }
-        Some(Commands::Status) => {
-            let root = paths::DataRoot::resolve()?;
-            commands::daemon::handle_status(&root).await?;
-        }
-
-        // --- Agent operations ---
-        Some(Commands::Agent { command }) => {
-            let root = paths::DataRoot::resolve()?;
-            ccube_core::db::init_databases(&root.data_dir)?;
-            match command {
-                AgentCommands::Curate { dry_run, json } => {
-                    commands::curate::handle_curate(&root, dry_run, json).await?;
-                }
-                AgentCommands::Reflect { command } => match command {
-                    ReflectCommands::Run { dry_run, json } => {
-                        commands::reflect::handle_reflect(&root, dry_run, json).await?;
-                    }
-                    ReflectCommands::Accept => {
-                        commands::reflect::handle_accept(&root).await?;
-                    }
-                    ReflectCommands::Reject => {
-                        commands::reflect::handle_reject(&root).await?;
-                    }
-                    ReflectCommands::Show { json } => {
-                        commands::reflect::handle_show_pending(&root, json).await?;
-                    }
-                },
-            }
-        }
-
-        // --- Data inspection and management ---
-        Some(Commands::Data { command }) => {
-            let root = paths::DataRoot::resolve()?;
-            ccube_core::db::init_databases(&root.data_dir)?;
-            match command {
-                DataCommands::Activity { hours } => {
-                    commands::activity::handle_recent(&root, hours).await?;
-                }
-                DataCommands::Prune => {
-                    commands::activity::handle_prune(&root)?;
-                }
-                DataCommands::Corrections { pending, limit } => {
-                    commands::correct::handle_corrections_list(&root, pending, limit).await?;
-                }
-                DataCommands::Correction { id } => {
-                    commands::correct::handle_corrections_show(&root, id).await?;
-                }
-                DataCommands::Memory { command } => match command {
-                    MemoryCommands::Show { target } => {
-                        commands::memory::handle_show(&root, &target).await?;
-                    }
-                    MemoryCommands::Edit { target } => {
-                        commands::memory::handle_edit(&root, &target)?;
-                    }
-                    MemoryCommands::History { target } => {
-                        commands::memory::handle_history(&root, &target)?;
-                    }
-                    MemoryCommands::Restore { target, timestamp } => {
-                        commands::memory::handle_restore(&root, &target, timestamp)?;
-                    }
-                    MemoryCommands::Diff { target, ts1, ts2 } => {
-                        commands::memory::handle_diff(&root, &target, ts1, ts2)?;
-                    }
-                },
-            }
-        }
-
-        // --- Daemon lifecycle ---
-        Some(Commands::Daemon { command }) => {
-            let root = paths::DataRoot::resolve()?;
-            match command {
-                DaemonCommands::Start => {
-                    commands::daemon::handle_start(&root).await?;
-                }
-                DaemonCommands::Stop => {
-                    commands::daemon::handle_stop(&root).await?;
-                }
-                DaemonCommands::Status => {
-                    commands::daemon::handle_status(&root).await?;
-                }
-                DaemonCommands::Logs { follow, agent } => {
-                    commands::daemon::handle_logs(&root, follow, agent.as_deref())?;
-                }
-                DaemonCommands::Capture => {
-                    commands::capture::handle_capture_run(&root).await?;
-                }
-                DaemonCommands::Install => {
-                    commands::daemon::handle_install(&root)?;
-                }
-                DaemonCommands::Uninstall => {
-                    commands::daemon::handle_uninstall()?;
-                }
-            }
-        }
-
-        None => {
-            Cli::parse_from(["ccube", "--help"]);
-        }
-    }
-
-    Ok(())
-}
+mod commands;
+mod daemon_client;
+mod paths;
+
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+use commands::memory::{EditTarget, MemoryTarget};
+
+#[derive(Parser)]
+#[command(
+    name = "ccube",
+    version,
+    about = "Companion Cube — ADHD focus companion"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Commands>,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// Run the detector once
+    Detect {
+        /// Show result without delivering a notification
+        #[arg(long)]
+        dry_run: bool,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Record a correction
+    Correct {
+        /// Decision ID to correct (shown in notifications and detect output)
+        decision_id: i64,
+        /// Your verdict (e.g. "wasn't drift", "should have nudged")
+        verdict: String,
+    },
+    /// Rate whether a nudge was helpful
+    Feedback {
+        /// Decision ID of the nudge (shown in the notification)
+        decision_id: i64,
+        /// The nudge was wrong (default: it was helpful)
+        #[arg(long)]
+        wrong: bool,
+    },
+    /// Hold back nudge notifications for a while
+    Snooze {
+        /// How long to snooze, in minutes (0 resumes nudges)
+        #[arg(long, default_value = "15")]
+        minutes: i64,
+    },
+    /// Count the next few minutes as active even while idle (reading, watching)
+    Active {
+        /// How long, in minutes (max 60; 0 ends an earlier declaration)
+        #[arg(long, default_value = "30")]
+        minutes: i64,
+    },
+    /// Ask the LLM a question about a day's activity
+    Ask {
+        /// The question, e.g. "did I spend too much time on Slack?"
+        question: String,
+        /// Day to ask about (YYYY-MM-DD, default: today)
+        #[arg(long)]
+        date: Option<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show the current briefing the detector would see
+    Briefing {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show daemon status
+    Status,
+    /// Agent operations (curate, reflect)
+    Agent {
+        #[command(subcommand)]
+        command: AgentCommands,
+    },
+    /// Activity statistics
+    Stats {
+        #[command(subcommand)]
+        command: StatsCommands,
+    },
+    /// LLM server diagnostics
+    Llm {
+        #[command(subcommand)]
+        command: LlmCommands,
+    },
+    /// Data inspection and management
+    Data {
+        #[command(subcommand)]
+        command: DataCommands,
+    },
+    /// Daemon lifecycle control
+    Daemon {
+        #[command(subcommand)]
+        command: DaemonCommands,
+    },
+}
+
+// ---------------------------------------------------------------------------
+// Agent subcommands
+// ---------------------------------------------------------------------------
+
+#[derive(Subcommand)]
+enum AgentCommands {
+    /// Run the curator agent
+    Curate {
+        /// Propose changes without writing to patterns.md
+        #[arg(long)]
+        dry_run: bool,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Run the reflector agent
+    Reflect {
+        #[command(subcommand)]
+        command: ReflectCommands,
+    },
+}
+
+#[derive(Subcommand)]
+enum ReflectCommands {
+    /// Run the reflector to consolidate patterns.md
+    Run {
+        /// Propose changes without writing to patterns.md
+        #[arg(long)]
+        dry_run: bool,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Accept a pending reflector rewrite
+    Accept,
+    /// Reject a pending reflector rewrite
+    Reject,
+    /// Show pending reflector output (if any)
+    Show {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+// ---------------------------------------------------------------------------
+// LLM subcommands
+// ---------------------------------------------------------------------------
+
+#[derive(Subcommand)]
+enum LlmCommands {
+    /// List the models the LLM server reports
+    Models {
+        /// Show the full raw response (or error) for diagnostics
+        #[arg(long)]
+        raw: bool,
+    },
+}
+
+// ---------------------------------------------------------------------------
+// Stats subcommands
+// ---------------------------------------------------------------------------
+
+#[derive(Subcommand)]
+enum StatsCommands {
+    /// Focus score and active time for each day of a week
+    Week {
+        /// Any date in the week to show (YYYY-MM-DD, default: today)
+        #[arg(long)]
+        start: Option<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// The app you lost the most unfocused time to on a day
+    Distraction {
+        /// Day to inspect (YYYY-MM-DD, default: today)
+        #[arg(long)]
+        date: Option<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// The stretches away from your main task that cost the most on a day
+    Distractions {
+        /// Day to inspect (YYYY-MM-DD, default: today)
+        #[arg(long)]
+        date: Option<String>,
+        /// Number of stretches to show
+        #[arg(long, default_value = "3")]
+        limit: usize,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Apps that added the most focused time, and the biggest time sinks
+    Contribution {
+        /// Day to inspect (YYYY-MM-DD, default: today)
+        #[arg(long)]
+        date: Option<String>,
+        /// Apps per list
+        #[arg(long, default_value = "5")]
+        limit: usize,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Share of a day spent on windows matching study keywords
+    OnTopic {
+        /// Day to inspect (YYYY-MM-DD, default: today)
+        #[arg(long)]
+        date: Option<String>,
+        /// Comma-separated title keywords (default: CCUBE_STUDY_KEYWORDS)
+        #[arg(long)]
+        keywords: Option<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Usage of only the given apps on a day
+    Apps {
+        /// Comma-separated app names, matched case-insensitively by substring
+        #[arg(long, required = true)]
+        apps: String,
+        /// Day to inspect (YYYY-MM-DD, default: today)
+        #[arg(long)]
+        date: Option<String>,
+        /// Also have the LLM analyse the time in these apps
+        #[arg(long)]
+        analyze: bool,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Breaks between focus blocks and how well focus recovered after them
+    Breaks {
+        /// Day to inspect (YYYY-MM-DD, default: today)
+        #[arg(long)]
+        date: Option<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Top app for each hour of a day
+    Hourly {
+        /// Day to inspect (YYYY-MM-DD, default: today)
+        #[arg(long)]
+        date: Option<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Apps that never matched a focus mode, most used first
+    Unclassified {
+        /// How many days back to look
+        #[arg(long, default_value = "7")]
+        days: i64,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Hours of the day ranked by average focus score
+    ProductiveHours {
+        /// How many days back to look
+        #[arg(long, default_value = "30")]
+        days: i64,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Suggested deep-work and admin hours from productive-hours history
+    Schedule {
+        /// How many days back to look
+        #[arg(long, default_value = "30")]
+        days: i64,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Review of a day: focus, top apps, modes, longest block, distraction
+    Review {
+        /// Day to review (YYYY-MM-DD, default: yesterday)
+        #[arg(long)]
+        date: Option<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Letter grade for a day from focus, deep work, distraction and switching
+    ReportCard {
+        /// Day to grade (YYYY-MM-DD, default: today)
+        #[arg(long)]
+        date: Option<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Compare the last hour against your usual switch rate and focus
+    Deviation {
+        /// Days of history that make up the baseline
+        #[arg(long, default_value = "14")]
+        days: i64,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// How scattered the last five minutes were (0-100)
+    Pressure {
+        /// How quickly the meter rises (0.1-5.0)
+        #[arg(long, default_value = "1.0")]
+        sensitivity: f64,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// How long you've been focused without a real break
+    Streak {
+        /// Longest unfocused stretch that doesn't end the streak, in minutes
+        #[arg(long, default_value = "2")]
+        max_break: i64,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Time per focus mode for a day
+    Modes {
+        /// Day to inspect (YYYY-MM-DD, default: today)
+        #[arg(long)]
+        date: Option<String>,
+        /// Include idle time as its own entry
+        #[arg(long)]
+        include_idle: bool,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Apps within one focus mode for a day
+    Mode {
+        /// Mode name, e.g. Coding (use "uncategorized" for Unspecified)
+        mode: String,
+        /// Day to inspect (YYYY-MM-DD, default: today)
+        #[arg(long)]
+        date: Option<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Weekly minutes per focus mode, to see habits change over time
+    ModeTrend {
+        /// Number of weeks, including this one
+        #[arg(long, default_value = "8")]
+        weeks: usize,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Per-app session count and typical session length for a day
+    Sessions {
+        /// Day to inspect (YYYY-MM-DD, default: today)
+        #[arg(long)]
+        date: Option<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Focused share of active time per day or hour
+    Trend {
+        /// Number of days to include, counting today
+        #[arg(long, default_value = "7")]
+        days: i64,
+        /// Bucket size: day or hour
+        #[arg(long, default_value = "day")]
+        bucket: String,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Stored daily focus scores (kept after raw events are pruned)
+    Daily {
+        /// Number of days to include, counting today
+        #[arg(long, default_value = "30")]
+        days: i64,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Today's focus compared with the average of recent days
+    VsAverage {
+        /// Number of past days to average over
+        #[arg(long, default_value = "30")]
+        days: i64,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Year-long activity heatmap from the daily rollups
+    Calendar {
+        /// Year to show (default: this year, up to today)
+        #[arg(long)]
+        year: Option<i32>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// How often recent nudges were rated helpful
+    Nudges {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Projected end-of-day focus score for today
+    Projection {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Estimated time lost to refocusing after app switches on a day
+    SwitchingCost {
+        /// Day to inspect (YYYY-MM-DD, default: today)
+        #[arg(long)]
+        date: Option<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Whether a video-call app has been in front long enough to count as a
+    /// meeting
+    Meeting {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Today's focused time against the daily focus target
+    Target {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Activity since you came back from your last long break today
+    SinceReturn {
+        /// Minimum break length, in minutes, that counts as being away
+        #[arg(long, default_value = "30")]
+        min_idle: i64,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Focus score over the last 5 minutes, 30 minutes and hour
+    Timeframes {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// LLM response times, cold vs warm
+    Latency {
+        /// Number of recent calls to include
+        #[arg(long, default_value = "100")]
+        limit: i64,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// LLM calls and token counts per day
+    Usage {
+        /// Number of days to include, counting today
+        #[arg(long, default_value = "7")]
+        days: i64,
+        /// Clear the counters instead of showing them
+        #[arg(long)]
+        reset: bool,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+// ---------------------------------------------------------------------------
+// Data subcommands
+// ---------------------------------------------------------------------------
+
+#[derive(Subcommand)]
+enum DataCommands {
+    /// Show recent activity events
+    Activity {
+        /// Number of hours to look back (default: 1)
+        #[arg(long, default_value = "1.0")]
+        hours: f64,
+    },
+    /// Import activity spans from another tracker (JSON array of {ts, duration_ms, app, title})
+    Ingest {
+        /// Path to the JSON file
+        file: std::path::PathBuf,
+        /// Source tag stored with each event
+        #[arg(long, default_value = "external")]
+        source: String,
+    },
+    /// Import window events from an ActivityWatch JSON export
+    ImportAw {
+        /// Path to the export file
+        file: std::path::PathBuf,
+        /// Source tag stored with each event
+        #[arg(long, default_value = ccube_core::ingest::AW_SOURCE)]
+        source: String,
+    },
+    /// Export activity as CSV for analysis in other tools
+    Export {
+        /// First day to include (YYYY-MM-DD, default: six days before --to)
+        #[arg(long)]
+        from: Option<String>,
+        /// Last day to include (YYYY-MM-DD, default: today)
+        #[arg(long)]
+        to: Option<String>,
+        /// One row per hour (active/focused minutes, switches) instead of per span
+        #[arg(long)]
+        hourly: bool,
+        /// Output file (default: stdout)
+        #[arg(long)]
+        out: Option<std::path::PathBuf>,
+    },
+    /// Show the events a detector decision was made on
+    DecisionEvents {
+        /// Decision ID
+        id: i64,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Re-infer the focus mode of all stored events with the current rules
+    BackfillModes,
+    /// Recompute stored daily focus rollups from events (e.g. after
+    /// backfill-modes); days already pruned keep their rollup
+    RecomputeDaily {
+        /// First day (YYYY-MM-DD, default: start of the retention window)
+        #[arg(long)]
+        from: Option<String>,
+        /// Last day (YYYY-MM-DD, default: today)
+        #[arg(long)]
+        to: Option<String>,
+    },
+    /// Delete events older than the retention window (default 14 days)
+    Prune,
+    /// Check the databases for corruption
+    CheckDb {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Move a damaged database aside and recreate it (daemon must be stopped)
+    RepairDb {
+        /// Database file (events.sqlite, corrections.sqlite, eval_runs.sqlite)
+        file: String,
+        /// Don't copy readable rows back from the damaged copy
+        #[arg(long)]
+        no_recover: bool,
+        /// Rebuild even if the file passes the integrity check
+        #[arg(long)]
+        force: bool,
+    },
+    /// List corrections
+    Corrections {
+        /// Show only pending corrections
+        #[arg(long)]
+        pending: bool,
+        /// Maximum number of corrections to show
+        #[arg(long, default_value = "20")]
+        limit: i64,
+    },
+    /// Show full details for a correction
+    Correction {
+        /// Correction ID
+        id: i64,
+    },
+    /// Memory file management (profile, patterns)
+    Memory {
+        #[command(subcommand)]
+        command: MemoryCommands,
+    },
+}
+
+#[derive(Subcommand)]
+enum MemoryCommands {
+    /// Show memory contents (profile, patterns, or corrections)
+    Show {
+        /// Which memory layer to display
+        target: MemoryTarget,
+    },
+    /// Open a memory file in your editor
+    Edit {
+        /// Which memory file to edit
+        target: EditTarget,
+    },
+    /// List history snapshots for a memory file
+    History {
+        /// Which memory file's history to show
+        target: EditTarget,
+    },
+    /// Restore a memory file from a history snapshot
+    Restore {
+        /// Which memory file to restore
+        target: EditTarget,
+        /// Unix timestamp of the snapshot to restore
+        timestamp: i64,
+    },
+    /// Diff two history snapshots
+    Diff {
+        /// Which memory file to diff
+        target: EditTarget,
+        /// Unix timestamp of the first (older) snapshot
+        ts1: i64,
+        /// Unix timestamp of the second (newer) snapshot
+        ts2: i64,
+    },
+}
+
+// ---------------------------------------------------------------------------
+// Daemon subcommands
+// ---------------------------------------------------------------------------
+
+#[derive(Subcommand)]
+enum DaemonCommands {
+    /// Start the daemon in the background
+    Start,
+    /// Stop the running daemon
+    Stop,
+    /// Show daemon status
+    Status,
+    /// Show daemon logs
+    Logs {
+        /// Follow the log file (like tail -f)
+        #[arg(long)]
+        follow: bool,
+        /// Filter by agent (detector, curator, reflector)
+        #[arg(long)]
+        agent: Option<String>,
+    },
+    /// Run continuous activity capture (Ctrl+C to stop)
+    Capture,
+    /// Register daemon to start on logon
+    Install,
+    /// Remove daemon autostart registration
+    Uninstall,
+}
+
+// ---------------------------------------------------------------------------
+// Dispatch
+// ---------------------------------------------------------------------------
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    dotenvy::dotenv().ok();
+
+    let cli = Cli::parse();
+
+    match cli.command {
+        // --- Top-level shortcuts (daily workflow) ---
+        Some(Commands::Detect { dry_run, json }) => {
+            let root = paths::DataRoot::resolve()?;
+            ccube_core::db::init_databases(&root.data_dir)?;
+            commands::detect::handle_detect(&root, dry_run, json).await?;
+        }
+        Some(Commands::Correct {
+            decision_id,
+            verdict,
+        }) => {
+            let root = paths::DataRoot::resolve()?;
+            ccube_core::db::init_databases(&root.data_dir)?;
+            commands::correct::handle_correct(&root, decision_id, &verdict).await?;
+        }
+        Some(Commands::Ask {
+            question,
+            date,
+            json,
+        }) => {
+            let root = paths::DataRoot::resolve()?;
+            ccube_core::db::init_databases(&root.data_dir)?;
+            commands::llm::handle_ask(&root, &question, date.as_deref(), json).await?;
+        }
+        Some(Commands::Briefing { json }) => {
+            let root = paths::DataRoot::resolve()?;
+            ccube_core::db::init_databases(&root.data_dir)?;
+            commands::detect::handle_briefing(&root, json).await?;
+        }
+        Some(Commands::Feedback { decision_id, wrong }) => {
+            let root = paths::DataRoot::resolve()?;
+            ccube_core::db::init_databases(&root.data_dir)?;
+            commands::correct::handle_feedback(&root, decision_id, !wrong).await?;
+        }
+        Some(Commands::Snooze { minutes }) => {
+            commands::detect::handle_snooze(minutes).await?;
+        }
+        Some(Commands::Active { minutes }) => {
+            let root = paths::DataRoot::resolve()?;
+            commands::activity::handle_declare_active(&root, minutes).await?;
+        }
+
+        Some(Commands::Status) => {
+            let root = paths::DataRoot::resolve()?;
+            commands::daemon::handle_status(&root).await?;
+        }
+
+        // --- Agent operations ---
+        Some(Commands::Agent { command }) => {
+            let root = paths::DataRoot::resolve()?;
+            ccube_core::db::init_databases(&root.data_dir)?;
+            match command {
+                AgentCommands::Curate { dry_run, json } => {
+                    commands::curate::handle_curate(&root, dry_run, json).await?;
+                }
+                AgentCommands::Reflect { command } => match command {
+                    ReflectCommands::Run { dry_run, json } => {
+                        commands::reflect::handle_reflect(&root, dry_run, json).await?;
+                    }
+                    ReflectCommands::Accept => {
+                        commands::reflect::handle_accept(&root).await?;
+                    }
+                    ReflectCommands::Reject => {
+                        commands::reflect::handle_reject(&root).await?;
+                    }
+                    ReflectCommands::Show { json } => {
+                        commands::reflect::handle_show_pending(&root, json).await?;
+                    }
+                },
+            }
+        }
+
+        // --- LLM diagnostics ---
+        Some(Commands::Llm { command }) => match command {
+            LlmCommands::Models { raw } => {
+                commands::llm::handle_models(raw).await?;
+            }
+        },
+
+        // --- Activity statistics ---
+        Some(Commands::Stats { command }) => {
+            let root = paths::DataRoot::resolve()?;
+            ccube_core::db::init_databases(&root.data_dir)?;
+            match command {
+                StatsCommands::Week { start, json } => {
+                    commands::stats::handle_week(&root, start.as_deref(), json).await?;
+                }
+                StatsCommands::Distraction { date, json } => {
+                    commands::stats::handle_distraction(&root, date.as_deref(), json).await?;
+                }
+                StatsCommands::Distractions { date, limit, json } => {
+                    commands::stats::handle_distractions(&root, date.as_deref(), limit, json)
+                        .await?;
+                }
+                StatsCommands::Contribution { date, limit, json } => {
+                    commands::stats::handle_contribution(&root, date.as_deref(), limit, json)
+                        .await?;
+                }
+                StatsCommands::OnTopic {
+                    date,
+                    keywords,
+                    json,
+                } => {
+                    commands::stats::handle_on_topic(
+                        &root,
+                        date.as_deref(),
+                        keywords.as_deref(),
+                        json,
+                    )
+                    .await?;
+                }
+                StatsCommands::Apps {
+                    apps,
+                    date,
+                    analyze,
+                    json,
+                } => {
+                    commands::stats::handle_apps(&root, &apps, date.as_deref(), analyze, json)
+                        .await?;
+                }
+                StatsCommands::Breaks { date, json } => {
+                    commands::stats::handle_breaks(&root, date.as_deref(), json).await?;
+                }
+                StatsCommands::Hourly { date, json } => {
+                    commands::stats::handle_hourly(&root, date.as_deref(), json).await?;
+                }
+                StatsCommands::Unclassified { days, json } => {
+                    commands::stats::handle_unclassified(&root, days, json).await?;
+                }
+                StatsCommands::ProductiveHours { days, json } => {
+                    commands::stats::handle_productive_hours(&root, days, json).await?;
+                }
+                StatsCommands::Schedule { days, json } => {
+                    commands::stats::handle_schedule(&root, days, json).await?;
+                }
+                StatsCommands::Review { date, json } => {
+                    commands::stats::handle_review(&root, date.as_deref(), json).await?;
+                }
+                StatsCommands::ReportCard { date, json } => {
+                    commands::stats::handle_report_card(&root, date.as_deref(), json).await?;
+                }
+                StatsCommands::Deviation { days, json } => {
+                    commands::stats::handle_deviation(&root, days, json).await?;
+                }
+                StatsCommands::Pressure { sensitivity, json } => {
+                    commands::stats::handle_pressure(&root, sensitivity, json).await?;
+                }
+                StatsCommands::Streak { max_break, json } => {
+                    commands::stats::handle_streak(&root, max_break, json).await?;
+                }
+                StatsCommands::Modes {
+                    date,
+                    include_idle,
+                    json,
+                } => {
+                    commands::stats::handle_modes(&root, date.as_deref(), include_idle, json)
+                        .await?;
+                }
+                StatsCommands::Mode { mode, date, json } => {
+                    commands::stats::handle_mode_detail(&root, &mode, date.as_deref(), json)
+                        .await?;
+                }
+                StatsCommands::ModeTrend { weeks, json } => {
+                    commands::stats::handle_mode_trend(&root, weeks, json).await?;
+                }
+                StatsCommands::Sessions { date, json } => {
+                    commands::stats::handle_sessions(&root, date.as_deref(), json).await?;
+                }
+                StatsCommands::Trend { days, bucket, json } => {
+                    commands::stats::handle_trend(&root, days, &bucket, json).await?;
+                }
+                StatsCommands::Daily { days, json } => {
+                    commands::stats::handle_daily(&root, days, json).await?;
+                }
+                StatsCommands::VsAverage { days, json } => {
+                    commands::stats::handle_vs_average(&root, days, json).await?;
+                }
+                StatsCommands::Calendar { year, json } => {
+                    commands::stats::handle_calendar(&root, year, json).await?;
+                }
+                StatsCommands::Nudges { json } => {
+                    commands::stats::handle_nudge_accuracy(&root, json).await?;
+                }
+                StatsCommands::Projection { json } => {
+                    commands::stats::handle_projection(&root, json).await?;
+                }
+                StatsCommands::SwitchingCost { date, json } => {
+                    commands::stats::handle_switching_cost(&root, date.as_deref(), json).await?;
+                }
+                StatsCommands::Meeting { json } => {
+                    commands::stats::handle_meeting(&root, json).await?;
+                }
+                StatsCommands::Target { json } => {
+                    commands::stats::handle_target(&root, json).await?;
+                }
+                StatsCommands::SinceReturn { min_idle, json } => {
+                    commands::stats::handle_since_return(&root, min_idle, json).await?;
+                }
+                StatsCommands::Timeframes { json } => {
+                    commands::stats::handle_timeframes(&root, json).await?;
+                }
+                StatsCommands::Latency { limit, json } => {
+                    commands::stats::handle_latency(&root, limit, json).await?;
+                }
+                StatsCommands::Usage { days, reset, json } => {
+                    commands::stats::handle_usage(&root, days, reset, json).await?;
+                }
+            }
+        }
+
+        // --- Data inspection and management ---
+        Some(Commands::Data { command }) => {
+            let root = paths::DataRoot::resolve()?;
+            // The integrity commands must work on databases too damaged to open
+            if !matches!(
+                command,
+                DataCommands::CheckDb { .. } | DataCommands::RepairDb { .. }
+            ) {
+                ccube_core::db::init_databases(&root.data_dir)?;
+            }
+            match command {
+                DataCommands::Activity { hours } => {
+                    commands::activity::handle_recent(&root, hours).await?;
+                }
+                DataCommands::Ingest { file, source } => {
+                    commands::activity::handle_ingest(&root, &file, &source).await?;
+                }
+                DataCommands::ImportAw { file, source } => {
+                    commands::activity::handle_import_aw(&root, &file, &source).await?;
+                }
+                DataCommands::Export {
+                    from,
+                    to,
+                    hourly,
+                    out,
+                } => {
+                    commands::activity::handle_export(
+                        &root,
+                        from.as_deref(),
+                        to.as_deref(),
+                        hourly,
+                        out.as_deref(),
+                    )?;
+                }
+                DataCommands::DecisionEvents { id, json } => {
+                    commands::activity::handle_decision_events(&root, id, json).await?;
+                }
+                DataCommands::BackfillModes => {
+                    commands::activity::handle_backfill_modes(&root).await?;
+                }
+                DataCommands::RecomputeDaily { from, to } => {
+                    commands::activity::handle_recompute_daily(
+                        &root,
+                        from.as_deref(),
+                        to.as_deref(),
+                    )
+                    .await?;
+                }
+                DataCommands::Prune => {
+                    commands::activity::handle_prune(&root)?;
+                }
+                DataCommands::CheckDb { json } => {
+                    commands::integrity::handle_check(&root, json)?;
+                }
+                DataCommands::RepairDb {
+                    file,
+                    no_recover,
+                    force,
+                } => {
+                    commands::integrity::handle_repair(&root, &file, no_recover, force).await?;
+                }
+                DataCommands::Corrections { pending, limit } => {
+                    commands::correct::handle_corrections_list(&root, pending, limit).await?;
+                }
+                DataCommands::Correction { id } => {
+                    commands::correct::handle_corrections_show(&root, id).await?;
+                }
+                DataCommands::Memory { command } => match command {
+                    MemoryCommands::Show { target } => {
+                        commands::memory::handle_show(&root, &target).await?;
+                    }
+                    MemoryCommands::Edit { target } => {
+                        commands::memory::handle_edit(&root, &target)?;
+                    }
+                    MemoryCommands::History { target } => {
+                        commands::memory::handle_history(&root, &target)?;
+                    }
+                    MemoryCommands::Restore { target, timestamp } => {
+                        commands::memory::handle_restore(&root, &target, timestamp)?;
+                    }
+                    MemoryCommands::Diff { target, ts1, ts2 } => {
+                        commands::memory::handle_diff(&root, &target, ts1, ts2)?;
+                    }
+                },
+            }
+        }
+
+        // --- Daemon lifecycle ---
+        Some(Commands::Daemon { command }) => {
+            let root = paths::DataRoot::resolve()?;
+            match command {
+                DaemonCommands::Start => {
+                    commands::daemon::handle_start(&root).await?;
+                }
+                DaemonCommands::Stop => {
+                    commands::daemon::handle_stop(&root).await?;
+                }
+                DaemonCommands::Status => {
+                    commands::daemon::handle_status(&root).await?;
+                }
+                DaemonCommands::Logs { follow, agent } => {
+                    commands::daemon::handle_logs(&root, follow, agent.as_deref())?;
+                }
+                DaemonCommands::Capture => {
+                    commands::capture::handle_capture_run(&root).await?;
+                }
+                DaemonCommands::Install => {
+                    commands::daemon::handle_install(&root)?;
+                }
+                DaemonCommands::Uninstall => {
+                    commands::daemon::handle_uninstall()?;
+                }
+            }
+        }
+
+        None => {
+            Cli::parse_from(["ccube", "--help"]);
+        }
+    }
+
+    Ok(())
+}
@@ -1 +1 @@
-pub use ccube_core::paths::DataRoot;
+pub use ccube_core::paths::DataRoot;
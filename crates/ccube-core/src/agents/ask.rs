@@ -0,0 +1,587 @@
+// Ask agent — answers a free-form question about one day's activity,
+// analyses the time spent in a user-picked set of apps, or drafts a daily
+// schedule from the hour-by-hour focus history.
+//
+// The LLM never sees raw events: it gets a short digest of the day's stats
+// (totals, top apps, modes, longest focus block), so the prompt stays small
+// however busy the day was. Runs on demand only (CLI/HTTP), never scheduled.
+
+use crate::llm::{LlmBackend, LlmError};
+use crate::stats::{
+    self, AppsSummary, FocusScoring, ProductiveHour, ScheduleBlock, ScheduleRecommendations,
+    ScheduleSource, Span,
+};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+/// Prompt template version.
+pub const PROMPT_VERSION: &str = "ask.v1";
+
+/// Prompt template version for the selected-apps analysis.
+pub const APPS_PROMPT_VERSION: &str = "ask_apps.v1";
+
+/// Prompt template version for the suggested schedule.
+pub const SCHEDULE_PROMPT_VERSION: &str = "ask_schedule.v1";
+
+/// Longest question accepted, in characters.
+pub const MAX_QUESTION_CHARS: usize = 500;
+
+/// Apps listed in the context, most used first.
+const CONTEXT_TOP_APPS: usize = 15;
+
+/// GBNF grammar that constrains llama.cpp to produce valid AskOutput JSON.
+pub const ASK_GRAMMAR: &str = r#"
+root ::= "{" ws
+  "\"answer\"" ws ":" ws string
+  ws "}"
+
+string ::= "\"" chars "\""
+chars ::= "" | char chars
+char ::= [^"\\] | "\\" escape
+escape ::= "\"" | "\\" | "/" | "b" | "f" | "n" | "r" | "t"
+
+ws ::= | " " | "\n" | "\r" | "\t"
+"#;
+
+/// The JSON schema description embedded in the prompt.
+const SCHEMA_DESC: &str = r#"{
+  "answer": "your answer to the question"
+}"#;
+
+/// GBNF grammar for the suggested schedule: two lists of hour ranges and a
+/// short rationale.
+pub const SCHEDULE_GRAMMAR: &str = r#"
+root ::= "{" ws
+  "\"deep_work\"" ws ":" ws block-array "," ws
+  "\"admin\"" ws ":" ws block-array "," ws
+  "\"rationale\"" ws ":" ws string
+  ws "}"
+
+block-array ::= "[]" | "[" ws block ( "," ws block )* ws "]"
+block ::= "{" ws
+  "\"start_hour\"" ws ":" ws int "," ws
+  "\"end_hour\"" ws ":" ws int
+  ws "}"
+int ::= [0-9]+
+
+string ::= "\"" chars "\""
+chars ::= "" | char chars
+char ::= [^"\\] | "\\" escape
+escape ::= "\"" | "\\" | "/" | "b" | "f" | "n" | "r" | "t"
+
+ws ::= | " " | "\n" | "\r" | "\t"
+"#;
+
+/// The schedule JSON schema description embedded in the prompt.
+const SCHEDULE_SCHEMA_DESC: &str = r#"{
+  "deep_work": [{"start_hour": 9, "end_hour": 11}],
+  "admin": [{"start_hour": 14, "end_hour": 15}],
+  "rationale": "one or two sentences on why"
+}"#;
+
+/// The LLM's answer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AskOutput {
+    pub answer: String,
+}
+
+/// The chosen apps' usage with the LLM's reading of it. `analysis` is None
+/// when the LLM couldn't be reached, leaving `summary` as the offline answer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppsAnalysis {
+    pub summary: AppsSummary,
+    pub analysis: Option<String>,
+}
+
+/// The LLM's schedule before it's checked against the data.
+#[derive(Debug, Deserialize)]
+struct ScheduleResponse {
+    deep_work: Vec<HourRange>,
+    admin: Vec<HourRange>,
+    rationale: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct HourRange {
+    start_hour: u32,
+    end_hour: u32,
+}
+
+/// Errors specific to the ask agent.
+#[derive(Debug, thiserror::Error)]
+pub enum AskError {
+    #[error("question is empty")]
+    EmptyQuestion,
+    #[error("question is too long ({0} chars, max {MAX_QUESTION_CHARS})")]
+    QuestionTooLong(usize),
+    #[error("LLM unavailable: {0}")]
+    LlmUnavailable(String),
+    #[error("failed to parse ask response: {0}")]
+    ParseFailed(String),
+}
+
+/// Trim a question and check it fits the prompt.
+pub fn validate_question(question: &str) -> Result<&str, AskError> {
+    let question = question.trim();
+    let chars = question.chars().count();
+    if chars == 0 {
+        return Err(AskError::EmptyQuestion);
+    }
+    if chars > MAX_QUESTION_CHARS {
+        return Err(AskError::QuestionTooLong(chars));
+    }
+    Ok(question)
+}
+
+/// Plain-text digest of `date`'s activity for the prompt. Bounded by the
+/// number of apps and modes, not by how many events the day had.
+pub fn build_context(date: NaiveDate, spans: &[Span], scoring: &FocusScoring) -> String {
+    let totals = scoring.totals(spans);
+    if totals.active_ms == 0 {
+        return format!("Date: {date}\nNo activity was recorded.");
+    }
+
+    let mut lines = vec![
+        format!("Date: {date}"),
+        format!(
+            "Active: {:.0} min, focused: {:.0} min, focus score: {:.0}%",
+            stats::ms_to_minutes(totals.active_ms),
+            stats::ms_to_minutes(totals.focused_ms),
+            totals.focus_score().unwrap_or(0.0)
+        ),
+        format!("App switches: {}", stats::count_switches(spans)),
+    ];
+    if let Some(block) = stats::longest_focus_block(spans, scoring) {
+        lines.push(format!("Longest focus block: {:.0} min", block.minutes));
+    }
+
+    lines.push(String::new());
+    lines.push("Top apps:".to_string());
+    for a in stats::app_totals(spans).iter().take(CONTEXT_TOP_APPS) {
+        lines.push(format!("- {}: {:.0} min", a.app, a.minutes));
+    }
+
+    lines.push(String::new());
+    lines.push("Focus modes:".to_string());
+    for m in stats::mode_totals(spans) {
+        lines.push(format!("- {}: {:.0} min", m.mode, m.minutes));
+    }
+
+    lines.join("\n")
+}
+
+/// Plain-text digest of the selected apps' usage on `date` for the prompt.
+/// Bounded like `build_context`: per-app sessions and the top titles only.
+pub fn build_apps_context(date: NaiveDate, summary: &AppsSummary) -> String {
+    let apps = summary.apps.join(", ");
+    if summary.minutes <= 0.0 {
+        return format!("Date: {date}\nSelected apps: {apps}\nNo time was recorded in them.");
+    }
+
+    let mut lines = vec![
+        format!("Date: {date}"),
+        format!("Selected apps: {apps}"),
+        format!(
+            "Time in them: {:.0} min ({:.0}% of active time), focused: {:.0} min",
+            summary.minutes,
+            summary.share.unwrap_or(0.0),
+            summary.focused_minutes
+        ),
+        format!("Switches between them: {}", summary.switches),
+    ];
+
+    lines.push(String::new());
+    lines.push("Per app:".to_string());
+    for a in &summary.sessions {
+        lines.push(format!(
+            "- {}: {:.0} min in {} sessions (median {:.0} min)",
+            a.app, a.total_minutes, a.sessions, a.median_minutes
+        ));
+    }
+
+    if !summary.top_titles.is_empty() {
+        lines.push(String::new());
+        lines.push("Top window titles:".to_string());
+        for t in &summary.top_titles {
+            lines.push(format!("- {}: {:.0} min", t.title, t.minutes));
+        }
+    }
+
+    lines.join("\n")
+}
+
+/// Render the ask prompt by substituting placeholders in the template.
+///
+/// Uses a single-pass replacement approach (same as the other agents) so that
+/// the question cannot inject placeholder names.
+pub fn render_prompt(context: &str, question: &str) -> String {
+    let template = include_str!("../prompts/ask.v1.md");
+
+    let replacements: &[(&str, &str)] = &[
+        ("{context}", context),
+        ("{question}", question),
+        ("{schema}", SCHEMA_DESC),
+    ];
+
+    super::render_template(template, replacements)
+}
+
+/// Render the selected-apps prompt. Window titles in `context` can't inject
+/// placeholders, as in `render_prompt`.
+pub fn render_apps_prompt(context: &str) -> String {
+    let template = include_str!("../prompts/ask_apps.v1.md");
+
+    let replacements: &[(&str, &str)] = &[("{context}", context), ("{schema}", SCHEMA_DESC)];
+
+    super::render_template(template, replacements)
+}
+
+/// Have the LLM analyse `summary`. An unreachable LLM isn't an error here:
+/// the result then carries the summary alone.
+pub async fn analyze_apps(
+    date: NaiveDate,
+    summary: AppsSummary,
+    llm: &dyn LlmBackend,
+) -> Result<AppsAnalysis, AskError> {
+    let prompt = render_apps_prompt(&build_apps_context(date, &summary));
+
+    let analysis = match llm.complete(&prompt, ASK_GRAMMAR, 512, 0.3).await {
+        Ok(resp) => Some(
+            serde_json::from_str::<AskOutput>(&resp.content)
+                .map_err(|e| AskError::ParseFailed(format!("{e}: {}", resp.content)))?
+                .answer,
+        ),
+        Err(LlmError::Unreachable(msg)) => {
+            tracing::warn!(error = %msg, "apps analysis: LLM unavailable, returning the summary only");
+            None
+        }
+        Err(LlmError::BadResponse(msg)) => return Err(AskError::ParseFailed(msg)),
+    };
+    Ok(AppsAnalysis { summary, analysis })
+}
+
+/// Hour-by-hour focus history for the schedule prompt, in clock order.
+pub fn build_schedule_context(hours: &[ProductiveHour]) -> String {
+    let mut sorted: Vec<&ProductiveHour> = hours.iter().collect();
+    sorted.sort_by_key(|h| h.hour);
+    sorted
+        .iter()
+        .map(|h| {
+            format!(
+                "- {:02}:00-{:02}:00: {:.0}% average focus over {} days",
+                h.hour,
+                h.hour + 1,
+                h.avg_focus_score,
+                h.samples
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Render the schedule prompt.
+pub fn render_schedule_prompt(context: &str) -> String {
+    let template = include_str!("../prompts/ask_schedule.v1.md");
+
+    let replacements: &[(&str, &str)] = &[
+        ("{context}", context),
+        ("{schema}", SCHEDULE_SCHEMA_DESC),
+    ];
+
+    super::render_template(template, replacements)
+}
+
+/// Have the LLM draw up deep-work and admin blocks from `hours`. Falls back to
+/// the rule-based `stats::schedule_recommendations` when the LLM can't be
+/// reached or its blocks don't fit the data: outside the day, over hours with
+/// no history, or overlapping each other.
+pub async fn suggest_schedule(
+    hours: &[ProductiveHour],
+    llm: &dyn LlmBackend,
+) -> ScheduleRecommendations {
+    let prompt = render_schedule_prompt(&build_schedule_context(hours));
+
+    let reason = match llm.complete(&prompt, SCHEDULE_GRAMMAR, 512, 0.3).await {
+        Ok(resp) => match serde_json::from_str::<ScheduleResponse>(&resp.content) {
+            Ok(parsed) => match checked_schedule(hours, parsed) {
+                Some(plan) => return plan,
+                None => "LLM blocks don't fit the data".to_string(),
+            },
+            Err(e) => format!("failed to parse LLM response: {e}"),
+        },
+        Err(LlmError::Unreachable(msg)) => format!("LLM unreachable: {msg}"),
+        Err(LlmError::BadResponse(msg)) => format!("LLM bad response: {msg}"),
+    };
+    tracing::warn!(reason = %reason, "schedule: using the rule-based schedule");
+    stats::schedule_recommendations(hours)
+}
+
+/// The LLM's schedule as blocks scored from `hours`, or None if any block is
+/// out of range, covers no measured hour, or overlaps another.
+fn checked_schedule(
+    hours: &[ProductiveHour],
+    response: ScheduleResponse,
+) -> Option<ScheduleRecommendations> {
+    let blocks = |ranges: &[HourRange]| -> Option<Vec<ScheduleBlock>> {
+        let mut blocks = ranges
+            .iter()
+            .map(|r| stats::schedule_block(hours, r.start_hour, r.end_hour))
+            .collect::<Option<Vec<_>>>()?;
+        blocks.sort_by_key(|b| b.start_hour);
+        Some(blocks)
+    };
+    let deep_work = blocks(&response.deep_work)?;
+    let admin = blocks(&response.admin)?;
+
+    let mut all: Vec<&ScheduleBlock> = deep_work.iter().chain(&admin).collect();
+    all.sort_by_key(|b| b.start_hour);
+    if all.windows(2).any(|pair| pair[1].start_hour < pair[0].end_hour) {
+        return None;
+    }
+
+    Some(ScheduleRecommendations {
+        deep_work,
+        admin,
+        source: ScheduleSource::Llm,
+        rationale: Some(response.rationale),
+    })
+}
+
+/// Answer `question` from `context`. An unreachable LLM is reported as
+/// `AskError::LlmUnavailable`; there is no offline fallback.
+pub async fn run(
+    question: &str,
+    context: &str,
+    llm: &dyn LlmBackend,
+) -> Result<AskOutput, AskError> {
+    let question = validate_question(question)?;
+    let prompt = render_prompt(context, question);
+
+    match llm.complete(&prompt, ASK_GRAMMAR, 512, 0.3).await {
+        Ok(resp) => serde_json::from_str::<AskOutput>(&resp.content)
+            .map_err(|e| AskError::ParseFailed(format!("{e}: {}", resp.content))),
+        Err(LlmError::Unreachable(msg)) => Err(AskError::LlmUnavailable(msg)),
+        Err(LlmError::BadResponse(msg)) => Err(AskError::ParseFailed(msg)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::LlmResponse;
+    use async_trait::async_trait;
+
+    const MIN: i64 = 60_000;
+
+    fn span(start: i64, end: i64, app: &str, mode: &str) -> Span {
+        Span {
+            start,
+            end,
+            app: app.to_string(),
+            title: None,
+            mode: mode.to_string(),
+        }
+    }
+
+    struct MockAskLlm {
+        response: String,
+    }
+
+    #[async_trait]
+    impl LlmBackend for MockAskLlm {
+        async fn complete(
+            &self,
+            _prompt: &str,
+            _grammar: &str,
+            _n_predict: u32,
+            _temperature: f32,
+        ) -> Result<LlmResponse, LlmError> {
+            Ok(LlmResponse {
+                content: self.response.clone(),
+                model: Some("test".to_string()),
+                usage: None,
+                endpoint: None,
+            })
+        }
+    }
+
+    struct FailingLlm;
+
+    #[async_trait]
+    impl LlmBackend for FailingLlm {
+        async fn complete(
+            &self,
+            _prompt: &str,
+            _grammar: &str,
+            _n_predict: u32,
+            _temperature: f32,
+        ) -> Result<LlmResponse, LlmError> {
+            Err(LlmError::Unreachable("mock down".into()))
+        }
+    }
+
+    #[test]
+    fn test_build_context() {
+        let date = NaiveDate::from_ymd_opt(2026, 3, 4).unwrap();
+        let spans = vec![
+            span(0, 40 * MIN, "Code.exe", "Coding"),
+            span(40 * MIN, 60 * MIN, "slack.exe", "Unspecified"),
+        ];
+        let context = build_context(date, &spans, &FocusScoring::Modes);
+        assert!(context.contains("Date: 2026-03-04"));
+        assert!(context.contains("Active: 60 min, focused: 40 min, focus score: 67%"));
+        assert!(context.contains("- slack.exe: 20 min"));
+        assert!(context.contains("- Coding: 40 min"));
+
+        assert!(build_context(date, &[], &FocusScoring::Modes).contains("No activity"));
+    }
+
+    #[test]
+    fn test_render_prompt_injection_safe() {
+        let prompt = render_prompt("CONTEXT", "what about {context}?");
+        assert!(prompt.contains("CONTEXT"));
+        assert!(prompt.contains("what about {context}?"));
+        assert!(!prompt.contains("{schema}"));
+    }
+
+    #[test]
+    fn test_validate_question() {
+        assert_eq!(
+            validate_question("  too much Slack?  ").unwrap(),
+            "too much Slack?"
+        );
+        assert!(matches!(
+            validate_question("   "),
+            Err(AskError::EmptyQuestion)
+        ));
+        let long = "a".repeat(MAX_QUESTION_CHARS + 1);
+        assert!(matches!(
+            validate_question(&long),
+            Err(AskError::QuestionTooLong(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_run_parses_answer() {
+        let llm = MockAskLlm {
+            response: r#"{"answer": "You spent 20 minutes in Slack."}"#.to_string(),
+        };
+        let out = run("Too much Slack?", "ctx", &llm).await.unwrap();
+        assert_eq!(out.answer, "You spent 20 minutes in Slack.");
+    }
+
+    fn apps_summary() -> AppsSummary {
+        let spans = vec![
+            Span {
+                title: Some("main.rs - ccube".to_string()),
+                ..span(0, 40 * MIN, "Code.exe", "Coding")
+            },
+            span(40 * MIN, 60 * MIN, "slack.exe", "Unspecified"),
+        ];
+        stats::apps_summary(
+            &spans,
+            &stats::parse_keywords("code"),
+            stats::DEFAULT_SESSION_MERGE_GAP_MS,
+        )
+    }
+
+    #[test]
+    fn test_build_apps_context() {
+        let date = NaiveDate::from_ymd_opt(2026, 3, 4).unwrap();
+        let context = build_apps_context(date, &apps_summary());
+        assert!(context.contains("Selected apps: code"));
+        assert!(context.contains("Time in them: 40 min (67% of active time), focused: 40 min"));
+        assert!(context.contains("- Code.exe: 40 min in 1 sessions"));
+        assert!(context.contains("- main.rs - ccube: 40 min"));
+        assert!(!context.contains("slack"));
+
+        let empty = stats::apps_summary(&[], &stats::parse_keywords("code"), MIN);
+        assert!(build_apps_context(date, &empty).contains("No time was recorded"));
+    }
+
+    #[tokio::test]
+    async fn test_analyze_apps_falls_back_to_summary() {
+        let date = NaiveDate::from_ymd_opt(2026, 3, 4).unwrap();
+        let llm = MockAskLlm {
+            response: r#"{"answer": "One long editor session."}"#.to_string(),
+        };
+        let out = analyze_apps(date, apps_summary(), &llm).await.unwrap();
+        assert_eq!(out.analysis.as_deref(), Some("One long editor session."));
+
+        let offline = analyze_apps(date, apps_summary(), &FailingLlm)
+            .await
+            .unwrap();
+        assert_eq!(offline.analysis, None);
+        assert_eq!(offline.summary, apps_summary());
+    }
+
+    fn productive_hours() -> Vec<ProductiveHour> {
+        [(9, 90.0), (10, 80.0), (11, 60.0), (14, 20.0), (16, 30.0)]
+            .into_iter()
+            .map(|(hour, avg_focus_score)| ProductiveHour {
+                hour,
+                avg_focus_score,
+                samples: 5,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_build_schedule_context() {
+        let context = build_schedule_context(&productive_hours());
+        assert!(context.starts_with("- 09:00-10:00: 90% average focus over 5 days"));
+        assert!(context.ends_with("- 16:00-17:00: 30% average focus over 5 days"));
+    }
+
+    #[tokio::test]
+    async fn test_suggest_schedule_from_llm() {
+        let llm = MockAskLlm {
+            response: r#"{"deep_work": [{"start_hour": 9, "end_hour": 12}],
+                "admin": [{"start_hour": 16, "end_hour": 17}, {"start_hour": 14, "end_hour": 15}],
+                "rationale": "Mornings are your best focus."}"#
+                .to_string(),
+        };
+        let plan = suggest_schedule(&productive_hours(), &llm).await;
+        assert_eq!(plan.source, ScheduleSource::Llm);
+        assert_eq!(
+            plan.deep_work,
+            vec![ScheduleBlock {
+                start_hour: 9,
+                end_hour: 12,
+                avg_focus_score: 76.7
+            }]
+        );
+        let admin: Vec<u32> = plan.admin.iter().map(|b| b.start_hour).collect();
+        assert_eq!(admin, [14, 16]);
+        assert_eq!(plan.rationale.as_deref(), Some("Mornings are your best focus."));
+    }
+
+    #[tokio::test]
+    async fn test_suggest_schedule_falls_back_to_rules() {
+        let rules = stats::schedule_recommendations(&productive_hours());
+
+        let offline = suggest_schedule(&productive_hours(), &FailingLlm).await;
+        assert_eq!(offline, rules);
+        assert_eq!(offline.source, ScheduleSource::Rules);
+
+        // Overlapping blocks, or a block over hours with no history, are rejected
+        for response in [
+            r#"{"deep_work": [{"start_hour": 9, "end_hour": 11}], "admin": [{"start_hour": 10, "end_hour": 12}], "rationale": ""}"#,
+            r#"{"deep_work": [{"start_hour": 19, "end_hour": 21}], "admin": [], "rationale": ""}"#,
+            r#"{"deep_work": [{"start_hour": 9, "end_hour": 25}], "admin": [], "rationale": ""}"#,
+        ] {
+            let llm = MockAskLlm {
+                response: response.to_string(),
+            };
+            assert_eq!(suggest_schedule(&productive_hours(), &llm).await, rules);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_llm_unavailable() {
+        let err = run("Too much Slack?", "ctx", &FailingLlm)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AskError::LlmUnavailable(_)));
+    }
+}
@@ -1,679 +1,727 @@
-// Detector agent — Phase 4 implementation (v1) + Phase 8 two-step pipeline (v2).
-
-use crate::briefing::{
-    AnnotatedEntry, AnnotatedTimeline, Briefing, BriefingV2, DetectorDecision,
-    DetectorOutput, DetectorV2Output,
-};
-use crate::llm::{LlmBackend, LlmError};
-
-/// Prompt template version, logged with every decision.
-pub const PROMPT_VERSION: &str = "detector.v1";
-
-/// V2 prompt version (Phase 8 two-step pipeline).
-pub const PROMPT_VERSION_V2: &str = "detector.v2";
-
-/// GBNF grammar that constrains llama.cpp to produce valid DetectorOutput JSON.
-pub const DETECTOR_GRAMMAR: &str = r#"
-root ::= "{" ws
-  "\"decision\"" ws ":" ws decision "," ws
-  "\"reasoning\"" ws ":" ws string "," ws
-  "\"nudge_style\"" ws ":" ws nullable-nudge-style "," ws
-  "\"nudge_message\"" ws ":" ws nullable-string "," ws
-  "\"vault_category\"" ws ":" ws nullable-string "," ws
-  "\"patterns_cited\"" ws ":" ws int-array
-  ws "}"
-
-decision ::= "\"nudge\"" | "\"silent\"" | "\"vault\""
-nudge-style ::= "\"gentle\"" | "\"direct\"" | "\"vault_offer\""
-nullable-nudge-style ::= nudge-style | "null"
-nullable-string ::= string | "null"
-
-int-array ::= "[]" | "[" ws int ( "," ws int )* ws "]"
-int ::= [0-9]+
-
-string ::= "\"" chars "\""
-chars ::= "" | char chars
-char ::= [^"\\] | "\\" escape
-escape ::= "\"" | "\\" | "/" | "b" | "f" | "n" | "r" | "t"
-
-ws ::= | " " | "\n" | "\r" | "\t"
-"#;
-
-/// The JSON schema description embedded in the prompt.
-const SCHEMA_DESC: &str = r#"{
-  "decision": "nudge" | "silent" | "vault",
-  "reasoning": "one sentence",
-  "nudge_style": "gentle" | "direct" | "vault_offer" | null,
-  "nudge_message": "string or null",
-  "vault_category": "string or null",
-  "patterns_cited": [line_indices]
-}"#;
-
-/// Render the detector prompt by substituting placeholders in the template.
-///
-/// Uses a single-pass replacement approach so that user-provided content
-/// (profile, patterns, titles) cannot collide with placeholder names.
-/// For example, if `profile` contains the literal text `{patterns}`, it will
-/// appear verbatim in the output rather than being replaced by patterns content.
-pub fn render_prompt(briefing: &Briefing) -> String {
-    let template = include_str!("../prompts/detector.v1.md");
-
-    let active_mode = match &briefing.active_mode {
-        Some(m) => format!("{:?}", m),
-        None => "Unspecified".to_string(),
-    };
-
-    let right_now_title = briefing.right_now.title.as_deref().unwrap_or("(no title)");
-
-    let (just_before_app, just_before_title) = match &briefing.just_before {
-        Some(s) => (s.app.as_str(), s.title.as_deref().unwrap_or("(no title)")),
-        None => ("none", "none"),
-    };
-
-    let past_hour = if briefing.past_hour.is_empty() {
-        "no activity".to_string()
-    } else {
-        briefing
-            .past_hour
-            .iter()
-            .map(|a| {
-                let mins = a.total_ms / 60_000;
-                let titles = if a.top_titles.is_empty() {
-                    "(no titles)".to_string()
-                } else {
-                    a.top_titles.join(", ")
-                };
-                format!("{} ({}m): {}", a.app, mins, titles)
-            })
-            .collect::<Vec<_>>()
-            .join("\n")
-    };
-
-    let calendar = briefing.calendar_hint.as_deref().unwrap_or("no event");
-
-    let vault_today = if briefing.vault_today.is_empty() {
-        "none".to_string()
-    } else {
-        briefing
-            .vault_today
-            .iter()
-            .map(|v| format!("[{}] {}", v.category, v.summary))
-            .collect::<Vec<_>>()
-            .join(", ")
-    };
-
-    // Build a replacement table: placeholder -> value
-    let replacements: &[(&str, &str)] = &[
-        ("{profile}", &briefing.profile_snippet),
-        ("{patterns}", &briefing.patterns_snippet),
-        ("{active_mode}", &active_mode),
-        ("{right_now.app}", &briefing.right_now.app),
-        ("{right_now.title}", right_now_title),
-        (
-            "{right_now.duration_ms}",
-            // We need an owned string but the slice borrows &str, so we
-            // handle this specially below via a pre-formatted string.
-            "",
-        ),
-        ("{just_before.app}", just_before_app),
-        ("{just_before.title}", just_before_title),
-        ("{past_hour}", &past_hour),
-        ("{calendar_hint}", calendar),
-        ("{vault_today}", &vault_today),
-        ("{schema}", SCHEMA_DESC),
-    ];
-
-    let duration_str = briefing.right_now.duration_ms.to_string();
-
-    // Single-pass scan using char_indices for UTF-8 safety.
-    // We check byte-level '{' to find placeholder candidates, then match
-    // against the remaining &str slice (which is always valid UTF-8).
-    let mut result = String::with_capacity(template.len());
-    let mut i = 0;
-    while i < template.len() {
-        if template.as_bytes()[i] == b'{' {
-            let remaining = &template[i..];
-            // Special-case duration_ms since it needs an owned string
-            if remaining.starts_with("{right_now.duration_ms}") {
-                result.push_str(&duration_str);
-                i += "{right_now.duration_ms}".len();
-                continue;
-            }
-            let mut matched = false;
-            for &(placeholder, value) in replacements {
-                if placeholder == "{right_now.duration_ms}" {
-                    continue; // handled above
-                }
-                if remaining.starts_with(placeholder) {
-                    result.push_str(value);
-                    i += placeholder.len();
-                    matched = true;
-                    break;
-                }
-            }
-            if !matched {
-                result.push('{');
-                i += 1;
-            }
-        } else {
-            // Advance by one full UTF-8 character
-            let ch = &template[i..];
-            let c = ch.chars().next().unwrap();
-            result.push(c);
-            i += c.len_utf8();
-        }
-    }
-
-    result
-}
-
-/// Run the detector: render prompt, call LLM, parse response.
-///
-/// On any failure (LLM unreachable, bad response, parse error), returns a
-/// Silent fallback decision — the detector never panics or crashes the daemon.
-pub async fn run(briefing: &Briefing, llm: &dyn LlmBackend) -> DetectorOutput {
-    let prompt = render_prompt(briefing);
-
-    match llm.complete(&prompt, DETECTOR_GRAMMAR, 512, 0.2).await {
-        Ok(resp) => match serde_json::from_str::<DetectorOutput>(&resp.content) {
-            Ok(output) => output,
-            Err(e) => {
-                tracing::warn!(error = %e, "detector: failed to parse LLM response");
-                silent_fallback("LLM response parse error")
-            }
-        },
-        Err(LlmError::Unreachable(msg)) => {
-            tracing::warn!(error = %msg, "detector: LLM unreachable");
-            silent_fallback("LLM unreachable")
-        }
-        Err(LlmError::BadResponse(msg)) => {
-            tracing::warn!(error = %msg, "detector: LLM bad response");
-            silent_fallback("LLM bad response")
-        }
-    }
-}
-
-fn silent_fallback(reason: &str) -> DetectorOutput {
-    DetectorOutput {
-        decision: DetectorDecision::Silent,
-        reasoning: reason.to_string(),
-        nudge_style: None,
-        nudge_message: None,
-        vault_category: None,
-        patterns_cited: vec![],
-    }
-}
-
-// ---------------------------------------------------------------------------
-// V2 two-step pipeline (Phase 8)
-// ---------------------------------------------------------------------------
-
-/// GBNF grammar for Step 1 annotation output.
-pub const ANNOTATION_GRAMMAR: &str = r#"
-root ::= "{" ws
-  "\"annotations\"" ws ":" ws annotation-array ( "," ws "\"rhythm_notes\"" ws ":" ws nullable-string )?
-  ws "}"
-
-annotation-array ::= "[]" | "[" ws annotation ( "," ws annotation )* ws "]"
-annotation ::= "{" ws
-  "\"event_ts\"" ws ":" ws int "," ws
-  "\"intent\"" ws ":" ws string ( "," ws "\"intent_reasoning\"" ws ":" ws nullable-string )?
-  ws "}"
-
-nullable-string ::= string | "null"
-int ::= [0-9]+
-string ::= "\"" chars "\""
-chars ::= "" | char chars
-char ::= [^"\\] | "\\" escape
-escape ::= "\"" | "\\" | "/" | "b" | "f" | "n" | "r" | "t"
-ws ::= | " " | "\n" | "\r" | "\t"
-"#;
-
-/// JSON schema description embedded in the Step 1 prompt.
-const STEP1_SCHEMA_DESC: &str = r#"{
-  "annotations": [
-    {"event_ts": <ts>, "intent": "<guess>", "intent_reasoning": "<why?>"}
-    ...
-  ],
-  "rhythm_notes": "overall rhythm pattern or null"
-}"#;
-
-/// JSON schema description embedded in the Step 2 prompt (same as v1 output).
-const STEP2_SCHEMA_DESC: &str = r#"{
-  "decision": "nudge" | "silent" | "vault",
-  "reasoning": "one sentence",
-  "nudge_style": "gentle" | "direct" | "vault_offer" | null,
-  "nudge_message": "string or null",
-  "vault_category": "string or null",
-  "patterns_cited": [line_indices]
-}"#;
-
-/// Format timeline events for the Step 1 prompt.
-fn format_timeline_events(events: &[crate::briefing::TimelineEvent]) -> String {
-    if events.is_empty() {
-        return "no activity this window".to_string();
-    }
-
-    events
-        .iter()
-        .map(|e| {
-            let ts_hms = {
-                let secs = e.ts / 1000;
-                let h = (secs / 3600) % 24;
-                let m = (secs / 60) % 60;
-                let s = secs % 60;
-                format!("{h:02}:{m:02}:{s:02}")
-            };
-            let dur_secs = e.duration_ms / 1000;
-            let ocr_line = e
-                .ocr_text
-                .as_ref()
-                .map(|t| format!(" | ocr: \"{}\"", t.replace('\n', " | ")))
-                .unwrap_or_default();
-            let url_line = e
-                .url
-                .as_ref()
-                .map(|u| format!(" | url: {}", u))
-                .unwrap_or_default();
-            let title = e.title.as_deref().unwrap_or("(no title)");
-            format!(
-                "  [{ts_hms}] {app} | {title} | {dur_secs}s | mode: {mode}{ocr_line}{url_line}",
-                app = e.app,
-                mode = e.mode,
-            )
-        })
-        .collect::<Vec<_>>()
-        .join("\n")
-}
-
-/// Render the Step 1 prompt (intent annotation).
-pub fn render_step1_prompt(briefing: &BriefingV2) -> String {
-    let template = include_str!("../prompts/detector_v2_step1.md");
-    let events_formatted = format_timeline_events(&briefing.events);
-
-    let replacements: &[(&str, &str)] = &[
-        ("{profile}", &briefing.memory.profile),
-        ("{patterns}", &briefing.memory.patterns),
-        ("{events}", &events_formatted),
-        ("{schema}", STEP1_SCHEMA_DESC),
-    ];
-
-    let switch_count = briefing.metrics.switch_count.to_string();
-    let avg_duration = briefing.metrics.avg_session_duration_ms.to_string();
-    let is_afk = if briefing.metrics.is_currently_afk {
-        "yes"
-    } else {
-        "no"
-    };
-    let transitioned_afk = if briefing.metrics.transitioned_afk_to_active {
-        "yes"
-    } else {
-        "no"
-    };
-
-    let mut result = String::with_capacity(template.len());
-    let mut i = 0;
-    while i < template.len() {
-        if template.as_bytes()[i] == b'{' {
-            let remaining = &template[i..];
-            // Handle special-cased metrics placeholders
-            if remaining.starts_with("{switch_count}") {
-                result.push_str(&switch_count);
-                i += "{switch_count}".len();
-                continue;
-            }
-            if remaining.starts_with("{avg_duration}") {
-                result.push_str(&avg_duration);
-                i += "{avg_duration}".len();
-                continue;
-            }
-            if remaining.starts_with("{is_afk}") {
-                result.push_str(is_afk);
-                i += "{is_afk}".len();
-                continue;
-            }
-            if remaining.starts_with("{transitioned_afk}") {
-                result.push_str(transitioned_afk);
-                i += "{transitioned_afk}".len();
-                continue;
-            }
-            let mut matched = false;
-            for &(placeholder, value) in replacements {
-                if remaining.starts_with(placeholder) {
-                    result.push_str(value);
-                    i += placeholder.len();
-                    matched = true;
-                    break;
-                }
-            }
-            if !matched {
-                result.push('{');
-                i += 1;
-            }
-        } else {
-            let ch = &template[i..];
-            let c = ch.chars().next().unwrap();
-            result.push(c);
-            i += c.len_utf8();
-        }
-    }
-
-    result
-}
-
-/// Format annotated events for the Step 2 prompt.
-fn format_annotated_events(events: &[crate::briefing::TimelineEvent], annotations: &[AnnotatedEntry]) -> String {
-    if events.is_empty() {
-        return "no activity this window".to_string();
-    }
-
-    events
-        .iter()
-        .map(|e| {
-            let ts_hms = {
-                let secs = e.ts / 1000;
-                let h = (secs / 3600) % 24;
-                let m = (secs / 60) % 60;
-                let s = secs % 60;
-                format!("{h:02}:{m:02}:{s:02}")
-            };
-            let dur_secs = e.duration_ms / 1000;
-            let title = e.title.as_deref().unwrap_or("(no title)");
-
-            let annotation = annotations
-                .iter()
-                .find(|a| a.event_ts == e.ts)
-                .map(|a| {
-                    let reason = a
-                        .intent_reasoning
-                        .as_deref()
-                        .map(|r| format!(" ({r})"))
-                        .unwrap_or_default();
-                    format!(" → intent: \"{}\"{}", a.intent, reason)
-                })
-                .unwrap_or_default();
-
-            format!(
-                "  [{ts_hms}] {app} | {title} | {dur_secs}s | mode: {mode}{annotation}",
-                app = e.app,
-                mode = e.mode,
-            )
-        })
-        .collect::<Vec<_>>()
-        .join("\n")
-}
-
-/// Render the Step 2 prompt (verdict).
-pub fn render_step2_prompt(
-    briefing: &BriefingV2,
-    annotations: &[AnnotatedEntry],
-    rhythm_notes: Option<&str>,
-) -> String {
-    let template = include_str!("../prompts/detector_v2_step2.md");
-    let annotated_formatted = format_annotated_events(&briefing.events, annotations);
-    let rhythm = rhythm_notes.unwrap_or("no clear rhythm pattern detected");
-
-    let replacements: &[(&str, &str)] = &[
-        ("{profile}", &briefing.memory.profile),
-        ("{patterns}", &briefing.memory.patterns),
-        ("{annotated_events}", &annotated_formatted),
-        ("{rhythm_notes}", rhythm),
-        ("{schema}", STEP2_SCHEMA_DESC),
-    ];
-
-    let switch_count = briefing.metrics.switch_count.to_string();
-    let avg_duration = briefing.metrics.avg_session_duration_ms.to_string();
-    let is_afk = if briefing.metrics.is_currently_afk {
-        "yes"
-    } else {
-        "no"
-    };
-    let transitioned_afk = if briefing.metrics.transitioned_afk_to_active {
-        "yes"
-    } else {
-        "no"
-    };
-
-    let mut result = String::with_capacity(template.len());
-    let mut i = 0;
-    while i < template.len() {
-        if template.as_bytes()[i] == b'{' {
-            let remaining = &template[i..];
-            if remaining.starts_with("{switch_count}") {
-                result.push_str(&switch_count);
-                i += "{switch_count}".len();
-                continue;
-            }
-            if remaining.starts_with("{avg_duration}") {
-                result.push_str(&avg_duration);
-                i += "{avg_duration}".len();
-                continue;
-            }
-            if remaining.starts_with("{is_afk}") {
-                result.push_str(is_afk);
-                i += "{is_afk}".len();
-                continue;
-            }
-            if remaining.starts_with("{transitioned_afk}") {
-                result.push_str(transitioned_afk);
-                i += "{transitioned_afk}".len();
-                continue;
-            }
-            let mut matched = false;
-            for &(placeholder, value) in replacements {
-                if remaining.starts_with(placeholder) {
-                    result.push_str(value);
-                    i += placeholder.len();
-                    matched = true;
-                    break;
-                }
-            }
-            if !matched {
-                result.push('{');
-                i += 1;
-            }
-        } else {
-            let ch = &template[i..];
-            let c = ch.chars().next().unwrap();
-            result.push(c);
-            i += c.len_utf8();
-        }
-    }
-
-    result
-}
-
-/// Run the v2 two-step detector pipeline.
-///
-/// Step 1: Annotate each event with inferred user intent.
-/// Step 2: Decide verdict based on annotated timeline.
-///
-/// On any LLM failure, returns a Silent fallback with empty annotations.
-pub async fn run_v2(briefing: &BriefingV2, llm: &dyn LlmBackend) -> DetectorV2Output {
-    // Step 1: Intent annotation
-    let step1_prompt = render_step1_prompt(briefing);
-
-    let (annotations, rhythm_notes) = match llm
-        .complete(&step1_prompt, ANNOTATION_GRAMMAR, 2048, 0.2)
-        .await
-    {
-        Ok(resp) => match serde_json::from_str::<AnnotatedTimeline>(&resp.content) {
-            Ok(timeline) => (timeline.annotations, timeline.rhythm_notes),
-            Err(e) => {
-                tracing::warn!(error = %e, "detector_v2: failed to parse step1 annotation");
-                return silent_fallback_v2("step1 parse error", vec![], None);
-            }
-        },
-        Err(e) => {
-            tracing::warn!(error = %e, "detector_v2: step1 LLM call failed");
-            return silent_fallback_v2("step1 LLM error", vec![], None);
-        }
-    };
-
-    // Step 2: Verdict
-    let step2_prompt = render_step2_prompt(briefing, &annotations, rhythm_notes.as_deref());
-
-    match llm.complete(&step2_prompt, DETECTOR_GRAMMAR, 512, 0.2).await {
-        Ok(resp) => match serde_json::from_str::<DetectorOutput>(&resp.content) {
-            Ok(output) => DetectorV2Output {
-                decision: output.decision,
-                reasoning: output.reasoning,
-                nudge_style: output.nudge_style,
-                nudge_message: output.nudge_message,
-                vault_category: output.vault_category,
-                patterns_cited: output.patterns_cited,
-                annotations,
-                rhythm_notes,
-            },
-            Err(e) => {
-                tracing::warn!(error = %e, "detector_v2: failed to parse step2 verdict");
-                silent_fallback_v2("step2 parse error", annotations, rhythm_notes)
-            }
-        },
-        Err(e) => {
-            tracing::warn!(error = %e, "detector_v2: step2 LLM call failed");
-            silent_fallback_v2("step2 LLM error", annotations, rhythm_notes)
-        }
-    }
-}
-
-fn silent_fallback_v2(
-    reason: &str,
-    annotations: Vec<AnnotatedEntry>,
-    rhythm_notes: Option<String>,
-) -> DetectorV2Output {
-    DetectorV2Output {
-        decision: DetectorDecision::Silent,
-        reasoning: reason.to_string(),
-        nudge_style: None,
-        nudge_message: None,
-        vault_category: None,
-        patterns_cited: vec![],
-        annotations,
-        rhythm_notes,
-    }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::briefing::{ActivitySnapshot, FocusMode, NudgeStyle};
-    use crate::llm::LlmResponse;
-    use async_trait::async_trait;
-
-    fn test_briefing() -> Briefing {
-        Briefing {
-            ts: 1000000,
-            active_mode: Some(FocusMode::Coding),
-            right_now: ActivitySnapshot {
-                app: "Code.exe".to_string(),
-                title: Some("main.rs".to_string()),
-                url: None,
-                duration_ms: 30000,
-            },
-            just_before: Some(ActivitySnapshot {
-                app: "chrome.exe".to_string(),
-                title: Some("Google".to_string()),
-                url: None,
-                duration_ms: 15000,
-            }),
-            past_hour: vec![],
-            calendar_hint: None,
-            vault_today: vec![],
-            profile_snippet: "I am a developer".to_string(),
-            patterns_snippet: "§ coding in rust is on-task".to_string(),
-            patterns_hash: "abc123".to_string(),
-        }
-    }
-
-    struct MockLlm {
-        response: Result<String, LlmError>,
-    }
-
-    #[async_trait]
-    impl LlmBackend for MockLlm {
-        async fn complete(
-            &self,
-            _prompt: &str,
-            _grammar: &str,
-            _n_predict: u32,
-            _temperature: f32,
-        ) -> Result<LlmResponse, LlmError> {
-            match &self.response {
-                Ok(content) => Ok(LlmResponse {
-                    content: content.clone(),
-                    model: Some("test-model".to_string()),
-                }),
-                Err(_) => Err(LlmError::Unreachable("mock down".into())),
-            }
-        }
-    }
-
-    #[tokio::test]
-    async fn test_happy_path_silent() {
-        let llm = MockLlm {
-            response: Ok(r#"{"decision":"silent","reasoning":"user is coding in Rust, on-task","nudge_style":null,"nudge_message":null,"vault_category":null,"patterns_cited":[0]}"#.to_string()),
-        };
-        let output = run(&test_briefing(), &llm).await;
-        assert_eq!(output.decision, DetectorDecision::Silent);
-        assert!(output.reasoning.contains("coding"));
-        assert_eq!(output.patterns_cited, vec![0]);
-    }
-
-    #[tokio::test]
-    async fn test_happy_path_nudge() {
-        let llm = MockLlm {
-            response: Ok(r#"{"decision":"nudge","reasoning":"browsing social media","nudge_style":"gentle","nudge_message":"Looks like you drifted to social media","vault_category":null,"patterns_cited":[]}"#.to_string()),
-        };
-        let output = run(&test_briefing(), &llm).await;
-        assert_eq!(output.decision, DetectorDecision::Nudge);
-        assert_eq!(output.nudge_style, Some(NudgeStyle::Gentle));
-        assert!(output.nudge_message.is_some());
-    }
-
-    #[tokio::test]
-    async fn test_llm_unreachable_returns_silent() {
-        let llm = MockLlm {
-            response: Err(LlmError::Unreachable("down".into())),
-        };
-        let output = run(&test_briefing(), &llm).await;
-        assert_eq!(output.decision, DetectorDecision::Silent);
-        assert_eq!(output.reasoning, "LLM unreachable");
-    }
-
-    #[tokio::test]
-    async fn test_malformed_json_returns_silent() {
-        let llm = MockLlm {
-            response: Ok("not valid json at all".to_string()),
-        };
-        let output = run(&test_briefing(), &llm).await;
-        assert_eq!(output.decision, DetectorDecision::Silent);
-        assert_eq!(output.reasoning, "LLM response parse error");
-    }
-
-    #[test]
-    fn test_prompt_render_no_placeholders_remain() {
-        let prompt = render_prompt(&test_briefing());
-        assert!(!prompt.contains("{profile}"));
-        assert!(!prompt.contains("{patterns}"));
-        assert!(!prompt.contains("{active_mode}"));
-        assert!(!prompt.contains("{right_now.app}"));
-        assert!(!prompt.contains("{schema}"));
-        assert!(prompt.contains("I am a developer"));
-        assert!(prompt.contains("Code.exe"));
-    }
-
-    #[test]
-    fn test_prompt_injection_safe() {
-        // Profile containing a placeholder name should NOT cause it to be
-        // substituted by a later .replace() call.
-        let mut b = test_briefing();
-        b.profile_snippet = "Profile with {patterns} placeholder".to_string();
-        b.patterns_snippet = "REAL_PATTERNS".to_string();
-        let prompt = render_prompt(&b);
-        // The literal "{patterns}" from profile should appear in the output,
-        // and the real patterns should also appear separately.
-        assert!(prompt.contains("{patterns}"));
-        assert!(prompt.contains("REAL_PATTERNS"));
-    }
-}
+// Detector agent — Phase 4 implementation (v1) + Phase 8 two-step pipeline (v2).
+
+use crate::briefing::{
+    AnnotatedEntry, AnnotatedTimeline, Briefing, BriefingV2, DetectorDecision,
+    DetectorOutput, DetectorV2Output,
+};
+use crate::db::EventRow;
+use crate::llm::{LlmBackend, LlmError};
+
+/// Prompt template version, logged with every decision.
+pub const PROMPT_VERSION: &str = "detector.v1";
+
+/// V2 prompt version (Phase 8 two-step pipeline).
+pub const PROMPT_VERSION_V2: &str = "detector.v2";
+
+/// GBNF grammar that constrains llama.cpp to produce valid DetectorOutput JSON.
+pub const DETECTOR_GRAMMAR: &str = r#"
+root ::= "{" ws
+  "\"decision\"" ws ":" ws decision "," ws
+  "\"reasoning\"" ws ":" ws string "," ws
+  "\"nudge_style\"" ws ":" ws nullable-nudge-style "," ws
+  "\"nudge_message\"" ws ":" ws nullable-string "," ws
+  "\"vault_category\"" ws ":" ws nullable-string "," ws
+  "\"patterns_cited\"" ws ":" ws int-array
+  ws "}"
+
+decision ::= "\"nudge\"" | "\"silent\"" | "\"vault\""
+nudge-style ::= "\"gentle\"" | "\"direct\"" | "\"vault_offer\""
+nullable-nudge-style ::= nudge-style | "null"
+nullable-string ::= string | "null"
+
+int-array ::= "[]" | "[" ws int ( "," ws int )* ws "]"
+int ::= [0-9]+
+
+string ::= "\"" chars "\""
+chars ::= "" | char chars
+char ::= [^"\\] | "\\" escape
+escape ::= "\"" | "\\" | "/" | "b" | "f" | "n" | "r" | "t"
+
+ws ::= | " " | "\n" | "\r" | "\t"
+"#;
+
+/// The JSON schema description embedded in the prompt.
+const SCHEMA_DESC: &str = r#"{
+  "decision": "nudge" | "silent" | "vault",
+  "reasoning": "one sentence",
+  "nudge_style": "gentle" | "direct" | "vault_offer" | null,
+  "nudge_message": "string or null",
+  "vault_category": "string or null",
+  "patterns_cited": [line_indices]
+}"#;
+
+/// Render the detector prompt by substituting placeholders in the template.
+///
+/// Uses a single-pass replacement approach so that user-provided content
+/// (profile, patterns, titles) cannot collide with placeholder names.
+/// For example, if `profile` contains the literal text `{patterns}`, it will
+/// appear verbatim in the output rather than being replaced by patterns content.
+pub fn render_prompt(briefing: &Briefing) -> String {
+    let template = include_str!("../prompts/detector.v1.md");
+
+    let active_mode = match &briefing.active_mode {
+        Some(m) => format!("{:?}", m),
+        None => "Unspecified".to_string(),
+    };
+
+    let right_now_title = briefing.right_now.title.as_deref().unwrap_or("(no title)");
+
+    let (just_before_app, just_before_title) = match &briefing.just_before {
+        Some(s) => (s.app.as_str(), s.title.as_deref().unwrap_or("(no title)")),
+        None => ("none", "none"),
+    };
+
+    let past_hour = if briefing.past_hour.is_empty() {
+        "no activity".to_string()
+    } else {
+        briefing
+            .past_hour
+            .iter()
+            .map(|a| {
+                let mins = a.total_ms / 60_000;
+                let titles = if a.top_titles.is_empty() {
+                    "(no titles)".to_string()
+                } else {
+                    a.top_titles.join(", ")
+                };
+                format!("{} ({}m): {}", a.app, mins, titles)
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    let calendar = briefing.calendar_hint.as_deref().unwrap_or("no event");
+
+    let vault_today = if briefing.vault_today.is_empty() {
+        "none".to_string()
+    } else {
+        briefing
+            .vault_today
+            .iter()
+            .map(|v| format!("[{}] {}", v.category, v.summary))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    let duration_str = briefing.right_now.duration_ms.to_string();
+
+    // Build a replacement table: placeholder -> value
+    let replacements: &[(&str, &str)] = &[
+        ("{profile}", &briefing.profile_snippet),
+        ("{patterns}", &briefing.patterns_snippet),
+        ("{active_mode}", &active_mode),
+        ("{right_now.app}", &briefing.right_now.app),
+        ("{right_now.title}", right_now_title),
+        ("{right_now.duration_ms}", &duration_str),
+        ("{just_before.app}", just_before_app),
+        ("{just_before.title}", just_before_title),
+        ("{past_hour}", &past_hour),
+        ("{calendar_hint}", calendar),
+        ("{vault_today}", &vault_today),
+        ("{schema}", SCHEMA_DESC),
+    ];
+
+    super::render_template(template, replacements)
+}
+
+/// Run the detector: render prompt, call LLM, parse response.
+///
+/// On any failure (LLM unreachable, bad response, parse error), returns a
+/// Silent fallback decision — the detector never panics or crashes the daemon.
+pub async fn run(briefing: &Briefing, llm: &dyn LlmBackend) -> DetectorOutput {
+    let prompt = render_prompt(briefing);
+
+    match llm.complete(&prompt, DETECTOR_GRAMMAR, 512, 0.2).await {
+        Ok(resp) => match serde_json::from_str::<DetectorOutput>(&resp.content) {
+            Ok(output) => output,
+            Err(e) => {
+                tracing::warn!(error = %e, "detector: failed to parse LLM response");
+                silent_fallback("LLM response parse error")
+            }
+        },
+        Err(LlmError::Unreachable(msg)) => {
+            tracing::warn!(error = %msg, "detector: LLM unreachable");
+            silent_fallback("LLM unreachable")
+        }
+        Err(LlmError::BadResponse(msg)) => {
+            tracing::warn!(error = %msg, "detector: LLM bad response");
+            silent_fallback("LLM bad response")
+        }
+    }
+}
+
+fn silent_fallback(reason: &str) -> DetectorOutput {
+    DetectorOutput {
+        decision: DetectorDecision::Silent,
+        reasoning: reason.to_string(),
+        nudge_style: None,
+        nudge_message: None,
+        vault_category: None,
+        patterns_cited: vec![],
+    }
+}
+
+// ---------------------------------------------------------------------------
+// V2 two-step pipeline (Phase 8)
+// ---------------------------------------------------------------------------
+
+/// GBNF grammar for Step 1 annotation output.
+pub const ANNOTATION_GRAMMAR: &str = r#"
+root ::= "{" ws
+  "\"annotations\"" ws ":" ws annotation-array ( "," ws "\"rhythm_notes\"" ws ":" ws nullable-string )?
+  ws "}"
+
+annotation-array ::= "[]" | "[" ws annotation ( "," ws annotation )* ws "]"
+annotation ::= "{" ws
+  "\"event_ts\"" ws ":" ws int "," ws
+  "\"intent\"" ws ":" ws string ( "," ws "\"intent_reasoning\"" ws ":" ws nullable-string )?
+  ws "}"
+
+nullable-string ::= string | "null"
+int ::= [0-9]+
+string ::= "\"" chars "\""
+chars ::= "" | char chars
+char ::= [^"\\] | "\\" escape
+escape ::= "\"" | "\\" | "/" | "b" | "f" | "n" | "r" | "t"
+ws ::= | " " | "\n" | "\r" | "\t"
+"#;
+
+/// JSON schema description embedded in the Step 1 prompt.
+const STEP1_SCHEMA_DESC: &str = r#"{
+  "annotations": [
+    {"event_ts": <ts>, "intent": "<guess>", "intent_reasoning": "<why?>"}
+    ...
+  ],
+  "rhythm_notes": "overall rhythm pattern or null"
+}"#;
+
+/// JSON schema description embedded in the Step 2 prompt (same as v1 output).
+const STEP2_SCHEMA_DESC: &str = r#"{
+  "decision": "nudge" | "silent" | "vault",
+  "reasoning": "one sentence",
+  "nudge_style": "gentle" | "direct" | "vault_offer" | null,
+  "nudge_message": "string or null",
+  "vault_category": "string or null",
+  "patterns_cited": [line_indices]
+}"#;
+
+/// Format timeline events for the Step 1 prompt.
+fn format_timeline_events(events: &[crate::briefing::TimelineEvent]) -> String {
+    if events.is_empty() {
+        return "no activity this window".to_string();
+    }
+
+    events
+        .iter()
+        .map(format_timeline_event)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// One timeline line for the Step 1 prompt.
+fn format_timeline_event(e: &crate::briefing::TimelineEvent) -> String {
+    let ts_hms = {
+        let secs = e.ts / 1000;
+        let h = (secs / 3600) % 24;
+        let m = (secs / 60) % 60;
+        let s = secs % 60;
+        format!("{h:02}:{m:02}:{s:02}")
+    };
+    let dur_secs = e.duration_ms / 1000;
+    let ocr_line = e
+        .ocr_text
+        .as_ref()
+        .map(|t| format!(" | ocr: \"{}\"", t.replace('\n', " | ")))
+        .unwrap_or_default();
+    let url_line = e
+        .url
+        .as_ref()
+        .map(|u| format!(" | url: {}", u))
+        .unwrap_or_default();
+    let title = e.title.as_deref().unwrap_or("(no title)");
+    format!(
+        "  [{ts_hms}] {app} | {title} | {dur_secs}s | mode: {mode}{ocr_line}{url_line}",
+        app = e.app,
+        mode = e.mode,
+    )
+}
+
+/// Render the Step 1 prompt (intent annotation).
+pub fn render_step1_prompt(briefing: &BriefingV2) -> String {
+    let template = include_str!("../prompts/detector_v2_step1.md");
+    let events_formatted = format_timeline_events(&briefing.events);
+    let window_minutes = briefing
+        .window_start
+        .map_or(crate::briefing::DEFAULT_WINDOW_MINUTES, |start| {
+            (briefing.ts - start) / 60_000
+        })
+        .to_string();
+
+    let switch_count = briefing.metrics.switch_count.to_string();
+    let avg_duration = briefing.metrics.avg_session_duration_ms.to_string();
+    let is_afk = if briefing.metrics.is_currently_afk {
+        "yes"
+    } else {
+        "no"
+    };
+    let transitioned_afk = if briefing.metrics.transitioned_afk_to_active {
+        "yes"
+    } else {
+        "no"
+    };
+
+    let replacements: &[(&str, &str)] = &[
+        ("{profile}", &briefing.memory.profile),
+        ("{patterns}", &briefing.memory.patterns),
+        ("{events}", &events_formatted),
+        ("{window_minutes}", &window_minutes),
+        ("{schema}", STEP1_SCHEMA_DESC),
+        ("{switch_count}", &switch_count),
+        ("{avg_duration}", &avg_duration),
+        ("{is_afk}", is_afk),
+        ("{transitioned_afk}", transitioned_afk),
+    ];
+
+    super::render_template(template, replacements)
+}
+
+/// Format annotated events for the Step 2 prompt.
+fn format_annotated_events(events: &[crate::briefing::TimelineEvent], annotations: &[AnnotatedEntry]) -> String {
+    if events.is_empty() {
+        return "no activity this window".to_string();
+    }
+
+    events
+        .iter()
+        .map(|e| {
+            let ts_hms = {
+                let secs = e.ts / 1000;
+                let h = (secs / 3600) % 24;
+                let m = (secs / 60) % 60;
+                let s = secs % 60;
+                format!("{h:02}:{m:02}:{s:02}")
+            };
+            let dur_secs = e.duration_ms / 1000;
+            let title = e.title.as_deref().unwrap_or("(no title)");
+
+            let annotation = annotations
+                .iter()
+                .find(|a| a.event_ts == e.ts)
+                .map(|a| {
+                    let reason = a
+                        .intent_reasoning
+                        .as_deref()
+                        .map(|r| format!(" ({r})"))
+                        .unwrap_or_default();
+                    format!(" → intent: \"{}\"{}", a.intent, reason)
+                })
+                .unwrap_or_default();
+
+            format!(
+                "  [{ts_hms}] {app} | {title} | {dur_secs}s | mode: {mode}{annotation}",
+                app = e.app,
+                mode = e.mode,
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Render the Step 2 prompt (verdict).
+pub fn render_step2_prompt(
+    briefing: &BriefingV2,
+    annotations: &[AnnotatedEntry],
+    rhythm_notes: Option<&str>,
+) -> String {
+    let template = include_str!("../prompts/detector_v2_step2.md");
+    let annotated_formatted = format_annotated_events(&briefing.events, annotations);
+    let rhythm = rhythm_notes.unwrap_or("no clear rhythm pattern detected");
+
+    let switch_count = briefing.metrics.switch_count.to_string();
+    let avg_duration = briefing.metrics.avg_session_duration_ms.to_string();
+    let is_afk = if briefing.metrics.is_currently_afk {
+        "yes"
+    } else {
+        "no"
+    };
+    let transitioned_afk = if briefing.metrics.transitioned_afk_to_active {
+        "yes"
+    } else {
+        "no"
+    };
+
+    let replacements: &[(&str, &str)] = &[
+        ("{profile}", &briefing.memory.profile),
+        ("{patterns}", &briefing.memory.patterns),
+        ("{annotated_events}", &annotated_formatted),
+        ("{rhythm_notes}", rhythm),
+        ("{schema}", STEP2_SCHEMA_DESC),
+        ("{switch_count}", &switch_count),
+        ("{avg_duration}", &avg_duration),
+        ("{is_afk}", is_afk),
+        ("{transitioned_afk}", transitioned_afk),
+    ];
+
+    super::render_template(template, replacements)
+}
+
+/// Read `CCUBE_MAX_PROMPT_CHARS`: a cap on the Step 1 prompt size for models
+/// with small context windows. None (no cap) when unset.
+pub fn max_prompt_chars_from_env() -> Result<Option<usize>, String> {
+    match std::env::var("CCUBE_MAX_PROMPT_CHARS") {
+        Ok(v) if !v.trim().is_empty() => match v.trim().parse::<usize>() {
+            Ok(n) if n >= 1000 => Ok(Some(n)),
+            _ => Err(format!("invalid CCUBE_MAX_PROMPT_CHARS (need >= 1000): {v}")),
+        },
+        _ => Ok(None),
+    }
+}
+
+/// Read `CCUBE_DETECTOR_MIN_ACTIVE_SECS` (0-300): scheduled runs are skipped
+/// when the briefing window had less active time than this. Returned in ms;
+/// 0 (the default) always runs.
+pub fn min_active_ms_from_env() -> Result<i64, String> {
+    match std::env::var("CCUBE_DETECTOR_MIN_ACTIVE_SECS") {
+        Ok(v) if !v.trim().is_empty() => match v.trim().parse::<i64>() {
+            Ok(n) if (0..=300).contains(&n) => Ok(n * 1000),
+            _ => Err(format!(
+                "invalid CCUBE_DETECTOR_MIN_ACTIVE_SECS (need 0-300): {v}"
+            )),
+        },
+        _ => Ok(0),
+    }
+}
+
+/// Whether [window_start, now_ms) had too little active time for a decision
+/// to mean anything. Never true when `min_active_ms` is 0.
+pub fn too_quiet(events: &[EventRow], window_start: i64, now_ms: i64, min_active_ms: i64) -> bool {
+    if min_active_ms <= 0 {
+        return false;
+    }
+    let spans = crate::stats::active_spans(events, window_start, now_ms, now_ms);
+    crate::stats::focus_totals(&spans).active_ms < min_active_ms
+}
+
+/// Shrink the briefing's timeline until the Step 1 prompt fits in `max_chars`:
+/// drop the oldest events first, then the OCR text of what's left. Returns the
+/// number of events dropped. Metrics are left as computed over the full window.
+pub fn trim_to_prompt_budget(briefing: &mut BriefingV2, max_chars: usize) -> usize {
+    let original_len = render_step1_prompt(briefing).len();
+    if original_len <= max_chars {
+        return 0;
+    }
+
+    // The timeline is one line per event joined by newlines, so the prompt
+    // shrinks by exactly the lines removed; render once and keep count
+    let mut len = original_len;
+    let mut dropped = 0;
+    for e in &briefing.events[..briefing.events.len().saturating_sub(1)] {
+        if len <= max_chars {
+            break;
+        }
+        len -= format_timeline_event(e).len() + 1;
+        dropped += 1;
+    }
+    briefing.events.drain(..dropped);
+
+    let mut ocr_cleared = false;
+    if len > max_chars {
+        for e in &mut briefing.events {
+            if e.ocr_text.is_some() {
+                let before = format_timeline_event(e).len();
+                e.ocr_text = None;
+                len -= before - format_timeline_event(e).len();
+                ocr_cleared = true;
+            }
+        }
+    }
+
+    tracing::warn!(
+        original_len,
+        trimmed_len = len,
+        max_chars,
+        dropped_events = dropped,
+        ocr_cleared,
+        "detector prompt over budget, trimmed timeline"
+    );
+    dropped
+}
+
+/// Run the v2 two-step detector pipeline.
+///
+/// Step 1: Annotate each event with inferred user intent.
+/// Step 2: Decide verdict based on annotated timeline.
+///
+/// On any LLM failure, returns a Silent fallback with empty annotations.
+pub async fn run_v2(briefing: &BriefingV2, llm: &dyn LlmBackend) -> DetectorV2Output {
+    // Step 1: Intent annotation
+    let step1_prompt = render_step1_prompt(briefing);
+
+    let (annotations, rhythm_notes) = match llm
+        .complete(&step1_prompt, ANNOTATION_GRAMMAR, 2048, 0.2)
+        .await
+    {
+        Ok(resp) => match serde_json::from_str::<AnnotatedTimeline>(&resp.content) {
+            Ok(timeline) => (timeline.annotations, timeline.rhythm_notes),
+            Err(e) => {
+                tracing::warn!(error = %e, "detector_v2: failed to parse step1 annotation");
+                return silent_fallback_v2("step1 parse error", vec![], None);
+            }
+        },
+        Err(e) => {
+            tracing::warn!(error = %e, "detector_v2: step1 LLM call failed");
+            return silent_fallback_v2("step1 LLM error", vec![], None);
+        }
+    };
+
+    // Step 2: Verdict
+    let step2_prompt = render_step2_prompt(briefing, &annotations, rhythm_notes.as_deref());
+
+    match llm.complete(&step2_prompt, DETECTOR_GRAMMAR, 512, 0.2).await {
+        Ok(resp) => match serde_json::from_str::<DetectorOutput>(&resp.content) {
+            Ok(output) => DetectorV2Output {
+                decision: output.decision,
+                reasoning: output.reasoning,
+                nudge_style: output.nudge_style,
+                nudge_message: output.nudge_message,
+                vault_category: output.vault_category,
+                patterns_cited: output.patterns_cited,
+                annotations,
+                rhythm_notes,
+            },
+            Err(e) => {
+                tracing::warn!(error = %e, "detector_v2: failed to parse step2 verdict");
+                silent_fallback_v2("step2 parse error", annotations, rhythm_notes)
+            }
+        },
+        Err(e) => {
+            tracing::warn!(error = %e, "detector_v2: step2 LLM call failed");
+            silent_fallback_v2("step2 LLM error", annotations, rhythm_notes)
+        }
+    }
+}
+
+fn silent_fallback_v2(
+    reason: &str,
+    annotations: Vec<AnnotatedEntry>,
+    rhythm_notes: Option<String>,
+) -> DetectorV2Output {
+    DetectorV2Output {
+        decision: DetectorDecision::Silent,
+        reasoning: reason.to_string(),
+        nudge_style: None,
+        nudge_message: None,
+        vault_category: None,
+        patterns_cited: vec![],
+        annotations,
+        rhythm_notes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::briefing::{ActivitySnapshot, FocusMode, NudgeStyle};
+    use crate::llm::LlmResponse;
+    use async_trait::async_trait;
+
+    fn test_briefing() -> Briefing {
+        Briefing {
+            ts: 1000000,
+            active_mode: Some(FocusMode::Coding),
+            right_now: ActivitySnapshot {
+                app: "Code.exe".to_string(),
+                title: Some("main.rs".to_string()),
+                url: None,
+                duration_ms: 30000,
+            },
+            just_before: Some(ActivitySnapshot {
+                app: "chrome.exe".to_string(),
+                title: Some("Google".to_string()),
+                url: None,
+                duration_ms: 15000,
+            }),
+            past_hour: vec![],
+            calendar_hint: None,
+            vault_today: vec![],
+            profile_snippet: "I am a developer".to_string(),
+            patterns_snippet: "§ coding in rust is on-task".to_string(),
+            patterns_hash: "abc123".to_string(),
+        }
+    }
+
+    struct MockLlm {
+        response: Result<String, LlmError>,
+    }
+
+    #[async_trait]
+    impl LlmBackend for MockLlm {
+        async fn complete(
+            &self,
+            _prompt: &str,
+            _grammar: &str,
+            _n_predict: u32,
+            _temperature: f32,
+        ) -> Result<LlmResponse, LlmError> {
+            match &self.response {
+                Ok(content) => Ok(LlmResponse {
+                    content: content.clone(),
+                    model: Some("test-model".to_string()),
+                    usage: None,
+                    endpoint: None,
+                }),
+                Err(_) => Err(LlmError::Unreachable("mock down".into())),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_happy_path_silent() {
+        let llm = MockLlm {
+            response: Ok(r#"{"decision":"silent","reasoning":"user is coding in Rust, on-task","nudge_style":null,"nudge_message":null,"vault_category":null,"patterns_cited":[0]}"#.to_string()),
+        };
+        let output = run(&test_briefing(), &llm).await;
+        assert_eq!(output.decision, DetectorDecision::Silent);
+        assert!(output.reasoning.contains("coding"));
+        assert_eq!(output.patterns_cited, vec![0]);
+    }
+
+    #[tokio::test]
+    async fn test_happy_path_nudge() {
+        let llm = MockLlm {
+            response: Ok(r#"{"decision":"nudge","reasoning":"browsing social media","nudge_style":"gentle","nudge_message":"Looks like you drifted to social media","vault_category":null,"patterns_cited":[]}"#.to_string()),
+        };
+        let output = run(&test_briefing(), &llm).await;
+        assert_eq!(output.decision, DetectorDecision::Nudge);
+        assert_eq!(output.nudge_style, Some(NudgeStyle::Gentle));
+        assert!(output.nudge_message.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_llm_unreachable_returns_silent() {
+        let llm = MockLlm {
+            response: Err(LlmError::Unreachable("down".into())),
+        };
+        let output = run(&test_briefing(), &llm).await;
+        assert_eq!(output.decision, DetectorDecision::Silent);
+        assert_eq!(output.reasoning, "LLM unreachable");
+    }
+
+    #[tokio::test]
+    async fn test_malformed_json_returns_silent() {
+        let llm = MockLlm {
+            response: Ok("not valid json at all".to_string()),
+        };
+        let output = run(&test_briefing(), &llm).await;
+        assert_eq!(output.decision, DetectorDecision::Silent);
+        assert_eq!(output.reasoning, "LLM response parse error");
+    }
+
+    #[test]
+    fn test_prompt_render_no_placeholders_remain() {
+        let prompt = render_prompt(&test_briefing());
+        assert!(!prompt.contains("{profile}"));
+        assert!(!prompt.contains("{patterns}"));
+        assert!(!prompt.contains("{active_mode}"));
+        assert!(!prompt.contains("{right_now.app}"));
+        assert!(!prompt.contains("{schema}"));
+        assert!(prompt.contains("I am a developer"));
+        assert!(prompt.contains("Code.exe"));
+    }
+
+    #[test]
+    fn test_prompt_injection_safe() {
+        // Profile containing a placeholder name should NOT cause it to be
+        // substituted by a later .replace() call.
+        let mut b = test_briefing();
+        b.profile_snippet = "Profile with {patterns} placeholder".to_string();
+        b.patterns_snippet = "REAL_PATTERNS".to_string();
+        let prompt = render_prompt(&b);
+        // The literal "{patterns}" from profile should appear in the output,
+        // and the real patterns should also appear separately.
+        assert!(prompt.contains("{patterns}"));
+        assert!(prompt.contains("REAL_PATTERNS"));
+    }
+
+    #[test]
+    fn test_too_quiet() {
+        let focus = |ts: i64, duration_ms: i64| EventRow {
+            id: ts,
+            ts,
+            kind: "app_focus".to_string(),
+            app: Some("Code.exe".to_string()),
+            title: None,
+            duration_ms: Some(duration_ms),
+            mode: Some("Coding".to_string()),
+            ocr_text: None,
+            source: None,
+        };
+        let now = 10 * 60_000;
+        let window_start = now - 5 * 60_000;
+        let events = vec![focus(now - 20_000, 20_000)];
+
+        // 20s of activity against a 60s minimum: skip
+        assert!(too_quiet(&events, window_start, now, 60_000));
+        assert!(!too_quiet(&events, window_start, now, 10_000));
+        // A minimum of 0 never skips, even with no activity
+        assert!(!too_quiet(&events, window_start, now, 0));
+        assert!(!too_quiet(&[], window_start, now, 0));
+        assert!(too_quiet(&[], window_start, now, 1000));
+    }
+
+    #[test]
+    fn test_trim_to_prompt_budget() {
+        use crate::briefing::TimelineEvent;
+
+        let mut briefing = crate::briefing::build_v2(0, &[], "profile", "patterns", &[]);
+        briefing.events = (0..50)
+            .map(|i| TimelineEvent {
+                ts: i * 1000,
+                app: format!("app{i}.exe"),
+                title: Some("x".repeat(100)),
+                ocr_text: None,
+                url: None,
+                duration_ms: 1000,
+                mode: "Unspecified".to_string(),
+            })
+            .collect();
+        let full = render_step1_prompt(&briefing).len();
+
+        // Fits already: untouched
+        assert_eq!(trim_to_prompt_budget(&mut briefing, full), 0);
+
+        let budget = full - 2000;
+        let dropped = trim_to_prompt_budget(&mut briefing, budget);
+        assert!(dropped > 0);
+        assert!(render_step1_prompt(&briefing).len() <= budget);
+        // The most recent event is kept
+        assert_eq!(briefing.events.last().unwrap().app, "app49.exe");
+        assert_eq!(briefing.events.len(), 50 - dropped);
+
+        // Still over budget with one event left: OCR text is cleared too
+        for e in &mut briefing.events {
+            e.ocr_text = Some("o".repeat(500));
+        }
+        trim_to_prompt_budget(&mut briefing, 1000);
+        assert_eq!(briefing.events.len(), 1);
+        assert!(briefing.events[0].ocr_text.is_none());
+    }
+
+    #[test]
+    fn test_step1_prompt_states_window_length() {
+        let default = crate::briefing::build_v2(3_600_000, &[], "profile", "patterns", &[]);
+        assert!(render_step1_prompt(&default).contains("(last 5 minutes)"));
+
+        let long = crate::briefing::build_v2_with_window(
+            3_600_000,
+            15 * 60_000,
+            &[],
+            "profile",
+            "patterns",
+            &[],
+        );
+        let prompt = render_step1_prompt(&long);
+        assert!(prompt.contains("(last 15 minutes)"));
+        assert!(!prompt.contains("{window_minutes}"));
+    }
+}
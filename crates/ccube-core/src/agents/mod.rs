@@ -1,3 +1,41 @@
-pub mod curator;
-pub mod detector;
-pub mod reflector;
+pub mod ask;
+pub mod curator;
+pub mod detector;
+pub mod reflector;
+
+/// Substitute `{placeholder}` names in `template` in a single pass.
+///
+/// Substituted values are never rescanned, so user-provided content (profile,
+/// patterns, titles, questions) cannot inject placeholder names: a profile
+/// containing the literal text `{patterns}` appears verbatim in the output.
+/// Placeholders are tried in table order, so list a name before any other
+/// name it starts with. Unknown `{...}` text is kept as is.
+pub(crate) fn render_template(template: &str, replacements: &[(&str, &str)]) -> String {
+    let mut result = String::with_capacity(template.len());
+    let mut i = 0;
+    while i < template.len() {
+        if template.as_bytes()[i] == b'{' {
+            let remaining = &template[i..];
+            match replacements
+                .iter()
+                .find(|(placeholder, _)| remaining.starts_with(placeholder))
+            {
+                Some(&(placeholder, value)) => {
+                    result.push_str(value);
+                    i += placeholder.len();
+                }
+                None => {
+                    result.push('{');
+                    i += 1;
+                }
+            }
+        } else {
+            // Advance by one full UTF-8 character
+            let c = template[i..].chars().next().unwrap();
+            result.push(c);
+            i += c.len_utf8();
+        }
+    }
+
+    result
+}
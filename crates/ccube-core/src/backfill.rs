@@ -0,0 +1,163 @@
+// Focus mode backfill.
+//
+// Re-runs mode inference over every stored app_focus event so history picks up
+// changes to the rules in focus_mode.rs. Works in id-ordered batches, each in
+// its own transaction, so the capture loop is never locked out for long.
+
+use anyhow::Result;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+
+use crate::db;
+use crate::focus_mode;
+
+/// Rows re-inferred per transaction.
+pub const BATCH_SIZE: i64 = 500;
+
+/// Outcome of a backfill run.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct BackfillSummary {
+    /// app_focus events examined.
+    pub scanned: u64,
+    /// Events whose stored mode changed.
+    pub updated: u64,
+    /// Distinct apps with at least one event whose mode changed.
+    #[serde(default)]
+    pub apps_updated: u64,
+    /// Per-app mode changes, most events first, so the user can see why
+    /// their stats shifted.
+    #[serde(default)]
+    pub mode_changes: Vec<ModeChange>,
+}
+
+/// Events of one app moved from one mode to another by a backfill.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModeChange {
+    pub app: String,
+    /// Previously stored mode; None when the event had none.
+    pub from: Option<String>,
+    pub to: String,
+    pub events: u64,
+}
+
+/// Re-infer the focus mode of every app_focus event, updating rows whose
+/// stored mode differs (including NULL) from the current inference.
+pub fn backfill_modes(conn: &Connection, batch_size: i64) -> Result<BackfillSummary> {
+    let mut summary = BackfillSummary::default();
+    let mut after_id = 0;
+    let mut changes: BTreeMap<(String, Option<String>, String), u64> = BTreeMap::new();
+
+    loop {
+        let batch = db::list_app_focus_after(conn, after_id, batch_size.max(1))?;
+        let Some(last) = batch.last() else { break };
+        after_id = last.id;
+
+        let tx = conn.unchecked_transaction()?;
+        for e in &batch {
+            summary.scanned += 1;
+            let inferred = focus_mode::infer_focus_mode(
+                e.app.as_deref().unwrap_or(""),
+                e.title.as_deref(),
+                None,
+            );
+            let mode = focus_mode::focus_mode_to_str(&inferred);
+            if e.mode.as_deref() != Some(mode) {
+                db::update_event_mode(&tx, e.id, mode)?;
+                summary.updated += 1;
+                if let Some(app) = e.app.as_deref() {
+                    *changes
+                        .entry((app.to_string(), e.mode.clone(), mode.to_string()))
+                        .or_default() += 1;
+                }
+            }
+        }
+        tx.commit()?;
+    }
+
+    let apps: HashSet<&str> = changes.keys().map(|(app, _, _)| app.as_str()).collect();
+    summary.apps_updated = apps.len() as u64;
+    summary.mode_changes = changes
+        .into_iter()
+        .map(|((app, from, to), events)| ModeChange {
+            app,
+            from,
+            to,
+            events,
+        })
+        .collect();
+    // Stable sort keeps ties in app order
+    summary
+        .mode_changes
+        .sort_by_key(|c| std::cmp::Reverse(c.events));
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_backfill_updates_stale_and_null_modes() {
+        let dir = TempDir::new().unwrap();
+        db::init_databases(dir.path()).unwrap();
+        let conn = db::open_events_db(dir.path()).unwrap();
+
+        // Stale mode, NULL mode, already correct, and a non-focus event
+        db::insert_event(
+            &conn,
+            1000,
+            "app_focus",
+            Some("Code.exe"),
+            Some("main.rs"),
+            Some("Unspecified"),
+        )
+        .unwrap();
+        db::insert_event(&conn, 2000, "app_focus", Some("Code.exe"), None, None).unwrap();
+        db::insert_event(
+            &conn,
+            3000,
+            "app_focus",
+            Some("Code.exe"),
+            None,
+            Some("Coding"),
+        )
+        .unwrap();
+        db::insert_event(&conn, 4000, "idle_start", None, None, None).unwrap();
+
+        // Batch size 2 forces more than one batch
+        let summary = backfill_modes(&conn, 2).unwrap();
+        assert_eq!(summary.scanned, 3);
+        assert_eq!(summary.updated, 2);
+        assert_eq!(summary.apps_updated, 1);
+        assert_eq!(
+            summary.mode_changes,
+            vec![
+                ModeChange {
+                    app: "Code.exe".to_string(),
+                    from: None,
+                    to: "Coding".to_string(),
+                    events: 1,
+                },
+                ModeChange {
+                    app: "Code.exe".to_string(),
+                    from: Some("Unspecified".to_string()),
+                    to: "Coding".to_string(),
+                    events: 1,
+                },
+            ]
+        );
+
+        let rows = db::query_recent_events(&conn, 0).unwrap();
+        assert!(
+            rows[..3]
+                .iter()
+                .all(|r| r.mode.as_deref() == Some("Coding"))
+        );
+        assert_eq!(rows[3].mode, None);
+
+        // Running again changes nothing
+        assert_eq!(backfill_modes(&conn, BATCH_SIZE).unwrap().updated, 0);
+    }
+}
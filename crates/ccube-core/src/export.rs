@@ -0,0 +1,246 @@
+// CSV export of activity data for analysis outside ccube (pandas, Excel).
+//
+// Two shapes, both one header row plus one row per record, comma-separated,
+// RFC 4180 quoting:
+//
+// spans — one row per resolved active span (idle time already removed):
+//   start_ms, end_ms    span bounds, Unix milliseconds
+//   start               span start as RFC 3339 UTC
+//   duration_s          span length in seconds (3 decimals)
+//   app                 foreground executable
+//   title               window title, empty when none was captured
+//   mode                inferred focus mode (Coding, Writing, ..., Unspecified)
+//   focused             1 if the span counts as focused work, else 0 (per
+//                       CCUBE_FOCUS_SCORING)
+//
+// hourly — one row per local clock hour in the range, including empty hours:
+//   hour_start_ms       bucket start, Unix milliseconds
+//   hour_start          bucket start as RFC 3339 UTC
+//   active_min          active minutes in the hour
+//   focused_min         focused minutes in the hour
+//   focus_score         focused share of active time (0-100), empty with no activity
+//   switches            app changes between consecutive spans
+//   apps                distinct apps used
+
+use crate::stats::{self, FocusScoring, Span};
+use chrono::TimeZone;
+use std::io::{self, Write};
+
+pub const SPANS_HEADER: &str = "start_ms,end_ms,start,duration_s,app,title,mode,focused";
+
+pub const HOURLY_HEADER: &str =
+    "hour_start_ms,hour_start,active_min,focused_min,focus_score,switches,apps";
+
+/// Spans as CSV, oldest first.
+pub fn spans_csv(spans: &[Span], scoring: &FocusScoring) -> String {
+    let mut out = Vec::new();
+    write_spans_csv(&mut out, spans, scoring).expect("writing to a Vec cannot fail");
+    String::from_utf8(out).expect("CSV is built from UTF-8 strings")
+}
+
+/// Write spans as CSV, oldest first, one row at a time. Returns the number of
+/// data rows written.
+pub fn write_spans_csv<W: Write>(
+    out: &mut W,
+    spans: &[Span],
+    scoring: &FocusScoring,
+) -> io::Result<usize> {
+    let mut sorted: Vec<&Span> = spans.iter().collect();
+    sorted.sort_by_key(|s| s.start);
+
+    writeln!(out, "{SPANS_HEADER}")?;
+    for s in &sorted {
+        let row = [
+            s.start.to_string(),
+            s.end.to_string(),
+            rfc3339(s.start),
+            format!("{:.3}", s.duration_ms() as f64 / 1000.0),
+            escape(&s.app),
+            escape(s.title.as_deref().unwrap_or("")),
+            escape(&s.mode),
+            u8::from(scoring.is_focused(s)).to_string(),
+        ];
+        writeln!(out, "{}", row.join(","))?;
+    }
+    Ok(sorted.len())
+}
+
+/// Per-hour metrics over [from_ts, to_ts) as CSV. Buckets are aligned to the
+/// local clock hour in `tz`.
+pub fn hourly_csv<Tz: TimeZone>(
+    spans: &[Span],
+    from_ts: i64,
+    to_ts: i64,
+    tz: &Tz,
+    scoring: &FocusScoring,
+) -> String {
+    let mut out = Vec::new();
+    write_hourly_csv(&mut out, spans, from_ts, to_ts, tz, scoring)
+        .expect("writing to a Vec cannot fail");
+    String::from_utf8(out).expect("CSV is built from UTF-8 strings")
+}
+
+/// Write per-hour metrics as CSV, one row at a time. Returns the number of
+/// data rows written.
+pub fn write_hourly_csv<W: Write, Tz: TimeZone>(
+    out: &mut W,
+    spans: &[Span],
+    from_ts: i64,
+    to_ts: i64,
+    tz: &Tz,
+    scoring: &FocusScoring,
+) -> io::Result<usize> {
+    writeln!(out, "{HOURLY_HEADER}")?;
+
+    let edges = stats::local_hour_edges(from_ts, to_ts, tz);
+    let buckets = stats::bucket_spans(spans, &edges);
+    for (start, hour) in edges.iter().zip(&buckets) {
+        let totals = scoring.totals(hour);
+        let apps: std::collections::HashSet<&str> = hour.iter().map(|s| s.app.as_str()).collect();
+        let row = [
+            start.to_string(),
+            rfc3339(*start),
+            format!("{:.1}", totals.active_ms as f64 / 60_000.0),
+            format!("{:.1}", totals.focused_ms as f64 / 60_000.0),
+            totals
+                .focus_score()
+                .map(|s| format!("{s:.1}"))
+                .unwrap_or_default(),
+            stats::count_switches(hour).to_string(),
+            apps.len().to_string(),
+        ];
+        writeln!(out, "{}", row.join(","))?;
+    }
+    Ok(buckets.len())
+}
+
+fn rfc3339(ms: i64) -> String {
+    chrono::DateTime::from_timestamp_millis(ms)
+        .map(|dt| dt.to_rfc3339_opts(chrono::SecondsFormat::Millis, true))
+        .unwrap_or_default()
+}
+
+/// Quote a field if it contains a delimiter, quote or line break.
+fn escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stats::HOUR_MS;
+    use chrono::{FixedOffset, Utc};
+
+    const MIN: i64 = 60_000;
+
+    fn span(start: i64, end: i64, app: &str, title: Option<&str>, mode: &str) -> Span {
+        Span {
+            start,
+            end,
+            app: app.to_string(),
+            title: title.map(str::to_string),
+            mode: mode.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_spans_csv() {
+        let spans = vec![
+            span(
+                MIN,
+                2 * MIN,
+                "chrome.exe",
+                Some("Say \"hi\", world"),
+                "Unspecified",
+            ),
+            span(0, MIN + 500, "Code.exe", None, "Coding"),
+        ];
+        let csv = spans_csv(&spans, &FocusScoring::Modes);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], SPANS_HEADER);
+        assert_eq!(
+            lines[1],
+            "0,60500,1970-01-01T00:00:00.000Z,60.500,Code.exe,,Coding,1"
+        );
+        assert_eq!(
+            lines[2],
+            "60000,120000,1970-01-01T00:01:00.000Z,60.000,chrome.exe,\"Say \"\"hi\"\", world\",Unspecified,0"
+        );
+
+        // Whitelist scoring decides the focused column
+        let whitelist = FocusScoring::Whitelist {
+            apps: stats::parse_keywords("chrome"),
+            work_hours: None,
+        };
+        let csv = spans_csv(&spans, &whitelist);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert!(lines[1].ends_with(",Coding,0"));
+        assert!(lines[2].ends_with(",Unspecified,1"));
+    }
+
+    #[test]
+    fn test_hourly_csv() {
+        let spans = vec![
+            span(0, 30 * MIN, "Code.exe", None, "Coding"),
+            span(30 * MIN, 40 * MIN, "chrome.exe", None, "Unspecified"),
+            // Crosses into the second hour
+            span(50 * MIN, 70 * MIN, "Code.exe", None, "Coding"),
+        ];
+        let csv = hourly_csv(&spans, 5 * MIN, 3 * HOUR_MS, &Utc, &FocusScoring::Modes);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[1], "0,1970-01-01T00:00:00.000Z,50.0,40.0,80.0,2,2");
+        assert_eq!(
+            lines[2],
+            "3600000,1970-01-01T01:00:00.000Z,10.0,10.0,100.0,0,1"
+        );
+        // Empty hour: blank focus score
+        assert_eq!(lines[3], "7200000,1970-01-01T02:00:00.000Z,0.0,0.0,,0,0");
+
+        // Half-hour offset: rows start on the local hour
+        let ist = FixedOffset::east_opt(5 * 3600 + 30 * 60).unwrap();
+        let csv = hourly_csv(&spans, 30 * MIN, 90 * MIN, &ist, &FocusScoring::Modes);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            lines[1],
+            "1800000,1970-01-01T00:30:00.000Z,30.0,20.0,66.7,1,2"
+        );
+    }
+
+    #[test]
+    fn test_write_csv_counts_rows() {
+        let spans = vec![
+            span(0, MIN, "Code.exe", None, "Coding"),
+            span(MIN, 2 * MIN, "chrome.exe", None, "Unspecified"),
+        ];
+        let mut out = Vec::new();
+        assert_eq!(
+            write_spans_csv(&mut out, &spans, &FocusScoring::Modes).unwrap(),
+            2
+        );
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            spans_csv(&spans, &FocusScoring::Modes)
+        );
+
+        let hours = write_hourly_csv(
+            &mut Vec::new(),
+            &spans,
+            0,
+            3 * HOUR_MS,
+            &Utc,
+            &FocusScoring::Modes,
+        )
+        .unwrap();
+        assert_eq!(hours, 3);
+        assert_eq!(
+            write_spans_csv(&mut Vec::new(), &[], &FocusScoring::Modes).unwrap(),
+            0
+        );
+    }
+}
@@ -0,0 +1,356 @@
+// External activity ingestion.
+//
+// Lets other trackers feed finished app-focus spans into events.sqlite so they
+// count alongside native capture. Each row is tagged with a source name.
+
+use anyhow::Result;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+use crate::db;
+use crate::focus_mode;
+
+/// Source tag used when the caller does not supply one.
+pub const DEFAULT_SOURCE: &str = "external";
+
+/// Source tag for events imported from an ActivityWatch export.
+pub const AW_SOURCE: &str = "activitywatch";
+
+/// ActivityWatch bucket type holding foreground window events.
+const AW_WINDOW_BUCKET_TYPE: &str = "currentwindow";
+
+/// Longest single activity span accepted (24 hours).
+const MAX_DURATION_MS: i64 = 86_400_000;
+
+/// Spans shorter than this are dropped. Off (0) by default; ActivityWatch
+/// and similar trackers emit sub-second blips while windows change, which
+/// only inflate switch and session counts, so setting about 1000 helps there.
+pub const DEFAULT_MIN_DURATION_MS: i64 = 0;
+
+/// How far into the future a timestamp may be before it's rejected (clock skew).
+const MAX_FUTURE_SKEW_MS: i64 = 60_000;
+
+/// One activity span reported by an external source.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExternalActivity {
+    pub ts: i64,
+    pub duration_ms: i64,
+    pub app: String,
+    #[serde(default)]
+    pub title: Option<String>,
+}
+
+/// Outcome of an ingest call.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct IngestSummary {
+    pub inserted: usize,
+    pub duplicates: usize,
+    /// Valid entries dropped for being shorter than the minimum duration.
+    #[serde(default)]
+    pub too_short: usize,
+    pub rejected: Vec<RejectedActivity>,
+}
+
+/// An input entry that failed validation, identified by its position in the batch.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RejectedActivity {
+    pub index: usize,
+    pub reason: String,
+}
+
+/// Check a single entry. Returns a human-readable reason on failure.
+pub fn validate(activity: &ExternalActivity, now_ms: i64) -> Result<(), String> {
+    if activity.app.trim().is_empty() {
+        return Err("app must not be empty".to_string());
+    }
+    if activity.ts <= 0 {
+        return Err("ts must be a positive millisecond timestamp".to_string());
+    }
+    if activity.ts > now_ms + MAX_FUTURE_SKEW_MS {
+        return Err("ts is in the future".to_string());
+    }
+    if activity.duration_ms <= 0 {
+        return Err("duration_ms must be positive".to_string());
+    }
+    if activity.duration_ms > MAX_DURATION_MS {
+        return Err("duration_ms exceeds 24 hours".to_string());
+    }
+    Ok(())
+}
+
+/// Validate a source tag (non-empty, short, no whitespace).
+pub fn validate_source(source: &str) -> Result<(), String> {
+    if source.is_empty() || source.len() > 64 || source.chars().any(char::is_whitespace) {
+        return Err("source must be 1-64 characters with no whitespace".to_string());
+    }
+    Ok(())
+}
+
+/// Read `CCUBE_MIN_ACTIVITY_MS`: the shortest span that is kept, on ingest
+/// and in `stats::active_spans` (0-60000). DEFAULT_MIN_DURATION_MS (keep
+/// everything) when unset.
+pub fn min_duration_ms_from_env() -> Result<i64, String> {
+    match std::env::var("CCUBE_MIN_ACTIVITY_MS") {
+        Ok(v) if !v.trim().is_empty() => match v.trim().parse::<i64>() {
+            Ok(n) if (0..=60_000).contains(&n) => Ok(n),
+            _ => Err(format!("invalid CCUBE_MIN_ACTIVITY_MS (need 0-60000): {v}")),
+        },
+        _ => Ok(DEFAULT_MIN_DURATION_MS),
+    }
+}
+
+/// Store a batch of external activities as app_focus events.
+///
+/// Invalid entries are reported in `rejected` and skipped; valid entries shorter
+/// than `min_duration_ms` are counted in `too_short` and skipped; entries that
+/// match an existing app_focus event (same ts and app) are counted as duplicates
+/// and skipped. The focus mode is inferred the same way as for native capture.
+pub fn ingest(
+    conn: &Connection,
+    activities: &[ExternalActivity],
+    source: &str,
+    min_duration_ms: i64,
+    now_ms: i64,
+) -> Result<IngestSummary> {
+    let mut summary = IngestSummary::default();
+    let tx = conn.unchecked_transaction()?;
+
+    for (index, activity) in activities.iter().enumerate() {
+        if let Err(reason) = validate(activity, now_ms) {
+            summary.rejected.push(RejectedActivity { index, reason });
+            continue;
+        }
+        if activity.duration_ms < min_duration_ms {
+            summary.too_short += 1;
+            continue;
+        }
+
+        let app = activity.app.trim();
+        if db::app_focus_exists(&tx, activity.ts, app)? {
+            summary.duplicates += 1;
+            continue;
+        }
+
+        let title = activity.title.as_deref().filter(|t| !t.is_empty());
+        let mode = focus_mode::infer_focus_mode(app, title, None);
+        db::insert_external_event(
+            &tx,
+            activity.ts,
+            app,
+            title,
+            activity.duration_ms,
+            Some(focus_mode::focus_mode_to_str(&mode)),
+            source,
+        )?;
+        summary.inserted += 1;
+    }
+
+    tx.commit()?;
+    Ok(summary)
+}
+
+// ---------------------------------------------------------------------------
+// ActivityWatch export import
+// ---------------------------------------------------------------------------
+
+#[derive(Deserialize)]
+struct AwExport {
+    buckets: std::collections::HashMap<String, AwBucket>,
+}
+
+#[derive(Deserialize)]
+struct AwBucket {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    events: Vec<AwEvent>,
+}
+
+#[derive(Deserialize)]
+struct AwEvent {
+    timestamp: String,
+    /// Seconds
+    duration: f64,
+    #[serde(default)]
+    data: serde_json::Value,
+}
+
+/// Window events parsed from an ActivityWatch export, oldest first.
+#[derive(Debug)]
+pub struct AwImport {
+    pub activities: Vec<ExternalActivity>,
+    /// Events dropped while parsing: zero-length heartbeats, missing app, or an
+    /// unparseable timestamp.
+    pub skipped: usize,
+}
+
+/// Parse an ActivityWatch JSON export (`{"buckets": {...}}`, as written by the
+/// web UI's export or `GET /api/0/export`). Only window buckets are read; AFK
+/// and web buckets are ignored.
+pub fn parse_aw_export(raw: &str) -> Result<AwImport, String> {
+    let export: AwExport = serde_json::from_str(raw)
+        .map_err(|e| format!("not an ActivityWatch export (expected {{\"buckets\": ...}}): {e}"))?;
+
+    let windows: Vec<&AwBucket> = export
+        .buckets
+        .values()
+        .filter(|b| b.kind == AW_WINDOW_BUCKET_TYPE)
+        .collect();
+    if windows.is_empty() {
+        return Err(format!("export has no '{AW_WINDOW_BUCKET_TYPE}' buckets"));
+    }
+
+    let mut activities = Vec::new();
+    let mut skipped = 0;
+    for event in windows.iter().flat_map(|b| &b.events) {
+        let ts = chrono::DateTime::parse_from_rfc3339(&event.timestamp)
+            .map(|dt| dt.timestamp_millis())
+            .ok();
+        let app = event
+            .data
+            .get("app")
+            .and_then(|v| v.as_str())
+            .map(str::trim);
+        let duration_ms = (event.duration * 1000.0).round() as i64;
+        match (ts, app) {
+            (Some(ts), Some(app)) if !app.is_empty() && duration_ms > 0 => {
+                activities.push(ExternalActivity {
+                    ts,
+                    duration_ms,
+                    app: app.to_string(),
+                    title: event
+                        .data
+                        .get("title")
+                        .and_then(|v| v.as_str())
+                        .map(str::to_string),
+                });
+            }
+            _ => skipped += 1,
+        }
+    }
+    activities.sort_by_key(|a| a.ts);
+
+    Ok(AwImport {
+        activities,
+        skipped,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn activity(ts: i64, duration_ms: i64, app: &str, title: Option<&str>) -> ExternalActivity {
+        ExternalActivity {
+            ts,
+            duration_ms,
+            app: app.to_string(),
+            title: title.map(|t| t.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_validate_rejects_bad_entries() {
+        let now = 1_000_000;
+        assert!(validate(&activity(1000, 5000, "Anki", None), now).is_ok());
+        assert!(validate(&activity(1000, 5000, "  ", None), now).is_err());
+        assert!(validate(&activity(0, 5000, "Anki", None), now).is_err());
+        assert!(validate(&activity(now + 120_000, 5000, "Anki", None), now).is_err());
+        assert!(validate(&activity(1000, 0, "Anki", None), now).is_err());
+        assert!(validate(&activity(1000, -5, "Anki", None), now).is_err());
+        assert!(validate(&activity(1000, MAX_DURATION_MS + 1, "Anki", None), now).is_err());
+    }
+
+    #[test]
+    fn test_validate_source() {
+        assert!(validate_source("anki-sync").is_ok());
+        assert!(validate_source("").is_err());
+        assert!(validate_source("has space").is_err());
+        assert!(validate_source(&"x".repeat(65)).is_err());
+    }
+
+    #[test]
+    fn test_ingest_inserts_dedupes_and_rejects() {
+        let dir = TempDir::new().unwrap();
+        db::init_databases(dir.path()).unwrap();
+        let conn = db::open_events_db(dir.path()).unwrap();
+
+        let batch = vec![
+            activity(1000, 60_000, "Code.exe", Some("main.rs")),
+            activity(61_000, 30_000, "Anki", None),
+            activity(91_000, 0, "Anki", None), // rejected
+        ];
+        let summary = ingest(&conn, &batch, "tracker", 0, 1_000_000).unwrap();
+        assert_eq!(summary.inserted, 2);
+        assert_eq!(summary.duplicates, 0);
+        assert_eq!(summary.rejected.len(), 1);
+        assert_eq!(summary.rejected[0].index, 2);
+
+        // Re-sending the same batch inserts nothing new
+        let again = ingest(&conn, &batch[..2], "tracker", 0, 1_000_000).unwrap();
+        assert_eq!(again.inserted, 0);
+        assert_eq!(again.duplicates, 2);
+
+        let rows = db::query_recent_events(&conn, 0).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].source.as_deref(), Some("tracker"));
+        assert_eq!(rows[0].mode.as_deref(), Some("Coding"));
+        assert_eq!(rows[1].duration_ms, Some(30_000));
+    }
+
+    #[test]
+    fn test_ingest_drops_short_spans() {
+        let dir = TempDir::new().unwrap();
+        db::init_databases(dir.path()).unwrap();
+        let conn = db::open_events_db(dir.path()).unwrap();
+
+        let batch = vec![
+            activity(1000, 60_000, "Code.exe", None),
+            activity(61_000, 400, "explorer.exe", None), // too short
+            activity(61_400, 0, "explorer.exe", None),   // rejected
+            activity(61_400, -20, "chrome.exe", None),   // rejected
+            activity(61_400, 1000, "chrome.exe", None),
+        ];
+        let summary = ingest(&conn, &batch, "tracker", 1000, 1_000_000).unwrap();
+        assert_eq!(summary.inserted, 2);
+        assert_eq!(summary.too_short, 1);
+        assert_eq!(summary.rejected.len(), 2);
+
+        let rows = db::query_recent_events(&conn, 0).unwrap();
+        let apps: Vec<&str> = rows.iter().filter_map(|r| r.app.as_deref()).collect();
+        assert_eq!(apps, ["Code.exe", "chrome.exe"]);
+    }
+
+    #[test]
+    fn test_parse_aw_export() {
+        let raw = r#"{"buckets": {
+            "aw-watcher-window_host": {
+                "id": "aw-watcher-window_host", "type": "currentwindow", "hostname": "host",
+                "events": [
+                    {"timestamp": "2026-10-14T09:05:00.000000+00:00", "duration": 90.5,
+                     "data": {"app": "Code.exe", "title": "main.rs"}},
+                    {"timestamp": "2026-10-14T09:00:00Z", "duration": 300,
+                     "data": {"app": "chrome.exe", "title": "Docs"}},
+                    {"timestamp": "2026-10-14T09:06:30Z", "duration": 0,
+                     "data": {"app": "Code.exe", "title": "main.rs"}}
+                ]
+            },
+            "aw-watcher-afk_host": {
+                "id": "aw-watcher-afk_host", "type": "afkstatus",
+                "events": [{"timestamp": "2026-10-14T09:00:00Z", "duration": 600,
+                            "data": {"status": "not-afk"}}]
+            }
+        }}"#;
+        let import = parse_aw_export(raw).unwrap();
+        assert_eq!(import.skipped, 1);
+        assert_eq!(import.activities.len(), 2);
+        assert_eq!(import.activities[0].app, "chrome.exe");
+        assert_eq!(import.activities[0].ts, 1_791_968_400_000);
+        assert_eq!(import.activities[1].duration_ms, 90_500);
+        assert_eq!(import.activities[1].title.as_deref(), Some("main.rs"));
+
+        assert!(parse_aw_export(r#"{"buckets": {}}"#).is_err());
+        assert!(parse_aw_export("[]").is_err());
+    }
+}
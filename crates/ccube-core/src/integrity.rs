@@ -0,0 +1,340 @@
+// Database integrity check and repair.
+//
+// Runs `PRAGMA integrity_check` over each ccube database, and repairs a damaged
+// one by moving it aside and recreating a fresh schema, optionally copying back
+// whatever rows are still readable from the damaged copy.
+
+use anyhow::{Context, Result};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::db;
+
+/// Database files managed by `db::init_databases`.
+pub const DB_FILES: [&str; 3] = ["events.sqlite", "corrections.sqlite", "eval_runs.sqlite"];
+
+/// Integrity check result for one database file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DbIntegrity {
+    pub file: String,
+    pub ok: bool,
+    /// "ok", the problems SQLite reported, or why the check couldn't run.
+    pub detail: String,
+}
+
+/// Outcome of repairing one database file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepairOutcome {
+    pub file: String,
+    /// Where the damaged file was moved.
+    pub backup: PathBuf,
+    /// Rows copied back per table (empty when recovery wasn't requested).
+    pub recovered: Vec<TableRecovery>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableRecovery {
+    pub table: String,
+    pub rows: u64,
+    /// Set when the table couldn't be read from the damaged copy.
+    pub error: Option<String>,
+}
+
+/// Run `PRAGMA integrity_check` on every database file that exists.
+pub fn check_all(data_dir: &Path) -> Vec<DbIntegrity> {
+    DB_FILES
+        .iter()
+        .filter(|f| data_dir.join(f).exists())
+        .map(|f| check_file(&data_dir.join(f)))
+        .collect()
+}
+
+/// Run `PRAGMA integrity_check` on a single database file.
+pub fn check_file(path: &Path) -> DbIntegrity {
+    let file = path
+        .file_name()
+        .map(|f| f.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    match run_integrity_check(path) {
+        Ok(problems) if problems == ["ok"] => DbIntegrity {
+            file,
+            ok: true,
+            detail: "ok".to_string(),
+        },
+        Ok(problems) => DbIntegrity {
+            file,
+            ok: false,
+            detail: problems.join("; "),
+        },
+        Err(e) => DbIntegrity {
+            file,
+            ok: false,
+            detail: e.to_string(),
+        },
+    }
+}
+
+fn run_integrity_check(path: &Path) -> Result<Vec<String>> {
+    let conn = Connection::open(path)?;
+    let mut stmt = conn.prepare("PRAGMA integrity_check")?;
+    let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+    let mut results = Vec::new();
+    for row in rows {
+        results.push(row?);
+    }
+    Ok(results)
+}
+
+/// Move `file` (and its WAL/SHM sidecars) aside as `<file>.corrupt-<ts_ms>`,
+/// recreate the schema, and with `recover` copy readable rows back from the
+/// moved copy. Refuses a file that passes the integrity check unless `force`.
+/// If recreating or recovering fails, the original files are put back. Must
+/// not run while the daemon has the database open.
+pub fn repair(
+    data_dir: &Path,
+    file: &str,
+    recover: bool,
+    force: bool,
+    ts_ms: i64,
+) -> Result<RepairOutcome> {
+    if !DB_FILES.contains(&file) {
+        anyhow::bail!("unknown database '{file}' (expected one of {})", DB_FILES.join(", "));
+    }
+    let path = data_dir.join(file);
+    let backup = data_dir.join(format!("{file}.corrupt-{ts_ms}"));
+
+    if path.exists() && !force && check_file(&path).ok {
+        anyhow::bail!("{file} passes the integrity check; use --force to rebuild it anyway");
+    }
+
+    tracing::info!(file, backup = %backup.display(), "moving damaged database aside");
+    let mut moved = Vec::new();
+    // A stale WAL would otherwise be replayed into the fresh database
+    for suffix in ["", "-wal", "-shm"] {
+        let from = data_dir.join(format!("{file}{suffix}"));
+        if !from.exists() {
+            continue;
+        }
+        let to = data_dir.join(format!("{file}.corrupt-{ts_ms}{suffix}"));
+        if let Err(e) = std::fs::rename(&from, &to) {
+            restore(data_dir, file, &moved);
+            return Err(e).with_context(|| format!("failed to move {} aside", from.display()));
+        }
+        moved.push((from, to));
+    }
+
+    match rebuild(data_dir, file, &backup, recover) {
+        Ok(recovered) => Ok(RepairOutcome {
+            file: file.to_string(),
+            backup,
+            recovered,
+        }),
+        Err(e) => {
+            restore(data_dir, file, &moved);
+            Err(e.context(format!(
+                "repair of {file} failed; the original was put back"
+            )))
+        }
+    }
+}
+
+/// Recreate the schema for `file` and, with `recover`, copy readable rows
+/// back from `backup`.
+fn rebuild(
+    data_dir: &Path,
+    file: &str,
+    backup: &Path,
+    recover: bool,
+) -> Result<Vec<TableRecovery>> {
+    tracing::info!(file, "recreating schema");
+    db::init_databases(data_dir)?;
+
+    let mut recovered = Vec::new();
+    if recover && backup.exists() {
+        let conn = Connection::open(data_dir.join(file))?;
+        conn.execute(
+            "ATTACH DATABASE ?1 AS damaged",
+            [backup.to_string_lossy().as_ref()],
+        )?;
+        for table in plain_tables(&conn)? {
+            let result = copy_table(&conn, &table);
+            match &result {
+                Ok(rows) => tracing::info!(file, table, rows, "recovered rows"),
+                Err(e) => tracing::warn!(file, table, error = %e, "table not recoverable"),
+            }
+            recovered.push(TableRecovery {
+                table,
+                rows: *result.as_ref().unwrap_or(&0),
+                error: result.err().map(|e| e.to_string()),
+            });
+        }
+        conn.execute("DETACH DATABASE damaged", [])?;
+    }
+    Ok(recovered)
+}
+
+/// Undo a partial repair: drop whatever was created in place of `file` and
+/// move the originals back. Failures are logged; the backups stay on disk.
+fn restore(data_dir: &Path, file: &str, moved: &[(PathBuf, PathBuf)]) {
+    for suffix in ["", "-wal", "-shm"] {
+        let fresh = data_dir.join(format!("{file}{suffix}"));
+        if fresh.exists()
+            && let Err(e) = std::fs::remove_file(&fresh)
+        {
+            tracing::error!(path = %fresh.display(), error = %e, "failed to remove partial database");
+        }
+    }
+    for (from, to) in moved {
+        if let Err(e) = std::fs::rename(to, from) {
+            tracing::error!(backup = %to.display(), error = %e, "failed to put database back");
+        }
+    }
+}
+
+/// Ordinary tables in the fresh schema (FTS tables are rebuilt by triggers).
+fn plain_tables(conn: &Connection) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare(
+        "SELECT name FROM main.sqlite_master
+         WHERE type = 'table' AND name NOT LIKE 'sqlite_%' AND name NOT LIKE '%_fts%'
+         ORDER BY name",
+    )?;
+    let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+    let mut tables = Vec::new();
+    for row in rows {
+        tables.push(row?);
+    }
+    Ok(tables)
+}
+
+/// Copy the columns both copies share from damaged.<table> into main.<table>.
+fn copy_table(conn: &Connection, table: &str) -> Result<u64> {
+    let new_cols = table_columns(conn, "main", table)?;
+    let old_cols = table_columns(conn, "damaged", table)?;
+    let shared: Vec<&String> = new_cols.iter().filter(|c| old_cols.contains(c)).collect();
+    if shared.is_empty() {
+        return Ok(0);
+    }
+    let cols = shared
+        .iter()
+        .map(|c| format!("\"{c}\""))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let rows = conn.execute(
+        &format!("INSERT OR IGNORE INTO main.\"{table}\" ({cols}) SELECT {cols} FROM damaged.\"{table}\""),
+        [],
+    )?;
+    Ok(rows as u64)
+}
+
+fn table_columns(conn: &Connection, schema: &str, table: &str) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare(&format!("PRAGMA {schema}.table_info(\"{table}\")"))?;
+    let rows = stmt.query_map([], |row| row.get::<_, String>(1))?;
+    let mut cols = Vec::new();
+    for row in rows {
+        cols.push(row?);
+    }
+    Ok(cols)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_check_detects_garbage_file() {
+        let dir = TempDir::new().unwrap();
+        db::init_databases(dir.path()).unwrap();
+        assert!(check_all(dir.path()).iter().all(|r| r.ok));
+
+        std::fs::write(dir.path().join("events.sqlite"), b"definitely not sqlite").unwrap();
+        let _ = std::fs::remove_file(dir.path().join("events.sqlite-wal"));
+        let _ = std::fs::remove_file(dir.path().join("events.sqlite-shm"));
+        let results = check_all(dir.path());
+        let events = results.iter().find(|r| r.file == "events.sqlite").unwrap();
+        assert!(!events.ok);
+    }
+
+    #[test]
+    fn test_repair_recreates_and_recovers_rows() {
+        let dir = TempDir::new().unwrap();
+        db::init_databases(dir.path()).unwrap();
+        {
+            let conn = db::open_corrections_db(dir.path()).unwrap();
+            conn.execute(
+                "INSERT INTO corrections (ts, decision_id, original_decision, user_verdict,
+                    ctx_snapshot, patterns_hash) VALUES (1, 7, 'Nudge', 'was fine', '{}', 'h')",
+                [],
+            )
+            .unwrap();
+        }
+
+        // A healthy file is only rebuilt when forced
+        assert!(repair(dir.path(), "corrections.sqlite", true, false, 41).is_err());
+        assert!(!dir.path().join("corrections.sqlite.corrupt-41").exists());
+
+        let outcome = repair(dir.path(), "corrections.sqlite", true, true, 42).unwrap();
+        assert!(outcome.backup.ends_with("corrections.sqlite.corrupt-42"));
+        assert!(outcome.backup.exists());
+        let corrections = outcome
+            .recovered
+            .iter()
+            .find(|t| t.table == "corrections")
+            .unwrap();
+        assert_eq!(corrections.rows, 1);
+
+        let conn = db::open_corrections_db(dir.path()).unwrap();
+        let rows = db::list_corrections(&conn, 10, false).unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].user_verdict, "was fine");
+        assert!(check_file(&dir.path().join("corrections.sqlite")).ok);
+    }
+
+    #[test]
+    fn test_repair_rejects_unknown_file() {
+        let dir = TempDir::new().unwrap();
+        assert!(repair(dir.path(), "../etc/passwd", false, true, 1).is_err());
+    }
+
+    #[test]
+    fn test_repair_recovers_tables_from_damaged_file() {
+        let dir = TempDir::new().unwrap();
+        db::init_databases(dir.path()).unwrap();
+        {
+            let conn = db::open_events_db(dir.path()).unwrap();
+            db::insert_decision(
+                &conn, 5, "timer", "silent", "ok", None, None, "{}", "h", "v1", 10,
+            )
+            .unwrap();
+            // Enough rows to push llm_calls onto pages at the end of the file
+            for i in 0..2000 {
+                db::insert_llm_call(&conn, i, "detector", 100, false, true).unwrap();
+            }
+            conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE)")
+                .unwrap();
+        }
+
+        // Scribble over the last two pages
+        let path = dir.path().join("events.sqlite");
+        let mut bytes = std::fs::read(&path).unwrap();
+        let len = bytes.len();
+        bytes[len - 8192..].fill(0xAB);
+        std::fs::write(&path, bytes).unwrap();
+        assert!(!check_file(&path).ok);
+
+        let outcome = repair(dir.path(), "events.sqlite", true, false, 7).unwrap();
+        let decisions = outcome
+            .recovered
+            .iter()
+            .find(|t| t.table == "decisions")
+            .unwrap();
+        assert_eq!((decisions.rows, decisions.error.as_deref()), (1, None));
+
+        assert!(check_file(&path).ok);
+        let conn = db::open_events_db(dir.path()).unwrap();
+        let rows = db::list_decisions(&conn, 0, 10).unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].reasoning, "ok");
+    }
+}
@@ -1,9 +1,16 @@
-pub mod agents;
-pub mod briefing;
-pub mod db;
-pub mod eval;
-pub mod focus_mode;
-pub mod llm;
-pub mod memory;
-pub mod paths;
-pub mod service;
+pub mod agents;
+pub mod backfill;
+pub mod briefing;
+pub mod db;
+pub mod eval;
+pub mod export;
+pub mod focus_mode;
+pub mod ingest;
+pub mod integrity;
+pub mod llm;
+pub mod memory;
+pub mod nudge_policy;
+pub mod paths;
+pub mod report_card;
+pub mod service;
+pub mod stats;
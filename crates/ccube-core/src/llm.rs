@@ -1,310 +1,1006 @@
-// LLM client supporting OpenAI-compatible chat completion endpoints.
-// Works with both llama.cpp (OpenAI-compatible mode) and OpenAI API proxies.
-
-use async_trait::async_trait;
-use serde::{Deserialize, Serialize};
-use std::time::Duration;
-
-/// Response from an LLM completion call.
-#[derive(Debug)]
-pub struct LlmResponse {
-    pub content: String,
-    pub model: Option<String>,
-}
-
-/// Errors that can occur during LLM calls.
-#[derive(Debug, thiserror::Error)]
-pub enum LlmError {
-    #[error("LLM unreachable: {0}")]
-    Unreachable(String),
-    #[error("LLM bad response: {0}")]
-    BadResponse(String),
-}
-
-/// Trait for LLM backends, enabling test mocking.
-#[async_trait]
-pub trait LlmBackend: Send + Sync {
-    async fn complete(
-        &self,
-        prompt: &str,
-        grammar: &str,
-        n_predict: u32,
-        temperature: f32,
-    ) -> Result<LlmResponse, LlmError>;
-}
-
-/// Concrete LLM client using the OpenAI chat completions protocol.
-pub struct LlamaCppClient {
-    base_url: String,
-    http: reqwest::Client,
-    /// Stored for potential inspection; consumed during construction.
-    #[allow(dead_code)]
-    token: Option<String>,
-}
-
-// -- OpenAI chat completions request / response shapes --
-
-#[derive(Serialize)]
-struct ChatCompletionRequest<'a> {
-    model: &'a str,
-    messages: &'a [ChatMessage<'a>],
-    max_tokens: u32,
-    temperature: f32,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    grammar: Option<&'a str>,
-}
-
-#[derive(Serialize)]
-struct ChatMessage<'a> {
-    role: &'a str,
-    content: &'a str,
-}
-
-#[derive(Deserialize)]
-struct ChatCompletionResponse {
-    #[allow(dead_code)]
-    id: Option<String>,
-    choices: Vec<Choice>,
-    model: Option<String>,
-}
-
-#[derive(Deserialize)]
-struct Choice {
-    message: MessageContent,
-}
-
-#[derive(Deserialize)]
-struct MessageContent {
-    content: Option<String>,
-}
-
-impl LlamaCppClient {
-    /// Create a client from `CCUBE_LLM_URL` (default `http://localhost:8080`).
-    /// If `CCUBE_LLM_TOKEN` is set, it is sent as a Bearer token.
-    pub fn from_env() -> Result<Self, String> {
-        Self::from_env_with_timeout(Duration::from_secs(10))
-    }
-
-    /// Create a client with a custom timeout.
-    /// Use longer timeouts for curator/reflector calls that produce more output.
-    pub fn from_env_with_timeout(timeout: Duration) -> Result<Self, String> {
-        let base_url =
-            std::env::var("CCUBE_LLM_URL").unwrap_or_else(|_| "http://localhost:8080".to_string());
-
-        let token = std::env::var("CCUBE_LLM_TOKEN").ok().filter(|t| !t.is_empty());
-
-        let mut builder = reqwest::Client::builder().timeout(timeout);
-
-        // Attach Bearer token if provided
-        if let Some(ref t) = token {
-            let mut headers = reqwest::header::HeaderMap::new();
-            let auth_value = format!("Bearer {}", t);
-            headers.insert(
-                reqwest::header::AUTHORIZATION,
-                reqwest::header::HeaderValue::from_str(&auth_value)
-                    .map_err(|e| format!("invalid CCUBE_LLM_TOKEN: {e}"))?,
-            );
-            builder = builder.default_headers(headers);
-        }
-
-        let http = builder
-            .build()
-            .map_err(|e| format!("failed to build HTTP client: {e}"))?;
-
-        Ok(Self {
-            base_url,
-            http,
-            token,
-        })
-    }
-
-    /// The model identifier sent in the request body.
-    /// Read from `CCUBE_LLM_MODEL` or defaults to "default".
-    fn model() -> String {
-        std::env::var("CCUBE_LLM_MODEL").unwrap_or_else(|_| "default".to_string())
-    }
-}
-
-#[async_trait]
-impl LlmBackend for LlamaCppClient {
-    async fn complete(
-        &self,
-        prompt: &str,
-        grammar: &str,
-        n_predict: u32,
-        temperature: f32,
-    ) -> Result<LlmResponse, LlmError> {
-        // Strip trailing slash so we can append cleanly
-        let base = self.base_url.trim_end_matches('/');
-
-        // Try OpenAI chat completions endpoint first.
-        let url = format!("{}/chat/completions", base);
-
-        let body = ChatCompletionRequest {
-            model: &Self::model(),
-            messages: &[ChatMessage {
-                role: "user",
-                content: prompt,
-            }],
-            max_tokens: n_predict,
-            temperature,
-            grammar: if grammar.is_empty() { None } else { Some(grammar) },
-        };
-
-        let resp = self
-            .http
-            .post(&url)
-            .json(&body)
-            .send()
-            .await
-            .map_err(|e| LlmError::Unreachable(e.to_string()))?;
-
-        if !resp.status().is_success() {
-            let status = resp.status();
-            let body_text = resp.text().await.unwrap_or_default();
-            return Err(LlmError::Unreachable(format!(
-                "HTTP {}: {}",
-                status, body_text
-            )));
-        }
-
-        let parsed: ChatCompletionResponse = resp
-            .json()
-            .await
-            .map_err(|e| LlmError::BadResponse(format!("failed to parse response: {e}")))?;
-
-        let content = parsed
-            .choices
-            .into_iter()
-            .next()
-            .and_then(|c| c.message.content)
-            .ok_or_else(|| LlmError::BadResponse("empty response — no choices".into()))?;
-
-        // Strip markdown code fences — many LLMs wrap JSON in ```json ... ``` blocks
-        let content = strip_markdown_fences(&content);
-
-        if content.trim().is_empty() {
-            return Err(LlmError::BadResponse("empty response content".into()));
-        }
-
-        Ok(LlmResponse {
-            content,
-            model: parsed.model,
-        })
-    }
-}
-
-/// Strip markdown code fences (```json ... ```) from LLM output if present.
-/// Many LLMs wrap JSON in code fences when grammar constraints aren't
-/// enforced server-side (e.g. OpenAI API ignores GBNF grammars).
-fn strip_markdown_fences(s: &str) -> String {
-    let s = s.trim();
-    if let Some(after_open) = s.strip_prefix("```") {
-        // after_open includes everything after the opening ```
-        // e.g. "json\n{...}\n```" or "\n{...}\n```"
-        // Find the end of the first line (language tag or empty)
-        let content_start = after_open.find('\n').map(|i| i + 1).unwrap_or(0);
-        let content = &after_open[content_start.min(after_open.len())..];
-        // Find and strip the closing ``` if present
-        if let Some(end) = content.rfind("```") {
-            return content[..end].trim().to_string();
-        }
-        return content.trim().to_string();
-    }
-    s.to_string()
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    // ------------------------------------------------------------------
-    // strip_markdown_fences tests
-    // ------------------------------------------------------------------
-
-    #[test]
-    fn test_strip_no_fences() {
-        assert_eq!(strip_markdown_fences("plain text"), "plain text");
-    }
-
-    #[test]
-    fn test_strip_plain_json() {
-        let json = r#"{"key":"value"}"#;
-        assert_eq!(strip_markdown_fences(json), json);
-    }
-
-    #[test]
-    fn test_strip_with_lang_tag() {
-        let wrapped = "```json\n{\"key\":\"value\"}\n```".to_string();
-        assert_eq!(strip_markdown_fences(&wrapped), r#"{"key":"value"}"#);
-    }
-
-    #[test]
-    fn test_strip_without_lang_tag() {
-        let wrapped = "```\n{\"key\":\"value\"}\n```".to_string();
-        assert_eq!(strip_markdown_fences(&wrapped), r#"{"key":"value"}"#);
-    }
-
-    #[test]
-    fn test_strip_multiline_with_fences() {
-        let wrapped = "```json\n{\n  \"new_patterns_md\": \"§ rule 1\",\n  \"rationale\": \"merged\"\n}\n```".to_string();
-        let result = strip_markdown_fences(&wrapped);
-        assert!(result.contains("\"new_patterns_md\""));
-        assert!(result.contains("\"rationale\""));
-        assert!(!result.contains("```"));
-    }
-
-    #[test]
-    fn test_strip_no_closing_fence() {
-        let wrapped = "```json\n{\"key\":\"value\"}".to_string();
-        assert_eq!(strip_markdown_fences(&wrapped), r#"{"key":"value"}"#);
-    }
-
-    #[test]
-    fn test_strip_whitespace_around() {
-        let wrapped = "  \n```json\n{\"key\":\"value\"}\n```\n  ".to_string();
-        assert_eq!(strip_markdown_fences(&wrapped), r#"{"key":"value"}"#);
-    }
-
-    struct MockLlm {
-        response: Result<String, LlmError>,
-    }
-
-    #[async_trait]
-    impl LlmBackend for MockLlm {
-        async fn complete(
-            &self,
-            _prompt: &str,
-            _grammar: &str,
-            _n_predict: u32,
-            _temperature: f32,
-        ) -> Result<LlmResponse, LlmError> {
-            match &self.response {
-                Ok(content) => Ok(LlmResponse {
-                    content: content.clone(),
-                    model: Some("test-model".to_string()),
-                }),
-                Err(_) => Err(LlmError::Unreachable("mock unreachable".into())),
-            }
-        }
-    }
-
-    #[tokio::test]
-    async fn test_mock_returns_content() {
-        let llm = MockLlm {
-            response: Ok(r#"{"decision":"silent","reasoning":"test"}"#.to_string()),
-        };
-        let resp = llm.complete("prompt", "", 512, 0.2).await.unwrap();
-        assert!(resp.content.contains("silent"));
-        assert_eq!(resp.model.as_deref(), Some("test-model"));
-    }
-
-    #[tokio::test]
-    async fn test_mock_unreachable() {
-        let llm = MockLlm {
-            response: Err(LlmError::Unreachable("down".into())),
-        };
-        let err = llm.complete("prompt", "", 512, 0.2).await.unwrap_err();
-        assert!(matches!(err, LlmError::Unreachable(_)));
-    }
-}
+// LLM client supporting OpenAI-compatible chat completion endpoints.
+// Works with both llama.cpp (OpenAI-compatible mode) and OpenAI API proxies.
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Response from an LLM completion call.
+#[derive(Debug)]
+pub struct LlmResponse {
+    pub content: String,
+    pub model: Option<String>,
+    /// Token counts, when the server reports them.
+    pub usage: Option<TokenUsage>,
+    /// Base URL of the endpoint that answered, when the backend talks to a
+    /// server.
+    pub endpoint: Option<String>,
+}
+
+/// Token counts from the response's `usage` object (llama.cpp and Ollama's
+/// OpenAI-compatible endpoint both send it).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    #[serde(default)]
+    pub prompt_tokens: u32,
+    #[serde(default)]
+    pub completion_tokens: u32,
+}
+
+/// Errors that can occur during LLM calls.
+#[derive(Debug, thiserror::Error)]
+pub enum LlmError {
+    #[error("LLM unreachable: {0}")]
+    Unreachable(String),
+    #[error("LLM bad response: {0}")]
+    BadResponse(String),
+}
+
+/// Trait for LLM backends, enabling test mocking.
+#[async_trait]
+pub trait LlmBackend: Send + Sync {
+    async fn complete(
+        &self,
+        prompt: &str,
+        grammar: &str,
+        n_predict: u32,
+        temperature: f32,
+    ) -> Result<LlmResponse, LlmError>;
+}
+
+/// Concrete LLM client using the OpenAI chat completions protocol.
+pub struct LlamaCppClient {
+    /// One or more endpoints. Calls rotate round-robin across them and fall
+    /// through to the next on an unreachable/HTTP error.
+    base_urls: Vec<String>,
+    /// Index of the endpoint to try first on the next call.
+    next_endpoint: AtomicUsize,
+    /// Client for remote endpoints; honours system proxies.
+    http: reqwest::Client,
+    /// Client for endpoints on this machine. A corporate proxy can't reach
+    /// our localhost, so it bypasses proxies.
+    local_http: reqwest::Client,
+    /// Stored for potential inspection; consumed during construction.
+    #[allow(dead_code)]
+    token: Option<String>,
+    /// Seconds the server should keep the model loaded after a call
+    /// (`-1` = forever, `0` = unload immediately). `None` leaves the server default.
+    keep_alive: Option<i64>,
+}
+
+// -- OpenAI chat completions request / response shapes --
+
+#[derive(Serialize)]
+struct ChatCompletionRequest<'a> {
+    model: &'a str,
+    messages: &'a [ChatMessage<'a>],
+    max_tokens: u32,
+    temperature: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    grammar: Option<&'a str>,
+    /// Honoured by Ollama; other OpenAI-compatible servers ignore unknown fields.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    keep_alive: Option<i64>,
+}
+
+#[derive(Serialize)]
+struct ChatMessage<'a> {
+    role: &'a str,
+    content: &'a str,
+}
+
+#[derive(Deserialize)]
+struct ChatCompletionResponse {
+    #[allow(dead_code)]
+    id: Option<String>,
+    choices: Vec<Choice>,
+    model: Option<String>,
+    #[serde(default)]
+    usage: Option<TokenUsage>,
+}
+
+#[derive(Deserialize)]
+struct Choice {
+    message: MessageContent,
+}
+
+#[derive(Deserialize)]
+struct MessageContent {
+    content: Option<String>,
+}
+
+impl LlamaCppClient {
+    /// Create a client from `CCUBE_LLM_URL` (default `http://localhost:8080`).
+    /// Several comma-separated URLs spread calls across endpoints.
+    /// If `CCUBE_LLM_TOKEN` is set, it is sent as a Bearer token.
+    /// If `CCUBE_LLM_KEEP_ALIVE_SECS` is set, it is sent as `keep_alive`.
+    /// System proxies are bypassed for endpoints on this machine.
+    pub fn from_env() -> Result<Self, String> {
+        Self::from_env_with_timeout(Duration::from_secs(10))
+    }
+
+    /// Create a client with a custom timeout.
+    /// Use longer timeouts for curator/reflector calls that produce more output.
+    pub fn from_env_with_timeout(timeout: Duration) -> Result<Self, String> {
+        let base_urls = std::env::var("CCUBE_LLM_URL")
+            .ok()
+            .map(|v| parse_base_urls(&v))
+            .filter(|urls| !urls.is_empty())
+            .unwrap_or_else(|| vec!["http://localhost:8080".to_string()]);
+
+        let token = std::env::var("CCUBE_LLM_TOKEN").ok().filter(|t| !t.is_empty());
+
+        let keep_alive = match std::env::var("CCUBE_LLM_KEEP_ALIVE_SECS") {
+            Ok(v) if !v.trim().is_empty() => Some(
+                parse_keep_alive(&v)
+                    .ok_or_else(|| format!("invalid CCUBE_LLM_KEEP_ALIVE_SECS: {v}"))?,
+            ),
+            _ => None,
+        };
+
+        // Attach Bearer token if provided
+        let mut headers = reqwest::header::HeaderMap::new();
+        if let Some(ref t) = token {
+            let auth_value = format!("Bearer {}", t);
+            headers.insert(
+                reqwest::header::AUTHORIZATION,
+                reqwest::header::HeaderValue::from_str(&auth_value)
+                    .map_err(|e| format!("invalid CCUBE_LLM_TOKEN: {e}"))?,
+            );
+        }
+
+        let build = |direct: bool| {
+            let mut builder = reqwest::Client::builder()
+                .timeout(timeout)
+                .default_headers(headers.clone());
+            if direct {
+                builder = builder.no_proxy();
+            }
+            builder
+                .build()
+                .map_err(|e| format!("failed to build HTTP client: {e}"))
+        };
+        let http = build(false)?;
+        let local_http = build(true)?;
+
+        Ok(Self {
+            base_urls,
+            next_endpoint: AtomicUsize::new(0),
+            http,
+            local_http,
+            token,
+            keep_alive,
+        })
+    }
+
+    /// The model identifier sent in the request body.
+    /// Read from `CCUBE_LLM_MODEL` or defaults to "default".
+    fn model() -> String {
+        std::env::var("CCUBE_LLM_MODEL").unwrap_or_else(|_| "default".to_string())
+    }
+
+    /// The endpoints in the order the next call should try them, advancing
+    /// the round-robin start for the call after.
+    fn endpoint_order(&self) -> Vec<&str> {
+        let n = self.base_urls.len();
+        let start = self.next_endpoint.fetch_add(1, Ordering::Relaxed) % n;
+        (0..n)
+            .map(|i| self.base_urls[(start + i) % n].as_str())
+            .collect()
+    }
+
+    /// The HTTP client to reach `base` with: direct for this machine,
+    /// through any system proxy otherwise.
+    fn http_for(&self, base: &str) -> &reqwest::Client {
+        if is_local_url(base) {
+            &self.local_http
+        } else {
+            &self.http
+        }
+    }
+
+    /// One chat completion request against a single endpoint.
+    async fn complete_at(
+        &self,
+        base: &str,
+        body: &ChatCompletionRequest<'_>,
+    ) -> Result<LlmResponse, LlmError> {
+        let url = format!("{}/chat/completions", base);
+
+        let resp = self
+            .http_for(base)
+            .post(&url)
+            .json(body)
+            .send()
+            .await
+            .map_err(|e| LlmError::Unreachable(e.to_string()))?;
+
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let body_text = resp.text().await.unwrap_or_default();
+            return Err(LlmError::Unreachable(format!(
+                "HTTP {}: {}",
+                status, body_text
+            )));
+        }
+
+        let parsed: ChatCompletionResponse = resp
+            .json()
+            .await
+            .map_err(|e| LlmError::BadResponse(format!("failed to parse response: {e}")))?;
+
+        let content = parsed
+            .choices
+            .into_iter()
+            .next()
+            .and_then(|c| c.message.content)
+            .ok_or_else(|| LlmError::BadResponse("empty response — no choices".into()))?;
+
+        // Strip markdown code fences — many LLMs wrap JSON in ```json ... ``` blocks
+        let content = strip_markdown_fences(&content);
+
+        if content.trim().is_empty() {
+            return Err(LlmError::BadResponse("empty response content".into()));
+        }
+
+        Ok(LlmResponse {
+            content,
+            model: parsed.model,
+            usage: parsed.usage,
+            endpoint: Some(base.to_string()),
+        })
+    }
+}
+
+#[async_trait]
+impl LlmBackend for LlamaCppClient {
+    /// Tries each endpoint in round-robin order. Only unreachable/HTTP errors
+    /// fall through to the next endpoint; a bad response is returned as-is
+    /// since it reflects the model output rather than the endpoint.
+    async fn complete(
+        &self,
+        prompt: &str,
+        grammar: &str,
+        n_predict: u32,
+        temperature: f32,
+    ) -> Result<LlmResponse, LlmError> {
+        let model = Self::model();
+        let body = ChatCompletionRequest {
+            model: &model,
+            messages: &[ChatMessage {
+                role: "user",
+                content: prompt,
+            }],
+            max_tokens: n_predict,
+            temperature,
+            grammar: if grammar.is_empty() { None } else { Some(grammar) },
+            keep_alive: self.keep_alive,
+        };
+
+        let mut last_err = None;
+        for base in self.endpoint_order() {
+            match self.complete_at(base, &body).await {
+                Err(LlmError::Unreachable(e)) => {
+                    if self.base_urls.len() > 1 {
+                        tracing::warn!(endpoint = base, error = %e, "LLM endpoint failed, trying next");
+                    }
+                    last_err = Some(LlmError::Unreachable(e));
+                }
+                result => return result,
+            }
+        }
+        Err(last_err.unwrap_or_else(|| LlmError::Unreachable("no LLM endpoints configured".into())))
+    }
+}
+
+/// Raw result of asking the server for its model list, for diagnostics.
+/// Exactly what came back (or why nothing did), without interpretation.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ModelsProbe {
+    pub url: String,
+    /// HTTP status, if the server answered at all.
+    pub status: Option<u16>,
+    /// Response body: parsed JSON, or a JSON string if it wasn't JSON.
+    pub body: Option<serde_json::Value>,
+    pub error: Option<String>,
+}
+
+impl ModelsProbe {
+    /// Model names actually reported by the server. Understands the OpenAI
+    /// (`data[].id`) and Ollama (`models[].name`) shapes. Empty when the server
+    /// was unreachable, errored, or listed nothing — never a made-up default.
+    pub fn model_ids(&self) -> Vec<String> {
+        if self.error.is_some() {
+            return Vec::new();
+        }
+        let Some(body) = self.body.as_ref() else {
+            return Vec::new();
+        };
+        let (list, key) = match (body.get("data"), body.get("models")) {
+            (Some(data), _) => (data, "id"),
+            (None, Some(models)) => (models, "name"),
+            (None, None) => return Vec::new(),
+        };
+        list.as_array()
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(|m| m.get(key).and_then(|v| v.as_str()))
+                    .filter(|id| !id.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+impl LlamaCppClient {
+    /// GET `{base_url}/models` on every configured endpoint and return each
+    /// raw payload or failure, in configured order. Endpoints are probed
+    /// concurrently so one that hangs doesn't multiply the wait.
+    pub async fn probe_models(&self) -> Vec<ModelsProbe> {
+        let handles: Vec<_> = self
+            .base_urls
+            .iter()
+            .map(|base| {
+                let http = self.http_for(base).clone();
+                tokio::spawn(probe_models_at(http, base.clone()))
+            })
+            .collect();
+        let mut probes = Vec::with_capacity(handles.len());
+        for (handle, base) in handles.into_iter().zip(&self.base_urls) {
+            probes.push(handle.await.unwrap_or_else(|e| ModelsProbe {
+                url: format!("{base}/models"),
+                status: None,
+                body: None,
+                error: Some(format!("probe failed: {e}")),
+            }));
+        }
+        probes
+    }
+}
+
+/// GET `{base}/models` on a single endpoint.
+async fn probe_models_at(http: reqwest::Client, base: String) -> ModelsProbe {
+    let url = format!("{base}/models");
+    let resp = match http.get(&url).send().await {
+        Ok(r) => r,
+        Err(e) => {
+            return ModelsProbe {
+                url,
+                status: None,
+                body: None,
+                error: Some(format!("unreachable: {e}")),
+            };
+        }
+    };
+
+    let status = resp.status();
+    let text = resp.text().await.unwrap_or_default();
+    let body = serde_json::from_str(&text).unwrap_or(serde_json::Value::String(text));
+    ModelsProbe {
+        url,
+        status: Some(status.as_u16()),
+        body: Some(body),
+        error: (!status.is_success()).then(|| format!("HTTP {status}")),
+    }
+}
+
+/// Split a comma-separated `CCUBE_LLM_URL` into endpoints, trimming whitespace
+/// and trailing slashes and dropping empty entries.
+pub fn parse_base_urls(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|u| u.trim().trim_end_matches('/'))
+        .filter(|u| !u.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Whether `url` points at this machine (localhost or a loopback address).
+pub fn is_local_url(url: &str) -> bool {
+    let Ok(parsed) = reqwest::Url::parse(url) else {
+        return false;
+    };
+    let Some(host) = parsed.host_str() else {
+        return false;
+    };
+    // IPv6 hosts come back bracketed, e.g. "[::1]"
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    host.eq_ignore_ascii_case("localhost")
+        || host
+            .parse::<std::net::IpAddr>()
+            .is_ok_and(|ip| ip.is_loopback())
+}
+
+/// Parse a keep-alive setting: whole seconds (`-1` = keep forever).
+/// `true`/`false` are accepted for the old keep-loaded toggle and map to -1 and 0.
+pub fn parse_keep_alive(value: &str) -> Option<i64> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "true" => Some(-1),
+        "false" => Some(0),
+        v => v.parse::<i64>().ok().filter(|&secs| secs >= -1),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Latency recording — wraps a backend and logs each call to llm_calls
+// ---------------------------------------------------------------------------
+
+/// A call is treated as cold when no call reached the server for this long.
+/// llama.cpp keeps the model resident, but servers like Ollama unload it after
+/// roughly five idle minutes by default.
+pub const COLD_AFTER_IDLE: Duration = Duration::from_secs(300);
+
+/// Time of the most recent call to each endpoint, shared by every recorder
+/// that talks to the same servers so detector and curator calls warm each
+/// other. Backends that don't report an endpoint are tracked under "".
+pub type LastCall = Arc<Mutex<HashMap<String, Instant>>>;
+
+/// Backend wrapper that records per-call latency (and whether the model was
+/// likely cold) into the `llm_calls` table. Recording failures are logged and
+/// never affect the call result.
+pub struct RecordingLlm {
+    inner: Arc<dyn LlmBackend>,
+    client: &'static str,
+    data_dir: PathBuf,
+    last_call: LastCall,
+}
+
+impl RecordingLlm {
+    pub fn new(
+        inner: Arc<dyn LlmBackend>,
+        client: &'static str,
+        data_dir: PathBuf,
+        last_call: LastCall,
+    ) -> Self {
+        Self {
+            inner,
+            client,
+            data_dir,
+            last_call,
+        }
+    }
+}
+
+#[async_trait]
+impl LlmBackend for RecordingLlm {
+    async fn complete(
+        &self,
+        prompt: &str,
+        grammar: &str,
+        n_predict: u32,
+        temperature: f32,
+    ) -> Result<LlmResponse, LlmError> {
+        let start = Instant::now();
+        let result = self
+            .inner
+            .complete(prompt, grammar, n_predict, temperature)
+            .await;
+        let latency_ms = start.elapsed().as_millis() as i64;
+
+        // Cold is judged per endpoint: with several servers each keeps its
+        // own model loaded (or not)
+        let cold = {
+            let mut last = self.last_call.lock().unwrap_or_else(|e| e.into_inner());
+            let idle = |t: &Instant| start.saturating_duration_since(*t) >= COLD_AFTER_IDLE;
+            match &result {
+                Ok(resp) => {
+                    let endpoint = resp.endpoint.clone().unwrap_or_default();
+                    let cold = last.get(&endpoint).is_none_or(idle);
+                    last.insert(endpoint, start);
+                    cold
+                }
+                // Nothing answered: cold unless some endpoint did recently
+                Err(_) => last.values().all(idle),
+            }
+        };
+
+        let ts = chrono::Utc::now().timestamp_millis();
+        let usage = result
+            .as_ref()
+            .ok()
+            .and_then(|r| r.usage)
+            .unwrap_or_default();
+        let date = chrono::Local::now().date_naive().to_string();
+        let recorded = crate::db::open_events_db(&self.data_dir).and_then(|conn| {
+            crate::db::insert_llm_call(&conn, ts, self.client, latency_ms, cold, result.is_ok())?;
+            crate::db::add_llm_usage(
+                &conn,
+                &date,
+                self.client,
+                result.is_ok(),
+                usage.prompt_tokens as i64,
+                usage.completion_tokens as i64,
+            )
+        });
+        if let Err(e) = recorded {
+            tracing::warn!(error = %e, client = self.client, "failed to record LLM latency");
+        }
+
+        result
+    }
+}
+
+/// Cold vs warm latency overview plus the underlying call history.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LatencyHistory {
+    pub cold_calls: usize,
+    pub warm_calls: usize,
+    pub cold_avg_ms: Option<i64>,
+    pub warm_avg_ms: Option<i64>,
+    pub calls: Vec<crate::db::LlmCallRow>,
+}
+
+/// Average successful-call latency, split by cold and warm.
+pub fn latency_history(calls: Vec<crate::db::LlmCallRow>) -> LatencyHistory {
+    let avg = |cold: bool| -> (usize, Option<i64>) {
+        let lat: Vec<i64> = calls
+            .iter()
+            .filter(|c| c.ok && c.cold == cold)
+            .map(|c| c.latency_ms)
+            .collect();
+        if lat.is_empty() {
+            (0, None)
+        } else {
+            (lat.len(), Some(lat.iter().sum::<i64>() / lat.len() as i64))
+        }
+    };
+    let (cold_calls, cold_avg_ms) = avg(true);
+    let (warm_calls, warm_avg_ms) = avg(false);
+    LatencyHistory {
+        cold_calls,
+        warm_calls,
+        cold_avg_ms,
+        warm_avg_ms,
+        calls,
+    }
+}
+
+/// LLM call and token totals over a range of days.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UsageSummary {
+    pub calls: i64,
+    pub failed: i64,
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+    /// Totals per client (detector, curator, ...), most calls first.
+    pub clients: Vec<ClientUsage>,
+    /// The stored per-day, per-client rows, oldest first.
+    pub days: Vec<crate::db::LlmUsageRow>,
+}
+
+/// One client's share of the calls in a usage summary.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClientUsage {
+    pub client: String,
+    pub calls: i64,
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+}
+
+/// Total up daily usage rows.
+pub fn usage_summary(days: Vec<crate::db::LlmUsageRow>) -> UsageSummary {
+    let mut clients: Vec<ClientUsage> = Vec::new();
+    for row in &days {
+        let idx = match clients.iter().position(|c| c.client == row.client) {
+            Some(i) => i,
+            None => {
+                clients.push(ClientUsage {
+                    client: row.client.clone(),
+                    calls: 0,
+                    prompt_tokens: 0,
+                    completion_tokens: 0,
+                });
+                clients.len() - 1
+            }
+        };
+        let c = &mut clients[idx];
+        c.calls += row.calls;
+        c.prompt_tokens += row.prompt_tokens;
+        c.completion_tokens += row.completion_tokens;
+    }
+    clients.sort_by(|a, b| b.calls.cmp(&a.calls).then_with(|| a.client.cmp(&b.client)));
+
+    UsageSummary {
+        calls: days.iter().map(|d| d.calls).sum(),
+        failed: days.iter().map(|d| d.failed).sum(),
+        prompt_tokens: days.iter().map(|d| d.prompt_tokens).sum(),
+        completion_tokens: days.iter().map(|d| d.completion_tokens).sum(),
+        clients,
+        days,
+    }
+}
+
+/// Strip markdown code fences (```json ... ```) from LLM output if present.
+/// Many LLMs wrap JSON in code fences when grammar constraints aren't
+/// enforced server-side (e.g. OpenAI API ignores GBNF grammars).
+fn strip_markdown_fences(s: &str) -> String {
+    let s = s.trim();
+    if let Some(after_open) = s.strip_prefix("```") {
+        // after_open includes everything after the opening ```
+        // e.g. "json\n{...}\n```" or "\n{...}\n```"
+        // Find the end of the first line (language tag or empty)
+        let content_start = after_open.find('\n').map(|i| i + 1).unwrap_or(0);
+        let content = &after_open[content_start.min(after_open.len())..];
+        // Find and strip the closing ``` if present
+        if let Some(end) = content.rfind("```") {
+            return content[..end].trim().to_string();
+        }
+        return content.trim().to_string();
+    }
+    s.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ------------------------------------------------------------------
+    // strip_markdown_fences tests
+    // ------------------------------------------------------------------
+
+    #[test]
+    fn test_strip_no_fences() {
+        assert_eq!(strip_markdown_fences("plain text"), "plain text");
+    }
+
+    #[test]
+    fn test_strip_plain_json() {
+        let json = r#"{"key":"value"}"#;
+        assert_eq!(strip_markdown_fences(json), json);
+    }
+
+    #[test]
+    fn test_strip_with_lang_tag() {
+        let wrapped = "```json\n{\"key\":\"value\"}\n```".to_string();
+        assert_eq!(strip_markdown_fences(&wrapped), r#"{"key":"value"}"#);
+    }
+
+    #[test]
+    fn test_strip_without_lang_tag() {
+        let wrapped = "```\n{\"key\":\"value\"}\n```".to_string();
+        assert_eq!(strip_markdown_fences(&wrapped), r#"{"key":"value"}"#);
+    }
+
+    #[test]
+    fn test_strip_multiline_with_fences() {
+        let wrapped = "```json\n{\n  \"new_patterns_md\": \"§ rule 1\",\n  \"rationale\": \"merged\"\n}\n```".to_string();
+        let result = strip_markdown_fences(&wrapped);
+        assert!(result.contains("\"new_patterns_md\""));
+        assert!(result.contains("\"rationale\""));
+        assert!(!result.contains("```"));
+    }
+
+    #[test]
+    fn test_strip_no_closing_fence() {
+        let wrapped = "```json\n{\"key\":\"value\"}".to_string();
+        assert_eq!(strip_markdown_fences(&wrapped), r#"{"key":"value"}"#);
+    }
+
+    #[test]
+    fn test_strip_whitespace_around() {
+        let wrapped = "  \n```json\n{\"key\":\"value\"}\n```\n  ".to_string();
+        assert_eq!(strip_markdown_fences(&wrapped), r#"{"key":"value"}"#);
+    }
+
+    // ------------------------------------------------------------------
+    // model listing tests
+    // ------------------------------------------------------------------
+
+    fn probe(body: serde_json::Value, error: Option<&str>) -> ModelsProbe {
+        ModelsProbe {
+            url: "http://localhost:8080/models".into(),
+            status: Some(200),
+            body: Some(body),
+            error: error.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_model_ids_only_real_names() {
+        let openai = probe(
+            serde_json::json!({"data": [{"id": "qwen3"}, {"id": "llama3"}]}),
+            None,
+        );
+        assert_eq!(openai.model_ids(), vec!["qwen3", "llama3"]);
+
+        let ollama = probe(serde_json::json!({"models": [{"name": "mistral:7b"}]}), None);
+        assert_eq!(ollama.model_ids(), vec!["mistral:7b"]);
+
+        // Empty list, unexpected shape, HTTP error, or no response: no models
+        assert!(probe(serde_json::json!({"data": []}), None).model_ids().is_empty());
+        assert!(probe(serde_json::json!("Not Found"), None).model_ids().is_empty());
+        assert!(
+            probe(serde_json::json!({"data": [{"id": "x"}]}), Some("HTTP 500"))
+                .model_ids()
+                .is_empty()
+        );
+        let unreachable = ModelsProbe {
+            url: String::new(),
+            status: None,
+            body: None,
+            error: Some("unreachable".into()),
+        };
+        assert!(unreachable.model_ids().is_empty());
+    }
+
+    // ------------------------------------------------------------------
+    // endpoint configuration tests
+    // ------------------------------------------------------------------
+
+    #[test]
+    fn test_parse_base_urls() {
+        assert_eq!(
+            parse_base_urls("http://a:8080/, http://b:11434/v1 ,,"),
+            vec!["http://a:8080", "http://b:11434/v1"]
+        );
+        assert!(parse_base_urls(" , ").is_empty());
+    }
+
+    #[test]
+    fn test_parse_keep_alive() {
+        assert_eq!(parse_keep_alive("600"), Some(600));
+        assert_eq!(parse_keep_alive(" 0 "), Some(0));
+        assert_eq!(parse_keep_alive("-1"), Some(-1));
+        assert_eq!(parse_keep_alive("true"), Some(-1));
+        assert_eq!(parse_keep_alive("FALSE"), Some(0));
+        assert_eq!(parse_keep_alive("-5"), None);
+        assert_eq!(parse_keep_alive("10m"), None);
+    }
+
+    // ------------------------------------------------------------------
+    // endpoint rotation tests
+    // ------------------------------------------------------------------
+
+    fn client_for(urls: &[&str]) -> LlamaCppClient {
+        let direct = reqwest::Client::builder()
+            .timeout(Duration::from_secs(2))
+            .no_proxy()
+            .build()
+            .unwrap();
+        LlamaCppClient {
+            base_urls: urls.iter().map(|u| u.to_string()).collect(),
+            next_endpoint: AtomicUsize::new(0),
+            http: direct.clone(),
+            local_http: direct,
+            token: None,
+            keep_alive: None,
+        }
+    }
+
+    /// Serve one canned chat completion per connection on a local port.
+    async fn fake_llm_server(reply: &'static str) -> String {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut sock, _)) = listener.accept().await {
+                let mut buf = vec![0u8; 65536];
+                let _ = sock.read(&mut buf).await;
+                let body = format!(
+                    r#"{{"choices":[{{"message":{{"content":"{reply}"}}}}],"model":"m"}}"#
+                );
+                let resp = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                let _ = sock.write_all(resp.as_bytes()).await;
+            }
+        });
+        format!("http://{addr}")
+    }
+
+    #[test]
+    fn test_endpoint_order_round_robin() {
+        let client = client_for(&["http://a", "http://b", "http://c"]);
+        assert_eq!(client.endpoint_order(), vec!["http://a", "http://b", "http://c"]);
+        assert_eq!(client.endpoint_order(), vec!["http://b", "http://c", "http://a"]);
+        assert_eq!(client.endpoint_order(), vec!["http://c", "http://a", "http://b"]);
+        assert_eq!(client.endpoint_order()[0], "http://a");
+    }
+
+    #[tokio::test]
+    async fn test_complete_falls_back_to_next_endpoint() {
+        let live = fake_llm_server("ok").await;
+        // Port 1 on loopback refuses connections immediately
+        let client = client_for(&["http://127.0.0.1:1", &live]);
+
+        let resp = client.complete("hi", "", 8, 0.0).await.unwrap();
+        assert_eq!(resp.content, "ok");
+
+        // All endpoints down: the error surfaces
+        let dead = client_for(&["http://127.0.0.1:1"]);
+        assert!(matches!(
+            dead.complete("hi", "", 8, 0.0).await,
+            Err(LlmError::Unreachable(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_probe_models_asks_every_endpoint() {
+        let live = fake_llm_server("ok").await;
+        let client = client_for(&["http://127.0.0.1:1", &live]);
+
+        let probes = client.probe_models().await;
+        assert_eq!(probes.len(), 2);
+        assert_eq!(probes[0].url, "http://127.0.0.1:1/models");
+        assert_eq!(probes[1].url, format!("{live}/models"));
+        assert_eq!(probes[0].status, None);
+        assert!(probes[0].error.is_some());
+        assert_eq!(probes[1].status, Some(200));
+    }
+
+    #[tokio::test]
+    async fn test_recording_llm_tracks_cold_per_endpoint() {
+        let dir = tempfile::TempDir::new().unwrap();
+        crate::db::init_databases(dir.path()).unwrap();
+        let (a, b) = (fake_llm_server("a").await, fake_llm_server("b").await);
+
+        let llm = RecordingLlm::new(
+            Arc::new(client_for(&[&a, &b])),
+            "detector",
+            dir.path().to_path_buf(),
+            LastCall::default(),
+        );
+        // Round-robin: a, b, a
+        for _ in 0..3 {
+            llm.complete("hi", "", 8, 0.0).await.unwrap();
+        }
+
+        let conn = crate::db::open_events_db(dir.path()).unwrap();
+        let rows = crate::db::list_llm_calls(&conn, 10).unwrap();
+        // Newest first: the second endpoint's first call was cold too
+        let cold: Vec<bool> = rows.iter().map(|r| r.cold).collect();
+        assert_eq!(cold, vec![false, true, true]);
+    }
+
+    // ------------------------------------------------------------------
+    // proxy bypass tests
+    // ------------------------------------------------------------------
+
+    #[test]
+    fn test_is_local_url() {
+        assert!(is_local_url("http://localhost:8080"));
+        assert!(is_local_url("http://LocalHost:11434/v1"));
+        assert!(is_local_url("http://127.0.0.1:8080"));
+        assert!(is_local_url("http://[::1]:8080"));
+        assert!(!is_local_url("http://192.168.1.20:11434"));
+        assert!(!is_local_url("https://llm.example.com/v1"));
+        assert!(!is_local_url("not a url"));
+    }
+
+    #[tokio::test]
+    async fn test_mixed_endpoints_proxy_only_remote_calls() {
+        let local = fake_llm_server("direct").await;
+        // Stands in for a proxy: answers whatever it is sent
+        let proxy = fake_llm_server("proxied").await;
+        let mut client = client_for(&[&local, "http://llm.example.com"]);
+        client.http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(2))
+            .proxy(reqwest::Proxy::all(&proxy).unwrap())
+            .build()
+            .unwrap();
+
+        let first = client.complete("hi", "", 8, 0.0).await.unwrap();
+        assert_eq!(first.content, "direct");
+        assert_eq!(first.endpoint.as_deref(), Some(local.as_str()));
+
+        let second = client.complete("hi", "", 8, 0.0).await.unwrap();
+        assert_eq!(second.content, "proxied");
+        assert_eq!(second.endpoint.as_deref(), Some("http://llm.example.com"));
+    }
+
+    // ------------------------------------------------------------------
+    // backend and recording tests
+    // ------------------------------------------------------------------
+
+    struct MockLlm {
+        response: Result<String, LlmError>,
+    }
+
+    #[async_trait]
+    impl LlmBackend for MockLlm {
+        async fn complete(
+            &self,
+            _prompt: &str,
+            _grammar: &str,
+            _n_predict: u32,
+            _temperature: f32,
+        ) -> Result<LlmResponse, LlmError> {
+            match &self.response {
+                Ok(content) => Ok(LlmResponse {
+                    content: content.clone(),
+                    model: Some("test-model".to_string()),
+                    usage: Some(TokenUsage {
+                        prompt_tokens: 120,
+                        completion_tokens: 30,
+                    }),
+                    endpoint: None,
+                }),
+                Err(_) => Err(LlmError::Unreachable("mock unreachable".into())),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mock_returns_content() {
+        let llm = MockLlm {
+            response: Ok(r#"{"decision":"silent","reasoning":"test"}"#.to_string()),
+        };
+        let resp = llm.complete("prompt", "", 512, 0.2).await.unwrap();
+        assert!(resp.content.contains("silent"));
+        assert_eq!(resp.model.as_deref(), Some("test-model"));
+    }
+
+    #[tokio::test]
+    async fn test_recording_llm_marks_cold_then_warm() {
+        let dir = tempfile::TempDir::new().unwrap();
+        crate::db::init_databases(dir.path()).unwrap();
+
+        let llm = RecordingLlm::new(
+            Arc::new(MockLlm {
+                response: Ok("ok".to_string()),
+            }),
+            "detector",
+            dir.path().to_path_buf(),
+            LastCall::default(),
+        );
+        llm.complete("a", "", 16, 0.2).await.unwrap();
+        llm.complete("b", "", 16, 0.2).await.unwrap();
+
+        let conn = crate::db::open_events_db(dir.path()).unwrap();
+        let rows = crate::db::list_llm_calls(&conn, 10).unwrap();
+        assert_eq!(rows.len(), 2);
+        // Newest first: second call is warm, first was cold
+        assert!(!rows[0].cold);
+        assert!(rows[1].cold);
+        assert!(rows.iter().all(|r| r.ok && r.client == "detector"));
+
+        let usage =
+            usage_summary(crate::db::list_llm_usage(&conn, "0000-01-01", "9999-12-31").unwrap());
+        assert_eq!(usage.calls, 2);
+        assert_eq!(usage.prompt_tokens, 240);
+        assert_eq!(usage.completion_tokens, 60);
+        assert_eq!(usage.clients[0].client, "detector");
+    }
+
+    #[tokio::test]
+    async fn test_recording_llm_records_failures() {
+        let dir = tempfile::TempDir::new().unwrap();
+        crate::db::init_databases(dir.path()).unwrap();
+
+        let llm = RecordingLlm::new(
+            Arc::new(MockLlm {
+                response: Err(LlmError::Unreachable("down".into())),
+            }),
+            "curator",
+            dir.path().to_path_buf(),
+            LastCall::default(),
+        );
+        assert!(llm.complete("a", "", 16, 0.2).await.is_err());
+
+        let conn = crate::db::open_events_db(dir.path()).unwrap();
+        let rows = crate::db::list_llm_calls(&conn, 10).unwrap();
+        assert_eq!(rows.len(), 1);
+        assert!(!rows[0].ok);
+
+        let usage =
+            usage_summary(crate::db::list_llm_usage(&conn, "0000-01-01", "9999-12-31").unwrap());
+        assert_eq!((usage.calls, usage.failed, usage.prompt_tokens), (1, 1, 0));
+    }
+
+    #[tokio::test]
+    async fn test_mock_unreachable() {
+        let llm = MockLlm {
+            response: Err(LlmError::Unreachable("down".into())),
+        };
+        let err = llm.complete("prompt", "", 512, 0.2).await.unwrap_err();
+        assert!(matches!(err, LlmError::Unreachable(_)));
+    }
+}
@@ -0,0 +1,756 @@
+// Nudge policy — when the daemon is allowed to run the detector and notify.
+//
+// Configuration and gating logic; the scheduler consults it before each
+// detector run and before sending a notification. Only meeting detection
+// reads the events db itself, since it looks further back than the detector.
+
+use chrono::{Datelike, NaiveDate, Weekday};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+use crate::briefing::{FocusMode, NudgeStyle};
+use crate::db::{self, EventRow, NudgeFeedbackRow};
+use crate::focus_mode;
+use crate::stats::{self, FocusScoring, Span};
+
+/// A focused run at least this long counts as flow.
+pub const FLOW_MIN_MINUTES: f64 = 20.0;
+
+/// A run that ended longer ago than this isn't current.
+const FLOW_RECENCY_MS: i64 = 60_000;
+
+/// How far back nudge feedback is counted.
+pub const FEEDBACK_WINDOW_DAYS: i64 = 14;
+
+/// Rated nudges needed before feedback changes what gets shown.
+const FEEDBACK_MIN_RATED: usize = 5;
+
+/// Below this share of helpful nudges, only Direct nudges are shown.
+const FEEDBACK_MIN_ACCURACY: f64 = 50.0;
+
+/// Video-call apps, matched case-insensitively as a substring of the app name.
+pub const DEFAULT_CALL_APPS: &[&str] = &["zoom", "teams", "webex", "skype"];
+
+/// Minutes a call app must stay in front before it counts as a meeting.
+pub const DEFAULT_MEETING_MIN_MINUTES: i64 = 5;
+
+/// Focus events this close together count as one continuous call.
+const MEETING_GAP_MS: i64 = 60_000;
+
+/// How far back meeting detection looks for the start of a call.
+pub const MEETING_LOOKBACK_MS: i64 = 8 * 3_600_000;
+
+/// How the detector behaves on weekend days.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WeekendMode {
+    /// Same as weekdays.
+    #[default]
+    Normal,
+    /// Detector still runs and records decisions, but no notifications are sent.
+    Quiet,
+    /// Detector does not run at all.
+    Off,
+}
+
+/// Weekend behaviour and which days count as the weekend.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WeekendPolicy {
+    pub mode: WeekendMode,
+    pub days: Vec<Weekday>,
+}
+
+impl Default for WeekendPolicy {
+    fn default() -> Self {
+        Self {
+            mode: WeekendMode::Normal,
+            days: vec![Weekday::Sat, Weekday::Sun],
+        }
+    }
+}
+
+impl WeekendPolicy {
+    /// Read from `CCUBE_WEEKEND_MODE` (normal | quiet | off, default normal)
+    /// and `CCUBE_WEEKEND_DAYS` (comma-separated, default `sat,sun`).
+    pub fn from_env() -> Result<Self, String> {
+        let mut policy = Self::default();
+        if let Ok(v) = std::env::var("CCUBE_WEEKEND_MODE")
+            && !v.trim().is_empty()
+        {
+            policy.mode =
+                parse_weekend_mode(&v).ok_or_else(|| format!("invalid CCUBE_WEEKEND_MODE: {v}"))?;
+        }
+        if let Ok(v) = std::env::var("CCUBE_WEEKEND_DAYS")
+            && !v.trim().is_empty()
+        {
+            policy.days =
+                parse_weekdays(&v).ok_or_else(|| format!("invalid CCUBE_WEEKEND_DAYS: {v}"))?;
+        }
+        Ok(policy)
+    }
+
+    pub fn is_weekend(&self, date: NaiveDate) -> bool {
+        self.days.contains(&date.weekday())
+    }
+
+    /// The mode in effect on `date` (always Normal on weekdays).
+    pub fn mode_on(&self, date: NaiveDate) -> WeekendMode {
+        if self.is_weekend(date) {
+            self.mode
+        } else {
+            WeekendMode::Normal
+        }
+    }
+
+    /// Whether the detector should run on `date`.
+    pub fn allows_detector(&self, date: NaiveDate) -> bool {
+        self.mode_on(date) != WeekendMode::Off
+    }
+
+    /// Whether a nudge notification may be shown on `date`.
+    pub fn allows_notification(&self, date: NaiveDate) -> bool {
+        self.mode_on(date) == WeekendMode::Normal
+    }
+}
+
+/// Focus modes in which nudges are recorded but not shown. Empty by default,
+/// so every mode nudges.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModeNudgePolicy {
+    pub silent_modes: Vec<String>,
+}
+
+impl ModeNudgePolicy {
+    /// Read from `CCUBE_NUDGE_SILENT_MODES` (comma-separated mode names, e.g.
+    /// `VideoProduction,Writing`).
+    pub fn from_env() -> Result<Self, String> {
+        match std::env::var("CCUBE_NUDGE_SILENT_MODES") {
+            Ok(v) if !v.trim().is_empty() => Ok(Self {
+                silent_modes: parse_modes(&v)
+                    .ok_or_else(|| format!("invalid CCUBE_NUDGE_SILENT_MODES: {v}"))?,
+            }),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    /// Whether a nudge may be shown while the user is in `mode`.
+    pub fn allows_notification(&self, mode: &str) -> bool {
+        !self
+            .silent_modes
+            .iter()
+            .any(|m| m.eq_ignore_ascii_case(mode))
+    }
+}
+
+/// "Don't interrupt flow": suppress nudges while the user is in a long focused
+/// run. Only Direct nudges (the urgent style) get through.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FlowProtection {
+    pub enabled: bool,
+}
+
+impl FlowProtection {
+    /// Read from `CCUBE_PROTECT_FLOW` (true | false, default false).
+    pub fn from_env() -> Result<Self, String> {
+        match std::env::var("CCUBE_PROTECT_FLOW") {
+            Ok(v) if !v.trim().is_empty() => match v.trim().to_ascii_lowercase().as_str() {
+                "true" | "1" | "yes" => Ok(Self { enabled: true }),
+                "false" | "0" | "no" => Ok(Self { enabled: false }),
+                _ => Err(format!("invalid CCUBE_PROTECT_FLOW: {v}")),
+            },
+            _ => Ok(Self::default()),
+        }
+    }
+
+    /// Whether a nudge of `style` should be held back given recent `spans`.
+    pub fn suppresses(
+        &self,
+        spans: &[Span],
+        scoring: &FocusScoring,
+        style: Option<&NudgeStyle>,
+        now_ms: i64,
+    ) -> bool {
+        self.enabled && style != Some(&NudgeStyle::Direct) && in_flow(spans, scoring, now_ms)
+    }
+}
+
+/// Quiet start: for a while after the daemon starts, or after the user comes
+/// back from a long break, data is too sparse for a fair nudge. Decisions are
+/// still recorded, only the notification is held back. Off by default; set
+/// `CCUBE_NUDGE_GRACE_MINUTES` to enable it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StartupGrace {
+    /// 0 disables the grace period.
+    pub grace_ms: i64,
+}
+
+impl StartupGrace {
+    /// Read from `CCUBE_NUDGE_GRACE_MINUTES` (0-60, default 0 = off).
+    pub fn from_env() -> Result<Self, String> {
+        match std::env::var("CCUBE_NUDGE_GRACE_MINUTES") {
+            Ok(v) if !v.trim().is_empty() => match v.trim().parse::<i64>() {
+                Ok(m) if (0..=60).contains(&m) => Ok(Self {
+                    grace_ms: m * 60_000,
+                }),
+                _ => Err(format!("invalid CCUBE_NUDGE_GRACE_MINUTES: {v}")),
+            },
+            _ => Ok(Self::default()),
+        }
+    }
+
+    /// Whether `now_ms` is within the grace period after `started_ms` or after
+    /// the end of the last long idle period in `events`. An `idle_end` whose
+    /// start predates `events` counts as the end of a long break.
+    pub fn suppresses(&self, started_ms: i64, events: &[EventRow], now_ms: i64) -> bool {
+        if self.grace_ms <= 0 {
+            return false;
+        }
+        let orphan_return = events
+            .iter()
+            .take_while(|e| e.kind != "idle_start")
+            .find(|e| e.kind == "idle_end")
+            .map(|e| e.ts);
+        let long_return =
+            stats::last_return_from_idle(events, stats::DEFAULT_RETURN_MIN_IDLE_MS, now_ms)
+                .map(|(_, end)| end);
+        let since = [Some(started_ms), orphan_return, long_return]
+            .into_iter()
+            .flatten()
+            .max()
+            .unwrap_or(started_ms);
+        now_ms - since < self.grace_ms
+    }
+}
+
+/// Meeting detection: a video-call app has been in the foreground without a
+/// break for at least `min_ms`. Idle is ignored, since calls are often
+/// hands-off.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MeetingDetection {
+    /// Lowercase substrings of call app names.
+    pub call_apps: Vec<String>,
+    pub min_ms: i64,
+}
+
+impl Default for MeetingDetection {
+    fn default() -> Self {
+        Self {
+            call_apps: DEFAULT_CALL_APPS.iter().map(|a| a.to_string()).collect(),
+            min_ms: DEFAULT_MEETING_MIN_MINUTES * 60_000,
+        }
+    }
+}
+
+/// Whether the user is in a call right now, and since when.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MeetingStatus {
+    pub in_meeting: bool,
+    /// The call app in front, even if it hasn't been there long enough yet.
+    pub app: Option<String>,
+    /// When the call app came to the front.
+    pub since: Option<i64>,
+    pub minutes: f64,
+}
+
+impl MeetingDetection {
+    /// Read from `CCUBE_CALL_APPS` (comma-separated app name fragments,
+    /// default `zoom,teams,webex,skype`) and `CCUBE_MEETING_MIN_MINUTES`
+    /// (1-60, default 5).
+    pub fn from_env() -> Result<Self, String> {
+        let mut detection = Self::default();
+        if let Ok(v) = std::env::var("CCUBE_CALL_APPS")
+            && !v.trim().is_empty()
+        {
+            detection.call_apps = stats::parse_keywords(&v);
+        }
+        if let Ok(v) = std::env::var("CCUBE_MEETING_MIN_MINUTES")
+            && !v.trim().is_empty()
+        {
+            let minutes = v
+                .trim()
+                .parse::<i64>()
+                .ok()
+                .filter(|m| (1..=60).contains(m))
+                .ok_or_else(|| format!("invalid CCUBE_MEETING_MIN_MINUTES (need 1-60): {v}"))?;
+            detection.min_ms = minutes * 60_000;
+        }
+        Ok(detection)
+    }
+
+    pub fn is_call_app(&self, app: &str) -> bool {
+        let app = app.to_lowercase();
+        self.call_apps.iter().any(|c| app.contains(c.as_str()))
+    }
+
+    /// Meeting status from `events` (oldest first). The foreground app is the
+    /// last `app_focus` event; it is still current if it has no duration yet
+    /// or ended less than a minute ago.
+    pub fn status(&self, events: &[EventRow], now_ms: i64) -> MeetingStatus {
+        let focus: Vec<&EventRow> = events.iter().filter(|e| e.kind == "app_focus").collect();
+        let end_of = |e: &EventRow| e.duration_ms.map_or(now_ms, |d| e.ts + d.max(0));
+        let Some(last) = focus.last() else {
+            return MeetingStatus::default();
+        };
+        let app = last.app.as_deref().unwrap_or_default();
+        if !self.is_call_app(app) || now_ms - end_of(last) > MEETING_GAP_MS {
+            return MeetingStatus::default();
+        }
+
+        let mut since = last.ts;
+        for e in focus.iter().rev().skip(1) {
+            if !e.app.as_deref().is_some_and(|a| self.is_call_app(a))
+                || since - end_of(e) > MEETING_GAP_MS
+            {
+                break;
+            }
+            since = e.ts;
+        }
+        let held_ms = (now_ms - since).max(0);
+        MeetingStatus {
+            in_meeting: held_ms >= self.min_ms,
+            app: Some(app.to_string()),
+            since: Some(since),
+            minutes: stats::ms_to_minutes(held_ms),
+        }
+    }
+
+    /// Meeting status from the events db, looking back MEETING_LOOKBACK_MS
+    /// so a call that started before the detector's window is still seen.
+    pub fn current_status(&self, conn: &Connection, now_ms: i64) -> anyhow::Result<MeetingStatus> {
+        let events = db::query_recent_events(conn, now_ms - MEETING_LOOKBACK_MS)?;
+        Ok(self.status(&events, now_ms))
+    }
+
+    /// Whether a nudge should be held back because the user is in a call.
+    pub fn suppresses(&self, events: &[EventRow], now_ms: i64) -> bool {
+        self.status(events, now_ms).in_meeting
+    }
+}
+
+/// Friendly notification titles per nudge style, so internal style names
+/// ("vault_offer") never reach the user.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NudgeTitles {
+    pub gentle: String,
+    pub direct: String,
+    pub vault_offer: String,
+}
+
+impl Default for NudgeTitles {
+    fn default() -> Self {
+        Self {
+            gentle: "Quick check-in".to_string(),
+            direct: "Time to refocus".to_string(),
+            vault_offer: "Something from your vault".to_string(),
+        }
+    }
+}
+
+impl NudgeTitles {
+    /// Read overrides from `CCUBE_NUDGE_TITLES` (comma-separated
+    /// `style=title` pairs, e.g. `direct=Back to it,gentle=Hey`). Styles left
+    /// out keep their default title.
+    pub fn from_env() -> Result<Self, String> {
+        match std::env::var("CCUBE_NUDGE_TITLES") {
+            Ok(v) if !v.trim().is_empty() => {
+                parse_titles(&v).ok_or_else(|| format!("invalid CCUBE_NUDGE_TITLES: {v}"))
+            }
+            _ => Ok(Self::default()),
+        }
+    }
+
+    /// Display title for a nudge of `style`. Nudges without a style read as
+    /// gentle.
+    pub fn title(&self, style: Option<&NudgeStyle>) -> &str {
+        match style {
+            Some(NudgeStyle::Direct) => &self.direct,
+            Some(NudgeStyle::VaultOffer) => &self.vault_offer,
+            Some(NudgeStyle::Gentle) | None => &self.gentle,
+        }
+    }
+}
+
+/// How often recent nudges were rated helpful.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct NudgeAccuracy {
+    pub helpful: usize,
+    pub unhelpful: usize,
+    /// Percent rated helpful (None with no feedback).
+    pub accuracy: Option<f64>,
+    /// Whether the bar for showing a nudge is raised because most recent
+    /// nudges were marked wrong.
+    pub raised_bar: bool,
+}
+
+impl NudgeAccuracy {
+    /// Tally feedback rows. The bar is raised once at least 5 nudges are rated
+    /// and fewer than half were helpful.
+    pub fn from_feedback(rows: &[NudgeFeedbackRow]) -> Self {
+        let helpful = rows.iter().filter(|r| r.helpful).count();
+        let unhelpful = rows.len() - helpful;
+        let accuracy = (!rows.is_empty())
+            .then(|| (helpful as f64 * 1000.0 / rows.len() as f64).round() / 10.0);
+        Self {
+            helpful,
+            unhelpful,
+            accuracy,
+            raised_bar: rows.len() >= FEEDBACK_MIN_RATED
+                && accuracy.is_some_and(|a| a < FEEDBACK_MIN_ACCURACY),
+        }
+    }
+
+    /// Whether a nudge of `style` should be held back. With the bar raised,
+    /// only Direct nudges (the detector's most confident style) get through.
+    pub fn suppresses(&self, style: Option<&NudgeStyle>) -> bool {
+        self.raised_bar && style != Some(&NudgeStyle::Direct)
+    }
+}
+
+/// Whether the most recent focused run is still going and has lasted at least
+/// FLOW_MIN_MINUTES.
+pub fn in_flow(spans: &[Span], scoring: &FocusScoring, now_ms: i64) -> bool {
+    stats::focus_blocks(spans, scoring)
+        .last()
+        .is_some_and(|b| now_ms - b.end <= FLOW_RECENCY_MS && b.minutes >= FLOW_MIN_MINUTES)
+}
+
+pub fn parse_weekend_mode(value: &str) -> Option<WeekendMode> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "normal" => Some(WeekendMode::Normal),
+        "quiet" => Some(WeekendMode::Quiet),
+        "off" => Some(WeekendMode::Off),
+        _ => None,
+    }
+}
+
+/// Parse a comma-separated list of day names (`fri,sat`, `Saturday, Sunday`).
+/// Returns None if any entry is not a day name or the list is empty.
+pub fn parse_weekdays(value: &str) -> Option<Vec<Weekday>> {
+    let mut days = Vec::new();
+    for part in value.split(',') {
+        let day: Weekday = part.trim().parse().ok()?;
+        if !days.contains(&day) {
+            days.push(day);
+        }
+    }
+    if days.is_empty() { None } else { Some(days) }
+}
+
+/// Parse a comma-separated list of focus mode names into their stored
+/// spelling. Returns None if any entry is not a mode or the list is empty.
+pub fn parse_modes(value: &str) -> Option<Vec<String>> {
+    let known = [
+        FocusMode::Coding,
+        FocusMode::Writing,
+        FocusMode::VideoProduction,
+        FocusMode::Unspecified,
+    ];
+    let mut modes = Vec::new();
+    for part in value.split(',') {
+        let name = known
+            .iter()
+            .map(focus_mode::focus_mode_to_str)
+            .find(|m| m.eq_ignore_ascii_case(part.trim()))?;
+        if !modes.iter().any(|m| m == name) {
+            modes.push(name.to_string());
+        }
+    }
+    if modes.is_empty() { None } else { Some(modes) }
+}
+
+/// Parse `style=title` overrides on top of the default titles. Returns None
+/// for an unknown style or an empty title.
+pub fn parse_titles(value: &str) -> Option<NudgeTitles> {
+    let mut titles = NudgeTitles::default();
+    for part in value.split(',') {
+        let (style, title) = part.split_once('=')?;
+        let title = title.trim();
+        if title.is_empty() {
+            return None;
+        }
+        let slot = match style.trim().to_ascii_lowercase().as_str() {
+            "gentle" => &mut titles.gentle,
+            "direct" => &mut titles.direct,
+            "vault_offer" => &mut titles.vault_offer,
+            _ => return None,
+        };
+        *slot = title.to_string();
+    }
+    Some(titles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_parse_weekdays() {
+        assert_eq!(
+            parse_weekdays("fri, Sat"),
+            Some(vec![Weekday::Fri, Weekday::Sat])
+        );
+        assert_eq!(parse_weekdays("sunday"), Some(vec![Weekday::Sun]));
+        assert_eq!(parse_weekdays("sat,sat"), Some(vec![Weekday::Sat]));
+        assert_eq!(parse_weekdays("sat,funday"), None);
+        assert_eq!(parse_weekdays(""), None);
+    }
+
+    #[test]
+    fn test_weekend_gates() {
+        // 2026-10-16 is a Friday, 2026-10-17 a Saturday
+        let fri = date("2026-10-16");
+        let sat = date("2026-10-17");
+
+        let quiet = WeekendPolicy {
+            mode: WeekendMode::Quiet,
+            ..Default::default()
+        };
+        assert!(quiet.allows_notification(fri));
+        assert!(quiet.allows_detector(sat));
+        assert!(!quiet.allows_notification(sat));
+
+        let off_fri_sat = WeekendPolicy {
+            mode: WeekendMode::Off,
+            days: vec![Weekday::Fri, Weekday::Sat],
+        };
+        assert!(!off_fri_sat.allows_detector(fri));
+        assert!(off_fri_sat.allows_detector(date("2026-10-18")));
+
+        assert!(WeekendPolicy::default().allows_notification(sat));
+    }
+
+    #[test]
+    fn test_mode_nudge_policy() {
+        assert_eq!(
+            parse_modes("videoproduction, Writing,writing"),
+            Some(vec!["VideoProduction".to_string(), "Writing".to_string()])
+        );
+        assert_eq!(parse_modes("Coding,Gaming"), None);
+
+        let policy = ModeNudgePolicy {
+            silent_modes: parse_modes("VideoProduction").unwrap(),
+        };
+        assert!(!policy.allows_notification("VideoProduction"));
+        assert!(policy.allows_notification("Coding"));
+        assert!(ModeNudgePolicy::default().allows_notification("VideoProduction"));
+    }
+
+    #[test]
+    fn test_nudge_titles() {
+        let defaults = NudgeTitles::default();
+        assert_eq!(defaults.title(Some(&NudgeStyle::Direct)), "Time to refocus");
+        assert_eq!(defaults.title(None), "Quick check-in");
+
+        let titles = parse_titles("Direct = Back to it, vault_offer=From your notes").unwrap();
+        assert_eq!(titles.title(Some(&NudgeStyle::Direct)), "Back to it");
+        assert_eq!(
+            titles.title(Some(&NudgeStyle::VaultOffer)),
+            "From your notes"
+        );
+        assert_eq!(titles.gentle, defaults.gentle);
+
+        assert_eq!(parse_titles("urgent=Now"), None);
+        assert_eq!(parse_titles("direct="), None);
+        assert_eq!(parse_titles("direct"), None);
+    }
+
+    fn span(start_min: i64, end_min: i64, mode: &str) -> Span {
+        Span {
+            start: start_min * 60_000,
+            end: end_min * 60_000,
+            app: "Code.exe".to_string(),
+            title: None,
+            mode: mode.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_flow_protection() {
+        let flow = vec![span(0, 5, "Unspecified"), span(5, 30, "Coding")];
+        let now = 30 * 60_000;
+        let protect = FlowProtection { enabled: true };
+
+        assert!(protect.suppresses(&flow, &FocusScoring::Modes, Some(&NudgeStyle::Gentle), now));
+        assert!(protect.suppresses(&flow, &FocusScoring::Modes, None, now));
+        // The urgent style still gets through
+        assert!(!protect.suppresses(&flow, &FocusScoring::Modes, Some(&NudgeStyle::Direct), now));
+        // Off by default
+        assert!(!FlowProtection::default().suppresses(
+            &flow,
+            &FocusScoring::Modes,
+            Some(&NudgeStyle::Gentle),
+            now
+        ));
+
+        // Too short, or already over, isn't flow
+        let short = vec![span(20, 30, "Coding")];
+        assert!(!protect.suppresses(&short, &FocusScoring::Modes, Some(&NudgeStyle::Gentle), now));
+        let broken = vec![span(0, 25, "Coding"), span(25, 30, "Unspecified")];
+        assert!(!protect.suppresses(
+            &broken,
+            &FocusScoring::Modes,
+            Some(&NudgeStyle::Gentle),
+            now
+        ));
+    }
+
+    fn marker(id: i64, ts: i64, kind: &str) -> EventRow {
+        EventRow {
+            id,
+            ts,
+            kind: kind.to_string(),
+            app: None,
+            title: None,
+            duration_ms: None,
+            mode: None,
+            ocr_text: None,
+            source: None,
+        }
+    }
+
+    #[test]
+    fn test_startup_grace() {
+        let min = 60_000;
+        assert!(!StartupGrace::default().suppresses(100 * min, &[], 100 * min));
+        let grace = StartupGrace { grace_ms: 5 * min };
+
+        // Just after start
+        assert!(grace.suppresses(100 * min, &[], 103 * min));
+        assert!(!grace.suppresses(100 * min, &[], 106 * min));
+
+        // Back from a 40 minute break two minutes ago
+        let away = vec![
+            marker(1, 150 * min, "idle_start"),
+            marker(2, 190 * min, "idle_end"),
+        ];
+        assert!(grace.suppresses(100 * min, &away, 192 * min));
+        assert!(!grace.suppresses(100 * min, &away, 196 * min));
+
+        // A short break doesn't restart the grace period
+        let blip = vec![
+            marker(1, 185 * min, "idle_start"),
+            marker(2, 190 * min, "idle_end"),
+        ];
+        assert!(!grace.suppresses(100 * min, &blip, 192 * min));
+
+        // The break started before the loaded events
+        let orphan = vec![marker(1, 190 * min, "idle_end")];
+        assert!(grace.suppresses(100 * min, &orphan, 192 * min));
+
+        assert!(!StartupGrace { grace_ms: 0 }.suppresses(100 * min, &[], 100 * min));
+    }
+
+    fn call(id: i64, start_min: i64, app: &str, dur_min: Option<i64>) -> EventRow {
+        EventRow {
+            app: Some(app.to_string()),
+            duration_ms: dur_min.map(|d| d * 60_000),
+            ..marker(id, start_min * 60_000, "app_focus")
+        }
+    }
+
+    #[test]
+    fn test_meeting_status() {
+        let min = 60_000;
+        let detection = MeetingDetection::default();
+        assert!(detection.is_call_app("Zoom.exe"));
+        assert!(!detection.is_call_app("Code.exe"));
+
+        // Zoom, briefly Teams, then Zoom again: one call since minute 10
+        let events = vec![
+            call(1, 0, "Code.exe", Some(10)),
+            call(2, 10, "Zoom.exe", Some(5)),
+            marker(3, 12 * min, "idle_start"),
+            call(4, 15, "ms-teams.exe", Some(1)),
+            call(5, 16, "Zoom.exe", None),
+        ];
+        let status = detection.status(&events, 22 * min);
+        assert!(status.in_meeting);
+        assert_eq!(status.app.as_deref(), Some("Zoom.exe"));
+        assert_eq!(status.since, Some(10 * min));
+        assert_eq!(status.minutes, 12.0);
+
+        // Not long enough yet
+        assert!(!detection.suppresses(&events, 14 * min));
+
+        // The call window closed a while ago
+        let ended = vec![call(1, 0, "Zoom.exe", Some(30))];
+        assert!(!detection.suppresses(&ended, 40 * min));
+
+        // Back in the editor
+        let back = vec![
+            call(1, 0, "Zoom.exe", Some(30)),
+            call(2, 30, "Code.exe", None),
+        ];
+        assert_eq!(detection.status(&back, 35 * min), MeetingStatus::default());
+
+        // Custom list and threshold
+        let custom = MeetingDetection {
+            call_apps: stats::parse_keywords("Discord"),
+            min_ms: min,
+        };
+        let discord = vec![call(1, 0, "Discord.exe", None)];
+        assert!(custom.suppresses(&discord, 2 * min));
+        assert!(!detection.suppresses(&discord, 30 * min));
+    }
+
+    #[test]
+    fn test_meeting_status_sees_calls_longer_than_an_hour() {
+        let dir = tempfile::TempDir::new().unwrap();
+        db::init_databases(dir.path()).unwrap();
+        let conn = db::open_events_db(dir.path()).unwrap();
+        let min = 60_000;
+        let now = 10 * 60 * min;
+
+        // The call started three hours ago, before the detector's hour
+        let focus = |ts: i64, app: &str| {
+            db::insert_event(&conn, ts, "app_focus", Some(app), None, None).unwrap()
+        };
+        let code = focus(now - 190 * min, "Code.exe");
+        db::update_event_duration(&conn, code, 10 * min).unwrap();
+        focus(now - 180 * min, "Zoom.exe");
+
+        let detection = MeetingDetection::default();
+        let status = detection.current_status(&conn, now).unwrap();
+        assert!(status.in_meeting);
+        assert_eq!(status.since, Some(now - 180 * min));
+        assert_eq!(status.minutes, 180.0);
+    }
+
+    #[test]
+    fn test_nudge_accuracy() {
+        let rows = |helpful: &[bool]| -> Vec<NudgeFeedbackRow> {
+            helpful
+                .iter()
+                .enumerate()
+                .map(|(i, &h)| NudgeFeedbackRow {
+                    decision_id: i as i64,
+                    ts: i as i64,
+                    helpful: h,
+                })
+                .collect()
+        };
+
+        let none = NudgeAccuracy::from_feedback(&[]);
+        assert_eq!(none.accuracy, None);
+        assert!(!none.suppresses(Some(&NudgeStyle::Gentle)));
+
+        // Mostly wrong, but too few ratings to act on
+        let few = NudgeAccuracy::from_feedback(&rows(&[false, false, true]));
+        assert_eq!(few.accuracy, Some(33.3));
+        assert!(!few.raised_bar);
+
+        let wrong = NudgeAccuracy::from_feedback(&rows(&[false, false, false, true, true]));
+        assert_eq!((wrong.helpful, wrong.unhelpful), (2, 3));
+        assert!(wrong.raised_bar);
+        assert!(wrong.suppresses(Some(&NudgeStyle::Gentle)));
+        assert!(wrong.suppresses(None));
+        assert!(!wrong.suppresses(Some(&NudgeStyle::Direct)));
+
+        let good = NudgeAccuracy::from_feedback(&rows(&[true, true, true, false, false]));
+        assert!(!good.raised_bar);
+    }
+}
@@ -1,41 +1,54 @@
-use anyhow::{Context, Result};
-use std::path::PathBuf;
-
-/// Resolved data root with standard subdirectories.
-pub struct DataRoot {
-    pub memory_dir: PathBuf,
-    pub data_dir: PathBuf,
-    pub logs_dir: PathBuf,
-}
-
-impl DataRoot {
-    /// Resolve the ccube data root directory.
-    ///
-    /// Priority: `CCUBE_DATA_DIR` env var > platform default via `directories` crate.
-    pub fn resolve() -> Result<Self> {
-        let root = if let Ok(custom) = std::env::var("CCUBE_DATA_DIR") {
-            PathBuf::from(custom)
-        } else {
-            let dirs = directories::ProjectDirs::from("", "", "ccube")
-                .context("could not determine data directory for this platform")?;
-            dirs.data_dir().to_path_buf()
-        };
-
-        let memory_dir = root.join("memory");
-        let data_dir = root.join("data");
-        let logs_dir = root.join("logs");
-
-        std::fs::create_dir_all(&memory_dir)
-            .with_context(|| format!("failed to create memory dir: {}", memory_dir.display()))?;
-        std::fs::create_dir_all(&data_dir)
-            .with_context(|| format!("failed to create data dir: {}", data_dir.display()))?;
-        std::fs::create_dir_all(&logs_dir)
-            .with_context(|| format!("failed to create logs dir: {}", logs_dir.display()))?;
-
-        Ok(Self {
-            memory_dir,
-            data_dir,
-            logs_dir,
-        })
-    }
-}
+use anyhow::{Context, Result};
+use std::path::PathBuf;
+
+/// Data root used (relative to the working directory) when neither
+/// `CCUBE_DATA_DIR` nor a platform data directory is available.
+const FALLBACK_DIR_NAME: &str = "ccube-data";
+
+/// Resolved data root with standard subdirectories.
+pub struct DataRoot {
+    pub memory_dir: PathBuf,
+    pub data_dir: PathBuf,
+    pub logs_dir: PathBuf,
+}
+
+impl DataRoot {
+    /// Resolve the ccube data root directory.
+    ///
+    /// Priority: `CCUBE_DATA_DIR` env var > platform default via `directories` crate.
+    /// If the platform has no home directory (e.g. minimal containers), falls back
+    /// to `./ccube-data` with a warning instead of failing.
+    pub fn resolve() -> Result<Self> {
+        let root = if let Ok(custom) = std::env::var("CCUBE_DATA_DIR") {
+            PathBuf::from(custom)
+        } else if let Some(dirs) = directories::ProjectDirs::from("", "", "ccube") {
+            dirs.data_dir().to_path_buf()
+        } else {
+            let fallback = std::env::current_dir()
+                .context("could not determine data directory or current directory")?
+                .join(FALLBACK_DIR_NAME);
+            tracing::warn!(
+                path = %fallback.display(),
+                "could not determine the platform data directory; set CCUBE_DATA_DIR to choose"
+            );
+            fallback
+        };
+
+        let memory_dir = root.join("memory");
+        let data_dir = root.join("data");
+        let logs_dir = root.join("logs");
+
+        std::fs::create_dir_all(&memory_dir)
+            .with_context(|| format!("failed to create memory dir: {}", memory_dir.display()))?;
+        std::fs::create_dir_all(&data_dir)
+            .with_context(|| format!("failed to create data dir: {}", data_dir.display()))?;
+        std::fs::create_dir_all(&logs_dir)
+            .with_context(|| format!("failed to create logs dir: {}", logs_dir.display()))?;
+
+        Ok(Self {
+            memory_dir,
+            data_dir,
+            logs_dir,
+        })
+    }
+}
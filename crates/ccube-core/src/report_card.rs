@@ -0,0 +1,393 @@
+// Productivity report card — a letter grade for one day.
+//
+// Grades four factors from the day's spans (focus score, deep-work ratio,
+// distraction time, switch rate) against configurable cutoffs, then averages
+// them into an overall grade.
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::stats::{self, FocusScoring, Span};
+
+/// Focused blocks at least this long count as deep work.
+pub const DEEP_WORK_MIN_MINUTES: f64 = 25.0;
+
+/// Days with less activity than this aren't graded.
+const MIN_GRADED_ACTIVE_MINUTES: f64 = 30.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Grade {
+    A,
+    B,
+    C,
+    D,
+    F,
+}
+
+impl Grade {
+    fn points(self) -> f64 {
+        match self {
+            Grade::A => 4.0,
+            Grade::B => 3.0,
+            Grade::C => 2.0,
+            Grade::D => 1.0,
+            Grade::F => 0.0,
+        }
+    }
+
+    fn from_points(points: f64) -> Self {
+        match points.round() as i64 {
+            4.. => Grade::A,
+            3 => Grade::B,
+            2 => Grade::C,
+            1 => Grade::D,
+            _ => Grade::F,
+        }
+    }
+}
+
+impl std::fmt::Display for Grade {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+/// Cutoffs for grades A to D; anything worse is an F.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GradeCutoffs {
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+    pub d: f64,
+    /// False for metrics where lower values are better.
+    pub higher_is_better: bool,
+}
+
+impl GradeCutoffs {
+    const fn higher(a: f64, b: f64, c: f64, d: f64) -> Self {
+        Self {
+            a,
+            b,
+            c,
+            d,
+            higher_is_better: true,
+        }
+    }
+
+    const fn lower(a: f64, b: f64, c: f64, d: f64) -> Self {
+        Self {
+            a,
+            b,
+            c,
+            d,
+            higher_is_better: false,
+        }
+    }
+
+    pub fn grade(&self, value: f64) -> Grade {
+        let meets = |cutoff: f64| {
+            if self.higher_is_better {
+                value >= cutoff
+            } else {
+                value <= cutoff
+            }
+        };
+        if meets(self.a) {
+            Grade::A
+        } else if meets(self.b) {
+            Grade::B
+        } else if meets(self.c) {
+            Grade::C
+        } else if meets(self.d) {
+            Grade::D
+        } else {
+            Grade::F
+        }
+    }
+
+    /// The cutoff for the next grade up, or None at A.
+    fn next_cutoff(&self, grade: Grade) -> Option<(Grade, f64)> {
+        match grade {
+            Grade::A => None,
+            Grade::B => Some((Grade::A, self.a)),
+            Grade::C => Some((Grade::B, self.b)),
+            Grade::D => Some((Grade::C, self.c)),
+            Grade::F => Some((Grade::D, self.d)),
+        }
+    }
+}
+
+/// Grading cutoffs for every factor.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ReportCardThresholds {
+    /// Percent of active time that was focused.
+    pub focus_score: GradeCutoffs,
+    /// Percent of focused time spent in blocks of 25+ minutes.
+    pub deep_work: GradeCutoffs,
+    /// Minutes of unfocused active time.
+    pub distraction_minutes: GradeCutoffs,
+    /// App switches per active hour.
+    pub switches_per_hour: GradeCutoffs,
+}
+
+impl Default for ReportCardThresholds {
+    fn default() -> Self {
+        Self {
+            focus_score: GradeCutoffs::higher(80.0, 65.0, 50.0, 35.0),
+            deep_work: GradeCutoffs::higher(70.0, 50.0, 30.0, 15.0),
+            distraction_minutes: GradeCutoffs::lower(30.0, 60.0, 90.0, 120.0),
+            switches_per_hour: GradeCutoffs::lower(10.0, 15.0, 20.0, 30.0),
+        }
+    }
+}
+
+impl ReportCardThresholds {
+    /// Defaults, overridden per factor by `CCUBE_GRADE_FOCUS_SCORE`,
+    /// `CCUBE_GRADE_DEEP_WORK`, `CCUBE_GRADE_DISTRACTION_MINS` and
+    /// `CCUBE_GRADE_SWITCHES_PER_HOUR`, each four comma-separated A,B,C,D
+    /// cutoffs (e.g. `80,65,50,35`).
+    pub fn from_env() -> Result<Self, String> {
+        let mut thresholds = Self::default();
+        for (var, cutoffs) in [
+            ("CCUBE_GRADE_FOCUS_SCORE", &mut thresholds.focus_score),
+            ("CCUBE_GRADE_DEEP_WORK", &mut thresholds.deep_work),
+            (
+                "CCUBE_GRADE_DISTRACTION_MINS",
+                &mut thresholds.distraction_minutes,
+            ),
+            (
+                "CCUBE_GRADE_SWITCHES_PER_HOUR",
+                &mut thresholds.switches_per_hour,
+            ),
+        ] {
+            if let Ok(v) = std::env::var(var)
+                && !v.trim().is_empty()
+            {
+                *cutoffs = parse_cutoffs(&v, cutoffs.higher_is_better)
+                    .ok_or_else(|| format!("invalid {var}: {v}"))?;
+            }
+        }
+        Ok(thresholds)
+    }
+}
+
+/// Parse four comma-separated cutoffs, best grade first. Returns None unless
+/// they're numbers ordered from best to worst.
+pub fn parse_cutoffs(value: &str, higher_is_better: bool) -> Option<GradeCutoffs> {
+    let nums: Vec<f64> = value
+        .split(',')
+        .map(|p| p.trim().parse::<f64>().ok().filter(|n| n.is_finite()))
+        .collect::<Option<_>>()?;
+    let [a, b, c, d] = nums[..] else {
+        return None;
+    };
+    let ordered = if higher_is_better {
+        a >= b && b >= c && c >= d
+    } else {
+        a <= b && b <= c && c <= d
+    };
+    ordered.then_some(GradeCutoffs {
+        a,
+        b,
+        c,
+        d,
+        higher_is_better,
+    })
+}
+
+/// One graded factor with a one-line justification.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportFactor {
+    pub factor: String,
+    pub value: f64,
+    pub grade: Grade,
+    pub note: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportCard {
+    pub date: NaiveDate,
+    pub active_minutes: f64,
+    /// None when the day had too little activity to grade.
+    pub grade: Option<Grade>,
+    pub factors: Vec<ReportFactor>,
+}
+
+/// Grade one day's spans.
+pub fn report_card(
+    date: NaiveDate,
+    spans: &[Span],
+    scoring: &FocusScoring,
+    thresholds: &ReportCardThresholds,
+) -> ReportCard {
+    let totals = scoring.totals(spans);
+    let active_minutes = stats::ms_to_minutes(totals.active_ms);
+    if active_minutes < MIN_GRADED_ACTIVE_MINUTES {
+        return ReportCard {
+            date,
+            active_minutes,
+            grade: None,
+            factors: Vec::new(),
+        };
+    }
+
+    let focused_minutes = stats::ms_to_minutes(totals.focused_ms);
+    let deep_minutes: f64 = stats::focus_blocks(spans, scoring)
+        .iter()
+        .map(|b| b.minutes)
+        .filter(|m| *m >= DEEP_WORK_MIN_MINUTES)
+        .sum();
+    let deep_work = if focused_minutes > 0.0 {
+        deep_minutes * 100.0 / focused_minutes
+    } else {
+        0.0
+    };
+    let switches_per_hour = stats::count_switches(spans) as f64 * 60.0 / active_minutes;
+
+    let factors = vec![
+        factor(
+            "focus_score",
+            totals.focus_score().unwrap_or(0.0),
+            &thresholds.focus_score,
+            |v| format!("{v:.0}% of active time focused"),
+        ),
+        factor("deep_work", deep_work, &thresholds.deep_work, |v| {
+            format!("{v:.0}% of focused time in blocks of {DEEP_WORK_MIN_MINUTES:.0}+ min")
+        }),
+        factor(
+            "distraction_minutes",
+            active_minutes - focused_minutes,
+            &thresholds.distraction_minutes,
+            |v| format!("{v:.0} min of unfocused time"),
+        ),
+        factor(
+            "switches_per_hour",
+            switches_per_hour,
+            &thresholds.switches_per_hour,
+            |v| format!("{v:.1} app switches per active hour"),
+        ),
+    ];
+
+    let points = factors.iter().map(|f| f.grade.points()).sum::<f64>() / factors.len() as f64;
+    ReportCard {
+        date,
+        active_minutes,
+        grade: Some(Grade::from_points(points)),
+        factors,
+    }
+}
+
+fn factor(
+    name: &str,
+    value: f64,
+    cutoffs: &GradeCutoffs,
+    describe: impl Fn(f64) -> String,
+) -> ReportFactor {
+    let value = (value * 10.0).round() / 10.0;
+    let grade = cutoffs.grade(value);
+    let note = match cutoffs.next_cutoff(grade) {
+        Some((next, cutoff)) => {
+            let cmp = if cutoffs.higher_is_better { ">=" } else { "<=" };
+            format!("{}; {next} needs {cmp} {cutoff}", describe(value))
+        }
+        None => describe(value),
+    };
+    ReportFactor {
+        factor: name.to_string(),
+        value,
+        grade,
+        note,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MIN: i64 = 60_000;
+
+    fn span(start_min: i64, end_min: i64, app: &str, mode: &str) -> Span {
+        Span {
+            start: start_min * MIN,
+            end: end_min * MIN,
+            app: app.to_string(),
+            title: None,
+            mode: mode.to_string(),
+        }
+    }
+
+    fn date() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 10, 16).unwrap()
+    }
+
+    #[test]
+    fn test_parse_cutoffs() {
+        let c = parse_cutoffs("80, 65,50,35", true).unwrap();
+        assert_eq!(c.grade(80.0), Grade::A);
+        assert_eq!(c.grade(64.9), Grade::C);
+        assert_eq!(c.grade(10.0), Grade::F);
+        assert!(parse_cutoffs("35,50,65,80", true).is_none());
+        assert!(parse_cutoffs("10,20,30,40", false).is_some());
+        assert!(parse_cutoffs("80,65,50", true).is_none());
+    }
+
+    #[test]
+    fn test_report_card_grades() {
+        // 90 min of coding in one block, 10 min of browsing
+        let spans = vec![
+            span(0, 90, "Code.exe", "Coding"),
+            span(90, 100, "chrome.exe", "Unspecified"),
+        ];
+        let card = report_card(
+            date(),
+            &spans,
+            &FocusScoring::Modes,
+            &ReportCardThresholds::default(),
+        );
+        assert_eq!(card.grade, Some(Grade::A));
+        let focus = &card.factors[0];
+        assert_eq!(focus.factor, "focus_score");
+        assert_eq!(focus.value, 90.0);
+        assert_eq!(focus.grade, Grade::A);
+
+        // Whitelist scoring grades the same day by the listed apps only, so
+        // the 10 min of chrome is too short to count as deep work
+        let whitelist = FocusScoring::Whitelist {
+            apps: stats::parse_keywords("chrome"),
+            work_hours: None,
+        };
+        let card = report_card(date(), &spans, &whitelist, &ReportCardThresholds::default());
+        assert_eq!(card.factors[0].value, 10.0);
+        assert_eq!(card.factors[1].value, 0.0);
+
+        // Choppy day: short focus bursts, lots of switching
+        let mut choppy = Vec::new();
+        for i in 0..20 {
+            choppy.push(span(i * 6, i * 6 + 3, "Code.exe", "Coding"));
+            choppy.push(span(i * 6 + 3, i * 6 + 6, "discord.exe", "Unspecified"));
+        }
+        let card = report_card(
+            date(),
+            &choppy,
+            &FocusScoring::Modes,
+            &ReportCardThresholds::default(),
+        );
+        assert_eq!(card.factors[0].grade, Grade::C);
+        assert_eq!(card.factors[1].grade, Grade::F);
+        assert!(card.factors[1].note.ends_with("D needs >= 15"));
+        assert_eq!(card.grade, Some(Grade::C));
+    }
+
+    #[test]
+    fn test_report_card_too_little_activity() {
+        let spans = vec![span(0, 10, "Code.exe", "Coding")];
+        let card = report_card(
+            date(),
+            &spans,
+            &FocusScoring::Modes,
+            &ReportCardThresholds::default(),
+        );
+        assert!(card.grade.is_none());
+        assert!(card.factors.is_empty());
+    }
+}
@@ -0,0 +1,4169 @@
+// Activity statistics over the events table.
+//
+// Pure functions over event rows (no I/O), in the same spirit as briefing.rs.
+// "Active" time is app_focus time with idle periods removed. "Focused" time is
+// the part of it whose inferred mode is a concrete FocusMode (Coding, Writing,
+// VideoProduction); Unspecified time is active but unfocused.
+
+use anyhow::Result;
+use chrono::{Datelike, NaiveDate, TimeZone, Timelike};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::db::{self, EventRow};
+use crate::ingest;
+
+/// How far before a range to start loading events, so that spans which began
+/// before the range (e.g. a long editor session) are clipped rather than lost.
+const SPAN_LOOKBACK_MS: i64 = 3_600_000;
+
+pub(crate) const HOUR_MS: i64 = 3_600_000;
+
+/// Focused spans separated by less than this still count as one focus block
+/// (covers brief capture gaps between consecutive events).
+const FOCUS_BLOCK_GAP_MS: i64 = 60_000;
+
+/// Number of apps listed in a daily review.
+const REVIEW_TOP_APPS: usize = 5;
+
+/// An hour needs at least this much active time to count as a baseline sample.
+const BASELINE_MIN_ACTIVE_MS: i64 = 10 * 60_000;
+
+/// Sampled hours an hour of the day needs before its average focus is ranked.
+pub const PRODUCTIVE_HOUR_MIN_SAMPLES: usize = 3;
+
+/// Active time needed today before a focus projection is offered.
+const PROJECTION_MIN_ACTIVE_MS: i64 = 30 * 60_000;
+
+/// Weight of the last hour's score in the projection (the rest is the day so far).
+const PROJECTION_RECENT_WEIGHT: f64 = 0.3;
+
+/// Pseudo-mode name for idle time in mode breakdowns.
+pub const IDLE_MODE: &str = "Idle";
+
+/// Window that switch pressure looks at.
+pub const PRESSURE_WINDOW_MS: i64 = 5 * 60_000;
+
+/// Switch rate assumed when there's no baseline history yet.
+const DEFAULT_SWITCHES_PER_HOUR: f64 = 12.0;
+
+/// Current values more than this many standard deviations from the baseline
+/// mean are reported as out of range.
+const BASELINE_Z_LIMIT: f64 = 2.0;
+
+/// Gap between two uses of the same app that still counts as one session,
+/// unless `CCUBE_SESSION_MERGE_GAP_SECS` says otherwise.
+pub const DEFAULT_SESSION_MERGE_GAP_MS: i64 = 60_000;
+
+/// Shortest idle period that counts as "being away" for since-return summaries.
+pub const DEFAULT_RETURN_MIN_IDLE_MS: i64 = 30 * 60_000;
+
+/// Longest unfocused stretch a focus streak survives by default.
+pub const DEFAULT_STREAK_MAX_BREAK_MS: i64 = 2 * 60_000;
+
+/// How far back a focus streak is looked for.
+pub const STREAK_LOOKBACK_MS: i64 = 12 * HOUR_MS;
+
+/// Inactivity after which the live streak ends when
+/// `CCUBE_STREAK_IDLE_TIMEOUT_SECS` is unset. 0 leaves it to idle detection.
+pub const DEFAULT_STREAK_IDLE_TIMEOUT_MS: i64 = 0;
+
+/// Number of distractions returned by a ranked list unless asked otherwise.
+pub const DEFAULT_DISTRACTION_LIMIT: usize = 3;
+
+/// Impact weight of time spent in a focused mode other than the day's main
+/// one: a different task, but still work. Unfocused time weighs 1.0.
+const OTHER_TASK_WEIGHT: f64 = 0.5;
+
+/// Event kind for a stretch the user declared active by hand (reading, watching
+/// a tutorial) so idle detection doesn't cut it out. `duration_ms` holds its
+/// length.
+pub const MANUAL_ACTIVE_KIND: &str = "manual_active";
+
+/// Longest single manual active declaration. Events are only loaded this far
+/// before a range, so a longer one could be missed at the range start.
+pub const MAX_MANUAL_ACTIVE_MS: i64 = SPAN_LOOKBACK_MS;
+
+/// Shortest gap between focus blocks that counts as a break.
+const MIN_BREAK_MS: i64 = 5 * 60_000;
+
+/// Longest gap between focus blocks that still counts as a break; longer
+/// ones are meals, meetings or the end of a work stretch.
+const MAX_BREAK_MS: i64 = 2 * HOUR_MS;
+
+/// Daily focused-time target when `CCUBE_DAILY_FOCUS_TARGET_MINUTES` is unset.
+pub const DEFAULT_FOCUS_TARGET_MINUTES: u32 = 240;
+
+/// Refocusing time charged per app switch when `CCUBE_SWITCH_RECOVERY_SECS`
+/// is unset.
+pub const DEFAULT_SWITCH_RECOVERY_SECS: u32 = 23;
+
+/// Trailing windows a summary reports separate focus scores for, shortest
+/// first, so a short window can be read against the longer ones as a trend.
+pub const FOCUS_TIMEFRAMES: [(&str, i64); 3] =
+    [("5m", 5 * 60_000), ("30m", 30 * 60_000), ("1h", HOUR_MS)];
+
+/// How far back the longest of `FOCUS_TIMEFRAMES` reaches.
+pub const FOCUS_TIMEFRAMES_LOOKBACK_MS: i64 = FOCUS_TIMEFRAMES[FOCUS_TIMEFRAMES.len() - 1].1;
+
+/// A resolved stretch of active time in a single app.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Span {
+    pub start: i64,
+    pub end: i64,
+    pub app: String,
+    pub title: Option<String>,
+    pub mode: String,
+}
+
+impl Span {
+    pub fn duration_ms(&self) -> i64 {
+        self.end - self.start
+    }
+
+    pub fn is_focused(&self) -> bool {
+        is_focused_mode(&self.mode)
+    }
+}
+
+/// Active vs focused time over some range.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct FocusTotals {
+    pub active_ms: i64,
+    pub focused_ms: i64,
+}
+
+impl FocusTotals {
+    /// Focused share of active time as a 0-100 score, or None with no activity.
+    pub fn focus_score(&self) -> Option<f64> {
+        if self.active_ms <= 0 {
+            return None;
+        }
+        Some(round1(
+            self.focused_ms as f64 * 100.0 / self.active_ms as f64,
+        ))
+    }
+}
+
+/// One day's entry in the weekly focus view. Both fields are None for days
+/// that haven't happened yet, and for days whose events were pruned without
+/// a stored rollup.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DayFocus {
+    pub date: NaiveDate,
+    pub focus_score: Option<f64>,
+    pub active_minutes: Option<f64>,
+}
+
+/// Weekly minutes per focus mode over consecutive Monday-Sunday weeks.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModeWeeklyTrend {
+    /// Monday of each week, oldest first.
+    pub weeks: Vec<NaiveDate>,
+    /// Days of the last week elapsed so far (1-7); below 7 it is partial.
+    pub last_week_days: u32,
+    /// Modes with any time in the range, most used overall first.
+    pub modes: Vec<ModeWeeks>,
+}
+
+/// One mode's minutes for each week of a `ModeWeeklyTrend`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModeWeeks {
+    pub mode: String,
+    /// Parallel to `ModeWeeklyTrend::weeks`; None for weeks that began before
+    /// the oldest stored event, whose time is partly or wholly pruned.
+    pub minutes: Vec<Option<f64>>,
+}
+
+/// One day of a year-long activity calendar. Days without a stored rollup
+/// have zero active minutes and no focus score.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CalendarDay {
+    pub date: NaiveDate,
+    pub active_minutes: f64,
+    pub focus_score: Option<f64>,
+}
+
+/// Today's focus against the average of recent days.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TodayVsAverage {
+    pub today_focus_score: Option<f64>,
+    pub today_active_minutes: f64,
+    pub avg_focus_score: Option<f64>,
+    pub avg_active_minutes: Option<f64>,
+    /// Today's score minus the average, in points.
+    pub focus_score_delta: Option<f64>,
+    pub active_minutes_delta: Option<f64>,
+    /// Past days with activity the averages are taken over; fewer than asked
+    /// for while history is short.
+    pub sample_days: usize,
+    /// Set while today is still in progress: today's totals stop at the
+    /// current time but the averages are whole days, so active time in
+    /// particular reads low until the day is over.
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+/// How a break between focus blocks was spent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BreakKind {
+    /// Mostly away from the screen (idle, or the computer off).
+    Restful,
+    /// Mostly on the screen in unfocused apps.
+    Screen,
+}
+
+/// One break between two focus blocks.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BreakEntry {
+    pub start: i64,
+    pub end: i64,
+    pub minutes: f64,
+    /// Active (unfocused) screen time during the break.
+    pub screen_minutes: f64,
+    pub kind: BreakKind,
+    /// Length of the focus block the break was followed by.
+    pub focus_after_minutes: f64,
+}
+
+/// Breaks of one kind, averaged.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BreakKindSummary {
+    pub count: usize,
+    pub avg_minutes: Option<f64>,
+    pub avg_focus_after_minutes: Option<f64>,
+}
+
+/// Breaks between focus blocks, split into restful and screen breaks, with
+/// how long the focus that followed lasted.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BreakAnalysis {
+    pub breaks: Vec<BreakEntry>,
+    pub restful: BreakKindSummary,
+    pub screen: BreakKindSummary,
+}
+
+/// Progress toward the daily focused-time target.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TargetProgress {
+    pub target_minutes: u32,
+    pub focused_minutes: f64,
+    /// Focused time as a percentage of the target; goes past 100 once met.
+    pub percent: f64,
+    pub met: bool,
+}
+
+/// Estimated time lost to refocusing after app switches.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SwitchingCost {
+    /// Switches counted under `counting`.
+    pub switches: usize,
+    pub counting: SwitchCounting,
+    /// Refocusing time charged per switch.
+    pub recovery_secs: u32,
+    pub minutes_lost: f64,
+    /// `minutes_lost` as a percentage of active time (None with no activity).
+    pub share_of_active: Option<f64>,
+}
+
+/// Time spent in one app.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AppMinutes {
+    pub app: String,
+    pub minutes: f64,
+}
+
+/// Top app for one hour of a day (`hour` is the local clock hour, so a DST
+/// change skips or repeats one).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HourLeader {
+    pub hour: u32,
+    pub leader: Option<AppMinutes>,
+}
+
+/// Time spent in one focus mode.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModeMinutes {
+    pub mode: String,
+    pub minutes: f64,
+    /// Distinct apps used in this mode (0 for Idle).
+    #[serde(default)]
+    pub app_count: usize,
+}
+
+/// The longest unbroken stretch of focused work in a range.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FocusBlock {
+    pub start: i64,
+    pub end: i64,
+    pub minutes: f64,
+}
+
+/// Everything a "how did the day go?" review needs, in one object.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DailyReview {
+    pub date: NaiveDate,
+    pub active_minutes: f64,
+    pub focused_minutes: f64,
+    pub focus_score: Option<f64>,
+    pub top_apps: Vec<AppMinutes>,
+    pub modes: Vec<ModeMinutes>,
+    pub longest_focus_block: Option<FocusBlock>,
+    pub top_distraction: Option<AppMinutes>,
+    /// Every focus block of the day, in time order.
+    #[serde(default)]
+    pub sessions: Vec<FocusBlock>,
+    /// None when no daily target was given.
+    #[serde(default)]
+    pub target_progress: Option<TargetProgress>,
+}
+
+/// How one metric over the last hour compares to the historical baseline.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetricDeviation {
+    pub metric: String,
+    pub current: Option<f64>,
+    pub baseline_mean: Option<f64>,
+    pub baseline_stddev: Option<f64>,
+    /// (current - mean) / stddev; None if either side is missing or stddev is 0.
+    pub z_score: Option<f64>,
+    /// Percentage difference from the mean (+30 = 30% higher).
+    pub percent_diff: Option<f64>,
+    pub in_range: Option<bool>,
+    /// `z_score` banded by `Severity::from_z_score` (severe for any change
+    /// from a flat baseline).
+    #[serde(default)]
+    pub severity: Option<Severity>,
+    /// `severity` as 0-3.
+    #[serde(default)]
+    pub severity_level: Option<u8>,
+}
+
+/// Last-hour metrics against a baseline built from past active hours.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BaselineDeviation {
+    pub baseline_days: i64,
+    /// Number of past hours with enough activity to serve as samples.
+    pub samples: usize,
+    pub metrics: Vec<MetricDeviation>,
+}
+
+/// Which app changes count as a switch in switch-rate metrics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SwitchCounting {
+    /// Every change of foreground app.
+    #[default]
+    All,
+    /// Only changes that also change focus mode: editor to terminal while
+    /// coding doesn't count, editor to chat does.
+    CrossMode,
+}
+
+impl SwitchCounting {
+    /// Read `CCUBE_COUNT_SAME_MODE_SWITCHES` (true | false, default true).
+    /// false counts only cross-mode switches.
+    pub fn from_env() -> Result<Self, String> {
+        match std::env::var("CCUBE_COUNT_SAME_MODE_SWITCHES") {
+            Ok(v) if !v.trim().is_empty() => match v.trim().to_ascii_lowercase().as_str() {
+                "true" | "1" | "yes" => Ok(Self::All),
+                "false" | "0" | "no" => Ok(Self::CrossMode),
+                _ => Err(format!("invalid CCUBE_COUNT_SAME_MODE_SWITCHES: {v}")),
+            },
+            _ => Ok(Self::default()),
+        }
+    }
+
+    /// Switches in `spans` under this rule.
+    pub fn count(self, spans: &[Span]) -> usize {
+        match self {
+            Self::All => count_switches(spans),
+            Self::CrossMode => count_mode_switches(spans),
+        }
+    }
+}
+
+/// What counts as focused time wherever focus is measured: daily roll-ups,
+/// reviews, trends, baselines, report cards, exports and the target, and also
+/// focus blocks, breaks, streaks and flow protection. Ranked distractions stay
+/// mode-based, since they measure time away from the main focus mode.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum FocusScoring {
+    /// Time in a focused mode (Coding, Writing, VideoProduction).
+    #[default]
+    Modes,
+    /// Only time in `apps` (lowercase name fragments) counts as focused,
+    /// whatever its mode. Other active time counts against the score during
+    /// `work_hours` (local time), or at any hour when there are none.
+    Whitelist {
+        apps: Vec<String>,
+        work_hours: Option<WorkHours>,
+    },
+}
+
+/// Local clock hours [start_hour, end_hour) of the working day. An end at or
+/// before the start wraps past midnight (22-6).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WorkHours {
+    pub start_hour: u32,
+    pub end_hour: u32,
+}
+
+impl WorkHours {
+    /// Parse `start-end` in whole hours, e.g. `9-17`.
+    pub fn parse(value: &str) -> Option<Self> {
+        let (start, end) = value.trim().split_once('-')?;
+        let (start_hour, end_hour) = (start.trim().parse().ok()?, end.trim().parse().ok()?);
+        (start_hour < 24 && end_hour <= 24 && start_hour != end_hour).then_some(Self {
+            start_hour,
+            end_hour,
+        })
+    }
+
+    pub fn contains(&self, hour: u32) -> bool {
+        if self.start_hour < self.end_hour {
+            (self.start_hour..self.end_hour).contains(&hour)
+        } else {
+            hour >= self.start_hour || hour < self.end_hour
+        }
+    }
+
+    /// Time of `span` that falls within these hours, local time.
+    fn overlap_ms(&self, span: &Span) -> i64 {
+        let edges = local_hour_edges(span.start, span.end, &chrono::Local);
+        edges
+            .windows(2)
+            .filter(|w| {
+                chrono::Local
+                    .timestamp_millis_opt(w[0])
+                    .single()
+                    .is_some_and(|local| self.contains(local.hour()))
+            })
+            .map(|w| (span.end.min(w[1]) - span.start.max(w[0])).max(0))
+            .sum()
+    }
+}
+
+impl FocusScoring {
+    /// Read `CCUBE_FOCUS_SCORING` (modes | whitelist, default modes) and, for
+    /// whitelist, `CCUBE_FOCUS_APPS` (comma-separated app name fragments) and
+    /// the optional `CCUBE_FOCUS_WORK_HOURS` (e.g. 9-17, local time).
+    pub fn from_env() -> Result<Self, String> {
+        let scoring = match std::env::var("CCUBE_FOCUS_SCORING") {
+            Ok(v) if !v.trim().is_empty() => v,
+            _ => return Ok(Self::default()),
+        };
+        match scoring.trim().to_ascii_lowercase().as_str() {
+            "modes" => Ok(Self::Modes),
+            "whitelist" => {
+                let apps = parse_keywords(&std::env::var("CCUBE_FOCUS_APPS").unwrap_or_default());
+                if apps.is_empty() {
+                    return Err("CCUBE_FOCUS_SCORING=whitelist needs CCUBE_FOCUS_APPS".to_string());
+                }
+                let work_hours = match std::env::var("CCUBE_FOCUS_WORK_HOURS") {
+                    Ok(v) if !v.trim().is_empty() => {
+                        Some(WorkHours::parse(&v).ok_or_else(|| {
+                            format!("invalid CCUBE_FOCUS_WORK_HOURS (need e.g. 9-17): {v}")
+                        })?)
+                    }
+                    _ => None,
+                };
+                Ok(Self::Whitelist { apps, work_hours })
+            }
+            _ => Err(format!(
+                "invalid CCUBE_FOCUS_SCORING (need modes or whitelist): {scoring}"
+            )),
+        }
+    }
+
+    pub fn is_focused(&self, span: &Span) -> bool {
+        match self {
+            Self::Modes => span.is_focused(),
+            Self::Whitelist { apps, .. } => {
+                let app = span.app.to_lowercase();
+                apps.iter().any(|a| app.contains(a.as_str()))
+            }
+        }
+    }
+
+    /// Active and focused time across spans under this rule. Under a whitelist
+    /// with work hours, unfocused time outside them isn't counted as active.
+    pub fn totals(&self, spans: &[Span]) -> FocusTotals {
+        let work_hours = match self {
+            Self::Whitelist { work_hours, .. } => *work_hours,
+            Self::Modes => None,
+        };
+        let mut totals = FocusTotals::default();
+        for s in spans {
+            if self.is_focused(s) {
+                totals.active_ms += s.duration_ms();
+                totals.focused_ms += s.duration_ms();
+            } else {
+                totals.active_ms += work_hours.map_or(s.duration_ms(), |h| h.overlap_ms(s));
+            }
+        }
+        totals
+    }
+}
+
+/// Shared bands for scores a UI colors, so every view agrees on what counts
+/// as mild or severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    #[default]
+    None,
+    Mild,
+    Moderate,
+    Severe,
+}
+
+impl Severity {
+    /// 0 (none) to 3 (severe).
+    pub fn level(self) -> u8 {
+        self as u8
+    }
+
+    /// Band a 0-100 meter: under 25 none, then mild, moderate, and severe
+    /// from 75.
+    pub fn from_percent(value: f64) -> Self {
+        match value {
+            v if v >= 75.0 => Self::Severe,
+            v if v >= 50.0 => Self::Moderate,
+            v if v >= 25.0 => Self::Mild,
+            _ => Self::None,
+        }
+    }
+
+    /// Band a distance from the baseline mean in standard deviations, either
+    /// direction: under 1 none, then one band per deviation. Moderate starts
+    /// at the out-of-range limit.
+    pub fn from_z_score(z: f64) -> Self {
+        match z.abs() {
+            z if z >= BASELINE_Z_LIMIT + 1.0 => Self::Severe,
+            z if z >= BASELINE_Z_LIMIT => Self::Moderate,
+            z if z >= 1.0 => Self::Mild,
+            _ => Self::None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Mild => "mild",
+            Self::Moderate => "moderate",
+            Self::Severe => "severe",
+        }
+    }
+}
+
+/// Real-time "getting scattered" meter.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SwitchPressure {
+    /// 0 = at or below the usual switch rate, 100 = far above it.
+    pub pressure: u8,
+    /// App switches in the last five minutes.
+    pub switches: usize,
+    /// The subset of `switches` that also changed focus mode.
+    #[serde(default)]
+    pub mode_switches: usize,
+    /// Which of the two the pressure is based on.
+    #[serde(default)]
+    pub counting: SwitchCounting,
+    /// Switches expected in five minutes at the baseline rate.
+    pub expected: f64,
+    /// Whether `expected` came from history (false = built-in default).
+    pub has_baseline: bool,
+    /// `pressure` banded by `Severity::from_percent`.
+    #[serde(default)]
+    pub severity: Severity,
+    /// `severity` as 0-3.
+    #[serde(default)]
+    pub severity_level: u8,
+}
+
+/// The focused run still going on right now.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FocusStreak {
+    pub start: i64,
+    /// Focused minutes in the streak (short breaks inside it don't count).
+    pub minutes: f64,
+    /// The focused app with the most time in the streak.
+    pub app: String,
+}
+
+/// Estimated end-of-day focus score. This is a projection from the day so
+/// far, not a measurement.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FocusProjection {
+    pub projected_score: f64,
+    pub so_far_score: f64,
+    /// Last hour's score, when it had enough activity to count.
+    pub recent_score: Option<f64>,
+    pub active_minutes: f64,
+}
+
+/// The apps that made up one focus mode's time.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModeDetail {
+    pub mode: String,
+    pub focused: bool,
+    pub minutes: f64,
+    /// Percent of all active time spent in this mode (None with no activity).
+    pub share: Option<f64>,
+    pub apps: Vec<AppMinutes>,
+}
+
+/// One stretch of time away from the main task, scored by how much it cost.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RankedDistraction {
+    pub start: i64,
+    pub end: i64,
+    /// The app with the most time in the stretch.
+    pub app: String,
+    pub minutes: f64,
+    /// Minutes weighted by distance from the main task: unfocused time counts
+    /// in full, time in another focused mode at half.
+    pub impact: f64,
+}
+
+/// One app's split between focused and unfocused time.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AppContribution {
+    pub app: String,
+    pub focused_minutes: f64,
+    pub unfocused_minutes: f64,
+}
+
+/// Apps ranked by what they added to the day rather than raw time.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AppContributions {
+    /// Most focused time first.
+    pub productive: Vec<AppContribution>,
+    /// Most unfocused time first.
+    pub time_sinks: Vec<AppContribution>,
+}
+
+/// Time on declared study material versus everything else, matched by window
+/// title.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TopicFocus {
+    pub keywords: Vec<String>,
+    pub on_topic_minutes: f64,
+    pub off_topic_minutes: f64,
+    /// Active time with no window title, which can't be matched either way.
+    pub untitled_minutes: f64,
+    /// On-topic share of titled time (0-100), None with no titled activity.
+    pub on_topic_share: Option<f64>,
+    /// Titles with the most on-topic time, most first.
+    pub top_titles: Vec<TitleMinutes>,
+}
+
+/// Time spent under one window title.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TitleMinutes {
+    pub title: String,
+    pub minutes: f64,
+}
+
+/// How one app tends to be used: many short visits or a few long sessions.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AppSessionStats {
+    pub app: String,
+    pub sessions: usize,
+    pub total_minutes: f64,
+    pub avg_minutes: f64,
+    pub median_minutes: f64,
+}
+
+/// Usage of a user-picked set of apps, ignoring everything else.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AppsSummary {
+    /// The requested app names, lowercased; each matches any app containing it.
+    pub apps: Vec<String>,
+    pub minutes: f64,
+    pub focused_minutes: f64,
+    /// Share of all active time spent in the selected apps (0-100), None with
+    /// no activity.
+    pub share: Option<f64>,
+    /// Switches between the selected apps, not counting visits elsewhere.
+    pub switches: usize,
+    /// Per matched app, most used first.
+    pub sessions: Vec<AppSessionStats>,
+    /// Titles with the most time in the selected apps, most first.
+    pub top_titles: Vec<TitleMinutes>,
+}
+
+/// Average focus in one hour of the day across history.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProductiveHour {
+    /// Local hour of day (0-23).
+    pub hour: u32,
+    pub avg_focus_score: f64,
+    /// Clock hours with enough activity that went into the average.
+    pub samples: usize,
+}
+
+/// A run of consecutive hours of the day, [start_hour, end_hour).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScheduleBlock {
+    pub start_hour: u32,
+    pub end_hour: u32,
+    pub avg_focus_score: f64,
+}
+
+/// Suggested daily schedule from productive-hours history: when to do deep
+/// work and when to batch admin (email, chat, errands). Blocks are in time
+/// order.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScheduleRecommendations {
+    pub deep_work: Vec<ScheduleBlock>,
+    pub admin: Vec<ScheduleBlock>,
+    #[serde(default)]
+    pub source: ScheduleSource,
+    /// The LLM's reasoning for its blocks (None for the rule-based schedule).
+    #[serde(default)]
+    pub rationale: Option<String>,
+}
+
+/// Who drew up a `ScheduleRecommendations`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScheduleSource {
+    /// The LLM, from the hour-by-hour focus data.
+    Llm,
+    /// `schedule_recommendations`, used when the LLM is unavailable.
+    #[default]
+    Rules,
+}
+
+/// Bucket size for focus trends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrendBucket {
+    Hour,
+    Day,
+}
+
+impl std::str::FromStr for TrendBucket {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "hour" => Ok(TrendBucket::Hour),
+            "day" => Ok(TrendBucket::Day),
+            _ => Err(format!("invalid bucket '{s}', expected hour or day")),
+        }
+    }
+}
+
+/// Focused share of active time in one trend bucket. Idle time is already
+/// excluded from active time, so this is "how on-task was I while at the
+/// computer".
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrendPoint {
+    pub start: i64,
+    pub end: i64,
+    pub active_minutes: f64,
+    pub focused_minutes: f64,
+    /// None for buckets with no activity.
+    pub focus_ratio: Option<f64>,
+}
+
+/// Activity summary over an explicit window, with a human-readable label.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WindowSummary {
+    pub from: i64,
+    pub to: i64,
+    /// e.g. "since 13:05 (back after 47 min away)".
+    pub period: String,
+    pub active_minutes: f64,
+    pub focused_minutes: f64,
+    pub focus_score: Option<f64>,
+    pub top_apps: Vec<AppMinutes>,
+}
+
+/// How durations are shown in text output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeUnit {
+    /// "95 min"
+    #[default]
+    Minutes,
+    /// "1.6 h"
+    Hours,
+    /// Minutes under an hour, "1h 35m" above.
+    Auto,
+}
+
+impl TimeUnit {
+    /// Read from `CCUBE_TIME_UNIT` (minutes | hours | auto, default minutes).
+    pub fn from_env() -> Result<Self, String> {
+        match std::env::var("CCUBE_TIME_UNIT") {
+            Ok(v) if !v.trim().is_empty() => match v.trim().to_ascii_lowercase().as_str() {
+                "minutes" | "min" => Ok(TimeUnit::Minutes),
+                "hours" | "h" => Ok(TimeUnit::Hours),
+                "auto" => Ok(TimeUnit::Auto),
+                _ => Err(format!("invalid CCUBE_TIME_UNIT: {v}")),
+            },
+            _ => Ok(TimeUnit::Minutes),
+        }
+    }
+}
+
+/// Format a duration for display in the given unit.
+pub fn format_duration(seconds: i64, unit: TimeUnit) -> String {
+    let minutes = (seconds.max(0) as f64 / 60.0).round() as i64;
+    match unit {
+        TimeUnit::Minutes => format!("{minutes} min"),
+        TimeUnit::Hours => format!("{:.1} h", seconds.max(0) as f64 / 3600.0),
+        TimeUnit::Auto if minutes < 60 => format!("{minutes} min"),
+        TimeUnit::Auto => format!("{}h {:02}m", minutes / 60, minutes % 60),
+    }
+}
+
+/// Whether a stored mode string counts as focused work.
+pub fn is_focused_mode(mode: &str) -> bool {
+    matches!(mode, "Coding" | "Writing" | "VideoProduction")
+}
+
+/// Load the events needed to compute spans over [from_ts, to_ts).
+pub fn load_events(conn: &Connection, from_ts: i64, to_ts: i64) -> Result<Vec<EventRow>> {
+    db::query_events_between(conn, from_ts - SPAN_LOOKBACK_MS, to_ts)
+}
+
+/// Compute one day's focus totals from its events and store them in
+/// daily_stats, so the day's score survives event pruning.
+pub fn roll_up_day<Tz: TimeZone>(
+    conn: &Connection,
+    date: NaiveDate,
+    tz: &Tz,
+    scoring: &FocusScoring,
+    now_ms: i64,
+) -> Result<db::DailyStatsRow> {
+    let totals = scoring.totals(&day_spans(conn, date, tz, now_ms)?);
+    store_day_totals(conn, date, totals, now_ms)
+}
+
+/// Roll up every day from the one containing `from_ts` through today whose
+/// stored rollup is missing or was written before the day ended, so a day's
+/// totals are final before its events are pruned. Days with no events are
+/// left alone. Returns the number of days rolled up.
+pub fn roll_up_stale_days<Tz: TimeZone>(
+    conn: &Connection,
+    from_ts: i64,
+    tz: &Tz,
+    scoring: &FocusScoring,
+    now_ms: i64,
+) -> Result<usize> {
+    let local_date = |ts: i64| tz.timestamp_millis_opt(ts).single().map(|d| d.date_naive());
+    let (Some(from), Some(today)) = (local_date(from_ts), local_date(now_ms)) else {
+        return Ok(0);
+    };
+    let stored: HashMap<String, i64> =
+        db::list_daily_stats(conn, &from.to_string(), &today.to_string())?
+            .into_iter()
+            .map(|r| (r.date, r.updated_at))
+            .collect();
+
+    let mut rolled = 0;
+    for date in from.iter_days().take_while(|d| *d <= today) {
+        let (_, day_end) = day_bounds(date, tz);
+        if stored
+            .get(&date.format("%Y-%m-%d").to_string())
+            .is_some_and(|&updated_at| updated_at >= day_end)
+        {
+            continue;
+        }
+        let spans = day_spans(conn, date, tz, now_ms)?;
+        if spans.is_empty() {
+            continue;
+        }
+        store_day_totals(conn, date, scoring.totals(&spans), now_ms)?;
+        rolled += 1;
+    }
+    Ok(rolled)
+}
+
+/// Result of recomputing stored daily rollups over a range.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecomputeSummary {
+    pub days_updated: usize,
+    /// Days with no events left (pruned or never recorded), or that began
+    /// before the oldest stored event and so may be partly pruned. Their
+    /// stored rollup, if any, is kept as it was.
+    pub days_skipped: usize,
+}
+
+/// Longest range a single daily-stats recompute accepts.
+pub const MAX_RECOMPUTE_DAYS: i64 = 3650;
+
+/// Recompute the daily_stats rollup of every day in [from, to] from stored
+/// events, e.g. after a focus-mode backfill or a change of
+/// CCUBE_FOCUS_SCORING. Days whose events are gone, or that began before the
+/// oldest stored event, are skipped rather than zeroed or undercounted. All
+/// rows are written in one transaction.
+pub fn recompute_daily_stats<Tz: TimeZone>(
+    conn: &Connection,
+    from: NaiveDate,
+    to: NaiveDate,
+    tz: &Tz,
+    scoring: &FocusScoring,
+    now_ms: i64,
+) -> Result<RecomputeSummary> {
+    let mut summary = RecomputeSummary::default();
+    let Some(oldest) = db::oldest_event_ts(conn)? else {
+        summary.days_skipped = from.iter_days().take_while(|d| *d <= to).count();
+        return Ok(summary);
+    };
+
+    let tx = conn.unchecked_transaction()?;
+    for date in from.iter_days().take_while(|d| *d <= to) {
+        // Pruning cuts at a timestamp, so an earlier start may mean a partial day
+        if day_bounds(date, tz).0 < oldest {
+            summary.days_skipped += 1;
+            continue;
+        }
+        let spans = day_spans(&tx, date, tz, now_ms)?;
+        if spans.is_empty() {
+            summary.days_skipped += 1;
+            continue;
+        }
+        store_day_totals(&tx, date, scoring.totals(&spans), now_ms)?;
+        summary.days_updated += 1;
+    }
+    tx.commit()?;
+    Ok(summary)
+}
+
+fn store_day_totals(
+    conn: &Connection,
+    date: NaiveDate,
+    totals: FocusTotals,
+    now_ms: i64,
+) -> Result<db::DailyStatsRow> {
+    let row = db::DailyStatsRow {
+        date: date.format("%Y-%m-%d").to_string(),
+        active_ms: totals.active_ms,
+        focused_ms: totals.focused_ms,
+        focus_score: totals.focus_score(),
+        updated_at: now_ms,
+    };
+    db::upsert_daily_stats(conn, &row)?;
+    Ok(row)
+}
+
+/// One entry per day of `year` from stored daily rollups, for a
+/// contributions-style heatmap. The current year stops at `today`; a future
+/// year is empty.
+pub fn activity_calendar(
+    year: i32,
+    rows: &[db::DailyStatsRow],
+    today: NaiveDate,
+) -> Vec<CalendarDay> {
+    let Some(first) = NaiveDate::from_ymd_opt(year, 1, 1) else {
+        return Vec::new();
+    };
+    let last = NaiveDate::from_ymd_opt(year, 12, 31).map_or(today, |d| d.min(today));
+    let by_date: HashMap<&str, &db::DailyStatsRow> =
+        rows.iter().map(|r| (r.date.as_str(), r)).collect();
+
+    first
+        .iter_days()
+        .take_while(|d| *d <= last)
+        .map(|date| {
+            let row = by_date.get(date.format("%Y-%m-%d").to_string().as_str());
+            CalendarDay {
+                date,
+                active_minutes: row.map_or(0.0, |r| ms_to_minutes(r.active_ms)),
+                focus_score: row.and_then(|r| r.focus_score),
+            }
+        })
+        .collect()
+}
+
+/// Compare today's totals with the average of the stored daily rollups in
+/// `history`. Rows for `today` and days without activity are skipped.
+/// `as_of` is the local time today's totals run to, None once the day is
+/// complete; the result then carries a partial-day note.
+pub fn today_vs_average(
+    today: NaiveDate,
+    totals: FocusTotals,
+    history: &[db::DailyStatsRow],
+    as_of: Option<chrono::NaiveTime>,
+) -> TodayVsAverage {
+    let today_key = today.format("%Y-%m-%d").to_string();
+    let past: Vec<&db::DailyStatsRow> = history
+        .iter()
+        .filter(|r| r.date != today_key && r.active_ms > 0)
+        .collect();
+    let scores: Vec<f64> = past.iter().filter_map(|r| r.focus_score).collect();
+
+    let today_score = totals.focus_score();
+    let today_minutes = ms_to_minutes(totals.active_ms);
+    let avg_score =
+        (!scores.is_empty()).then(|| round1(scores.iter().sum::<f64>() / scores.len() as f64));
+    let avg_minutes = (!past.is_empty()).then(|| {
+        let active_ms: i64 = past.iter().map(|r| r.active_ms).sum();
+        ms_to_minutes(active_ms / past.len() as i64)
+    });
+
+    TodayVsAverage {
+        today_focus_score: today_score,
+        today_active_minutes: today_minutes,
+        avg_focus_score: avg_score,
+        avg_active_minutes: avg_minutes,
+        focus_score_delta: today_score.zip(avg_score).map(|(t, a)| round1(t - a)),
+        active_minutes_delta: avg_minutes.map(|a| round1(today_minutes - a)),
+        sample_days: past.len(),
+        note: as_of.map(|t| {
+            format!(
+                "partial day: today up to {} against full-day averages",
+                t.format("%H:%M")
+            )
+        }),
+    }
+}
+
+/// Load and resolve the active spans for one calendar day.
+pub fn day_spans<Tz: TimeZone>(
+    conn: &Connection,
+    date: NaiveDate,
+    tz: &Tz,
+    now_ms: i64,
+) -> Result<Vec<Span>> {
+    let (from, to) = day_bounds(date, tz);
+    let events = load_events(conn, from, to)?;
+    Ok(active_spans(&events, from, to, now_ms))
+}
+
+/// Resolve app_focus events into active spans clipped to [from_ts, to_ts).
+///
+/// Finalized events use their stored duration. An event with a NULL duration
+/// from the current session (after the latest daemon_start) runs until the next
+/// app_focus event or `now_ms`; from an earlier session it contributes nothing.
+/// Events with a zero or negative duration, or one below CCUBE_MIN_ACTIVITY_MS,
+/// are dropped. Idle periods (idle_start → idle_end) are cut out of the result,
+/// except where the user declared themselves active.
+pub fn active_spans(events: &[EventRow], from_ts: i64, to_ts: i64, now_ms: i64) -> Vec<Span> {
+    active_spans_with_min(events, from_ts, to_ts, now_ms, min_activity_ms())
+}
+
+/// CCUBE_MIN_ACTIVITY_MS, read once. The daemon rejects an invalid value at
+/// startup; here it falls back to keeping everything.
+fn min_activity_ms() -> i64 {
+    static MIN_MS: std::sync::OnceLock<i64> = std::sync::OnceLock::new();
+    *MIN_MS.get_or_init(|| {
+        ingest::min_duration_ms_from_env().unwrap_or(ingest::DEFAULT_MIN_DURATION_MS)
+    })
+}
+
+fn active_spans_with_min(
+    events: &[EventRow],
+    from_ts: i64,
+    to_ts: i64,
+    now_ms: i64,
+    min_duration_ms: i64,
+) -> Vec<Span> {
+    let to_ts = to_ts.min(now_ms);
+    if to_ts <= from_ts {
+        return Vec::new();
+    }
+
+    let session_start_ts = events
+        .iter()
+        .rev()
+        .find(|e| e.kind == "daemon_start")
+        .map(|e| e.ts)
+        .unwrap_or(i64::MIN);
+
+    let focus: Vec<&EventRow> = events.iter().filter(|e| e.kind == "app_focus").collect();
+    let idle = idle_intervals(events, now_ms);
+
+    let mut spans = Vec::new();
+    let mut dropped = 0;
+    for (i, e) in focus.iter().enumerate() {
+        let end = match e.duration_ms {
+            Some(d) => e.ts + d,
+            None if e.ts >= session_start_ts => {
+                focus.get(i + 1).map(|next| next.ts).unwrap_or(now_ms)
+            }
+            None => continue,
+        };
+        if end <= e.ts || end - e.ts < min_duration_ms {
+            dropped += 1;
+            continue;
+        }
+        let start = e.ts.max(from_ts);
+        let end = end.min(to_ts);
+        if end <= start {
+            continue;
+        }
+
+        let mode = e.mode.clone().unwrap_or_else(|| "Unspecified".to_string());
+        for (s, t) in subtract_intervals(start, end, &idle) {
+            spans.push(Span {
+                start: s,
+                end: t,
+                app: e.app.clone().unwrap_or_default(),
+                title: e.title.clone(),
+                mode: mode.clone(),
+            });
+        }
+    }
+    if dropped > 0 {
+        tracing::debug!(dropped, min_duration_ms, "dropped short app_focus events");
+    }
+    spans
+}
+
+/// Sum active and focused time across spans.
+pub fn focus_totals(spans: &[Span]) -> FocusTotals {
+    FocusScoring::Modes.totals(spans)
+}
+
+/// Total time per app, most used first (ties alphabetical).
+pub fn app_totals(spans: &[Span]) -> Vec<AppMinutes> {
+    let mut per_app: HashMap<&str, i64> = HashMap::new();
+    for s in spans {
+        *per_app.entry(s.app.as_str()).or_default() += s.duration_ms();
+    }
+    sorted_minutes(per_app)
+        .into_iter()
+        .map(|(app, minutes)| AppMinutes { app, minutes })
+        .collect()
+}
+
+/// Total time per focus mode, most used first (ties alphabetical).
+pub fn mode_totals(spans: &[Span]) -> Vec<ModeMinutes> {
+    let mut per_mode: HashMap<&str, i64> = HashMap::new();
+    let mut apps: HashMap<&str, std::collections::HashSet<&str>> = HashMap::new();
+    for s in spans {
+        *per_mode.entry(s.mode.as_str()).or_default() += s.duration_ms();
+        apps.entry(s.mode.as_str())
+            .or_default()
+            .insert(s.app.as_str());
+    }
+    sorted_minutes(per_mode)
+        .into_iter()
+        .map(|(mode, minutes)| ModeMinutes {
+            app_count: apps.get(mode.as_str()).map_or(0, |a| a.len()),
+            mode,
+            minutes,
+        })
+        .collect()
+}
+
+/// Per-app breakdown of one mode, most used first. Matching is
+/// case-insensitive, and "uncategorized" is accepted for Unspecified.
+pub fn mode_detail(spans: &[Span], mode: &str) -> ModeDetail {
+    let wanted = if mode.eq_ignore_ascii_case("uncategorized") {
+        "Unspecified"
+    } else {
+        mode
+    };
+    let in_mode: Vec<Span> = spans
+        .iter()
+        .filter(|s| s.mode.eq_ignore_ascii_case(wanted))
+        .cloned()
+        .collect();
+    // Report the stored spelling when there's a match
+    let mode = in_mode
+        .first()
+        .map(|s| s.mode.clone())
+        .unwrap_or_else(|| wanted.to_string());
+
+    let mode_ms: i64 = in_mode.iter().map(Span::duration_ms).sum();
+    let active_ms: i64 = spans.iter().map(Span::duration_ms).sum();
+    let share = (active_ms > 0).then(|| round1(mode_ms as f64 * 100.0 / active_ms as f64));
+
+    ModeDetail {
+        focused: is_focused_mode(&mode),
+        mode,
+        minutes: ms_to_minutes(mode_ms),
+        share,
+        apps: app_totals(&in_mode),
+    }
+}
+
+/// Read `CCUBE_SESSION_MERGE_GAP_SECS` (0-3600) as milliseconds.
+/// DEFAULT_SESSION_MERGE_GAP_MS when unset.
+pub fn session_merge_gap_ms_from_env() -> Result<i64, String> {
+    match std::env::var("CCUBE_SESSION_MERGE_GAP_SECS") {
+        Ok(v) if !v.trim().is_empty() => match v.trim().parse::<i64>() {
+            Ok(n) if (0..=3600).contains(&n) => Ok(n * 1000),
+            _ => Err(format!(
+                "invalid CCUBE_SESSION_MERGE_GAP_SECS (need 0-3600): {v}"
+            )),
+        },
+        _ => Ok(DEFAULT_SESSION_MERGE_GAP_MS),
+    }
+}
+
+/// Per-app session counts and lengths, most used app first (ties
+/// alphabetical). Uses of the same app separated by at most `merge_gap_ms`
+/// count as one session: a quick look at another window, or an app that
+/// crashed and was relaunched.
+pub fn app_session_stats(spans: &[Span], merge_gap_ms: i64) -> Vec<AppSessionStats> {
+    let mut sorted: Vec<&Span> = spans.iter().collect();
+    sorted.sort_by_key(|s| s.start);
+
+    // Per app: (last end, lengths of closed sessions, current session ms)
+    let mut per_app: HashMap<&str, (i64, Vec<i64>, i64)> = HashMap::new();
+    for s in sorted {
+        let entry = per_app
+            .entry(s.app.as_str())
+            .or_insert((s.start, Vec::new(), 0));
+        if s.start - entry.0 > merge_gap_ms {
+            entry.1.push(entry.2);
+            entry.2 = 0;
+        }
+        entry.2 += s.duration_ms();
+        entry.0 = entry.0.max(s.end);
+    }
+
+    let mut stats: Vec<AppSessionStats> = per_app
+        .into_iter()
+        .map(|(app, (_, mut lengths, current))| {
+            lengths.push(current);
+            lengths.sort_unstable();
+            let total: i64 = lengths.iter().sum();
+            let n = lengths.len();
+            let median = if n % 2 == 1 {
+                lengths[n / 2] as f64
+            } else {
+                (lengths[n / 2 - 1] + lengths[n / 2]) as f64 / 2.0
+            };
+            AppSessionStats {
+                app: app.to_string(),
+                sessions: n,
+                total_minutes: ms_to_minutes(total),
+                avg_minutes: ms_to_minutes(total / n as i64),
+                median_minutes: ms_to_minutes(median.round() as i64),
+            }
+        })
+        .collect();
+    stats.sort_by(|a, b| {
+        b.total_minutes
+            .total_cmp(&a.total_minutes)
+            .then_with(|| a.app.cmp(&b.app))
+    });
+    stats
+}
+
+/// Project today's end-of-day focus score from `spans` (today so far): a
+/// weighted blend of the score so far and the last hour's score, so a strong
+/// or weak recent stretch pulls the projection. None until there's at least
+/// 30 minutes of activity.
+pub fn project_focus(
+    spans: &[Span],
+    scoring: &FocusScoring,
+    now_ms: i64,
+) -> Option<FocusProjection> {
+    let totals = scoring.totals(spans);
+    if totals.active_ms < PROJECTION_MIN_ACTIVE_MS {
+        return None;
+    }
+    let so_far = totals.focus_score()?;
+
+    let recent: Vec<Span> = spans
+        .iter()
+        .filter_map(|s| clip_span(s, now_ms - HOUR_MS, now_ms))
+        .collect();
+    let recent_totals = scoring.totals(&recent);
+    let recent_score = if recent_totals.active_ms >= BASELINE_MIN_ACTIVE_MS {
+        recent_totals.focus_score()
+    } else {
+        None
+    };
+
+    let projected = match recent_score {
+        Some(r) => so_far * (1.0 - PROJECTION_RECENT_WEIGHT) + r * PROJECTION_RECENT_WEIGHT,
+        None => so_far,
+    };
+    Some(FocusProjection {
+        projected_score: round1(projected),
+        so_far_score: so_far,
+        recent_score,
+        active_minutes: ms_to_minutes(totals.active_ms),
+    })
+}
+
+/// The most recent idle period of at least `min_idle_ms` that has ended by
+/// `now_ms`, as (start, end).
+pub fn last_return_from_idle(
+    events: &[EventRow],
+    min_idle_ms: i64,
+    now_ms: i64,
+) -> Option<(i64, i64)> {
+    idle_intervals(events, now_ms)
+        .into_iter()
+        .rev()
+        .find(|(start, end)| *end < now_ms && end - start >= min_idle_ms)
+}
+
+/// Summarise activity since the user came back from the last idle period of
+/// at least `min_idle_ms`; with no such period, since `fallback_from` (e.g.
+/// local midnight). The `period` label says which window was used.
+pub fn since_return_summary<Tz: TimeZone>(
+    events: &[EventRow],
+    min_idle_ms: i64,
+    fallback_from: i64,
+    scoring: &FocusScoring,
+    now_ms: i64,
+    tz: &Tz,
+) -> WindowSummary
+where
+    Tz::Offset: std::fmt::Display,
+{
+    let clock = |ts: i64| {
+        tz.timestamp_millis_opt(ts)
+            .single()
+            .map(|dt| dt.format("%H:%M").to_string())
+            .unwrap_or_default()
+    };
+    let (from, period) = match last_return_from_idle(events, min_idle_ms, now_ms) {
+        Some((start, end)) if end > fallback_from => (
+            end,
+            format!(
+                "since {} (back after {:.0} min away)",
+                clock(end),
+                ms_to_minutes(end - start)
+            ),
+        ),
+        _ => (
+            fallback_from,
+            format!("since {} (no long break)", clock(fallback_from)),
+        ),
+    };
+
+    let spans = active_spans(events, from, now_ms, now_ms);
+    let totals = scoring.totals(&spans);
+    let mut top_apps = app_totals(&spans);
+    top_apps.truncate(REVIEW_TOP_APPS);
+    WindowSummary {
+        from,
+        to: now_ms,
+        period,
+        active_minutes: ms_to_minutes(totals.active_ms),
+        focused_minutes: ms_to_minutes(totals.focused_ms),
+        focus_score: totals.focus_score(),
+        top_apps,
+    }
+}
+
+/// Focus score over each of `FOCUS_TIMEFRAMES` ending at `now_ms`, keyed by
+/// label ("5m", "30m", "1h"). None where the timeframe had no activity.
+pub fn focus_by_timeframe(
+    spans: &[Span],
+    scoring: &FocusScoring,
+    now_ms: i64,
+) -> BTreeMap<String, Option<f64>> {
+    FOCUS_TIMEFRAMES
+        .iter()
+        .map(|&(label, len)| {
+            let clipped: Vec<Span> = spans
+                .iter()
+                .filter_map(|s| clip_span(s, now_ms - len, now_ms))
+                .collect();
+            (label.to_string(), scoring.totals(&clipped).focus_score())
+        })
+        .collect()
+}
+
+/// Total idle time within [from_ts, to_ts), capped at `now_ms`.
+pub fn idle_ms(events: &[EventRow], from_ts: i64, to_ts: i64, now_ms: i64) -> i64 {
+    let to_ts = to_ts.min(now_ms);
+    idle_intervals(events, now_ms)
+        .into_iter()
+        .map(|(s, e)| (e.min(to_ts) - s.max(from_ts)).max(0))
+        .sum()
+}
+
+/// Time per focus mode over a range. With `include_idle`, idle time is added
+/// as an "Idle" pseudo-mode so the breakdown covers breaks as well.
+pub fn mode_breakdown(
+    events: &[EventRow],
+    from_ts: i64,
+    to_ts: i64,
+    now_ms: i64,
+    include_idle: bool,
+) -> Vec<ModeMinutes> {
+    let mut modes = mode_totals(&active_spans(events, from_ts, to_ts, now_ms));
+    if include_idle {
+        let idle = idle_ms(events, from_ts, to_ts, now_ms);
+        if idle > 0 {
+            modes.push(ModeMinutes {
+                mode: IDLE_MODE.to_string(),
+                minutes: ms_to_minutes(idle),
+                app_count: 0,
+            });
+            modes.sort_by(|a, b| {
+                b.minutes
+                    .total_cmp(&a.minutes)
+                    .then_with(|| a.mode.cmp(&b.mode))
+            });
+        }
+    }
+    modes
+}
+
+/// Runs of focused spans in time order, allowing gaps up to
+/// FOCUS_BLOCK_GAP_MS. Any unfocused span ends the current run.
+pub fn focus_blocks(spans: &[Span], scoring: &FocusScoring) -> Vec<FocusBlock> {
+    focus_runs(spans, scoring)
+        .into_iter()
+        .map(to_focus_block)
+        .collect()
+}
+
+/// Classify the gaps between focus blocks (5 minutes to 2 hours) as restful
+/// when less than half of the gap was active screen time, otherwise as screen
+/// breaks, and pair each with the length of the focus block after it.
+pub fn break_analysis(spans: &[Span], scoring: &FocusScoring) -> BreakAnalysis {
+    let runs = focus_runs(spans, scoring);
+    let breaks: Vec<BreakEntry> = runs
+        .windows(2)
+        .filter_map(|pair| {
+            let (start, end) = (pair[0].1, pair[1].0);
+            let gap_ms = end - start;
+            if !(MIN_BREAK_MS..=MAX_BREAK_MS).contains(&gap_ms) {
+                return None;
+            }
+            let screen_ms: i64 = spans
+                .iter()
+                .filter_map(|s| clip_span(s, start, end))
+                .map(|s| s.duration_ms())
+                .sum();
+            Some(BreakEntry {
+                start,
+                end,
+                minutes: ms_to_minutes(gap_ms),
+                screen_minutes: ms_to_minutes(screen_ms),
+                kind: if screen_ms * 2 < gap_ms {
+                    BreakKind::Restful
+                } else {
+                    BreakKind::Screen
+                },
+                focus_after_minutes: ms_to_minutes(pair[1].2),
+            })
+        })
+        .collect();
+
+    let summary = |kind: BreakKind| {
+        let of_kind: Vec<&BreakEntry> = breaks.iter().filter(|b| b.kind == kind).collect();
+        let avg = |f: fn(&BreakEntry) -> f64| {
+            (!of_kind.is_empty())
+                .then(|| round1(of_kind.iter().map(|b| f(b)).sum::<f64>() / of_kind.len() as f64))
+        };
+        BreakKindSummary {
+            count: of_kind.len(),
+            avg_minutes: avg(|b| b.minutes),
+            avg_focus_after_minutes: avg(|b| b.focus_after_minutes),
+        }
+    };
+
+    BreakAnalysis {
+        restful: summary(BreakKind::Restful),
+        screen: summary(BreakKind::Screen),
+        breaks,
+    }
+}
+
+/// The longest of `focus_blocks` (the earliest on a tie).
+pub fn longest_focus_block(spans: &[Span], scoring: &FocusScoring) -> Option<FocusBlock> {
+    let mut best: Option<(i64, i64, i64)> = None;
+    for run in focus_runs(spans, scoring) {
+        if best.is_none_or(|b| run.2 > b.2) {
+            best = Some(run);
+        }
+    }
+    best.map(to_focus_block)
+}
+
+/// (start, end, focused_ms) for each focused run.
+fn focus_runs(spans: &[Span], scoring: &FocusScoring) -> Vec<(i64, i64, i64)> {
+    let mut sorted: Vec<&Span> = spans.iter().collect();
+    sorted.sort_by_key(|s| s.start);
+
+    let mut runs = Vec::new();
+    let mut current: Option<(i64, i64, i64)> = None;
+    for s in sorted {
+        if !scoring.is_focused(s) {
+            runs.extend(current.take());
+            continue;
+        }
+        current = match current {
+            Some((start, end, ms)) if s.start - end <= FOCUS_BLOCK_GAP_MS => {
+                Some((start, end.max(s.end), ms + s.duration_ms()))
+            }
+            other => {
+                runs.extend(other);
+                Some((s.start, s.end, s.duration_ms()))
+            }
+        };
+    }
+    runs.extend(current);
+    runs
+}
+
+fn to_focus_block((start, end, ms): (i64, i64, i64)) -> FocusBlock {
+    FocusBlock {
+        start,
+        end,
+        minutes: ms_to_minutes(ms),
+    }
+}
+
+/// Assemble the daily review from one day's spans.
+pub fn daily_review(
+    date: NaiveDate,
+    spans: &[Span],
+    scoring: &FocusScoring,
+    target_minutes: Option<u32>,
+) -> DailyReview {
+    let totals = scoring.totals(spans);
+    let mut top_apps = app_totals(spans);
+    top_apps.truncate(REVIEW_TOP_APPS);
+    DailyReview {
+        date,
+        active_minutes: ms_to_minutes(totals.active_ms),
+        focused_minutes: ms_to_minutes(totals.focused_ms),
+        focus_score: totals.focus_score(),
+        top_apps,
+        modes: mode_totals(spans),
+        longest_focus_block: longest_focus_block(spans, scoring),
+        top_distraction: top_distraction(spans, scoring),
+        sessions: focus_blocks(spans, scoring),
+        target_progress: target_minutes.map(|t| target_progress(totals, t)),
+    }
+}
+
+/// Number of times the foreground app changed between consecutive spans.
+pub fn count_switches(spans: &[Span]) -> usize {
+    let mut sorted: Vec<&Span> = spans.iter().collect();
+    sorted.sort_by_key(|s| s.start);
+    sorted.windows(2).filter(|w| w[0].app != w[1].app).count()
+}
+
+/// App switches between consecutive spans that also changed focus mode.
+pub fn count_mode_switches(spans: &[Span]) -> usize {
+    let mut sorted: Vec<&Span> = spans.iter().collect();
+    sorted.sort_by_key(|s| s.start);
+    sorted
+        .windows(2)
+        .filter(|w| w[0].app != w[1].app && w[0].mode != w[1].mode)
+        .count()
+}
+
+/// Compare the hour before `now_ms` against every earlier hour in the
+/// `baseline_days` before it that had at least 10 minutes of activity.
+///
+/// Metrics: app switches (as counted by `counting`) per active hour and focus
+/// score.
+pub fn baseline_deviation(
+    spans: &[Span],
+    baseline_days: i64,
+    counting: SwitchCounting,
+    scoring: &FocusScoring,
+    now_ms: i64,
+) -> BaselineDeviation {
+    let current_from = now_ms - HOUR_MS;
+    let hour_metrics = |clipped: &[Span]| -> Option<(f64, f64)> {
+        let totals = scoring.totals(clipped);
+        if totals.active_ms < BASELINE_MIN_ACTIVE_MS {
+            return None;
+        }
+        let per_hour = counting.count(clipped) as f64 * HOUR_MS as f64 / totals.active_ms as f64;
+        Some((per_hour, totals.focus_score().unwrap_or(0.0)))
+    };
+
+    // Hours are counted back from now; the last bucket is the current hour
+    let hours = baseline_days.max(0) * 24;
+    let baseline_from = current_from - hours * HOUR_MS;
+    let edges: Vec<i64> = (0..=hours + 1)
+        .map(|h| baseline_from + h * HOUR_MS)
+        .collect();
+    let mut buckets = bucket_spans(spans, &edges);
+    let current = buckets.pop().and_then(|b| hour_metrics(&b));
+    let samples: Vec<(f64, f64)> = buckets.iter().filter_map(|b| hour_metrics(b)).collect();
+
+    let switch_samples: Vec<f64> = samples.iter().map(|m| m.0).collect();
+    let score_samples: Vec<f64> = samples.iter().map(|m| m.1).collect();
+    BaselineDeviation {
+        baseline_days,
+        samples: samples.len(),
+        metrics: vec![
+            deviation("switches_per_hour", current.map(|m| m.0), &switch_samples),
+            deviation("focus_score", current.map(|m| m.1), &score_samples),
+        ],
+    }
+}
+
+/// Mean switches per active hour over the baseline window, if there's history.
+pub fn baseline_switch_rate(
+    spans: &[Span],
+    baseline_days: i64,
+    counting: SwitchCounting,
+    now_ms: i64,
+) -> Option<f64> {
+    baseline_deviation(spans, baseline_days, counting, &FocusScoring::Modes, now_ms)
+        .metrics
+        .into_iter()
+        .find(|m| m.metric == "switches_per_hour")
+        .and_then(|m| m.baseline_mean)
+}
+
+/// Switch pressure over the last five minutes. Pressure is 0 at the expected
+/// rate and climbs linearly, reaching 100 at three times it; `sensitivity`
+/// scales the climb (2.0 reaches 100 at twice the rate). `baseline_per_hour`
+/// should be counted the same way as `counting`.
+pub fn switch_pressure(
+    spans: &[Span],
+    baseline_per_hour: Option<f64>,
+    sensitivity: f64,
+    counting: SwitchCounting,
+    now_ms: i64,
+) -> SwitchPressure {
+    let recent: Vec<Span> = spans
+        .iter()
+        .filter_map(|s| clip_span(s, now_ms - PRESSURE_WINDOW_MS, now_ms))
+        .collect();
+    let switches = counting.count(&recent);
+
+    let per_hour = baseline_per_hour.unwrap_or(DEFAULT_SWITCHES_PER_HOUR);
+    let expected = per_hour * PRESSURE_WINDOW_MS as f64 / HOUR_MS as f64;
+    // Floor keeps a near-zero baseline from turning one switch into 100
+    let ratio = switches as f64 / expected.max(0.5);
+    let pressure = ((ratio - 1.0) * 50.0 * sensitivity.max(0.0)).clamp(0.0, 100.0);
+
+    let severity = Severity::from_percent(pressure);
+    SwitchPressure {
+        pressure: pressure.round() as u8,
+        switches: count_switches(&recent),
+        mode_switches: count_mode_switches(&recent),
+        counting,
+        expected: round1(expected),
+        has_baseline: baseline_per_hour.is_some(),
+        severity,
+        severity_level: severity.level(),
+    }
+}
+
+/// The focus streak running at `now_ms`, or None if there isn't one.
+///
+/// Walks back from `now_ms` through contiguous spans (gaps up to a minute).
+/// Unfocused stretches of at most `max_break_ms` are tolerated but not
+/// counted; a longer one, or a gap such as idle time, ends the streak.
+pub fn current_focus_streak(
+    spans: &[Span],
+    scoring: &FocusScoring,
+    max_break_ms: i64,
+    now_ms: i64,
+) -> Option<FocusStreak> {
+    let mut sorted: Vec<&Span> = spans.iter().filter(|s| s.start < now_ms).collect();
+    sorted.sort_by_key(|s| std::cmp::Reverse(s.start));
+
+    let mut cursor = now_ms;
+    let mut break_ms = 0;
+    let mut start = None;
+    let mut focused: Vec<&Span> = Vec::new();
+    for s in sorted {
+        if cursor - s.end.min(now_ms) > FOCUS_BLOCK_GAP_MS {
+            break;
+        }
+        if scoring.is_focused(s) {
+            focused.push(s);
+            start = Some(s.start);
+            break_ms = 0;
+        } else {
+            break_ms += s.duration_ms();
+            if break_ms > max_break_ms {
+                break;
+            }
+        }
+        cursor = s.start;
+    }
+
+    let top = top_app(focused.iter().copied())?;
+    let ms: i64 = focused.iter().map(|s| s.end.min(now_ms) - s.start).sum();
+    Some(FocusStreak {
+        start: start?,
+        minutes: ms_to_minutes(ms),
+        app: top.app,
+    })
+}
+
+/// The focus streak running at `now_ms`, from raw events over the last
+/// STREAK_LOOKBACK_MS. With a non-zero `idle_timeout_ms`, the streak is over
+/// once nothing has happened for that long, even before idle detection fires.
+pub fn live_focus_streak(
+    events: &[EventRow],
+    scoring: &FocusScoring,
+    max_break_ms: i64,
+    idle_timeout_ms: i64,
+    now_ms: i64,
+) -> Option<FocusStreak> {
+    if idle_timeout_ms > 0
+        && last_activity(events, now_ms).is_none_or(|ts| now_ms - ts > idle_timeout_ms)
+    {
+        return None;
+    }
+    let spans = active_spans(events, now_ms - STREAK_LOOKBACK_MS, now_ms, now_ms);
+    current_focus_streak(&spans, scoring, max_break_ms, now_ms)
+}
+
+/// When the user last did something: the latest focus change or return from
+/// idle, or the end of a recorded span or manual active declaration (capped
+/// at `now_ms`).
+pub fn last_activity(events: &[EventRow], now_ms: i64) -> Option<i64> {
+    events
+        .iter()
+        .filter_map(|e| match e.kind.as_str() {
+            "app_focus" | MANUAL_ACTIVE_KIND => Some(e.ts + e.duration_ms.unwrap_or(0).max(0)),
+            "idle_end" => Some(e.ts),
+            _ => None,
+        })
+        .max()
+        .map(|ts| ts.min(now_ms))
+}
+
+/// Read `CCUBE_STREAK_IDLE_TIMEOUT_SECS` (0-3600) as milliseconds.
+/// DEFAULT_STREAK_IDLE_TIMEOUT_MS when unset.
+pub fn streak_idle_timeout_ms_from_env() -> Result<i64, String> {
+    match std::env::var("CCUBE_STREAK_IDLE_TIMEOUT_SECS") {
+        Ok(v) if !v.trim().is_empty() => match v.trim().parse::<i64>() {
+            Ok(n) if (0..=3600).contains(&n) => Ok(n * 1000),
+            _ => Err(format!(
+                "invalid CCUBE_STREAK_IDLE_TIMEOUT_SECS (need 0-3600): {v}"
+            )),
+        },
+        _ => Ok(DEFAULT_STREAK_IDLE_TIMEOUT_MS),
+    }
+}
+
+fn deviation(metric: &str, current: Option<f64>, samples: &[f64]) -> MetricDeviation {
+    let (mean, stddev) = if samples.is_empty() {
+        (None, None)
+    } else {
+        let n = samples.len() as f64;
+        let mean = samples.iter().sum::<f64>() / n;
+        let var = samples.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n;
+        (Some(mean), Some(var.sqrt()))
+    };
+    let z_score = match (current, mean, stddev) {
+        (Some(c), Some(m), Some(sd)) if sd > 0.0 => Some((c - m) / sd),
+        _ => None,
+    };
+    let percent_diff = match (current, mean) {
+        (Some(c), Some(m)) if m != 0.0 => Some(round1((c - m) * 100.0 / m)),
+        _ => None,
+    };
+    let in_range = match (current, mean) {
+        (Some(c), Some(m)) => Some(z_score.map_or(c == m, |z| z.abs() <= BASELINE_Z_LIMIT)),
+        _ => None,
+    };
+    let severity = match (z_score, in_range) {
+        (Some(z), _) => Some(Severity::from_z_score(z)),
+        // Flat baseline: no change is none, any change is unprecedented
+        (None, Some(true)) => Some(Severity::None),
+        (None, Some(false)) => Some(Severity::Severe),
+        (None, None) => None,
+    };
+    MetricDeviation {
+        metric: metric.to_string(),
+        current: current.map(round1),
+        baseline_mean: mean.map(round1),
+        baseline_stddev: stddev.map(round1),
+        z_score: z_score.map(|z| (z * 100.0).round() / 100.0),
+        percent_diff,
+        in_range,
+        severity,
+        severity_level: severity.map(Severity::level),
+    }
+}
+
+/// The app with the most unfocused time, or None if all active time was focused.
+pub fn top_distraction(spans: &[Span], scoring: &FocusScoring) -> Option<AppMinutes> {
+    top_app(spans.iter().filter(|s| !scoring.is_focused(s)))
+}
+
+/// The `limit` apps that contributed the most focused time, and the `limit`
+/// that soaked up the most unfocused time (ties alphabetical). The same app
+/// can appear in both, e.g. a browser used for docs and for video.
+pub fn app_contributions(spans: &[Span], scoring: &FocusScoring, limit: usize) -> AppContributions {
+    let mut per_app: HashMap<&str, (i64, i64)> = HashMap::new();
+    for s in spans {
+        let entry = per_app.entry(s.app.as_str()).or_default();
+        if scoring.is_focused(s) {
+            entry.0 += s.duration_ms();
+        } else {
+            entry.1 += s.duration_ms();
+        }
+    }
+
+    let ranked = |key: fn(&(i64, i64)) -> i64| {
+        let mut apps: Vec<(&str, (i64, i64))> = per_app
+            .iter()
+            .map(|(app, ms)| (*app, *ms))
+            .filter(|(_, ms)| key(ms) > 0)
+            .collect();
+        apps.sort_by(|a, b| key(&b.1).cmp(&key(&a.1)).then_with(|| a.0.cmp(b.0)));
+        apps.truncate(limit);
+        apps.into_iter()
+            .map(|(app, (focused, unfocused))| AppContribution {
+                app: app.to_string(),
+                focused_minutes: ms_to_minutes(focused),
+                unfocused_minutes: ms_to_minutes(unfocused),
+            })
+            .collect()
+    };
+    AppContributions {
+        productive: ranked(|ms| ms.0),
+        time_sinks: ranked(|ms| ms.1),
+    }
+}
+
+/// The `limit` costliest stretches away from the main task (the focused mode
+/// with the most time), highest impact first (ties earliest first).
+/// Consecutive off-task spans up to a minute apart form one stretch. With no
+/// focused time at all, every unfocused stretch counts.
+pub fn ranked_distractions(spans: &[Span], limit: usize) -> Vec<RankedDistraction> {
+    let primary = mode_totals(spans)
+        .into_iter()
+        .find(|m| is_focused_mode(&m.mode))
+        .map(|m| m.mode);
+    let weight = |s: &Span| {
+        if primary.as_deref() == Some(s.mode.as_str()) {
+            0.0
+        } else if s.is_focused() {
+            OTHER_TASK_WEIGHT
+        } else {
+            1.0
+        }
+    };
+
+    let mut sorted: Vec<&Span> = spans.iter().collect();
+    sorted.sort_by_key(|s| s.start);
+
+    let mut runs: Vec<Vec<&Span>> = Vec::new();
+    let mut current: Vec<&Span> = Vec::new();
+    for s in sorted {
+        let joins = current
+            .last()
+            .is_some_and(|prev| s.start - prev.end <= FOCUS_BLOCK_GAP_MS);
+        if (weight(s) == 0.0 || !joins) && !current.is_empty() {
+            runs.push(std::mem::take(&mut current));
+        }
+        if weight(s) > 0.0 {
+            current.push(s);
+        }
+    }
+    if !current.is_empty() {
+        runs.push(current);
+    }
+
+    let mut ranked: Vec<RankedDistraction> = runs
+        .into_iter()
+        .filter_map(|run| {
+            let top = top_app(run.iter().copied())?;
+            let ms: i64 = run.iter().map(|s| s.duration_ms()).sum();
+            let weighted: f64 = run.iter().map(|s| s.duration_ms() as f64 * weight(s)).sum();
+            Some(RankedDistraction {
+                start: run[0].start,
+                end: run.iter().map(|s| s.end).max().unwrap_or(run[0].end),
+                app: top.app,
+                minutes: ms_to_minutes(ms),
+                impact: round1(weighted / 60_000.0),
+            })
+        })
+        .collect();
+    ranked.sort_by(|a, b| b.impact.total_cmp(&a.impact).then_with(|| a.start.cmp(&b.start)));
+    ranked.truncate(limit);
+    ranked
+}
+
+/// Read `CCUBE_DAILY_FOCUS_TARGET_MINUTES` (1-1440).
+/// DEFAULT_FOCUS_TARGET_MINUTES when unset.
+pub fn focus_target_from_env() -> Result<u32, String> {
+    match std::env::var("CCUBE_DAILY_FOCUS_TARGET_MINUTES") {
+        Ok(v) if !v.trim().is_empty() => match v.trim().parse::<u32>() {
+            Ok(n) if (1..=1440).contains(&n) => Ok(n),
+            _ => Err(format!(
+                "invalid CCUBE_DAILY_FOCUS_TARGET_MINUTES (need 1-1440): {v}"
+            )),
+        },
+        _ => Ok(DEFAULT_FOCUS_TARGET_MINUTES),
+    }
+}
+
+/// Read `CCUBE_SWITCH_RECOVERY_SECS` (1-3600).
+/// DEFAULT_SWITCH_RECOVERY_SECS when unset.
+pub fn switch_recovery_secs_from_env() -> Result<u32, String> {
+    match std::env::var("CCUBE_SWITCH_RECOVERY_SECS") {
+        Ok(v) if !v.trim().is_empty() => match v.trim().parse::<u32>() {
+            Ok(n) if (1..=3600).contains(&n) => Ok(n),
+            _ => Err(format!("invalid CCUBE_SWITCH_RECOVERY_SECS (need 1-3600): {v}")),
+        },
+        _ => Ok(DEFAULT_SWITCH_RECOVERY_SECS),
+    }
+}
+
+/// Estimate of the time switching cost: every switch counted under
+/// `counting` is charged `recovery_secs` of refocusing. A rule of thumb,
+/// not a measurement.
+pub fn switching_cost(
+    spans: &[Span],
+    counting: SwitchCounting,
+    recovery_secs: u32,
+) -> SwitchingCost {
+    let switches = counting.count(spans);
+    let lost_ms = switches as i64 * i64::from(recovery_secs) * 1000;
+    let active_ms = focus_totals(spans).active_ms;
+    SwitchingCost {
+        switches,
+        counting,
+        recovery_secs,
+        minutes_lost: ms_to_minutes(lost_ms),
+        share_of_active: (active_ms > 0).then(|| round1(lost_ms as f64 * 100.0 / active_ms as f64)),
+    }
+}
+
+/// Event kind the daemon records when it announces that the day's focus
+/// target was met, so a restart doesn't announce it again.
+pub const FOCUS_TARGET_MET_KIND: &str = "focus_target_met";
+
+/// Today's focused time against a target of `target_minutes`.
+pub fn target_progress(totals: FocusTotals, target_minutes: u32) -> TargetProgress {
+    let target_ms = i64::from(target_minutes.max(1)) * 60_000;
+    TargetProgress {
+        target_minutes,
+        focused_minutes: ms_to_minutes(totals.focused_ms),
+        percent: round1(totals.focused_ms as f64 * 100.0 / target_ms as f64),
+        met: totals.focused_ms >= target_ms,
+    }
+}
+
+/// Read the default study keywords from `CCUBE_STUDY_KEYWORDS`
+/// (comma-separated). Empty when unset.
+pub fn study_keywords_from_env() -> Vec<String> {
+    std::env::var("CCUBE_STUDY_KEYWORDS")
+        .map(|v| parse_keywords(&v))
+        .unwrap_or_default()
+}
+
+/// Split a comma-separated keyword list, lowercased, without blanks or
+/// duplicates.
+pub fn parse_keywords(value: &str) -> Vec<String> {
+    let mut keywords: Vec<String> = Vec::new();
+    for part in value.split(',') {
+        let k = part.trim().to_lowercase();
+        if !k.is_empty() && !keywords.contains(&k) {
+            keywords.push(k);
+        }
+    }
+    keywords
+}
+
+/// Split active time into on-topic (window title contains any of `keywords`,
+/// case-insensitive), off-topic and untitled.
+pub fn topic_focus(spans: &[Span], keywords: &[String]) -> TopicFocus {
+    let mut on_ms = 0;
+    let mut off_ms = 0;
+    let mut untitled_ms = 0;
+    let mut by_title: HashMap<&str, i64> = HashMap::new();
+    for s in spans {
+        match s.title.as_deref().filter(|t| !t.trim().is_empty()) {
+            None => untitled_ms += s.duration_ms(),
+            Some(title) => {
+                let lower = title.to_lowercase();
+                if keywords.iter().any(|k| lower.contains(k.as_str())) {
+                    on_ms += s.duration_ms();
+                    *by_title.entry(title).or_default() += s.duration_ms();
+                } else {
+                    off_ms += s.duration_ms();
+                }
+            }
+        }
+    }
+
+    let mut top_titles: Vec<TitleMinutes> = by_title
+        .into_iter()
+        .map(|(title, ms)| TitleMinutes {
+            title: title.to_string(),
+            minutes: ms_to_minutes(ms),
+        })
+        .collect();
+    top_titles.sort_by(|a, b| {
+        b.minutes
+            .total_cmp(&a.minutes)
+            .then_with(|| a.title.cmp(&b.title))
+    });
+    top_titles.truncate(REVIEW_TOP_APPS);
+
+    let titled_ms = on_ms + off_ms;
+    TopicFocus {
+        keywords: keywords.to_vec(),
+        on_topic_minutes: ms_to_minutes(on_ms),
+        off_topic_minutes: ms_to_minutes(off_ms),
+        untitled_minutes: ms_to_minutes(untitled_ms),
+        on_topic_share: (titled_ms > 0).then(|| round1(on_ms as f64 * 100.0 / titled_ms as f64)),
+        top_titles,
+    }
+}
+
+/// Summarise only the spans whose app name contains one of `apps`
+/// (case-insensitive, as produced by `parse_keywords`). Sessions are split as
+/// in `app_session_stats`.
+pub fn apps_summary(spans: &[Span], apps: &[String], merge_gap_ms: i64) -> AppsSummary {
+    let selected: Vec<Span> = spans
+        .iter()
+        .filter(|s| {
+            let app = s.app.to_lowercase();
+            apps.iter().any(|a| app.contains(a.as_str()))
+        })
+        .cloned()
+        .collect();
+    let totals = focus_totals(&selected);
+    let active_ms: i64 = spans.iter().map(Span::duration_ms).sum();
+
+    let mut by_title: HashMap<&str, i64> = HashMap::new();
+    for s in &selected {
+        if let Some(title) = s.title.as_deref().filter(|t| !t.trim().is_empty()) {
+            *by_title.entry(title).or_default() += s.duration_ms();
+        }
+    }
+    let top_titles = sorted_minutes(by_title)
+        .into_iter()
+        .take(REVIEW_TOP_APPS)
+        .map(|(title, minutes)| TitleMinutes { title, minutes })
+        .collect();
+
+    AppsSummary {
+        apps: apps.to_vec(),
+        minutes: ms_to_minutes(totals.active_ms),
+        focused_minutes: ms_to_minutes(totals.focused_ms),
+        share: (active_ms > 0).then(|| round1(totals.active_ms as f64 * 100.0 / active_ms as f64)),
+        switches: count_switches(&selected),
+        sessions: app_session_stats(&selected, merge_gap_ms),
+        top_titles,
+    }
+}
+
+/// Apps that never matched a focus mode pattern (always Unspecified), most
+/// used first. These are the apps whose categorisation is a placeholder rather
+/// than a considered choice, so they're the ones worth reviewing.
+pub fn unclassified_apps(spans: &[Span]) -> Vec<AppMinutes> {
+    let classified: std::collections::HashSet<&str> = spans
+        .iter()
+        .filter(|s| s.mode != "Unspecified")
+        .map(|s| s.app.as_str())
+        .collect();
+    app_totals(spans)
+        .into_iter()
+        .filter(|a| !a.app.is_empty() && !classified.contains(a.app.as_str()))
+        .collect()
+}
+
+/// The top app (by time) in each local clock hour of `date`. Hours with no
+/// activity, including those still in the future, have no leader.
+pub fn hourly_app_leaders<Tz: TimeZone>(
+    spans: &[Span],
+    date: NaiveDate,
+    tz: &Tz,
+) -> Vec<HourLeader> {
+    let (day_start, day_end) = day_bounds(date, tz);
+    if day_end <= day_start {
+        return Vec::new();
+    }
+    let edges = local_hour_edges(day_start, day_end, tz);
+    edges
+        .iter()
+        .zip(bucket_spans(spans, &edges))
+        .map(|(start, hour)| HourLeader {
+            hour: tz
+                .timestamp_millis_opt(*start)
+                .single()
+                .map_or(0, |local| local.hour()),
+            leader: top_app(hour.iter()),
+        })
+        .collect()
+}
+
+/// Hours of the day ranked by average focus score, best first (ties by
+/// hour). Each local clock hour with at least ten active minutes is one
+/// sample for its hour of the day; hours with fewer than `PRODUCTIVE_HOUR_MIN_SAMPLES` samples
+/// are left out. None when no hour has enough.
+pub fn productive_hours<Tz: TimeZone>(
+    spans: &[Span],
+    tz: &Tz,
+    scoring: &FocusScoring,
+) -> Option<Vec<ProductiveHour>> {
+    let first = spans.iter().map(|s| s.start).min()?;
+    let last = spans.iter().map(|s| s.end).max()?;
+
+    let mut scores: BTreeMap<u32, Vec<f64>> = BTreeMap::new();
+    let edges = local_hour_edges(first, last, tz);
+    for (start, hour) in edges.iter().zip(bucket_spans(spans, &edges)) {
+        let totals = scoring.totals(&hour);
+        let local = tz.timestamp_millis_opt(*start).single();
+        if totals.active_ms >= BASELINE_MIN_ACTIVE_MS
+            && let (Some(score), Some(local)) = (totals.focus_score(), local)
+        {
+            scores.entry(local.hour()).or_default().push(score);
+        }
+    }
+
+    let mut ranked: Vec<ProductiveHour> = scores
+        .into_iter()
+        .filter(|(_, s)| s.len() >= PRODUCTIVE_HOUR_MIN_SAMPLES)
+        .map(|(hour, s)| ProductiveHour {
+            hour,
+            avg_focus_score: round1(s.iter().sum::<f64>() / s.len() as f64),
+            samples: s.len(),
+        })
+        .collect();
+    ranked.sort_by(|a, b| {
+        b.avg_focus_score
+            .total_cmp(&a.avg_focus_score)
+            .then_with(|| a.hour.cmp(&b.hour))
+    });
+    (!ranked.is_empty()).then_some(ranked)
+}
+
+/// Split ranked productive hours into a suggested schedule: the best third
+/// of the hours for deep work, the worst third for admin, each merged into
+/// runs of consecutive hours. This is the offline fallback for the
+/// LLM-written schedule (`agents::ask::suggest_schedule`).
+pub fn schedule_recommendations(hours: &[ProductiveHour]) -> ScheduleRecommendations {
+    let mut ranked: Vec<&ProductiveHour> = hours.iter().collect();
+    ranked.sort_by(|a, b| {
+        b.avg_focus_score
+            .total_cmp(&a.avg_focus_score)
+            .then_with(|| a.hour.cmp(&b.hour))
+    });
+    let third = ranked.len().div_ceil(3);
+    let deep: Vec<&ProductiveHour> = ranked.iter().take(third).copied().collect();
+    // With very few ranked hours the two thirds overlap; deep work wins
+    let admin: Vec<&ProductiveHour> = ranked
+        .iter()
+        .rev()
+        .take(third)
+        .filter(|h| !deep.iter().any(|d| d.hour == h.hour))
+        .copied()
+        .collect();
+
+    ScheduleRecommendations {
+        deep_work: schedule_blocks(deep),
+        admin: schedule_blocks(admin),
+        source: ScheduleSource::Rules,
+        rationale: None,
+    }
+}
+
+/// The block [start_hour, end_hour) scored by the average of the productive
+/// hours inside it. None if the range isn't within the day or contains no
+/// measured hour.
+pub fn schedule_block(
+    hours: &[ProductiveHour],
+    start_hour: u32,
+    end_hour: u32,
+) -> Option<ScheduleBlock> {
+    if start_hour >= end_hour || end_hour > 24 {
+        return None;
+    }
+    let scores: Vec<f64> = hours
+        .iter()
+        .filter(|h| (start_hour..end_hour).contains(&h.hour))
+        .map(|h| h.avg_focus_score)
+        .collect();
+    (!scores.is_empty()).then(|| ScheduleBlock {
+        start_hour,
+        end_hour,
+        avg_focus_score: round1(scores.iter().sum::<f64>() / scores.len() as f64),
+    })
+}
+
+/// Merge hours into runs of consecutive hours, in time order.
+fn schedule_blocks(mut hours: Vec<&ProductiveHour>) -> Vec<ScheduleBlock> {
+    hours.sort_by_key(|h| h.hour);
+    let mut runs: Vec<Vec<&ProductiveHour>> = Vec::new();
+    for h in hours {
+        match runs.last_mut() {
+            Some(run) if run.last().is_some_and(|prev| prev.hour + 1 == h.hour) => run.push(h),
+            _ => runs.push(vec![h]),
+        }
+    }
+    runs.into_iter()
+        .map(|run| ScheduleBlock {
+            start_hour: run[0].hour,
+            end_hour: run[run.len() - 1].hour + 1,
+            avg_focus_score: round1(
+                run.iter().map(|h| h.avg_focus_score).sum::<f64>() / run.len() as f64,
+            ),
+        })
+        .collect()
+}
+
+/// Sum time per app and return the largest. Ties go to the alphabetically
+/// first app so the answer is stable.
+fn top_app<'a>(spans: impl Iterator<Item = &'a Span>) -> Option<AppMinutes> {
+    let mut per_app: HashMap<&str, i64> = HashMap::new();
+    for s in spans {
+        *per_app.entry(s.app.as_str()).or_default() += s.duration_ms();
+    }
+    per_app
+        .into_iter()
+        .filter(|(_, ms)| *ms > 0)
+        .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(a.0)))
+        .map(|(app, ms)| AppMinutes {
+            app: app.to_string(),
+            minutes: ms_to_minutes(ms),
+        })
+}
+
+/// The part of `span` inside [from, to), if any.
+fn clip_span(span: &Span, from: i64, to: i64) -> Option<Span> {
+    let (start, end) = (span.start.max(from), span.end.min(to));
+    (end > start).then(|| Span {
+        start,
+        end,
+        ..span.clone()
+    })
+}
+
+/// Split `spans` across the buckets between consecutive `edges` (sorted
+/// ascending) in one pass. Bucket i covers [edges[i], edges[i + 1]); time
+/// outside the first and last edge is dropped.
+pub(crate) fn bucket_spans(spans: &[Span], edges: &[i64]) -> Vec<Vec<Span>> {
+    let mut buckets = vec![Vec::new(); edges.len().saturating_sub(1)];
+    for span in spans {
+        let mut i = edges
+            .partition_point(|&e| e <= span.start)
+            .saturating_sub(1);
+        while i < buckets.len() && edges[i] < span.end {
+            if let Some(part) = clip_span(span, edges[i], edges[i + 1]) {
+                buckets[i].push(part);
+            }
+            i += 1;
+        }
+    }
+    buckets
+}
+
+/// Focus ratio per local hour or local day over [from_ts, now_ms). Buckets
+/// are aligned to the local clock, so half-hour offsets and DST shifts move
+/// the boundaries with it.
+pub fn focus_trend<Tz: TimeZone>(
+    events: &[EventRow],
+    from_ts: i64,
+    bucket: TrendBucket,
+    tz: &Tz,
+    scoring: &FocusScoring,
+    now_ms: i64,
+) -> Vec<TrendPoint> {
+    let spans = active_spans(events, from_ts, now_ms, now_ms);
+
+    let edges = match bucket {
+        TrendBucket::Hour => local_hour_edges(from_ts, now_ms, tz),
+        TrendBucket::Day => {
+            let Some(mut date) = tz
+                .timestamp_millis_opt(from_ts)
+                .single()
+                .map(|d| d.date_naive())
+            else {
+                return Vec::new();
+            };
+            let mut edges = vec![day_bounds(date, tz).0];
+            while edges.last().is_some_and(|&e| e < now_ms) {
+                match date.succ_opt() {
+                    Some(next) => date = next,
+                    None => break,
+                }
+                edges.push(day_bounds(date, tz).0);
+            }
+            edges
+        }
+    };
+
+    edges
+        .windows(2)
+        .zip(bucket_spans(&spans, &edges))
+        .map(|(bounds, clipped)| {
+            let totals = scoring.totals(&clipped);
+            TrendPoint {
+                start: bounds[0],
+                end: bounds[1],
+                active_minutes: ms_to_minutes(totals.active_ms),
+                focused_minutes: ms_to_minutes(totals.focused_ms),
+                focus_ratio: totals.focus_score(),
+            }
+        })
+        .collect()
+}
+
+/// Start of the local clock hour containing `ts`.
+fn local_hour_start<Tz: TimeZone>(ts: i64, tz: &Tz) -> i64 {
+    match tz.timestamp_millis_opt(ts).single() {
+        Some(local) => {
+            ts - (local.minute() as i64 * 60_000
+                + local.second() as i64 * 1000
+                + local.timestamp_subsec_millis() as i64)
+        }
+        None => ts - ts.rem_euclid(HOUR_MS),
+    }
+}
+
+/// Local clock-hour boundaries from the start of the hour containing `from`
+/// through the first boundary at or after `to`, for `bucket_spans`.
+pub(crate) fn local_hour_edges<Tz: TimeZone>(from: i64, to: i64, tz: &Tz) -> Vec<i64> {
+    let mut edges = vec![local_hour_start(from, tz)];
+    while let Some(&last) = edges.last()
+        && last < to
+    {
+        // Re-align after an offset change that isn't a whole hour
+        let next = local_hour_start(last + HOUR_MS, tz);
+        edges.push(if next > last { next } else { last + HOUR_MS });
+    }
+    edges
+}
+
+/// Focus score and active minutes for each day (Mon-Sun) of the week
+/// containing `week_start`. A day with a final stored rollup (written after
+/// the day ended) reads from it. Other days are computed from `events`,
+/// except days that ended before `oldest_event_ts`: their events are gone, so
+/// they use whatever rollup there is. Days after `now_ms`, and pruned days
+/// with no rollup, are returned with null values.
+pub fn weekly_focus_by_day<Tz: TimeZone>(
+    events: &[EventRow],
+    rollups: &[db::DailyStatsRow],
+    oldest_event_ts: Option<i64>,
+    week_start: NaiveDate,
+    tz: &Tz,
+    scoring: &FocusScoring,
+    now_ms: i64,
+) -> Vec<DayFocus> {
+    let by_date: HashMap<&str, &db::DailyStatsRow> =
+        rollups.iter().map(|r| (r.date.as_str(), r)).collect();
+    let monday = week_start_monday(week_start);
+    monday
+        .iter_days()
+        .take(7)
+        .map(|date| {
+            let (start, end) = day_bounds(date, tz);
+            let covered = oldest_event_ts.is_some_and(|oldest| end > oldest);
+            let rollup = by_date.get(date.format("%Y-%m-%d").to_string().as_str());
+            let (focus_score, active_minutes) = match rollup {
+                _ if start > now_ms => (None, None),
+                // A final rollup, or any rollup once the day's events are gone
+                Some(r) if r.updated_at >= end || !covered => {
+                    (r.focus_score, Some(ms_to_minutes(r.active_ms)))
+                }
+                _ if covered => {
+                    let totals = scoring.totals(&active_spans(events, start, end, now_ms));
+                    (totals.focus_score(), Some(ms_to_minutes(totals.active_ms)))
+                }
+                _ => (None, None),
+            };
+            DayFocus {
+                date,
+                focus_score,
+                active_minutes,
+            }
+        })
+        .collect()
+}
+
+/// Minutes per focus mode for each of the `weeks` weeks ending with the one
+/// containing `today`. The current week only counts up to `now_ms`. Weeks
+/// that began before `oldest_event_ts` have no minutes rather than zero, so
+/// pruned history doesn't read as a drop.
+pub fn mode_weekly_trend<Tz: TimeZone>(
+    events: &[EventRow],
+    oldest_event_ts: Option<i64>,
+    today: NaiveDate,
+    weeks: usize,
+    tz: &Tz,
+    now_ms: i64,
+) -> ModeWeeklyTrend {
+    let last = week_start_monday(today);
+    let mondays: Vec<NaiveDate> = (0..weeks.max(1) as i64)
+        .rev()
+        .map(|i| last - chrono::Duration::weeks(i))
+        .collect();
+
+    let mut per_week: Vec<Option<HashMap<String, i64>>> = Vec::with_capacity(mondays.len());
+    for &monday in &mondays {
+        let (from, _) = day_bounds(monday, tz);
+        if oldest_event_ts.is_none_or(|oldest| from < oldest) {
+            per_week.push(None);
+            continue;
+        }
+        let (_, to) = day_bounds(monday + chrono::Duration::days(6), tz);
+        let mut week: HashMap<String, i64> = HashMap::new();
+        for s in active_spans(events, from, to, now_ms) {
+            *week.entry(s.mode.clone()).or_default() += s.duration_ms();
+        }
+        per_week.push(Some(week));
+    }
+
+    let mut overall: HashMap<&str, i64> = HashMap::new();
+    for (mode, ms) in per_week.iter().flatten().flatten() {
+        *overall.entry(mode.as_str()).or_default() += ms;
+    }
+
+    let modes = sorted_minutes(overall)
+        .into_iter()
+        .map(|(mode, _)| ModeWeeks {
+            minutes: per_week
+                .iter()
+                .map(|w| {
+                    w.as_ref()
+                        .map(|w| ms_to_minutes(w.get(&mode).copied().unwrap_or(0)))
+                })
+                .collect(),
+            mode,
+        })
+        .collect();
+
+    ModeWeeklyTrend {
+        weeks: mondays,
+        last_week_days: today.weekday().num_days_from_monday() + 1,
+        modes,
+    }
+}
+
+/// The Monday on or before `date`.
+pub fn week_start_monday(date: NaiveDate) -> NaiveDate {
+    date - chrono::Duration::days(date.weekday().num_days_from_monday() as i64)
+}
+
+/// Millisecond bounds [start, end) of a calendar day in the given time zone.
+pub fn day_bounds<Tz: TimeZone>(date: NaiveDate, tz: &Tz) -> (i64, i64) {
+    let next = date.succ_opt().unwrap_or(date);
+    (local_midnight(date, tz), local_midnight(next, tz))
+}
+
+fn local_midnight<Tz: TimeZone>(date: NaiveDate, tz: &Tz) -> i64 {
+    let naive = date.and_hms_opt(0, 0, 0).unwrap_or_default();
+    tz.from_local_datetime(&naive)
+        .earliest()
+        .map(|dt| dt.timestamp_millis())
+        // Midnight skipped by a DST change — fall back to the UTC reading.
+        .unwrap_or_else(|| naive.and_utc().timestamp_millis())
+}
+
+/// Windows declared active by hand, as [start, end) pairs sorted by start.
+pub fn manual_active_windows(events: &[EventRow]) -> Vec<(i64, i64)> {
+    let mut windows: Vec<(i64, i64)> = events
+        .iter()
+        .filter(|e| e.kind == MANUAL_ACTIVE_KIND)
+        .filter_map(|e| Some((e.ts, e.ts + e.duration_ms.filter(|d| *d > 0)?)))
+        .collect();
+    windows.sort_unstable();
+    windows
+}
+
+/// End of the manual active window covering `now_ms`, if any.
+pub fn manual_active_until(events: &[EventRow], now_ms: i64) -> Option<i64> {
+    manual_active_windows(events)
+        .into_iter()
+        .filter(|(start, end)| *start <= now_ms && now_ms < *end)
+        .map(|(_, end)| end)
+        .max()
+}
+
+/// Idle periods as [start, end) pairs, minus any manual active windows. An
+/// idle period also ends at a session boundary; one still open at the end runs
+/// until `now_ms`.
+fn idle_intervals(events: &[EventRow], now_ms: i64) -> Vec<(i64, i64)> {
+    let mut intervals = Vec::new();
+    let mut open: Option<i64> = None;
+    for e in events {
+        match e.kind.as_str() {
+            "idle_start" if open.is_none() => open = Some(e.ts),
+            "idle_end" | "daemon_start" | "daemon_stop" => {
+                if let Some(start) = open.take() {
+                    intervals.push((start, e.ts));
+                }
+            }
+            _ => {}
+        }
+    }
+    if let Some(start) = open {
+        intervals.push((start, now_ms));
+    }
+
+    let manual = manual_active_windows(events);
+    if manual.is_empty() {
+        return intervals;
+    }
+    intervals
+        .into_iter()
+        .flat_map(|(start, end)| subtract_intervals(start, end, &manual))
+        .collect()
+}
+
+/// Remove `holes` (sorted by start) from [start, end), returning what's left.
+fn subtract_intervals(start: i64, end: i64, holes: &[(i64, i64)]) -> Vec<(i64, i64)> {
+    let mut pieces = Vec::new();
+    let mut cursor = start;
+    for &(h_start, h_end) in holes {
+        if h_end <= cursor || h_start >= end {
+            continue;
+        }
+        if h_start > cursor {
+            pieces.push((cursor, h_start));
+        }
+        cursor = cursor.max(h_end);
+        if cursor >= end {
+            break;
+        }
+    }
+    if cursor < end {
+        pieces.push((cursor, end));
+    }
+    pieces
+}
+
+/// Sort (key, ms) pairs by time descending then key, converting to minutes.
+fn sorted_minutes(totals: HashMap<&str, i64>) -> Vec<(String, f64)> {
+    let mut items: Vec<(&str, i64)> = totals.into_iter().filter(|(_, ms)| *ms > 0).collect();
+    items.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    items
+        .into_iter()
+        .map(|(k, ms)| (k.to_string(), ms_to_minutes(ms)))
+        .collect()
+}
+
+/// Milliseconds as minutes, rounded to one decimal.
+pub(crate) fn ms_to_minutes(ms: i64) -> f64 {
+    round1(ms as f64 / 60_000.0)
+}
+
+fn round1(v: f64) -> f64 {
+    (v * 10.0).round() / 10.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{FixedOffset, MappedLocalTime, Utc};
+
+    const MIN: i64 = 60_000;
+
+    fn focus(id: i64, ts: i64, app: &str, mode: &str, duration_ms: Option<i64>) -> EventRow {
+        EventRow {
+            id,
+            ts,
+            kind: "app_focus".to_string(),
+            app: Some(app.to_string()),
+            title: None,
+            duration_ms,
+            mode: Some(mode.to_string()),
+            ocr_text: None,
+            source: None,
+        }
+    }
+
+    fn marker(id: i64, ts: i64, kind: &str) -> EventRow {
+        EventRow {
+            id,
+            ts,
+            kind: kind.to_string(),
+            app: None,
+            title: None,
+            duration_ms: None,
+            mode: None,
+            ocr_text: None,
+            source: None,
+        }
+    }
+
+    fn utc_ms(date: &str, hour: u32) -> i64 {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .unwrap()
+            .and_hms_opt(hour, 0, 0)
+            .unwrap()
+            .and_utc()
+            .timestamp_millis()
+    }
+
+    #[test]
+    fn test_spans_clip_to_range() {
+        let events = vec![focus(1, 0, "Code.exe", "Coding", Some(10 * MIN))];
+        let spans = active_spans(&events, 5 * MIN, 20 * MIN, 60 * MIN);
+        assert_eq!(spans.len(), 1);
+        assert_eq!(spans[0].start, 5 * MIN);
+        assert_eq!(spans[0].end, 10 * MIN);
+    }
+
+    #[test]
+    fn test_open_event_runs_until_now() {
+        let events = vec![
+            marker(1, 0, "daemon_start"),
+            focus(2, MIN, "Code.exe", "Coding", None),
+        ];
+        let spans = active_spans(&events, 0, 60 * MIN, 11 * MIN);
+        assert_eq!(spans[0].duration_ms(), 10 * MIN);
+    }
+
+    #[test]
+    fn test_open_event_from_previous_session_ignored() {
+        let events = vec![
+            focus(1, MIN, "Code.exe", "Coding", None),
+            marker(2, 30 * MIN, "daemon_start"),
+        ];
+        let spans = active_spans(&events, 0, 60 * MIN, 40 * MIN);
+        assert!(spans.is_empty());
+    }
+
+    #[test]
+    fn test_short_and_empty_events_dropped() {
+        let events = vec![
+            focus(1, 0, "Code.exe", "Coding", Some(10 * MIN)),
+            focus(2, 10 * MIN, "chrome.exe", "Unspecified", Some(0)),
+            focus(3, 10 * MIN, "explorer.exe", "Unspecified", Some(-5_000)),
+            focus(4, 10 * MIN, "Slack.exe", "Unspecified", Some(500)),
+            focus(5, 11 * MIN, "Code.exe", "Coding", Some(5 * MIN)),
+        ];
+
+        // Zero and negative durations never make a span
+        let spans = active_spans_with_min(&events, 0, 60 * MIN, 60 * MIN, 0);
+        let apps: Vec<&str> = spans.iter().map(|s| s.app.as_str()).collect();
+        assert_eq!(apps, ["Code.exe", "Slack.exe", "Code.exe"]);
+
+        // A minimum also drops the sub-second blip
+        let spans = active_spans_with_min(&events, 0, 60 * MIN, 60 * MIN, 1_000);
+        assert_eq!(spans.len(), 2);
+        assert_eq!(count_switches(&spans), 0);
+        assert_eq!(focus_totals(&spans).active_ms, 15 * MIN);
+    }
+
+    #[test]
+    fn test_idle_is_subtracted() {
+        let events = vec![
+            focus(1, 0, "Code.exe", "Coding", Some(30 * MIN)),
+            marker(2, 10 * MIN, "idle_start"),
+            marker(3, 20 * MIN, "idle_end"),
+        ];
+        let spans = active_spans(&events, 0, 60 * MIN, 60 * MIN);
+        let totals = focus_totals(&spans);
+        assert_eq!(spans.len(), 2);
+        assert_eq!(totals.active_ms, 20 * MIN);
+    }
+
+    #[test]
+    fn test_manual_active_overrides_idle() {
+        let manual = |id: i64, ts: i64, duration_ms: i64| EventRow {
+            duration_ms: Some(duration_ms),
+            ..marker(id, ts, MANUAL_ACTIVE_KIND)
+        };
+        let events = vec![
+            focus(1, 0, "AcroRd32.exe", "Unspecified", Some(60 * MIN)),
+            marker(2, 10 * MIN, "idle_start"),
+            manual(3, 15 * MIN, 20 * MIN),
+            marker(4, 50 * MIN, "idle_end"),
+        ];
+        // Idle 10-50 minus the declared 15-35 leaves 20 minutes idle
+        let spans = active_spans(&events, 0, 60 * MIN, 60 * MIN);
+        assert_eq!(focus_totals(&spans).active_ms, 40 * MIN);
+        assert_eq!(idle_ms(&events, 0, 60 * MIN, 60 * MIN), 20 * MIN);
+
+        assert_eq!(manual_active_until(&events, 20 * MIN), Some(35 * MIN));
+        assert_eq!(manual_active_until(&events, 35 * MIN), None);
+    }
+
+    #[test]
+    fn test_focus_totals_and_score() {
+        let events = vec![
+            focus(1, 0, "Code.exe", "Coding", Some(30 * MIN)),
+            focus(2, 30 * MIN, "chrome.exe", "Unspecified", Some(10 * MIN)),
+        ];
+        let totals = focus_totals(&active_spans(&events, 0, 60 * MIN, 60 * MIN));
+        assert_eq!(totals.active_ms, 40 * MIN);
+        assert_eq!(totals.focused_ms, 30 * MIN);
+        assert_eq!(totals.focus_score(), Some(75.0));
+        assert_eq!(FocusTotals::default().focus_score(), None);
+    }
+
+    #[test]
+    fn test_whitelist_focus_scoring() {
+        let events = vec![
+            focus(1, 0, "Code.exe", "Coding", Some(30 * MIN)),
+            focus(2, 30 * MIN, "Figma.exe", "Unspecified", Some(10 * MIN)),
+            focus(3, 40 * MIN, "WINWORD.EXE", "Writing", Some(20 * MIN)),
+        ];
+        let spans = active_spans(&events, 0, 60 * MIN, 60 * MIN);
+        let whitelist = FocusScoring::Whitelist {
+            apps: parse_keywords("code, figma"),
+            work_hours: None,
+        };
+
+        // Figma counts whatever its mode; Word doesn't, even though it's Writing
+        let totals = whitelist.totals(&spans);
+        assert_eq!(totals.focused_ms, 40 * MIN);
+        assert_eq!(totals.active_ms, 60 * MIN);
+        assert_eq!(FocusScoring::Modes.totals(&spans), focus_totals(&spans));
+        assert_eq!(focus_totals(&spans).focused_ms, 50 * MIN);
+    }
+
+    #[test]
+    fn test_whitelist_work_hours() {
+        let hours = WorkHours::parse("9-17").unwrap();
+        assert!(hours.contains(9) && hours.contains(16) && !hours.contains(17));
+        let night = WorkHours::parse(" 22 - 6 ").unwrap();
+        assert!(night.contains(23) && night.contains(0) && !night.contains(6));
+        assert_eq!(WorkHours::parse("9-9"), None);
+        assert_eq!(WorkHours::parse("9-25"), None);
+        assert_eq!(WorkHours::parse("nine-five"), None);
+
+        // Local 08:00-10:00 browsing, 10:00-11:00 coding, 17:00-18:00 browsing
+        let at = |hour: u32| {
+            chrono::Local
+                .from_local_datetime(
+                    &NaiveDate::from_ymd_opt(2026, 10, 14)
+                        .unwrap()
+                        .and_hms_opt(hour, 0, 0)
+                        .unwrap(),
+                )
+                .earliest()
+                .unwrap()
+                .timestamp_millis()
+        };
+        let spans = vec![
+            Span {
+                start: at(8),
+                end: at(10),
+                app: "chrome.exe".to_string(),
+                title: None,
+                mode: "Unspecified".to_string(),
+            },
+            Span {
+                start: at(10),
+                end: at(11),
+                app: "Code.exe".to_string(),
+                title: None,
+                mode: "Coding".to_string(),
+            },
+            Span {
+                start: at(17),
+                end: at(18),
+                app: "chrome.exe".to_string(),
+                title: None,
+                mode: "Unspecified".to_string(),
+            },
+        ];
+        let whitelist = FocusScoring::Whitelist {
+            apps: parse_keywords("code"),
+            work_hours: Some(hours),
+        };
+
+        // Only the browsing from 09:00 counts against the score
+        let totals = whitelist.totals(&spans);
+        assert_eq!(totals.focused_ms, 60 * MIN);
+        assert_eq!(totals.active_ms, 120 * MIN);
+    }
+
+    #[test]
+    fn test_whitelist_focus_blocks_and_streak() {
+        // Figma isn't a focus mode, but the whitelist makes it focus time
+        let events = vec![
+            focus(1, 0, "Figma.exe", "Unspecified", Some(30 * MIN)),
+            focus(2, 30 * MIN, "Code.exe", "Coding", Some(30 * MIN)),
+        ];
+        let spans = active_spans(&events, 0, 60 * MIN, 60 * MIN);
+        let whitelist = FocusScoring::Whitelist {
+            apps: parse_keywords("figma"),
+            work_hours: None,
+        };
+
+        let blocks = focus_blocks(&spans, &whitelist);
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].minutes, 30.0);
+        assert_eq!(blocks[0].start, 0);
+        assert_eq!(
+            longest_focus_block(&spans, &FocusScoring::Modes)
+                .unwrap()
+                .start,
+            30 * MIN
+        );
+        assert_eq!(top_distraction(&spans, &whitelist).unwrap().app, "Code.exe");
+
+        // Coding ended the whitelisted run, so there's no streak at the end
+        assert!(current_focus_streak(&spans, &whitelist, 0, 60 * MIN).is_none());
+        assert!(current_focus_streak(&spans, &FocusScoring::Modes, 0, 60 * MIN).is_some());
+    }
+
+    #[test]
+    fn test_week_start_monday() {
+        let wed = NaiveDate::from_ymd_opt(2026, 10, 14).unwrap();
+        let mon = NaiveDate::from_ymd_opt(2026, 10, 12).unwrap();
+        assert_eq!(week_start_monday(wed), mon);
+        assert_eq!(week_start_monday(mon), mon);
+    }
+
+    #[test]
+    fn test_weekly_focus_by_day_partial_week() {
+        // Monday 2026-10-12: 1h coding. Tuesday: 30m coding + 30m unfocused.
+        let events = vec![
+            focus(
+                1,
+                utc_ms("2026-10-12", 9),
+                "Code.exe",
+                "Coding",
+                Some(60 * MIN),
+            ),
+            focus(
+                2,
+                utc_ms("2026-10-13", 9),
+                "Code.exe",
+                "Coding",
+                Some(30 * MIN),
+            ),
+            focus(
+                3,
+                utc_ms("2026-10-13", 10),
+                "chrome.exe",
+                "Unspecified",
+                Some(30 * MIN),
+            ),
+        ];
+        let now = utc_ms("2026-10-14", 12); // Wednesday noon
+        let week = weekly_focus_by_day(
+            &events,
+            &[],
+            Some(events[0].ts),
+            NaiveDate::from_ymd_opt(2026, 10, 14).unwrap(),
+            &Utc,
+            &FocusScoring::Modes,
+            now,
+        );
+
+        assert_eq!(week.len(), 7);
+        assert_eq!(week[0].date.to_string(), "2026-10-12");
+        assert_eq!(week[0].focus_score, Some(100.0));
+        assert_eq!(week[0].active_minutes, Some(60.0));
+        assert_eq!(week[1].focus_score, Some(50.0));
+        // Wednesday so far: no activity
+        assert_eq!(week[2].focus_score, None);
+        assert_eq!(week[2].active_minutes, Some(0.0));
+        // Thursday onwards hasn't happened yet
+        assert!(week[3..].iter().all(|d| d.active_minutes.is_none()));
+    }
+
+    #[test]
+    fn test_weekly_focus_by_day_reads_rollups_for_pruned_days() {
+        let rollup = |date: &str, active_ms: i64, updated_at: i64| db::DailyStatsRow {
+            date: date.to_string(),
+            active_ms,
+            focused_ms: active_ms / 2,
+            focus_score: Some(50.0),
+            updated_at,
+        };
+        // Events were pruned up to Wednesday noon; only Wednesday afternoon is left
+        let events = vec![focus(
+            1,
+            utc_ms("2026-10-14", 13),
+            "Code.exe",
+            "Coding",
+            Some(60 * MIN),
+        )];
+        let rollups = vec![
+            rollup("2026-10-12", 120 * MIN, utc_ms("2026-10-13", 0)),
+            // Written mid-day and never finalised, but the events are gone
+            rollup("2026-10-13", 30 * MIN, utc_ms("2026-10-13", 12)),
+        ];
+        let now = utc_ms("2026-10-15", 12);
+        let week = weekly_focus_by_day(
+            &events,
+            &rollups,
+            Some(utc_ms("2026-10-14", 12)),
+            NaiveDate::from_ymd_opt(2026, 10, 12).unwrap(),
+            &Utc,
+            &FocusScoring::Modes,
+            now,
+        );
+
+        assert_eq!(week[0].active_minutes, Some(120.0));
+        assert_eq!(week[0].focus_score, Some(50.0));
+        assert_eq!(week[1].active_minutes, Some(30.0));
+        // Wednesday still has events, so it's computed from them
+        assert_eq!(week[2].active_minutes, Some(60.0));
+        assert_eq!(week[2].focus_score, Some(100.0));
+        // Thursday so far: recorded but idle
+        assert_eq!(week[3].active_minutes, Some(0.0));
+
+        // With no events at all, days without a rollup are unknown, not zero
+        let week = weekly_focus_by_day(
+            &[],
+            &rollups[..1],
+            None,
+            NaiveDate::from_ymd_opt(2026, 10, 12).unwrap(),
+            &Utc,
+            &FocusScoring::Modes,
+            now,
+        );
+        assert_eq!(week[0].active_minutes, Some(120.0));
+        assert!(week[1..].iter().all(|d| d.active_minutes.is_none()));
+    }
+
+    #[test]
+    fn test_top_distraction() {
+        let events = vec![
+            focus(1, 0, "Code.exe", "Coding", Some(60 * MIN)),
+            focus(2, 60 * MIN, "chrome.exe", "Unspecified", Some(20 * MIN)),
+            focus(3, 80 * MIN, "Discord.exe", "Unspecified", Some(15 * MIN)),
+            focus(4, 95 * MIN, "chrome.exe", "Unspecified", Some(10 * MIN)),
+        ];
+        let spans = active_spans(&events, 0, 200 * MIN, 200 * MIN);
+        let top = top_distraction(&spans, &FocusScoring::Modes).unwrap();
+        assert_eq!(top.app, "chrome.exe");
+        assert_eq!(top.minutes, 30.0);
+
+        // Only focused work: no distraction
+        assert_eq!(top_distraction(&spans[..1], &FocusScoring::Modes), None);
+    }
+
+    #[test]
+    fn test_hourly_app_leaders() {
+        let day = NaiveDate::from_ymd_opt(2026, 10, 14).unwrap();
+        let nine = utc_ms("2026-10-14", 9);
+        let events = vec![
+            // 09:00-09:40 editor, 09:40-10:30 mail: editor wins 9h, mail wins 10h
+            focus(1, nine, "Code.exe", "Coding", Some(40 * MIN)),
+            focus(
+                2,
+                nine + 40 * MIN,
+                "OUTLOOK.EXE",
+                "Unspecified",
+                Some(50 * MIN),
+            ),
+        ];
+        let (from, to) = day_bounds(day, &Utc);
+        let spans = active_spans(&events, from, to, to);
+        let leaders = hourly_app_leaders(&spans, day, &Utc);
+
+        assert_eq!(leaders.len(), 24);
+        assert_eq!(leaders[8].leader, None);
+        let nine_am = leaders[9].leader.as_ref().unwrap();
+        assert_eq!(nine_am.app, "Code.exe");
+        assert_eq!(nine_am.minutes, 40.0);
+        let ten_am = leaders[10].leader.as_ref().unwrap();
+        assert_eq!(ten_am.app, "OUTLOOK.EXE");
+        assert_eq!(ten_am.minutes, 30.0);
+        assert_eq!(leaders[11].leader, None);
+    }
+
+    /// UTC+1 until 2026-03-29 01:00 UTC, then UTC+2: local clocks jump from
+    /// 02:00 to 03:00 that night, as in central Europe.
+    #[derive(Debug, Clone, Copy)]
+    struct SpringForward;
+
+    impl SpringForward {
+        fn offset_at(ts: i64) -> FixedOffset {
+            let hours = if ts < utc_ms("2026-03-29", 1) { 1 } else { 2 };
+            FixedOffset::east_opt(hours * 3600).unwrap()
+        }
+    }
+
+    impl TimeZone for SpringForward {
+        type Offset = FixedOffset;
+
+        fn from_offset(_: &FixedOffset) -> Self {
+            SpringForward
+        }
+
+        fn offset_from_local_date(&self, local: &NaiveDate) -> MappedLocalTime<FixedOffset> {
+            self.offset_from_local_datetime(&local.and_hms_opt(0, 0, 0).unwrap())
+        }
+
+        fn offset_from_local_datetime(
+            &self,
+            local: &chrono::NaiveDateTime,
+        ) -> MappedLocalTime<FixedOffset> {
+            let valid: Vec<FixedOffset> = [1, 2]
+                .into_iter()
+                .map(|h| FixedOffset::east_opt(h * 3600).unwrap())
+                .filter(|off| {
+                    let utc = (*local - *off).and_utc().timestamp_millis();
+                    Self::offset_at(utc) == *off
+                })
+                .collect();
+            match valid[..] {
+                [off] => MappedLocalTime::Single(off),
+                [a, b] => MappedLocalTime::Ambiguous(a, b),
+                _ => MappedLocalTime::None,
+            }
+        }
+
+        fn offset_from_utc_date(&self, utc: &NaiveDate) -> FixedOffset {
+            self.offset_from_utc_datetime(&utc.and_hms_opt(0, 0, 0).unwrap())
+        }
+
+        fn offset_from_utc_datetime(&self, utc: &chrono::NaiveDateTime) -> FixedOffset {
+            Self::offset_at(utc.and_utc().timestamp_millis())
+        }
+    }
+
+    #[test]
+    fn test_hourly_app_leaders_dst_day() {
+        let day = NaiveDate::from_ymd_opt(2026, 3, 29).unwrap();
+        // Local 01:00-02:00 (UTC+1) editor, then local 03:00-04:00 (UTC+2) mail
+        let one_am = utc_ms("2026-03-29", 0);
+        let events = vec![
+            focus(1, one_am, "Code.exe", "Coding", Some(60 * MIN)),
+            focus(
+                2,
+                one_am + 60 * MIN,
+                "OUTLOOK.EXE",
+                "Unspecified",
+                Some(60 * MIN),
+            ),
+        ];
+        let (from, to) = day_bounds(day, &SpringForward);
+        let spans = active_spans(&events, from, to, to);
+        let leaders = hourly_app_leaders(&spans, day, &SpringForward);
+
+        // 23 local hours with no 02:00, labeled by the clock
+        assert_eq!(leaders.len(), 23);
+        let hours: Vec<u32> = leaders.iter().map(|l| l.hour).collect();
+        assert_eq!(&hours[..4], &[0, 1, 3, 4]);
+        assert_eq!(hours[22], 23);
+        assert_eq!(leaders[1].leader.as_ref().unwrap().app, "Code.exe");
+        let three_am = leaders[2].leader.as_ref().unwrap();
+        assert_eq!(three_am.app, "OUTLOOK.EXE");
+        assert_eq!(three_am.minutes, 60.0);
+    }
+
+    #[test]
+    fn test_unclassified_apps() {
+        let events = vec![
+            focus(1, 0, "chrome.exe", "Unspecified", Some(10 * MIN)),
+            focus(2, 10 * MIN, "chrome.exe", "Writing", Some(5 * MIN)),
+            focus(3, 15 * MIN, "Slack.exe", "Unspecified", Some(5 * MIN)),
+            focus(4, 20 * MIN, "Anki.exe", "Unspecified", Some(20 * MIN)),
+            focus(5, 40 * MIN, "Slack.exe", "Unspecified", Some(5 * MIN)),
+        ];
+        let spans = active_spans(&events, 0, 60 * MIN, 60 * MIN);
+        let apps = unclassified_apps(&spans);
+
+        // chrome.exe matched Writing once, so it's excluded
+        let names: Vec<&str> = apps.iter().map(|a| a.app.as_str()).collect();
+        assert_eq!(names, vec!["Anki.exe", "Slack.exe"]);
+        assert_eq!(apps[1].minutes, 10.0);
+    }
+
+    #[test]
+    fn test_longest_focus_block() {
+        let events = vec![
+            focus(1, 0, "Code.exe", "Coding", Some(20 * MIN)),
+            focus(2, 20 * MIN, "WINWORD.EXE", "Writing", Some(15 * MIN)),
+            focus(3, 35 * MIN, "chrome.exe", "Unspecified", Some(5 * MIN)),
+            focus(4, 40 * MIN, "Code.exe", "Coding", Some(30 * MIN)),
+        ];
+        let spans = active_spans(&events, 0, 120 * MIN, 120 * MIN);
+        let block = longest_focus_block(&spans, &FocusScoring::Modes).unwrap();
+        // Coding + Writing back-to-back (35 min) beats the later 30 min block
+        assert_eq!(block.start, 0);
+        assert_eq!(block.end, 35 * MIN);
+        assert_eq!(block.minutes, 35.0);
+    }
+
+    #[test]
+    fn test_daily_review() {
+        let day = NaiveDate::from_ymd_opt(2026, 10, 14).unwrap();
+        let nine = utc_ms("2026-10-14", 9);
+        let events = vec![
+            focus(1, nine, "Code.exe", "Coding", Some(45 * MIN)),
+            focus(
+                2,
+                nine + 45 * MIN,
+                "chrome.exe",
+                "Unspecified",
+                Some(15 * MIN),
+            ),
+        ];
+        let (from, to) = day_bounds(day, &Utc);
+        let review = daily_review(
+            day,
+            &active_spans(&events, from, to, to),
+            &FocusScoring::Modes,
+            Some(60),
+        );
+
+        assert_eq!(review.active_minutes, 60.0);
+        assert_eq!(review.focused_minutes, 45.0);
+        assert_eq!(review.focus_score, Some(75.0));
+        assert_eq!(review.top_apps[0].app, "Code.exe");
+        assert_eq!(review.modes[1].mode, "Unspecified");
+        assert_eq!(review.longest_focus_block.unwrap().minutes, 45.0);
+        assert_eq!(review.top_distraction.unwrap().app, "chrome.exe");
+        assert_eq!(review.sessions.len(), 1);
+        assert_eq!(review.sessions[0].start, nine);
+        let target = review.target_progress.unwrap();
+        assert_eq!(target.percent, 75.0);
+        assert!(!target.met);
+    }
+
+    #[test]
+    fn test_count_switches() {
+        let events = vec![
+            focus(1, 0, "Code.exe", "Coding", Some(MIN)),
+            focus(2, MIN, "Code.exe", "Coding", Some(MIN)),
+            focus(3, 2 * MIN, "chrome.exe", "Unspecified", Some(MIN)),
+            focus(4, 3 * MIN, "Code.exe", "Coding", Some(MIN)),
+        ];
+        let spans = active_spans(&events, 0, 10 * MIN, 10 * MIN);
+        assert_eq!(count_switches(&spans), 2);
+        assert_eq!(count_mode_switches(&spans), 2);
+
+        // Editor to terminal while coding is a switch, but not a cross-mode one
+        let events = vec![
+            focus(1, 0, "Code.exe", "Coding", Some(MIN)),
+            focus(2, MIN, "WindowsTerminal.exe", "Coding", Some(MIN)),
+            focus(3, 2 * MIN, "slack.exe", "Unspecified", Some(MIN)),
+        ];
+        let spans = active_spans(&events, 0, 10 * MIN, 10 * MIN);
+        assert_eq!(SwitchCounting::All.count(&spans), 2);
+        assert_eq!(SwitchCounting::CrossMode.count(&spans), 1);
+    }
+
+    #[test]
+    fn test_baseline_deviation_flags_switch_spike() {
+        let hour = 60 * MIN;
+        let now = 10 * hour;
+        let mut events = Vec::new();
+        let mut id = 0;
+        // Hours 0..8: two 30-minute spans per hour (one switch each hour,
+        // alternating apps so the boundary between hours also switches).
+        for h in 0..8 {
+            for (i, app) in ["Code.exe", "chrome.exe"].iter().enumerate() {
+                id += 1;
+                let mode = if i == 0 { "Coding" } else { "Unspecified" };
+                events.push(focus(
+                    id,
+                    h * hour + i as i64 * 30 * MIN,
+                    app,
+                    mode,
+                    Some(30 * MIN),
+                ));
+            }
+        }
+        // Last hour: twelve 5-minute spans alternating apps (11 switches).
+        for i in 0..12 {
+            id += 1;
+            let app = if i % 2 == 0 { "Code.exe" } else { "Slack.exe" };
+            events.push(focus(
+                id,
+                9 * hour + i * 5 * MIN,
+                app,
+                "Unspecified",
+                Some(5 * MIN),
+            ));
+        }
+        let spans = active_spans(&events, 0, now, now);
+        let dev = baseline_deviation(&spans, 1, SwitchCounting::All, &FocusScoring::Modes, now);
+
+        assert_eq!(dev.samples, 8);
+        let switches = &dev.metrics[0];
+        assert_eq!(switches.metric, "switches_per_hour");
+        assert_eq!(switches.current, Some(11.0));
+        assert_eq!(switches.baseline_mean, Some(1.0));
+        assert_eq!(switches.in_range, Some(false));
+        assert_eq!(switches.severity, Some(Severity::Severe));
+        assert_eq!(switches.severity_level, Some(3));
+        let score = &dev.metrics[1];
+        assert_eq!(score.current, Some(0.0));
+        assert_eq!(score.baseline_mean, Some(50.0));
+    }
+
+    #[test]
+    fn test_bucket_spans_splits_at_edges() {
+        let span = |start: i64, end: i64| Span {
+            start,
+            end,
+            app: "app.exe".to_string(),
+            title: None,
+            mode: "Coding".to_string(),
+        };
+        let edges = [0, HOUR_MS, 2 * HOUR_MS, 3 * HOUR_MS];
+        // Starts before the first edge and runs into the third bucket
+        let spans = [
+            span(-10 * MIN, 2 * HOUR_MS + 5 * MIN),
+            span(3 * HOUR_MS, 4 * HOUR_MS),
+        ];
+        let buckets = bucket_spans(&spans, &edges);
+
+        assert_eq!(buckets.len(), 3);
+        let ranges: Vec<Vec<(i64, i64)>> = buckets
+            .iter()
+            .map(|b| b.iter().map(|s| (s.start, s.end)).collect())
+            .collect();
+        assert_eq!(
+            ranges,
+            vec![
+                vec![(0, HOUR_MS)],
+                vec![(HOUR_MS, 2 * HOUR_MS)],
+                vec![(2 * HOUR_MS, 2 * HOUR_MS + 5 * MIN)],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_severity_bands() {
+        assert_eq!(Severity::from_percent(0.0), Severity::None);
+        assert_eq!(Severity::from_percent(24.9), Severity::None);
+        assert_eq!(Severity::from_percent(25.0), Severity::Mild);
+        assert_eq!(Severity::from_percent(50.0), Severity::Moderate);
+        assert_eq!(Severity::from_percent(100.0), Severity::Severe);
+
+        assert_eq!(Severity::from_z_score(0.5), Severity::None);
+        assert_eq!(Severity::from_z_score(-1.5), Severity::Mild);
+        assert_eq!(Severity::from_z_score(2.0), Severity::Moderate);
+        assert_eq!(Severity::from_z_score(-3.2), Severity::Severe);
+
+        assert_eq!(Severity::None.level(), 0);
+        assert_eq!(Severity::Severe.level(), 3);
+        assert_eq!(
+            serde_json::to_string(&Severity::Moderate).unwrap(),
+            "\"moderate\""
+        );
+    }
+
+    #[test]
+    fn test_switch_pressure() {
+        let now = 60 * MIN;
+        // Six one-minute spans alternating apps before `now`: 5 switches
+        let events: Vec<EventRow> = (0..6)
+            .map(|i| {
+                let app = if i % 2 == 0 { "Code.exe" } else { "chrome.exe" };
+                focus(i, now - 6 * MIN + i * MIN, app, "Coding", Some(MIN))
+            })
+            .collect();
+        let spans = active_spans(&events, 0, now, now);
+
+        // 12/h baseline -> 1 expected in 5 min; 4 switches in window -> ratio 4
+        let p = switch_pressure(&spans, Some(12.0), 1.0, SwitchCounting::All, now);
+        assert_eq!(p.switches, 4);
+        assert_eq!(p.expected, 1.0);
+        assert_eq!(p.pressure, 100);
+        assert_eq!((p.severity, p.severity_level), (Severity::Severe, 3));
+
+        // Higher baseline: 48/h -> 4 expected -> no pressure
+        assert_eq!(
+            switch_pressure(&spans, Some(48.0), 1.0, SwitchCounting::All, now).pressure,
+            0
+        );
+
+        // 24/h -> 2 expected -> ratio 2 -> 50, doubled by sensitivity
+        assert_eq!(
+            switch_pressure(&spans, Some(24.0), 1.0, SwitchCounting::All, now).pressure,
+            50
+        );
+        assert_eq!(
+            switch_pressure(&spans, Some(24.0), 2.0, SwitchCounting::All, now).pressure,
+            100
+        );
+        assert!(!switch_pressure(&spans, None, 1.0, SwitchCounting::All, now).has_baseline);
+
+        // All six spans are Coding: no cross-mode switches, so no pressure
+        let cross = switch_pressure(&spans, Some(12.0), 1.0, SwitchCounting::CrossMode, now);
+        assert_eq!((cross.switches, cross.mode_switches), (4, 0));
+        assert_eq!(cross.pressure, 0);
+    }
+
+    #[test]
+    fn test_mode_breakdown_with_idle() {
+        let events = vec![
+            focus(1, 0, "Code.exe", "Coding", Some(60 * MIN)),
+            marker(2, 10 * MIN, "idle_start"),
+            marker(3, 30 * MIN, "idle_end"),
+            focus(4, 60 * MIN, "chrome.exe", "Unspecified", Some(10 * MIN)),
+        ];
+
+        let without = mode_breakdown(&events, 0, 120 * MIN, 120 * MIN, false);
+        assert_eq!(without.len(), 2);
+        assert_eq!(without[0].mode, "Coding");
+        assert_eq!(without[0].minutes, 40.0);
+
+        let with = mode_breakdown(&events, 0, 120 * MIN, 120 * MIN, true);
+        let names: Vec<&str> = with.iter().map(|m| m.mode.as_str()).collect();
+        assert_eq!(names, vec!["Coding", "Idle", "Unspecified"]);
+        assert_eq!(with[1].minutes, 20.0);
+        assert_eq!(with[1].app_count, 0);
+    }
+
+    #[test]
+    fn test_project_focus() {
+        let now = 180 * MIN;
+        // Two hours of coding, then an hour of browsing
+        let events = vec![
+            focus(1, 0, "Code.exe", "Coding", Some(120 * MIN)),
+            focus(2, 120 * MIN, "chrome.exe", "Unspecified", Some(60 * MIN)),
+        ];
+        let spans = active_spans(&events, 0, now, now);
+        let p = project_focus(&spans, &FocusScoring::Modes, now).unwrap();
+        assert_eq!(p.so_far_score, 66.7);
+        assert_eq!(p.recent_score, Some(0.0));
+        // 66.7 * 0.7 + 0 * 0.3
+        assert_eq!(p.projected_score, 46.7);
+
+        // Not enough data yet
+        let early = active_spans(&events, 0, 20 * MIN, 20 * MIN);
+        assert_eq!(project_focus(&early, &FocusScoring::Modes, 20 * MIN), None);
+    }
+
+    #[test]
+    fn test_since_return_summary() {
+        let events = vec![
+            focus(1, 0, "chrome.exe", "Unspecified", Some(60 * MIN)),
+            marker(2, 60 * MIN, "idle_start"),
+            marker(3, 110 * MIN, "idle_end"),
+            focus(4, 110 * MIN, "Code.exe", "Coding", Some(20 * MIN)),
+            // A short break that shouldn't reset the window
+            marker(5, 130 * MIN, "idle_start"),
+            marker(6, 133 * MIN, "idle_end"),
+            focus(7, 133 * MIN, "Code.exe", "Coding", Some(7 * MIN)),
+        ];
+        let now = 140 * MIN;
+
+        let summary = since_return_summary(&events, 30 * MIN, 0, &FocusScoring::Modes, now, &Utc);
+        assert_eq!(summary.from, 110 * MIN);
+        assert_eq!(summary.period, "since 01:50 (back after 50 min away)");
+        assert_eq!(summary.active_minutes, 27.0);
+        assert_eq!(summary.focus_score, Some(100.0));
+
+        // No break long enough: falls back to the given start
+        let fallback = since_return_summary(&events, 90 * MIN, 0, &FocusScoring::Modes, now, &Utc);
+        assert_eq!(fallback.from, 0);
+        assert!(fallback.period.contains("no long break"));
+    }
+
+    #[test]
+    fn test_focus_by_timeframe() {
+        let events = vec![
+            focus(1, 0, "Code.exe", "Coding", Some(40 * MIN)),
+            focus(2, 40 * MIN, "chrome.exe", "Unspecified", Some(15 * MIN)),
+            focus(3, 55 * MIN, "Code.exe", "Coding", Some(5 * MIN)),
+        ];
+        let now = 60 * MIN;
+        let spans = active_spans(&events, 0, now, now);
+
+        let scores = focus_by_timeframe(&spans, &FocusScoring::Modes, now);
+        assert_eq!(scores.len(), FOCUS_TIMEFRAMES.len());
+        assert_eq!(scores["5m"], Some(100.0));
+        assert_eq!(scores["30m"], Some(50.0));
+        assert_eq!(scores["1h"], Some(75.0));
+
+        // Nothing in the last five minutes
+        assert_eq!(
+            focus_by_timeframe(&spans, &FocusScoring::Modes, 2 * now)["5m"],
+            None
+        );
+    }
+
+    #[test]
+    fn test_mode_detail() {
+        let events = vec![
+            focus(1, 0, "Code.exe", "Coding", Some(30 * MIN)),
+            focus(2, 30 * MIN, "youtube", "Unspecified", Some(10 * MIN)),
+            focus(3, 40 * MIN, "WindowsTerminal.exe", "Coding", Some(10 * MIN)),
+            focus(4, 50 * MIN, "Code.exe", "Coding", Some(10 * MIN)),
+        ];
+        let spans = active_spans(&events, 0, 60 * MIN, 60 * MIN);
+
+        let coding = mode_detail(&spans, "coding");
+        assert_eq!(coding.mode, "Coding");
+        assert!(coding.focused);
+        assert_eq!(coding.minutes, 50.0);
+        assert_eq!(coding.share, Some(83.3));
+        assert_eq!(coding.apps[0].app, "Code.exe");
+        assert_eq!(coding.apps[0].minutes, 40.0);
+        assert_eq!(coding.apps[1].app, "WindowsTerminal.exe");
+
+        let other = mode_detail(&spans, "uncategorized");
+        assert_eq!(other.mode, "Unspecified");
+        assert_eq!(other.apps.len(), 1);
+
+        assert!(mode_detail(&spans, "Writing").apps.is_empty());
+
+        let totals = mode_totals(&spans);
+        assert_eq!(
+            (totals[0].mode.as_str(), totals[0].app_count),
+            ("Coding", 2)
+        );
+        assert_eq!(
+            (totals[1].mode.as_str(), totals[1].app_count),
+            ("Unspecified", 1)
+        );
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(95 * 60, TimeUnit::Minutes), "95 min");
+        assert_eq!(format_duration(95 * 60, TimeUnit::Hours), "1.6 h");
+        assert_eq!(format_duration(95 * 60, TimeUnit::Auto), "1h 35m");
+        assert_eq!(format_duration(59 * 60 + 20, TimeUnit::Auto), "59 min");
+        assert_eq!(format_duration(59 * 60 + 40, TimeUnit::Auto), "1h 00m");
+        assert_eq!(format_duration(-5, TimeUnit::Minutes), "0 min");
+    }
+
+    #[test]
+    fn test_app_session_stats() {
+        let events = vec![
+            focus(1, 0, "Code.exe", "Coding", Some(20 * MIN)),
+            // A 30s glance at Slack doesn't split the Code session
+            focus(2, 20 * MIN, "slack.exe", "Unspecified", Some(MIN / 2)),
+            focus(3, 20 * MIN + MIN / 2, "Code.exe", "Coding", Some(20 * MIN)),
+            focus(
+                4,
+                40 * MIN + MIN / 2,
+                "slack.exe",
+                "Unspecified",
+                Some(MIN / 2),
+            ),
+            focus(5, 41 * MIN, "chrome.exe", "Unspecified", Some(9 * MIN)),
+            focus(6, 50 * MIN, "slack.exe", "Unspecified", Some(2 * MIN)),
+        ];
+        let spans = active_spans(&events, 0, 60 * MIN, 60 * MIN);
+        let stats = app_session_stats(&spans, DEFAULT_SESSION_MERGE_GAP_MS);
+
+        let names: Vec<&str> = stats.iter().map(|s| s.app.as_str()).collect();
+        assert_eq!(names, ["Code.exe", "chrome.exe", "slack.exe"]);
+        assert_eq!(stats[0].sessions, 1);
+        assert_eq!(stats[0].avg_minutes, 40.0);
+
+        let slack = &stats[2];
+        assert_eq!(slack.sessions, 3);
+        assert_eq!(slack.total_minutes, 3.0);
+        assert_eq!(slack.avg_minutes, 1.0);
+        assert_eq!(slack.median_minutes, 0.5);
+    }
+
+    #[test]
+    fn test_app_session_stats_merges_crash_gap() {
+        // The editor crashed at 20 min and was back up three minutes later
+        let events = vec![
+            focus(1, 0, "Code.exe", "Coding", Some(20 * MIN)),
+            focus(2, 23 * MIN, "Code.exe", "Coding", Some(17 * MIN)),
+            // Coming back after lunch is still a new session
+            focus(3, 100 * MIN, "Code.exe", "Coding", Some(20 * MIN)),
+        ];
+        let spans = active_spans(&events, 0, 120 * MIN, 120 * MIN);
+
+        let split = app_session_stats(&spans, DEFAULT_SESSION_MERGE_GAP_MS);
+        assert_eq!(split[0].sessions, 3);
+
+        let merged = app_session_stats(&spans, 5 * MIN);
+        assert_eq!(merged[0].sessions, 2);
+        assert_eq!(merged[0].total_minutes, 57.0);
+        assert_eq!(merged[0].median_minutes, 28.5);
+    }
+
+    #[test]
+    fn test_app_contributions() {
+        let events = vec![
+            focus(1, 0, "Code.exe", "Coding", Some(40 * MIN)),
+            focus(2, 40 * MIN, "chrome.exe", "Coding", Some(10 * MIN)),
+            focus(3, 50 * MIN, "chrome.exe", "Unspecified", Some(30 * MIN)),
+            focus(4, 80 * MIN, "slack.exe", "Unspecified", Some(5 * MIN)),
+            focus(5, 85 * MIN, "WINWORD.EXE", "Writing", Some(10 * MIN)),
+        ];
+        let spans = active_spans(&events, 0, 95 * MIN, 95 * MIN);
+
+        let c = app_contributions(&spans, &FocusScoring::Modes, 2);
+        // chrome is used most overall but contributes little focused time
+        assert_eq!(c.productive.len(), 2);
+        assert_eq!(c.productive[0].app, "Code.exe");
+        assert_eq!(c.productive[0].focused_minutes, 40.0);
+        assert_eq!(c.productive[1].app, "WINWORD.EXE");
+        assert_eq!(c.time_sinks[0].app, "chrome.exe");
+        assert_eq!(c.time_sinks[0].focused_minutes, 10.0);
+        assert_eq!(c.time_sinks[0].unfocused_minutes, 30.0);
+        assert_eq!(c.time_sinks[1].app, "slack.exe");
+
+        let all = app_contributions(&spans, &FocusScoring::Modes, 10);
+        assert_eq!(all.productive.len(), 3);
+        assert_eq!(all.time_sinks.len(), 2);
+    }
+
+    #[test]
+    fn test_topic_focus() {
+        let span = |start: i64, end: i64, title: Option<&str>| Span {
+            start,
+            end,
+            app: "chrome.exe".to_string(),
+            title: title.map(str::to_string),
+            mode: "Unspecified".to_string(),
+        };
+        let spans = vec![
+            span(0, 40 * MIN, Some("Chemistry notes - Obsidian")),
+            span(40 * MIN, 60 * MIN, Some("YouTube")),
+            span(60 * MIN, 68 * MIN, Some("Organic CHEMISTRY flashcards")),
+            span(68 * MIN, 70 * MIN, None),
+        ];
+        let keywords = parse_keywords("chemistry, Notes,,notes");
+        assert_eq!(keywords, vec!["chemistry", "notes"]);
+
+        let t = topic_focus(&spans, &keywords);
+        assert_eq!(t.on_topic_minutes, 48.0);
+        assert_eq!(t.off_topic_minutes, 20.0);
+        assert_eq!(t.untitled_minutes, 2.0);
+        assert_eq!(t.on_topic_share, Some(70.6));
+        assert_eq!(t.top_titles[0].title, "Chemistry notes - Obsidian");
+        assert_eq!(t.top_titles.len(), 2);
+
+        // No keywords: everything titled is off-topic
+        assert_eq!(topic_focus(&spans, &[]).on_topic_share, Some(0.0));
+        assert_eq!(topic_focus(&[], &keywords).on_topic_share, None);
+    }
+
+    #[test]
+    fn test_apps_summary() {
+        let span = |start: i64, end: i64, app: &str, title: &str, mode: &str| Span {
+            start,
+            end,
+            app: app.to_string(),
+            title: Some(title.to_string()),
+            mode: mode.to_string(),
+        };
+        let spans = vec![
+            span(0, 30 * MIN, "Code.exe", "main.rs", "Coding"),
+            span(30 * MIN, 40 * MIN, "chrome.exe", "YouTube", "Unspecified"),
+            span(40 * MIN, 50 * MIN, "Terminal.exe", "cargo test", "Coding"),
+            span(50 * MIN, 80 * MIN, "Code.exe", "main.rs", "Coding"),
+        ];
+        let apps = parse_keywords("code, terminal");
+
+        let s = apps_summary(&spans, &apps, DEFAULT_SESSION_MERGE_GAP_MS);
+        assert_eq!(s.minutes, 70.0);
+        assert_eq!(s.focused_minutes, 70.0);
+        assert_eq!(s.share, Some(87.5));
+        // Chrome is ignored, so Code -> Terminal -> Code
+        assert_eq!(s.switches, 2);
+        assert_eq!(s.sessions[0].app, "Code.exe");
+        assert_eq!(s.sessions[0].sessions, 2);
+        assert_eq!(s.top_titles[0].title, "main.rs");
+        assert_eq!(s.top_titles[0].minutes, 60.0);
+
+        let slack = parse_keywords("slack");
+        let gap = DEFAULT_SESSION_MERGE_GAP_MS;
+        assert_eq!(apps_summary(&spans, &slack, gap).minutes, 0.0);
+        assert_eq!(apps_summary(&[], &apps, gap).share, None);
+    }
+
+    #[test]
+    fn test_productive_hours() {
+        let span = |start: i64, end: i64, mode: &str| Span {
+            start,
+            end,
+            app: "app.exe".to_string(),
+            title: None,
+            mode: mode.to_string(),
+        };
+        let day = 24 * HOUR_MS;
+        let mut spans = Vec::new();
+        for d in 0..3 {
+            // 09:00: fully focused
+            let nine = d * day + 9 * HOUR_MS;
+            spans.push(span(nine, nine + 40 * MIN, "Coding"));
+            // 14:00: a third focused
+            let two = d * day + 14 * HOUR_MS;
+            spans.push(span(two, two + 20 * MIN, "Coding"));
+            spans.push(span(two + 20 * MIN, two + HOUR_MS, "Unspecified"));
+        }
+        // 20:00 on one day only: not enough samples
+        spans.push(span(20 * HOUR_MS, 21 * HOUR_MS, "Coding"));
+        // 11:00 too short to count as a sample
+        spans.push(span(11 * HOUR_MS, 11 * HOUR_MS + 5 * MIN, "Coding"));
+
+        let hours = productive_hours(&spans, &chrono::Utc, &FocusScoring::Modes).unwrap();
+        assert_eq!(hours.len(), 2);
+        assert_eq!(hours[0].hour, 9);
+        assert_eq!(hours[0].avg_focus_score, 100.0);
+        assert_eq!(hours[0].samples, 3);
+        assert_eq!(hours[1].hour, 14);
+        assert_eq!(hours[1].avg_focus_score, 33.3);
+
+        // Half-hour offset: 09:00-09:40 UTC is 14:30-15:10 local
+        let ist = chrono::FixedOffset::east_opt(5 * 3600 + 30 * 60).unwrap();
+        let local = productive_hours(&spans, &ist, &FocusScoring::Modes).unwrap();
+        let best: Vec<u32> = local
+            .iter()
+            .filter(|h| h.avg_focus_score == 100.0)
+            .map(|h| h.hour)
+            .collect();
+        assert_eq!(best, vec![14, 15]);
+
+        assert_eq!(
+            productive_hours(&spans[..2], &chrono::Utc, &FocusScoring::Modes),
+            None
+        );
+        assert_eq!(
+            productive_hours(&[], &chrono::Utc, &FocusScoring::Modes),
+            None
+        );
+    }
+
+    #[test]
+    fn test_schedule_recommendations() {
+        let hour = |hour: u32, avg_focus_score: f64| ProductiveHour {
+            hour,
+            avg_focus_score,
+            samples: 5,
+        };
+        let hours = vec![
+            hour(9, 90.0),
+            hour(10, 80.0),
+            hour(11, 60.0),
+            hour(13, 50.0),
+            hour(14, 20.0),
+            hour(16, 30.0),
+        ];
+
+        let plan = schedule_recommendations(&hours);
+        assert_eq!(
+            plan.deep_work,
+            vec![ScheduleBlock {
+                start_hour: 9,
+                end_hour: 11,
+                avg_focus_score: 85.0
+            }]
+        );
+        let admin: Vec<(u32, u32)> = plan
+            .admin
+            .iter()
+            .map(|b| (b.start_hour, b.end_hour))
+            .collect();
+        assert_eq!(admin, vec![(14, 15), (16, 17)]);
+
+        // A single hour is all deep work
+        let plan = schedule_recommendations(&hours[..1]);
+        assert_eq!(plan.deep_work.len(), 1);
+        assert!(plan.admin.is_empty());
+    }
+
+    #[test]
+    fn test_today_vs_average() {
+        let row = |date: &str, active_ms: i64, focused_ms: i64| db::DailyStatsRow {
+            date: date.to_string(),
+            active_ms,
+            focused_ms,
+            focus_score: FocusTotals {
+                active_ms,
+                focused_ms,
+            }
+            .focus_score(),
+            updated_at: 0,
+        };
+        let history = vec![
+            row("2026-03-01", 120 * MIN, 60 * MIN),
+            row("2026-03-02", 0, 0),
+            row("2026-03-03", 240 * MIN, 180 * MIN),
+            // Today's own rollup is ignored in favour of the live totals
+            row("2026-03-04", 10 * MIN, 0),
+        ];
+        let today = NaiveDate::from_ymd_opt(2026, 3, 4).unwrap();
+        let totals = FocusTotals {
+            active_ms: 200 * MIN,
+            focused_ms: 160 * MIN,
+        };
+
+        let c = today_vs_average(today, totals, &history, None);
+        assert_eq!(c.sample_days, 2);
+        assert_eq!(c.today_focus_score, Some(80.0));
+        assert_eq!(c.avg_focus_score, Some(62.5));
+        assert_eq!(c.focus_score_delta, Some(17.5));
+        assert_eq!(c.avg_active_minutes, Some(180.0));
+        assert_eq!(c.active_minutes_delta, Some(20.0));
+
+        assert_eq!(c.note, None);
+
+        let noon = chrono::NaiveTime::from_hms_opt(12, 30, 0);
+        let c = today_vs_average(today, FocusTotals::default(), &[], noon);
+        assert_eq!(c.sample_days, 0);
+        assert_eq!(c.avg_focus_score, None);
+        assert_eq!(c.focus_score_delta, None);
+        assert_eq!(
+            c.note.as_deref(),
+            Some("partial day: today up to 12:30 against full-day averages")
+        );
+    }
+
+    #[test]
+    fn test_target_progress() {
+        let totals = |focused_ms: i64| FocusTotals {
+            active_ms: focused_ms * 2,
+            focused_ms,
+        };
+
+        let p = target_progress(totals(90 * MIN), 120);
+        assert_eq!(p.focused_minutes, 90.0);
+        assert_eq!(p.percent, 75.0);
+        assert!(!p.met);
+
+        let p = target_progress(totals(150 * MIN), 120);
+        assert_eq!(p.percent, 125.0);
+        assert!(p.met);
+
+        assert!(target_progress(totals(120 * MIN), 120).met);
+        assert_eq!(target_progress(FocusTotals::default(), 120).percent, 0.0);
+    }
+
+    #[test]
+    fn test_switching_cost() {
+        // Editor -> terminal (same mode) -> chat: 2 switches, 1 across modes
+        let events = vec![
+            focus(1, 0, "Code.exe", "Coding", Some(20 * MIN)),
+            focus(2, 20 * MIN, "WindowsTerminal.exe", "Coding", Some(20 * MIN)),
+            focus(3, 40 * MIN, "slack.exe", "Unspecified", Some(20 * MIN)),
+        ];
+        let spans = active_spans(&events, 0, 60 * MIN, 60 * MIN);
+
+        let cost = switching_cost(&spans, SwitchCounting::All, 30);
+        assert_eq!(cost.switches, 2);
+        assert_eq!(cost.minutes_lost, 1.0);
+        assert_eq!(cost.share_of_active, Some(1.7));
+
+        let cost = switching_cost(&spans, SwitchCounting::CrossMode, 30);
+        assert_eq!(cost.switches, 1);
+        assert_eq!(cost.minutes_lost, 0.5);
+
+        assert_eq!(
+            switching_cost(&[], SwitchCounting::All, 30).share_of_active,
+            None
+        );
+    }
+
+    #[test]
+    fn test_break_analysis() {
+        let events = vec![
+            focus(1, 0, "Code.exe", "Coding", Some(30 * MIN)),
+            // Away for 10 minutes (no activity), then 40 focused minutes
+            focus(2, 40 * MIN, "Code.exe", "Coding", Some(40 * MIN)),
+            // 15 minutes of YouTube, then 10 focused minutes
+            focus(3, 80 * MIN, "chrome.exe", "Unspecified", Some(15 * MIN)),
+            focus(4, 95 * MIN, "Code.exe", "Coding", Some(10 * MIN)),
+            // A 2-minute glance isn't a break
+            focus(5, 105 * MIN, "slack.exe", "Unspecified", Some(2 * MIN)),
+            focus(6, 107 * MIN, "Code.exe", "Coding", Some(13 * MIN)),
+        ];
+        let spans = active_spans(&events, 0, 120 * MIN, 120 * MIN);
+
+        let a = break_analysis(&spans, &FocusScoring::Modes);
+        assert_eq!(a.breaks.len(), 2);
+        assert_eq!(a.breaks[0].kind, BreakKind::Restful);
+        assert_eq!(a.breaks[0].minutes, 10.0);
+        assert_eq!(a.breaks[0].screen_minutes, 0.0);
+        assert_eq!(a.breaks[0].focus_after_minutes, 40.0);
+        assert_eq!(a.breaks[1].kind, BreakKind::Screen);
+        assert_eq!(a.breaks[1].focus_after_minutes, 10.0);
+
+        assert_eq!(a.restful.count, 1);
+        assert_eq!(a.restful.avg_focus_after_minutes, Some(40.0));
+        assert_eq!(a.screen.avg_minutes, Some(15.0));
+
+        let none = break_analysis(&[], &FocusScoring::Modes);
+        assert!(none.breaks.is_empty());
+        assert_eq!(none.restful.avg_minutes, None);
+    }
+
+    #[test]
+    fn test_ranked_distractions() {
+        let events = vec![
+            focus(1, 0, "Code.exe", "Coding", Some(30 * MIN)),
+            focus(2, 30 * MIN, "chrome.exe", "Unspecified", Some(10 * MIN)),
+            focus(3, 40 * MIN, "Code.exe", "Coding", Some(20 * MIN)),
+            // Another focused mode plus a quick chat: one stretch, half weight for Word
+            focus(4, 60 * MIN, "WINWORD.EXE", "Writing", Some(10 * MIN)),
+            focus(5, 70 * MIN, "slack.exe", "Unspecified", Some(2 * MIN)),
+            focus(6, 72 * MIN, "Code.exe", "Coding", Some(10 * MIN)),
+        ];
+        let spans = active_spans(&events, 0, 90 * MIN, 90 * MIN);
+
+        let ranked = ranked_distractions(&spans, DEFAULT_DISTRACTION_LIMIT);
+        assert_eq!(ranked.len(), 2);
+        assert_eq!(ranked[0].app, "chrome.exe");
+        assert_eq!(ranked[0].impact, 10.0);
+        assert_eq!(ranked[1].app, "WINWORD.EXE");
+        assert_eq!(ranked[1].minutes, 12.0);
+        assert_eq!(ranked[1].impact, 7.0);
+        assert_eq!((ranked[1].start, ranked[1].end), (60 * MIN, 72 * MIN));
+
+        assert_eq!(ranked_distractions(&spans, 1).len(), 1);
+        assert!(ranked_distractions(&[], 3).is_empty());
+    }
+
+    #[test]
+    fn test_current_focus_streak() {
+        let events = vec![
+            focus(1, 0, "chrome.exe", "Unspecified", Some(10 * MIN)),
+            focus(2, 10 * MIN, "Code.exe", "Coding", Some(20 * MIN)),
+            // A short look at chat doesn't break the streak
+            focus(3, 30 * MIN, "slack.exe", "Unspecified", Some(MIN)),
+            focus(4, 31 * MIN, "WINWORD.EXE", "Writing", Some(9 * MIN)),
+            focus(5, 40 * MIN, "Code.exe", "Coding", None),
+        ];
+        let now = 50 * MIN;
+        let spans = active_spans(&events, 0, now, now);
+
+        let streak = current_focus_streak(
+            &spans,
+            &FocusScoring::Modes,
+            DEFAULT_STREAK_MAX_BREAK_MS,
+            now,
+        )
+        .unwrap();
+        assert_eq!(streak.start, 10 * MIN);
+        assert_eq!(streak.minutes, 39.0);
+        assert_eq!(streak.app, "Code.exe");
+
+        // With no tolerance the chat break ends it
+        let strict = current_focus_streak(&spans, &FocusScoring::Modes, 0, now).unwrap();
+        assert_eq!(strict.start, 31 * MIN);
+
+        // A long distraction at the end means no current streak
+        let mut events = events;
+        events[4] = focus(5, 40 * MIN, "chrome.exe", "Unspecified", None);
+        let spans = active_spans(&events, 0, now, now);
+        assert!(
+            current_focus_streak(
+                &spans,
+                &FocusScoring::Modes,
+                DEFAULT_STREAK_MAX_BREAK_MS,
+                now
+            )
+            .is_none()
+        );
+    }
+
+    #[test]
+    fn test_live_focus_streak_idle_timeout() {
+        // Coding since minute 10, no window change since minute 20. Idle
+        // detection hasn't fired yet.
+        let events = vec![
+            focus(1, 10 * MIN, "Code.exe", "Coding", None),
+            focus(2, 20 * MIN, "Code.exe", "Coding", None),
+        ];
+        let now = 24 * MIN;
+        let max_break = DEFAULT_STREAK_MAX_BREAK_MS;
+        assert_eq!(last_activity(&events, now), Some(20 * MIN));
+
+        let streak = live_focus_streak(&events, &FocusScoring::Modes, max_break, 0, now).unwrap();
+        assert_eq!(streak.minutes, 14.0);
+        assert!(
+            live_focus_streak(&events, &FocusScoring::Modes, max_break, 5 * MIN, now).is_some()
+        );
+        assert!(
+            live_focus_streak(&events, &FocusScoring::Modes, max_break, 3 * MIN, now).is_none()
+        );
+
+        // A manual active declaration keeps it going
+        let mut reading = events;
+        reading.push(EventRow {
+            duration_ms: Some(10 * MIN),
+            ..marker(3, 20 * MIN, MANUAL_ACTIVE_KIND)
+        });
+        assert_eq!(last_activity(&reading, now), Some(now));
+        assert!(
+            live_focus_streak(&reading, &FocusScoring::Modes, max_break, 3 * MIN, now).is_some()
+        );
+
+        assert!(live_focus_streak(&[], &FocusScoring::Modes, max_break, 3 * MIN, now).is_none());
+    }
+
+    #[test]
+    fn test_mode_weekly_trend() {
+        // Last week: 2h coding. This week (Wednesday): 1h coding, 30m other.
+        let events = vec![
+            focus(
+                1,
+                utc_ms("2026-10-07", 9),
+                "Code.exe",
+                "Coding",
+                Some(120 * MIN),
+            ),
+            focus(
+                2,
+                utc_ms("2026-10-13", 9),
+                "Code.exe",
+                "Coding",
+                Some(60 * MIN),
+            ),
+            focus(
+                3,
+                utc_ms("2026-10-14", 9),
+                "chrome.exe",
+                "Unspecified",
+                Some(30 * MIN),
+            ),
+        ];
+        let now = utc_ms("2026-10-14", 12);
+        let today = NaiveDate::from_ymd_opt(2026, 10, 14).unwrap();
+        // History reaches back before the first week, which was just idle
+        let oldest = Some(utc_ms("2026-09-20", 9));
+        let trend = mode_weekly_trend(&events, oldest, today, 3, &Utc, now);
+
+        let weeks: Vec<String> = trend.weeks.iter().map(|d| d.to_string()).collect();
+        assert_eq!(weeks, ["2026-09-28", "2026-10-05", "2026-10-12"]);
+        assert_eq!(trend.last_week_days, 3);
+        assert_eq!(trend.modes.len(), 2);
+        assert_eq!(trend.modes[0].mode, "Coding");
+        assert_eq!(trend.modes[0].minutes, [Some(0.0), Some(120.0), Some(60.0)]);
+        assert_eq!(trend.modes[1].mode, "Unspecified");
+        assert_eq!(trend.modes[1].minutes, [Some(0.0), Some(0.0), Some(30.0)]);
+    }
+
+    #[test]
+    fn test_mode_weekly_trend_pruned_weeks_are_null() {
+        // Events pruned up to last Wednesday: that week and older are unknown
+        let events = vec![focus(
+            1,
+            utc_ms("2026-10-13", 9),
+            "Code.exe",
+            "Coding",
+            Some(60 * MIN),
+        )];
+        let now = utc_ms("2026-10-14", 12);
+        let today = NaiveDate::from_ymd_opt(2026, 10, 14).unwrap();
+        let oldest = Some(utc_ms("2026-10-07", 12));
+        let trend = mode_weekly_trend(&events, oldest, today, 3, &Utc, now);
+
+        assert_eq!(trend.modes.len(), 1);
+        assert_eq!(trend.modes[0].minutes, [None, None, Some(60.0)]);
+
+        // No events at all: nothing to report
+        let empty = mode_weekly_trend(&[], None, today, 3, &Utc, now);
+        assert_eq!(empty.weeks.len(), 3);
+        assert!(empty.modes.is_empty());
+    }
+
+    #[test]
+    fn test_focus_trend() {
+        let nine = utc_ms("2026-10-14", 9);
+        let events = vec![
+            focus(1, nine, "Code.exe", "Coding", Some(45 * MIN)),
+            focus(
+                2,
+                nine + 45 * MIN,
+                "chrome.exe",
+                "Unspecified",
+                Some(30 * MIN),
+            ),
+            // Idle time doesn't count against the ratio
+            marker(3, nine + 75 * MIN, "idle_start"),
+            marker(4, nine + 115 * MIN, "idle_end"),
+            focus(5, nine + 115 * MIN, "Code.exe", "Coding", Some(5 * MIN)),
+        ];
+        let now = nine + 150 * MIN;
+
+        let hourly = focus_trend(
+            &events,
+            nine + 10 * MIN,
+            TrendBucket::Hour,
+            &Utc,
+            &FocusScoring::Modes,
+            now,
+        );
+        assert_eq!(hourly.len(), 3);
+        assert_eq!(hourly[0].start, nine);
+        // 09:10-10:00 clipped: 35 min coding, 15 min browsing
+        assert_eq!(hourly[0].focus_ratio, Some(70.0));
+        // 10:00-11:00: 15 min browsing, 5 min coding, 40 min idle ignored
+        assert_eq!(hourly[1].active_minutes, 20.0);
+        assert_eq!(hourly[1].focus_ratio, Some(25.0));
+        assert_eq!(hourly[2].focus_ratio, None);
+
+        let daily = focus_trend(
+            &events,
+            utc_ms("2026-10-13", 0),
+            TrendBucket::Day,
+            &Utc,
+            &FocusScoring::Modes,
+            now,
+        );
+        assert_eq!(daily.len(), 2);
+        assert_eq!(daily[0].focus_ratio, None);
+        assert_eq!(daily[1].active_minutes, 80.0);
+
+        // Half-hour offset: buckets start on the local hour, not the UTC one
+        let ist = chrono::FixedOffset::east_opt(5 * 3600 + 30 * 60).unwrap();
+        let local = focus_trend(
+            &events,
+            nine,
+            TrendBucket::Hour,
+            &ist,
+            &FocusScoring::Modes,
+            now,
+        );
+        assert_eq!(local[0].start, nine - 30 * MIN);
+        assert_eq!(local[1].start, nine + 30 * MIN);
+        // Local 14:00-15:00 is 08:30-09:30 UTC: the first 30 min of coding
+        assert_eq!(local[0].focused_minutes, 30.0);
+
+        assert_eq!("Day".parse::<TrendBucket>(), Ok(TrendBucket::Day));
+        assert!("week".parse::<TrendBucket>().is_err());
+    }
+
+    #[test]
+    fn test_roll_up_day() {
+        let dir = tempfile::TempDir::new().unwrap();
+        db::init_databases(dir.path()).unwrap();
+        let conn = db::open_events_db(dir.path()).unwrap();
+        let day = NaiveDate::from_ymd_opt(2026, 10, 14).unwrap();
+        let nine = utc_ms("2026-10-14", 9);
+        db::insert_external_event(&conn, nine, "Code.exe", None, 30 * MIN, Some("Coding"), "t")
+            .unwrap();
+        db::insert_external_event(
+            &conn,
+            nine + 30 * MIN,
+            "chrome.exe",
+            None,
+            10 * MIN,
+            None,
+            "t",
+        )
+        .unwrap();
+
+        let now = utc_ms("2026-10-15", 1);
+        let row = roll_up_day(&conn, day, &Utc, &FocusScoring::Modes, now).unwrap();
+        assert_eq!(row.focus_score, Some(75.0));
+
+        // Re-running replaces rather than duplicates
+        roll_up_day(&conn, day, &Utc, &FocusScoring::Modes, now + 1).unwrap();
+        let rows = db::list_daily_stats(&conn, "2026-10-01", "2026-10-31").unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].date, "2026-10-14");
+        assert_eq!(rows[0].active_ms, 40 * MIN);
+        assert_eq!(rows[0].updated_at, now + 1);
+    }
+
+    #[test]
+    fn test_roll_up_stale_days() {
+        let dir = tempfile::TempDir::new().unwrap();
+        db::init_databases(dir.path()).unwrap();
+        let conn = db::open_events_db(dir.path()).unwrap();
+        for date in ["2026-10-11", "2026-10-12", "2026-10-13", "2026-10-14"] {
+            let nine = utc_ms(date, 9);
+            db::insert_external_event(&conn, nine, "Code.exe", None, 30 * MIN, Some("Coding"), "t")
+                .unwrap();
+        }
+        let stale = |date: &str, updated_at: i64| db::DailyStatsRow {
+            date: date.to_string(),
+            active_ms: MIN,
+            focused_ms: MIN,
+            focus_score: Some(100.0),
+            updated_at,
+        };
+        // The 11th was finalised after midnight; the 12th mid-day
+        db::upsert_daily_stats(&conn, &stale("2026-10-11", utc_ms("2026-10-12", 1))).unwrap();
+        db::upsert_daily_stats(&conn, &stale("2026-10-12", utc_ms("2026-10-12", 12))).unwrap();
+
+        let now = utc_ms("2026-10-15", 1);
+        let from = utc_ms("2026-10-10", 12);
+        let rolled = roll_up_stale_days(&conn, from, &Utc, &FocusScoring::Modes, now).unwrap();
+        // The 12th (stale), 13th and 14th (missing); the 10th and 15th are empty
+        assert_eq!(rolled, 3);
+
+        let rows = db::list_daily_stats(&conn, "2026-10-01", "2026-10-31").unwrap();
+        let active: Vec<(&str, i64)> = rows
+            .iter()
+            .map(|r| (r.date.as_str(), r.active_ms))
+            .collect();
+        assert_eq!(
+            active,
+            vec![
+                ("2026-10-11", MIN),
+                ("2026-10-12", 30 * MIN),
+                ("2026-10-13", 30 * MIN),
+                ("2026-10-14", 30 * MIN),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_recompute_daily_stats() {
+        let dir = tempfile::TempDir::new().unwrap();
+        db::init_databases(dir.path()).unwrap();
+        let conn = db::open_events_db(dir.path()).unwrap();
+        let day = NaiveDate::from_ymd_opt(2026, 10, 14).unwrap();
+        let nine = utc_ms("2026-10-14", 9);
+        db::insert_external_event(&conn, nine, "Code.exe", None, 30 * MIN, Some("Coding"), "t")
+            .unwrap();
+        db::insert_external_event(&conn, nine + 30 * MIN, "Figma.exe", None, 10 * MIN, None, "t")
+            .unwrap();
+
+        // An older day whose events were pruned up to its last few minutes
+        db::insert_external_event(
+            &conn,
+            utc_ms("2026-10-13", 23) + 50 * MIN,
+            "chrome.exe",
+            None,
+            5 * MIN,
+            None,
+            "t",
+        )
+        .unwrap();
+        let pruned = db::DailyStatsRow {
+            date: "2026-10-13".to_string(),
+            active_ms: 60 * MIN,
+            focused_ms: 30 * MIN,
+            focus_score: Some(50.0),
+            updated_at: 1,
+        };
+        db::upsert_daily_stats(&conn, &pruned).unwrap();
+
+        let now = utc_ms("2026-10-15", 1);
+        roll_up_day(&conn, day, &Utc, &FocusScoring::Modes, now).unwrap();
+        let whitelist = FocusScoring::Whitelist {
+            apps: parse_keywords("code,figma"),
+            work_hours: None,
+        };
+        let summary =
+            recompute_daily_stats(&conn, day.pred_opt().unwrap(), day, &Utc, &whitelist, now)
+                .unwrap();
+        assert_eq!(summary.days_updated, 1);
+        assert_eq!(summary.days_skipped, 1);
+
+        let rows = db::list_daily_stats(&conn, "2026-10-01", "2026-10-31").unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].focus_score, Some(50.0));
+        assert_eq!(rows[1].focus_score, Some(100.0));
+    }
+
+    #[test]
+    fn test_activity_calendar() {
+        let row = |date: &str, active_ms: i64, focus_score: Option<f64>| db::DailyStatsRow {
+            date: date.to_string(),
+            active_ms,
+            focused_ms: 0,
+            focus_score,
+            updated_at: 0,
+        };
+        let rows = vec![
+            row("2025-03-01", 90 * MIN, Some(60.0)),
+            row("2026-01-02", 30 * MIN, Some(80.0)),
+        ];
+        let today = NaiveDate::from_ymd_opt(2026, 1, 3).unwrap();
+
+        // Past year: every day, gaps filled with zero
+        let last_year = activity_calendar(2025, &rows, today);
+        assert_eq!(last_year.len(), 365);
+        let march = &last_year[59];
+        assert_eq!(march.date, NaiveDate::from_ymd_opt(2025, 3, 1).unwrap());
+        assert_eq!(march.active_minutes, 90.0);
+        assert_eq!(march.focus_score, Some(60.0));
+        assert_eq!(last_year[0].active_minutes, 0.0);
+        assert_eq!(last_year[0].focus_score, None);
+
+        // Current year stops at today
+        let this_year = activity_calendar(2026, &rows, today);
+        assert_eq!(this_year.len(), 3);
+        assert_eq!(this_year[1].focus_score, Some(80.0));
+
+        assert!(activity_calendar(2027, &rows, today).is_empty());
+    }
+}
@@ -1,636 +1,1857 @@
-use axum::{
-    Json, Router,
-    extract::{Path, Query, State},
-    http::StatusCode,
-    response::IntoResponse,
-    routing::{get, post},
-};
-use ccube_core::agents::{curator, reflector};
-use ccube_core::llm::LlmBackend;
-use ccube_core::{agents::detector, briefing, db, memory, paths::DataRoot};
-use serde::{Deserialize, Serialize};
-use std::sync::Arc;
-use tokio::sync::Notify;
-use tokio_util::sync::CancellationToken;
-
-/// Shared application state for all HTTP handlers.
-pub struct AppState {
-    pub data_root: DataRoot,
-    pub start_time: std::time::Instant,
-    pub shutdown_token: CancellationToken,
-    pub version: &'static str,
-    /// Frozen at startup — "memory never changes mid-session" (spec §15).
-    pub frozen_profile: String,
-    pub frozen_patterns: String,
-    pub frozen_patterns_hash: String,
-    /// LLM client for detector calls (10s timeout).
-    pub llm: Arc<dyn LlmBackend>,
-    /// LLM client for curator calls (120s timeout).
-    pub curator_llm: Arc<dyn LlmBackend>,
-    /// Signalled by the capture loop when an app-focus event arrives.
-    pub detector_trigger: Arc<Notify>,
-    /// Serializes curator runs (only one at a time).
-    pub curator_mutex: Arc<tokio::sync::Mutex<()>>,
-    /// Hour of day (0-23, local time) to run scheduled curator. Default 5 (5 AM).
-    pub curator_schedule_hour: u32,
-}
-
-/// Build the axum router with all endpoints.
-pub fn router(state: Arc<AppState>) -> Router {
-    Router::new()
-        .route("/health", get(health))
-        .route("/activity", get(activity))
-        .route("/briefing", get(get_briefing))
-        .route("/detect", post(detect))
-        .route("/memory/profile", get(memory_profile))
-        .route("/memory/patterns", get(memory_patterns))
-        .route("/memory/patterns/history", get(patterns_history))
-        .route("/shutdown", post(shutdown))
-        .route("/corrections", get(list_corrections_handler).post(create_correction))
-        .route("/corrections/{id}", get(get_correction_handler))
-        .route("/decisions", get(list_decisions_handler))
-        .route("/agents/curator/run", post(run_curator_handler))
-        .route("/agents/reflector/run", post(run_reflector_handler))
-        .route("/agents/reflector/pending", get(get_pending_handler))
-        .route("/agents/reflector/accept", post(accept_pending_handler))
-        .route("/agents/reflector/reject", post(reject_pending_handler))
-        .with_state(state)
-}
-
-// ---------- Response types ----------
-
-#[derive(Serialize)]
-struct HealthResponse {
-    status: &'static str,
-    uptime_s: u64,
-    daemon_version: &'static str,
-}
-
-#[derive(Deserialize)]
-struct ActivityQuery {
-    hours: Option<f64>,
-}
-
-#[derive(Deserialize)]
-struct DetectQuery {
-    dry_run: Option<bool>,
-}
-
-#[derive(Serialize)]
-struct ProfileResponse {
-    content: String,
-}
-
-#[derive(Serialize)]
-struct PatternsResponse {
-    content: String,
-    char_count: usize,
-    updated_at: Option<i64>,
-}
-
-#[derive(Serialize)]
-struct HistoryEntry {
-    timestamp: i64,
-    size_bytes: u64,
-}
-
-#[derive(Serialize)]
-struct ShutdownResponse {
-    status: &'static str,
-}
-
-// ---------- Error type ----------
-
-#[derive(Serialize)]
-struct ApiErrorBody {
-    code: String,
-    message: String,
-}
-
-#[derive(Serialize)]
-struct ApiErrorEnvelope {
-    error: ApiErrorBody,
-}
-
-struct ApiError {
-    status: StatusCode,
-    code: String,
-    message: String,
-}
-
-impl ApiError {
-    fn internal(msg: impl ToString) -> Self {
-        Self {
-            status: StatusCode::INTERNAL_SERVER_ERROR,
-            code: "INTERNAL_ERROR".to_string(),
-            message: msg.to_string(),
-        }
-    }
-
-    fn bad_request(msg: impl ToString) -> Self {
-        Self {
-            status: StatusCode::BAD_REQUEST,
-            code: "BAD_REQUEST".to_string(),
-            message: msg.to_string(),
-        }
-    }
-
-    fn not_found(msg: impl ToString) -> Self {
-        Self {
-            status: StatusCode::NOT_FOUND,
-            code: "NOT_FOUND".to_string(),
-            message: msg.to_string(),
-        }
-    }
-
-    fn conflict(msg: impl ToString) -> Self {
-        Self {
-            status: StatusCode::CONFLICT,
-            code: "CONFLICT".to_string(),
-            message: msg.to_string(),
-        }
-    }
-}
-
-impl IntoResponse for ApiError {
-    fn into_response(self) -> axum::response::Response {
-        let body = ApiErrorEnvelope {
-            error: ApiErrorBody {
-                code: self.code,
-                message: self.message,
-            },
-        };
-        (self.status, Json(body)).into_response()
-    }
-}
-
-// ---------- Handlers ----------
-
-async fn health(State(state): State<Arc<AppState>>) -> Json<HealthResponse> {
-    Json(HealthResponse {
-        status: "ok",
-        uptime_s: state.start_time.elapsed().as_secs(),
-        daemon_version: state.version,
-    })
-}
-
-async fn activity(
-    State(state): State<Arc<AppState>>,
-    Query(params): Query<ActivityQuery>,
-) -> Result<Json<Vec<db::EventRow>>, ApiError> {
-    let hours = params.hours.unwrap_or(1.0);
-    if hours <= 0.0 || !hours.is_finite() {
-        return Err(ApiError::bad_request(
-            "hours must be a positive finite number",
-        ));
-    }
-    // Cap at 14 days (the prune window) to avoid pointless full-table scans
-    let hours = hours.min(336.0);
-
-    let conn = db::open_events_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
-    let now = chrono::Utc::now().timestamp_millis();
-    let since_ts = now - (hours * 3_600_000.0) as i64;
-    let rows = db::query_recent_events(&conn, since_ts).map_err(ApiError::internal)?;
-
-    Ok(Json(rows))
-}
-
-async fn memory_profile(
-    State(state): State<Arc<AppState>>,
-) -> Result<Json<ProfileResponse>, ApiError> {
-    let content = memory::read_profile(&state.data_root.memory_dir).map_err(ApiError::internal)?;
-    Ok(Json(ProfileResponse { content }))
-}
-
-async fn memory_patterns(
-    State(state): State<Arc<AppState>>,
-) -> Result<Json<PatternsResponse>, ApiError> {
-    let content = memory::read_patterns(&state.data_root.memory_dir).map_err(ApiError::internal)?;
-    let char_count = content.len();
-
-    // Get file mtime for updated_at
-    let patterns_path = state.data_root.memory_dir.join("patterns.md");
-    let updated_at = std::fs::metadata(&patterns_path)
-        .ok()
-        .and_then(|m| m.modified().ok())
-        .and_then(|t| {
-            t.duration_since(std::time::UNIX_EPOCH)
-                .ok()
-                .map(|d| d.as_millis() as i64)
-        });
-
-    Ok(Json(PatternsResponse {
-        content,
-        char_count,
-        updated_at,
-    }))
-}
-
-async fn patterns_history(
-    State(state): State<Arc<AppState>>,
-) -> Result<Json<Vec<HistoryEntry>>, ApiError> {
-    let entries = memory::list_history(&state.data_root.memory_dir, "patterns.md")
-        .map_err(ApiError::internal)?;
-
-    let result: Vec<HistoryEntry> = entries
-        .into_iter()
-        .map(|(ts, path)| {
-            let size_bytes = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
-            HistoryEntry {
-                timestamp: ts,
-                size_bytes,
-            }
-        })
-        .collect();
-
-    Ok(Json(result))
-}
-
-async fn shutdown(State(state): State<Arc<AppState>>) -> Json<ShutdownResponse> {
-    tracing::info!("shutdown requested via HTTP");
-    state.shutdown_token.cancel();
-    Json(ShutdownResponse {
-        status: "shutting_down",
-    })
-}
-
-// ---------- Phase 4 handlers ----------
-
-/// GET /briefing — build and return the current briefing.
-async fn get_briefing(
-    State(state): State<Arc<AppState>>,
-) -> Result<Json<briefing::BriefingV2>, ApiError> {
-    let now_ms = chrono::Utc::now().timestamp_millis();
-    let since_ms = now_ms - 3_600_000;
-
-    let conn = db::open_events_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
-    let events = db::query_recent_events(&conn, since_ms).map_err(ApiError::internal)?;
-
-    let b = briefing::build_v2(
-        now_ms,
-        &events,
-        &state.frozen_profile,
-        &state.frozen_patterns,
-        &[],
-    );
-
-    Ok(Json(b))
-}
-
-/// POST /detect — run v2 two-step detector now, return DetectorV2Output with decision_id.
-/// Accepts optional `?dry_run=true` query param to suppress notifications.
-async fn detect(
-    State(state): State<Arc<AppState>>,
-    Query(params): Query<DetectQuery>,
-) -> Result<Json<DetectResponse>, ApiError> {
-    let start = std::time::Instant::now();
-    let now_ms = chrono::Utc::now().timestamp_millis();
-    let since_ms = now_ms - 3_600_000;
-
-    let conn = db::open_events_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
-    let events = db::query_recent_events(&conn, since_ms).map_err(ApiError::internal)?;
-
-    let briefing = briefing::build_v2(
-        now_ms,
-        &events,
-        &state.frozen_profile,
-        &state.frozen_patterns,
-        &[],
-    );
-
-    let mut output = detector::run_v2(&briefing, state.llm.as_ref()).await;
-    let duration_ms = start.elapsed().as_millis() as i64;
-
-    // In dry-run mode, strip the nudge_message so no notification fires
-    if params.dry_run.unwrap_or(false) {
-        output.nudge_message = None;
-    }
-
-    // Persist the decision
-    let decision_str = format!("{:?}", output.decision);
-    let nudge_style_str = output.nudge_style.as_ref().map(|s| format!("{:?}", s));
-    let briefing_json = serde_json::to_string(&briefing)
-        .map_err(|e| ApiError::internal(format!("failed to serialize briefing: {e}")))?;
-
-    let decision_id = db::insert_decision(
-        &conn,
-        now_ms,
-        "manual",
-        &decision_str,
-        &output.reasoning,
-        nudge_style_str.as_deref(),
-        output.nudge_message.as_deref(),
-        &briefing_json,
-        &state.frozen_patterns_hash,
-        detector::PROMPT_VERSION_V2,
-        duration_ms,
-    )
-    .map_err(ApiError::internal)?;
-
-    Ok(Json(DetectResponse {
-        decision_id,
-        output,
-    }))
-}
-
-// ---------- Phase 5 types ----------
-
-#[derive(Serialize, Deserialize)]
-pub struct DetectResponse {
-    pub decision_id: i64,
-    #[serde(flatten)]
-    pub output: briefing::DetectorV2Output,
-}
-
-#[derive(Deserialize)]
-struct CreateCorrectionRequest {
-    decision_id: i64,
-    verdict: String,
-}
-
-#[derive(Deserialize)]
-struct CorrectionsQuery {
-    status: Option<String>,
-    limit: Option<i64>,
-}
-
-#[derive(Deserialize)]
-struct DecisionsQuery {
-    since: Option<i64>,
-    limit: Option<i64>,
-}
-
-// ---------- Phase 5 handlers ----------
-
-/// POST /corrections — record a user correction for a detector decision.
-async fn create_correction(
-    State(state): State<Arc<AppState>>,
-    Json(body): Json<CreateCorrectionRequest>,
-) -> Result<(StatusCode, Json<db::CorrectionRow>), ApiError> {
-    // Look up the decision in events.sqlite
-    let events_conn =
-        db::open_events_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
-    let decision = db::get_decision(&events_conn, body.decision_id)
-        .map_err(ApiError::internal)?
-        .ok_or_else(|| {
-            ApiError::not_found(format!(
-                "decision #{} not found (may have been pruned)",
-                body.decision_id
-            ))
-        })?;
-
-    // Insert correction with the decision's full context
-    let corr_conn =
-        db::open_corrections_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
-    let corr_id = db::insert_correction(
-        &corr_conn,
-        decision.id,
-        &decision.decision,
-        &body.verdict,
-        &decision.briefing_json,
-        &decision.patterns_hash,
-    )
-    .map_err(ApiError::internal)?;
-
-    let row = db::get_correction(&corr_conn, corr_id)
-        .map_err(ApiError::internal)?
-        .ok_or_else(|| ApiError::internal("failed to read back correction"))?;
-
-    Ok((StatusCode::CREATED, Json(row)))
-}
-
-/// GET /corrections — list corrections, optionally filtered by status.
-async fn list_corrections_handler(
-    State(state): State<Arc<AppState>>,
-    Query(params): Query<CorrectionsQuery>,
-) -> Result<Json<Vec<db::CorrectionRow>>, ApiError> {
-    let limit = params.limit.unwrap_or(50).min(500);
-    let pending_only = params.status.as_deref() == Some("pending");
-
-    let conn =
-        db::open_corrections_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
-    let rows =
-        db::list_corrections(&conn, limit, pending_only).map_err(ApiError::internal)?;
-
-    Ok(Json(rows))
-}
-
-/// GET /corrections/:id — show a single correction with full context.
-async fn get_correction_handler(
-    State(state): State<Arc<AppState>>,
-    Path(id): Path<i64>,
-) -> Result<Json<db::CorrectionRow>, ApiError> {
-    let conn =
-        db::open_corrections_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
-    let row = db::get_correction(&conn, id)
-        .map_err(ApiError::internal)?
-        .ok_or_else(|| ApiError::not_found(format!("correction #{id} not found")))?;
-
-    Ok(Json(row))
-}
-
-/// GET /decisions — list recent detector decisions.
-async fn list_decisions_handler(
-    State(state): State<Arc<AppState>>,
-    Query(params): Query<DecisionsQuery>,
-) -> Result<Json<Vec<db::DecisionRow>>, ApiError> {
-    let since = params.since.unwrap_or(0);
-    let limit = params.limit.unwrap_or(50).min(500);
-
-    let conn = db::open_events_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
-    let rows = db::list_decisions(&conn, since, limit).map_err(ApiError::internal)?;
-
-    Ok(Json(rows))
-}
-
-// ---------- Phase 6: Curator endpoint ----------
-
-#[derive(Deserialize)]
-struct CuratorRunQuery {
-    dry_run: Option<bool>,
-}
-
-#[derive(Serialize)]
-pub struct CuratorRunResponse {
-    pub trigger: String,
-    pub corrections_processed: usize,
-    pub correction_verdicts: Vec<briefing::CorrectionVerdict>,
-    pub proposed_adds: Vec<briefing::PatternAdd>,
-    pub proposed_replaces: Vec<briefing::PatternReplace>,
-    pub candidate_patterns: String,
-    pub eval_passed: Option<bool>,
-    pub committed: bool,
-    pub dry_run: bool,
-    pub duration_ms: u64,
-}
-
-/// POST /agents/curator/run — trigger a curator run manually.
-/// Accepts optional `?dry_run=true` to skip eval + write.
-async fn run_curator_handler(
-    State(state): State<Arc<AppState>>,
-    Query(params): Query<CuratorRunQuery>,
-) -> Result<Json<CuratorRunResponse>, ApiError> {
-    let dry_run = params.dry_run.unwrap_or(false);
-
-    // Non-blocking try-acquire: if another curator run is in progress, reject.
-    let _guard = state
-        .curator_mutex
-        .try_lock()
-        .map_err(|_| ApiError::conflict("curator already running"))?;
-
-    let start = std::time::Instant::now();
-
-    let result = curator::run_curator(
-        &state.data_root.data_dir,
-        &state.data_root.memory_dir,
-        &state.frozen_profile,
-        &state.frozen_patterns,
-        state.curator_llm.as_ref(),
-        state.llm.as_ref(), // eval replay uses detector LLM (10s timeout)
-        dry_run,
-    )
-    .await
-    .map_err(ApiError::internal)?;
-
-    let duration_ms = start.elapsed().as_millis() as u64;
-
-    // Log to curator.ndjson
-    crate::scheduler::log_curator_run(&state.data_root.logs_dir, "manual", &result, duration_ms);
-
-    Ok(Json(CuratorRunResponse {
-        trigger: "manual".to_string(),
-        corrections_processed: result.corrections_processed,
-        correction_verdicts: result.output.correction_verdicts,
-        proposed_adds: result.output.proposed_adds,
-        proposed_replaces: result.output.proposed_replaces,
-        candidate_patterns: result.candidate_patterns,
-        eval_passed: result.eval_result.as_ref().map(|e| e.passed),
-        committed: result.committed,
-        dry_run: result.dry_run,
-        duration_ms,
-    }))
-}
-
-// ---------- Phase 7: Reflector endpoints ----------
-
-#[derive(Deserialize)]
-struct ReflectorRunQuery {
-    dry_run: Option<bool>,
-}
-
-#[derive(Serialize)]
-pub struct ReflectorRunResponse {
-    pub trigger: String,
-    pub patterns_after: String,
-    pub rationale: String,
-    pub eval_passed: Option<bool>,
-    pub eval_outcome: Option<String>,
-    pub committed: bool,
-    pub pending: bool,
-    pub dry_run: bool,
-    pub chars_before: usize,
-    pub chars_after: usize,
-    pub duration_ms: u64,
-}
-
-#[derive(Serialize)]
-struct PendingResponse {
-    exists: bool,
-    content: Option<String>,
-    chars: Option<usize>,
-}
-
-#[derive(Serialize)]
-struct PendingActionResponse {
-    status: &'static str,
-}
-
-/// POST /agents/reflector/run — trigger a reflector run manually.
-async fn run_reflector_handler(
-    State(state): State<Arc<AppState>>,
-    Query(params): Query<ReflectorRunQuery>,
-) -> Result<Json<ReflectorRunResponse>, ApiError> {
-    let dry_run = params.dry_run.unwrap_or(false);
-
-    let _guard = state
-        .curator_mutex
-        .try_lock()
-        .map_err(|_| ApiError::conflict("curator or reflector already running"))?;
-
-    let start = std::time::Instant::now();
-
-    // Read live patterns from disk (not frozen)
-    let live_patterns =
-        memory::read_patterns(&state.data_root.memory_dir).map_err(ApiError::internal)?;
-
-    let result = reflector::run_reflector(
-        &state.data_root.data_dir,
-        &state.data_root.memory_dir,
-        &state.frozen_profile,
-        &live_patterns,
-        state.curator_llm.as_ref(),
-        state.llm.as_ref(),
-        dry_run,
-    )
-    .await
-    .map_err(ApiError::internal)?;
-
-    let duration_ms = start.elapsed().as_millis() as u64;
-
-    crate::scheduler::log_reflector_run(
-        &state.data_root.logs_dir,
-        "manual",
-        &result,
-        duration_ms,
-    );
-
-    let eval_outcome = result.eval_outcome.map(|o| match o {
-        ccube_core::eval::ReflectorEvalOutcome::Pass => "pass".to_string(),
-        ccube_core::eval::ReflectorEvalOutcome::Borderline => "borderline".to_string(),
-        ccube_core::eval::ReflectorEvalOutcome::Fail => "fail".to_string(),
-    });
-
-    Ok(Json(ReflectorRunResponse {
-        trigger: "manual".to_string(),
-        patterns_after: result.patterns_after,
-        rationale: result.rationale,
-        eval_passed: result.eval_result.as_ref().map(|e| e.passed),
-        eval_outcome,
-        committed: result.committed,
-        pending: result.pending,
-        dry_run: result.dry_run,
-        chars_before: result.chars_before,
-        chars_after: result.chars_after,
-        duration_ms,
-    }))
-}
-
-/// GET /agents/reflector/pending — show pending proposal if any.
-async fn get_pending_handler(
-    State(state): State<Arc<AppState>>,
-) -> Result<Json<PendingResponse>, ApiError> {
-    let content =
-        reflector::read_pending(&state.data_root.memory_dir).map_err(ApiError::internal)?;
-
-    Ok(Json(PendingResponse {
-        exists: content.is_some(),
-        chars: content.as_ref().map(|c| c.len()),
-        content,
-    }))
-}
-
-/// POST /agents/reflector/accept — accept pending proposal.
-async fn accept_pending_handler(
-    State(state): State<Arc<AppState>>,
-) -> Result<Json<PendingActionResponse>, ApiError> {
-    reflector::accept_pending(&state.data_root.memory_dir).map_err(ApiError::internal)?;
-    Ok(Json(PendingActionResponse { status: "accepted" }))
-}
-
-/// POST /agents/reflector/reject — reject pending proposal.
-async fn reject_pending_handler(
-    State(state): State<Arc<AppState>>,
-) -> Result<Json<PendingActionResponse>, ApiError> {
-    reflector::reject_pending(&state.data_root.memory_dir).map_err(ApiError::internal)?;
-    Ok(Json(PendingActionResponse { status: "rejected" }))
-}
+use axum::{
+    Json, Router,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::{get, post},
+};
+use ccube_core::agents::{ask, curator, reflector};
+use chrono::Datelike;
+use ccube_core::llm::{self, LlmBackend};
+use ccube_core::{
+    agents::detector, backfill, briefing, db, ingest, memory, nudge_policy, paths::DataRoot,
+    report_card, stats,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicI64, Ordering};
+use tokio::sync::Notify;
+use tokio_util::sync::CancellationToken;
+
+/// Shared application state for all HTTP handlers.
+pub struct AppState {
+    pub data_root: DataRoot,
+    pub start_time: std::time::Instant,
+    pub shutdown_token: CancellationToken,
+    pub version: &'static str,
+    /// Frozen at startup — "memory never changes mid-session" (spec §15).
+    pub frozen_profile: String,
+    pub frozen_patterns: String,
+    pub frozen_patterns_hash: String,
+    /// LLM client for detector calls (10s timeout).
+    pub llm: Arc<dyn LlmBackend>,
+    /// LLM client for curator calls (120s timeout).
+    pub curator_llm: Arc<dyn LlmBackend>,
+    /// LLM client for /ask (120s timeout), recorded separately from the curator.
+    pub ask_llm: Arc<dyn LlmBackend>,
+    /// Signalled by the capture loop when an app-focus event arrives.
+    pub detector_trigger: Arc<Notify>,
+    /// Serializes detector runs so a manual /detect never races a scheduled
+    /// run (or another manual one) for the same window. Holds the latest run's
+    /// result, so a /detect that waited on a run can return it.
+    pub detector_mutex: Arc<tokio::sync::Mutex<Option<LastDetection>>>,
+    /// Serializes curator runs (only one at a time).
+    pub curator_mutex: Arc<tokio::sync::Mutex<()>>,
+    /// Hour of day (0-23, local time) to run scheduled curator. Default 5 (5 AM).
+    pub curator_schedule_hour: u32,
+    /// Weekend detector/notification behaviour.
+    pub weekend_policy: nudge_policy::WeekendPolicy,
+    /// Focus modes in which nudges aren't shown.
+    pub mode_nudge_policy: nudge_policy::ModeNudgePolicy,
+    /// Hold back non-urgent nudges during long focused runs.
+    pub flow_protection: nudge_policy::FlowProtection,
+    /// Notification titles per nudge style.
+    pub nudge_titles: nudge_policy::NudgeTitles,
+    /// No nudges shortly after startup or after returning from a long break.
+    pub startup_grace: nudge_policy::StartupGrace,
+    /// Call apps and how long one must be in front to count as a meeting.
+    pub meeting_detection: nudge_policy::MeetingDetection,
+    /// Cap on the detector's Step 1 prompt size (None = no cap).
+    pub max_prompt_chars: Option<usize>,
+    /// How far back the detector's briefing looks. Longer is steadier, shorter
+    /// reacts faster.
+    pub briefing_window_ms: i64,
+    /// Grading cutoffs for /stats/report-card.
+    pub report_thresholds: report_card::ReportCardThresholds,
+    /// Scheduled detector runs are skipped when the briefing window has less
+    /// active time than this. 0 disables the check.
+    pub detector_min_active_ms: i64,
+    /// Ingested spans shorter than this are dropped.
+    pub min_activity_ms: i64,
+    /// Default keywords for /stats/on-topic (lowercase).
+    pub study_keywords: Vec<String>,
+    /// Which app changes count toward switch-rate metrics.
+    pub switch_counting: stats::SwitchCounting,
+    /// The live focus streak ends after this long without activity (0 = only
+    /// when idle detection fires).
+    pub streak_idle_timeout_ms: i64,
+    /// Longest gap between uses of an app that still counts as one session.
+    pub session_merge_gap_ms: i64,
+    /// What counts as focused time in the focus score (rollups,
+    /// /stats/vs-average, /stats/target), focus blocks, streaks and flow
+    /// protection.
+    pub focus_scoring: stats::FocusScoring,
+    /// Daily focused-time target for /stats/target and the "target met"
+    /// notification.
+    pub focus_target_minutes: u32,
+    /// Day the "target met" notification was last shown, so it fires once a day.
+    pub target_met_notified: std::sync::Mutex<Option<chrono::NaiveDate>>,
+    /// Events and decisions older than this many days are pruned hourly.
+    pub retention_days: i64,
+    /// Refocusing time charged per switch by /stats/switching-cost.
+    pub switch_recovery_secs: u32,
+    /// Nudge notifications are held back until this time (ms; 0 = not snoozed).
+    /// Set from POST /nudges/snooze or a notification's snooze action.
+    pub nudges_snoozed_until: Arc<AtomicI64>,
+}
+
+/// Build the axum router with all endpoints.
+pub fn router(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/health", get(health))
+        .route("/activity", get(activity))
+        .route("/activity/ingest", post(ingest_activity))
+        .route("/activity/backfill-modes", post(backfill_modes))
+        .route("/activity/recompute-daily", post(recompute_daily))
+        .route("/activity/declare-active", post(declare_active))
+        .route("/briefing", get(get_briefing))
+        .route("/detect", post(detect))
+        .route("/memory/profile", get(memory_profile))
+        .route("/memory/patterns", get(memory_patterns))
+        .route("/memory/patterns/history", get(patterns_history))
+        .route("/shutdown", post(shutdown))
+        .route("/nudges/snooze", post(snooze_nudges))
+        .route("/nudges/accuracy", get(nudge_accuracy_handler))
+        .route("/nudges/{id}/feedback", post(nudge_feedback_handler))
+        .route("/corrections", get(list_corrections_handler).post(create_correction))
+        .route("/corrections/{id}", get(get_correction_handler))
+        .route("/decisions", get(list_decisions_handler))
+        .route("/decisions/{id}/events", get(decision_events_handler))
+        .route("/agents/curator/run", post(run_curator_handler))
+        .route("/agents/reflector/run", post(run_reflector_handler))
+        .route("/agents/reflector/pending", get(get_pending_handler))
+        .route("/agents/reflector/accept", post(accept_pending_handler))
+        .route("/agents/reflector/reject", post(reject_pending_handler))
+        .route("/stats/week", get(stats_week_handler))
+        .route("/stats/distraction", get(stats_distraction_handler))
+        .route("/stats/distractions", get(stats_distractions_handler))
+        .route("/stats/contribution", get(stats_contribution_handler))
+        .route("/stats/on-topic", get(stats_on_topic_handler))
+        .route("/stats/apps", get(stats_apps_handler))
+        .route("/stats/breaks", get(stats_breaks_handler))
+        .route("/stats/hourly-leaders", get(stats_hourly_leaders_handler))
+        .route("/stats/unclassified", get(stats_unclassified_handler))
+        .route("/stats/productive-hours", get(stats_productive_hours_handler))
+        .route("/stats/schedule", get(stats_schedule_handler))
+        .route("/stats/review", get(stats_review_handler))
+        .route("/stats/baseline-deviation", get(stats_baseline_deviation_handler))
+        .route("/stats/switch-pressure", get(stats_switch_pressure_handler))
+        .route("/stats/switching-cost", get(stats_switching_cost_handler))
+        .route("/stats/meeting", get(stats_meeting_handler))
+        .route("/stats/streak", get(stats_streak_handler))
+        .route("/stats/modes", get(stats_modes_handler))
+        .route("/stats/modes/{mode}", get(stats_mode_detail_handler))
+        .route("/stats/mode-trend", get(stats_mode_trend_handler))
+        .route("/stats/projection", get(stats_projection_handler))
+        .route("/stats/since-return", get(stats_since_return_handler))
+        .route("/stats/timeframes", get(stats_timeframes_handler))
+        .route("/stats/report-card", get(stats_report_card_handler))
+        .route("/stats/app-sessions", get(stats_app_sessions_handler))
+        .route("/stats/focus-trend", get(stats_focus_trend_handler))
+        .route("/stats/daily", get(stats_daily_handler))
+        .route("/stats/vs-average", get(stats_vs_average_handler))
+        .route("/stats/target", get(stats_target_handler))
+        .route("/stats/calendar", get(stats_calendar_handler))
+        .route("/llm/latency", get(llm_latency_handler))
+        .route("/llm/usage", get(llm_usage_handler))
+        .route("/llm/usage/reset", post(llm_usage_reset_handler))
+        .route("/ask", post(ask_handler))
+        .route("/ask/apps", post(ask_apps_handler))
+        .route("/llm/models/raw", get(llm_models_raw_handler))
+        .with_state(state)
+}
+
+// ---------- Response types ----------
+
+#[derive(Serialize)]
+struct HealthResponse {
+    status: &'static str,
+    uptime_s: u64,
+    daemon_version: &'static str,
+}
+
+#[derive(Deserialize)]
+struct ActivityQuery {
+    hours: Option<f64>,
+}
+
+#[derive(Deserialize)]
+struct IngestRequest {
+    source: Option<String>,
+    events: Vec<ingest::ExternalActivity>,
+}
+
+#[derive(Deserialize)]
+struct DetectQuery {
+    dry_run: Option<bool>,
+}
+
+#[derive(Serialize)]
+struct ProfileResponse {
+    content: String,
+}
+
+#[derive(Serialize)]
+struct PatternsResponse {
+    content: String,
+    char_count: usize,
+    updated_at: Option<i64>,
+}
+
+#[derive(Serialize)]
+struct HistoryEntry {
+    timestamp: i64,
+    size_bytes: u64,
+}
+
+#[derive(Serialize)]
+struct ShutdownResponse {
+    status: &'static str,
+}
+
+// ---------- Error type ----------
+
+#[derive(Serialize)]
+struct ApiErrorBody {
+    code: String,
+    message: String,
+}
+
+#[derive(Serialize)]
+struct ApiErrorEnvelope {
+    error: ApiErrorBody,
+}
+
+struct ApiError {
+    status: StatusCode,
+    code: String,
+    message: String,
+}
+
+impl ApiError {
+    fn internal(msg: impl ToString) -> Self {
+        Self {
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+            code: "INTERNAL_ERROR".to_string(),
+            message: msg.to_string(),
+        }
+    }
+
+    fn bad_request(msg: impl ToString) -> Self {
+        Self {
+            status: StatusCode::BAD_REQUEST,
+            code: "BAD_REQUEST".to_string(),
+            message: msg.to_string(),
+        }
+    }
+
+    fn not_found(msg: impl ToString) -> Self {
+        Self {
+            status: StatusCode::NOT_FOUND,
+            code: "NOT_FOUND".to_string(),
+            message: msg.to_string(),
+        }
+    }
+
+    fn conflict(msg: impl ToString) -> Self {
+        Self {
+            status: StatusCode::CONFLICT,
+            code: "CONFLICT".to_string(),
+            message: msg.to_string(),
+        }
+    }
+
+    fn llm_unavailable(msg: impl ToString) -> Self {
+        Self {
+            status: StatusCode::SERVICE_UNAVAILABLE,
+            code: "LLM_UNAVAILABLE".to_string(),
+            message: msg.to_string(),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> axum::response::Response {
+        let body = ApiErrorEnvelope {
+            error: ApiErrorBody {
+                code: self.code,
+                message: self.message,
+            },
+        };
+        (self.status, Json(body)).into_response()
+    }
+}
+
+// ---------- Handlers ----------
+
+async fn health(State(state): State<Arc<AppState>>) -> Json<HealthResponse> {
+    Json(HealthResponse {
+        status: "ok",
+        uptime_s: state.start_time.elapsed().as_secs(),
+        daemon_version: state.version,
+    })
+}
+
+async fn activity(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<ActivityQuery>,
+) -> Result<Json<Vec<db::EventRow>>, ApiError> {
+    let hours = params.hours.unwrap_or(1.0);
+    if hours <= 0.0 || !hours.is_finite() {
+        return Err(ApiError::bad_request(
+            "hours must be a positive finite number",
+        ));
+    }
+    // Cap at the prune window to avoid pointless full-table scans
+    let hours = hours.min((state.retention_days * 24) as f64);
+
+    let conn = db::open_events_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
+    let now = chrono::Utc::now().timestamp_millis();
+    let since_ts = now - (hours * 3_600_000.0) as i64;
+    let rows = db::query_recent_events(&conn, since_ts).map_err(ApiError::internal)?;
+
+    Ok(Json(rows))
+}
+
+#[derive(Deserialize)]
+struct DeclareActiveQuery {
+    minutes: Option<i64>,
+}
+
+#[derive(Serialize)]
+struct DeclareActiveResponse {
+    /// None when a declaration was ended.
+    active_until: Option<i64>,
+}
+
+/// POST /activity/declare-active — count the next `?minutes=N` (default 30,
+/// max 60) as active even if the user looks idle, e.g. while reading.
+/// `minutes=0` ends a running declaration early.
+async fn declare_active(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<DeclareActiveQuery>,
+) -> Result<Json<DeclareActiveResponse>, ApiError> {
+    let max_minutes = stats::MAX_MANUAL_ACTIVE_MS / 60_000;
+    let minutes = params.minutes.unwrap_or(30).clamp(0, max_minutes);
+    let now = chrono::Utc::now().timestamp_millis();
+
+    let conn = db::open_events_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
+    db::end_manual_active(&conn, now).map_err(ApiError::internal)?;
+    if minutes == 0 {
+        tracing::info!("manual active declaration ended");
+        return Ok(Json(DeclareActiveResponse { active_until: None }));
+    }
+    db::insert_manual_active(&conn, now, minutes * 60_000).map_err(ApiError::internal)?;
+    tracing::info!(minutes, "declared active");
+    Ok(Json(DeclareActiveResponse {
+        active_until: Some(now + minutes * 60_000),
+    }))
+}
+
+/// POST /activity/ingest — store activity spans reported by an external tracker.
+async fn ingest_activity(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<IngestRequest>,
+) -> Result<Json<ingest::IngestSummary>, ApiError> {
+    let source = body.source.as_deref().unwrap_or(ingest::DEFAULT_SOURCE);
+    ingest::validate_source(source).map_err(ApiError::bad_request)?;
+    if body.events.len() > 10_000 {
+        return Err(ApiError::bad_request(
+            "at most 10000 events per request",
+        ));
+    }
+
+    let conn = db::open_events_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
+    let now = chrono::Utc::now().timestamp_millis();
+    let summary = ingest::ingest(&conn, &body.events, source, state.min_activity_ms, now)
+        .map_err(ApiError::internal)?;
+
+    tracing::info!(
+        source,
+        inserted = summary.inserted,
+        duplicates = summary.duplicates,
+        too_short = summary.too_short,
+        rejected = summary.rejected.len(),
+        "external activity ingested"
+    );
+
+    Ok(Json(summary))
+}
+
+/// POST /activity/backfill-modes — re-infer the focus mode of every stored
+/// app_focus event with the current rules.
+async fn backfill_modes(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<backfill::BackfillSummary>, ApiError> {
+    let conn = db::open_events_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
+    let summary =
+        backfill::backfill_modes(&conn, backfill::BATCH_SIZE).map_err(ApiError::internal)?;
+
+    tracing::info!(
+        scanned = summary.scanned,
+        updated = summary.updated,
+        apps_updated = summary.apps_updated,
+        "focus modes backfilled"
+    );
+    for c in &summary.mode_changes {
+        tracing::info!(
+            app = %c.app,
+            from = c.from.as_deref().unwrap_or("none"),
+            to = %c.to,
+            events = c.events,
+            "app focus mode changed"
+        );
+    }
+
+    Ok(Json(summary))
+}
+
+#[derive(Deserialize)]
+struct RecomputeQuery {
+    from: Option<String>,
+    to: Option<String>,
+}
+
+/// POST /activity/recompute-daily — rewrite the stored daily focus rollups for
+/// `?from=&to=` (YYYY-MM-DD, default: the retention window up to today) from
+/// stored events. Days whose events were pruned, even in part, keep their
+/// rollup.
+async fn recompute_daily(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<RecomputeQuery>,
+) -> Result<Json<stats::RecomputeSummary>, ApiError> {
+    let to = parse_date_param(params.to.as_deref())?;
+    let from = match params.from.as_deref() {
+        Some(s) => parse_date_param(Some(s))?,
+        // The first day of the retention window; older days are partly pruned
+        None => to - chrono::Duration::days(state.retention_days - 1),
+    };
+    if from > to {
+        return Err(ApiError::bad_request("from must not be after to"));
+    }
+    if (to - from).num_days() >= stats::MAX_RECOMPUTE_DAYS {
+        return Err(ApiError::bad_request(format!(
+            "at most {} days per recompute",
+            stats::MAX_RECOMPUTE_DAYS
+        )));
+    }
+
+    let now = chrono::Utc::now().timestamp_millis();
+    let conn = db::open_events_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
+    let summary =
+        stats::recompute_daily_stats(&conn, from, to, &chrono::Local, &state.focus_scoring, now)
+            .map_err(ApiError::internal)?;
+
+    tracing::info!(
+        %from,
+        %to,
+        days_updated = summary.days_updated,
+        days_skipped = summary.days_skipped,
+        "daily stats recomputed"
+    );
+
+    Ok(Json(summary))
+}
+
+async fn memory_profile(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ProfileResponse>, ApiError> {
+    let content = memory::read_profile(&state.data_root.memory_dir).map_err(ApiError::internal)?;
+    Ok(Json(ProfileResponse { content }))
+}
+
+async fn memory_patterns(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<PatternsResponse>, ApiError> {
+    let content = memory::read_patterns(&state.data_root.memory_dir).map_err(ApiError::internal)?;
+    let char_count = content.len();
+
+    // Get file mtime for updated_at
+    let patterns_path = state.data_root.memory_dir.join("patterns.md");
+    let updated_at = std::fs::metadata(&patterns_path)
+        .ok()
+        .and_then(|m| m.modified().ok())
+        .and_then(|t| {
+            t.duration_since(std::time::UNIX_EPOCH)
+                .ok()
+                .map(|d| d.as_millis() as i64)
+        });
+
+    Ok(Json(PatternsResponse {
+        content,
+        char_count,
+        updated_at,
+    }))
+}
+
+async fn patterns_history(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<HistoryEntry>>, ApiError> {
+    let entries = memory::list_history(&state.data_root.memory_dir, "patterns.md")
+        .map_err(ApiError::internal)?;
+
+    let result: Vec<HistoryEntry> = entries
+        .into_iter()
+        .map(|(ts, path)| {
+            let size_bytes = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+            HistoryEntry {
+                timestamp: ts,
+                size_bytes,
+            }
+        })
+        .collect();
+
+    Ok(Json(result))
+}
+
+async fn shutdown(State(state): State<Arc<AppState>>) -> Json<ShutdownResponse> {
+    tracing::info!("shutdown requested via HTTP");
+    state.shutdown_token.cancel();
+    Json(ShutdownResponse {
+        status: "shutting_down",
+    })
+}
+
+#[derive(Deserialize)]
+struct SnoozeQuery {
+    minutes: Option<i64>,
+}
+
+#[derive(Serialize)]
+struct SnoozeResponse {
+    /// None when the snooze was cleared.
+    snoozed_until: Option<i64>,
+}
+
+/// POST /nudges/snooze — hold back nudge notifications for `?minutes=N`
+/// (default 15, max 240). `minutes=0` ends a snooze early. The detector keeps
+/// running; only the notifications are skipped.
+async fn snooze_nudges(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<SnoozeQuery>,
+) -> Json<SnoozeResponse> {
+    let minutes = params.minutes.unwrap_or(15).clamp(0, 240);
+    let until = if minutes == 0 {
+        0
+    } else {
+        chrono::Utc::now().timestamp_millis() + minutes * 60_000
+    };
+    state.nudges_snoozed_until.store(until, Ordering::Relaxed);
+    tracing::info!(minutes, "nudges snoozed");
+    Json(SnoozeResponse {
+        snoozed_until: (until > 0).then_some(until),
+    })
+}
+
+#[derive(Deserialize)]
+struct NudgeFeedbackRequest {
+    helpful: bool,
+}
+
+/// POST /nudges/{id}/feedback — rate a nudge as helpful or not. Rating the
+/// same nudge again replaces the earlier answer. 400 if the decision wasn't a
+/// nudge.
+async fn nudge_feedback_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    Json(body): Json<NudgeFeedbackRequest>,
+) -> Result<Json<db::NudgeFeedbackRow>, ApiError> {
+    let events_conn = db::open_events_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
+    let decision = db::get_decision(&events_conn, id)
+        .map_err(ApiError::internal)?
+        .ok_or_else(|| {
+            ApiError::not_found(format!("decision #{id} not found (may have been pruned)"))
+        })?;
+    if decision.decision != "Nudge" {
+        return Err(ApiError::bad_request(format!(
+            "decision #{id} was {}, not a nudge",
+            decision.decision
+        )));
+    }
+
+    let row = db::NudgeFeedbackRow {
+        decision_id: id,
+        ts: chrono::Utc::now().timestamp_millis(),
+        helpful: body.helpful,
+    };
+    let conn = db::open_corrections_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
+    db::upsert_nudge_feedback(&conn, row.decision_id, row.helpful, row.ts)
+        .map_err(ApiError::internal)?;
+    Ok(Json(row))
+}
+
+/// GET /nudges/accuracy — share of nudges rated helpful over the last two
+/// weeks, and whether that has raised the bar for showing nudges.
+async fn nudge_accuracy_handler(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<nudge_policy::NudgeAccuracy>, ApiError> {
+    let since =
+        chrono::Utc::now().timestamp_millis() - nudge_policy::FEEDBACK_WINDOW_DAYS * 86_400_000;
+    let conn = db::open_corrections_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
+    let rows = db::list_nudge_feedback(&conn, since).map_err(ApiError::internal)?;
+    Ok(Json(nudge_policy::NudgeAccuracy::from_feedback(&rows)))
+}
+
+// ---------- Phase 4 handlers ----------
+
+/// GET /briefing — build and return the current briefing.
+async fn get_briefing(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<briefing::BriefingV2>, ApiError> {
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    let since_ms = now_ms - 3_600_000;
+
+    let conn = db::open_events_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
+    let events = db::query_recent_events(&conn, since_ms).map_err(ApiError::internal)?;
+
+    let b = briefing::build_v2_with_window(
+        now_ms,
+        state.briefing_window_ms,
+        &events,
+        &state.frozen_profile,
+        &state.frozen_patterns,
+        &[],
+    );
+
+    Ok(Json(b))
+}
+
+/// POST /detect — run v2 two-step detector now, return DetectorV2Output with decision_id.
+/// Accepts optional `?dry_run=true` query param to suppress notifications.
+async fn detect(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<DetectQuery>,
+) -> Result<Json<DetectResponse>, ApiError> {
+    // Wait out a run already in flight: it covers the same window, so its
+    // result is returned rather than repeating the same LLM work.
+    let requested_ms = chrono::Utc::now().timestamp_millis();
+    let mut last = state.detector_mutex.lock().await;
+    if let Some(done) = last.as_ref().filter(|d| d.finished_ms >= requested_ms) {
+        let mut response = done.response.clone();
+        if params.dry_run.unwrap_or(false) {
+            response.output.nudge_message = None;
+        }
+        return Ok(Json(response));
+    }
+
+    let start = std::time::Instant::now();
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    let since_ms = now_ms - 3_600_000;
+
+    let conn = db::open_events_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
+    let events = db::query_recent_events(&conn, since_ms).map_err(ApiError::internal)?;
+
+    let mut briefing = briefing::build_v2_with_window(
+        now_ms,
+        state.briefing_window_ms,
+        &events,
+        &state.frozen_profile,
+        &state.frozen_patterns,
+        &[],
+    );
+    if let Some(max) = state.max_prompt_chars {
+        detector::trim_to_prompt_budget(&mut briefing, max);
+    }
+
+    let mut output = detector::run_v2(&briefing, state.llm.as_ref()).await;
+    let duration_ms = start.elapsed().as_millis() as i64;
+
+    // In dry-run mode, strip the nudge_message so no notification fires
+    if params.dry_run.unwrap_or(false) {
+        output.nudge_message = None;
+    }
+
+    // Persist the decision
+    let decision_str = format!("{:?}", output.decision);
+    let nudge_style_str = output.nudge_style.as_ref().map(|s| format!("{:?}", s));
+    let briefing_json = serde_json::to_string(&briefing)
+        .map_err(|e| ApiError::internal(format!("failed to serialize briefing: {e}")))?;
+
+    let decision_id = db::insert_decision(
+        &conn,
+        now_ms,
+        "manual",
+        &decision_str,
+        &output.reasoning,
+        nudge_style_str.as_deref(),
+        output.nudge_message.as_deref(),
+        &briefing_json,
+        &state.frozen_patterns_hash,
+        detector::PROMPT_VERSION_V2,
+        duration_ms,
+    )
+    .map_err(ApiError::internal)?;
+
+    let response = DetectResponse {
+        decision_id,
+        output,
+    };
+    *last = Some(LastDetection {
+        finished_ms: chrono::Utc::now().timestamp_millis(),
+        response: response.clone(),
+    });
+    Ok(Json(response))
+}
+
+// ---------- Phase 5 types ----------
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct DetectResponse {
+    pub decision_id: i64,
+    #[serde(flatten)]
+    pub output: briefing::DetectorV2Output,
+}
+
+/// A finished detector run (manual or scheduled), kept under
+/// `AppState::detector_mutex`.
+#[derive(Clone)]
+pub struct LastDetection {
+    pub finished_ms: i64,
+    pub response: DetectResponse,
+}
+
+#[derive(Deserialize)]
+struct CreateCorrectionRequest {
+    decision_id: i64,
+    verdict: String,
+}
+
+#[derive(Deserialize)]
+struct CorrectionsQuery {
+    status: Option<String>,
+    limit: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct DecisionsQuery {
+    since: Option<i64>,
+    limit: Option<i64>,
+}
+
+// ---------- Phase 5 handlers ----------
+
+/// POST /corrections — record a user correction for a detector decision.
+async fn create_correction(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<CreateCorrectionRequest>,
+) -> Result<(StatusCode, Json<db::CorrectionRow>), ApiError> {
+    // Look up the decision in events.sqlite
+    let events_conn =
+        db::open_events_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
+    let decision = db::get_decision(&events_conn, body.decision_id)
+        .map_err(ApiError::internal)?
+        .ok_or_else(|| {
+            ApiError::not_found(format!(
+                "decision #{} not found (may have been pruned)",
+                body.decision_id
+            ))
+        })?;
+
+    // Insert correction with the decision's full context
+    let corr_conn =
+        db::open_corrections_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
+    let corr_id = db::insert_correction(
+        &corr_conn,
+        decision.id,
+        &decision.decision,
+        &body.verdict,
+        &decision.briefing_json,
+        &decision.patterns_hash,
+    )
+    .map_err(ApiError::internal)?;
+
+    let row = db::get_correction(&corr_conn, corr_id)
+        .map_err(ApiError::internal)?
+        .ok_or_else(|| ApiError::internal("failed to read back correction"))?;
+
+    Ok((StatusCode::CREATED, Json(row)))
+}
+
+/// GET /corrections — list corrections, optionally filtered by status.
+async fn list_corrections_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<CorrectionsQuery>,
+) -> Result<Json<Vec<db::CorrectionRow>>, ApiError> {
+    let limit = params.limit.unwrap_or(50).min(500);
+    let pending_only = params.status.as_deref() == Some("pending");
+
+    let conn =
+        db::open_corrections_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
+    let rows =
+        db::list_corrections(&conn, limit, pending_only).map_err(ApiError::internal)?;
+
+    Ok(Json(rows))
+}
+
+/// GET /corrections/:id — show a single correction with full context.
+async fn get_correction_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Result<Json<db::CorrectionRow>, ApiError> {
+    let conn =
+        db::open_corrections_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
+    let row = db::get_correction(&conn, id)
+        .map_err(ApiError::internal)?
+        .ok_or_else(|| ApiError::not_found(format!("correction #{id} not found")))?;
+
+    Ok(Json(row))
+}
+
+/// GET /decisions — list recent detector decisions.
+async fn list_decisions_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<DecisionsQuery>,
+) -> Result<Json<Vec<db::DecisionRow>>, ApiError> {
+    let since = params.since.unwrap_or(0);
+    let limit = params.limit.unwrap_or(50).min(500);
+
+    let conn = db::open_events_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
+    let rows = db::list_decisions(&conn, since, limit).map_err(ApiError::internal)?;
+
+    Ok(Json(rows))
+}
+
+/// GET /decisions/{id}/events — the raw events from the window a decision was
+/// made on, for auditing it.
+async fn decision_events_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Result<Json<db::DecisionEvents>, ApiError> {
+    let conn = db::open_events_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
+    db::get_decision_events(&conn, id)
+        .map_err(ApiError::internal)?
+        .map(Json)
+        .ok_or_else(|| ApiError::not_found(format!("decision #{id} not found (may have been pruned)")))
+}
+
+// ---------- Phase 6: Curator endpoint ----------
+
+#[derive(Deserialize)]
+struct CuratorRunQuery {
+    dry_run: Option<bool>,
+}
+
+#[derive(Serialize)]
+pub struct CuratorRunResponse {
+    pub trigger: String,
+    pub corrections_processed: usize,
+    pub correction_verdicts: Vec<briefing::CorrectionVerdict>,
+    pub proposed_adds: Vec<briefing::PatternAdd>,
+    pub proposed_replaces: Vec<briefing::PatternReplace>,
+    pub candidate_patterns: String,
+    pub eval_passed: Option<bool>,
+    pub committed: bool,
+    pub dry_run: bool,
+    pub duration_ms: u64,
+}
+
+/// POST /agents/curator/run — trigger a curator run manually.
+/// Accepts optional `?dry_run=true` to skip eval + write.
+async fn run_curator_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<CuratorRunQuery>,
+) -> Result<Json<CuratorRunResponse>, ApiError> {
+    let dry_run = params.dry_run.unwrap_or(false);
+
+    // Non-blocking try-acquire: if another curator run is in progress, reject.
+    let _guard = state
+        .curator_mutex
+        .try_lock()
+        .map_err(|_| ApiError::conflict("curator already running"))?;
+
+    let start = std::time::Instant::now();
+
+    let result = curator::run_curator(
+        &state.data_root.data_dir,
+        &state.data_root.memory_dir,
+        &state.frozen_profile,
+        &state.frozen_patterns,
+        state.curator_llm.as_ref(),
+        state.llm.as_ref(), // eval replay uses detector LLM (10s timeout)
+        dry_run,
+    )
+    .await
+    .map_err(ApiError::internal)?;
+
+    let duration_ms = start.elapsed().as_millis() as u64;
+
+    // Log to curator.ndjson
+    crate::scheduler::log_curator_run(&state.data_root.logs_dir, "manual", &result, duration_ms);
+
+    Ok(Json(CuratorRunResponse {
+        trigger: "manual".to_string(),
+        corrections_processed: result.corrections_processed,
+        correction_verdicts: result.output.correction_verdicts,
+        proposed_adds: result.output.proposed_adds,
+        proposed_replaces: result.output.proposed_replaces,
+        candidate_patterns: result.candidate_patterns,
+        eval_passed: result.eval_result.as_ref().map(|e| e.passed),
+        committed: result.committed,
+        dry_run: result.dry_run,
+        duration_ms,
+    }))
+}
+
+// ---------- Phase 7: Reflector endpoints ----------
+
+#[derive(Deserialize)]
+struct ReflectorRunQuery {
+    dry_run: Option<bool>,
+}
+
+#[derive(Serialize)]
+pub struct ReflectorRunResponse {
+    pub trigger: String,
+    pub patterns_after: String,
+    pub rationale: String,
+    pub eval_passed: Option<bool>,
+    pub eval_outcome: Option<String>,
+    pub committed: bool,
+    pub pending: bool,
+    pub dry_run: bool,
+    pub chars_before: usize,
+    pub chars_after: usize,
+    pub duration_ms: u64,
+}
+
+#[derive(Serialize)]
+struct PendingResponse {
+    exists: bool,
+    content: Option<String>,
+    chars: Option<usize>,
+}
+
+#[derive(Serialize)]
+struct PendingActionResponse {
+    status: &'static str,
+}
+
+/// POST /agents/reflector/run — trigger a reflector run manually.
+async fn run_reflector_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<ReflectorRunQuery>,
+) -> Result<Json<ReflectorRunResponse>, ApiError> {
+    let dry_run = params.dry_run.unwrap_or(false);
+
+    let _guard = state
+        .curator_mutex
+        .try_lock()
+        .map_err(|_| ApiError::conflict("curator or reflector already running"))?;
+
+    let start = std::time::Instant::now();
+
+    // Read live patterns from disk (not frozen)
+    let live_patterns =
+        memory::read_patterns(&state.data_root.memory_dir).map_err(ApiError::internal)?;
+
+    let result = reflector::run_reflector(
+        &state.data_root.data_dir,
+        &state.data_root.memory_dir,
+        &state.frozen_profile,
+        &live_patterns,
+        state.curator_llm.as_ref(),
+        state.llm.as_ref(),
+        dry_run,
+    )
+    .await
+    .map_err(ApiError::internal)?;
+
+    let duration_ms = start.elapsed().as_millis() as u64;
+
+    crate::scheduler::log_reflector_run(
+        &state.data_root.logs_dir,
+        "manual",
+        &result,
+        duration_ms,
+    );
+
+    let eval_outcome = result.eval_outcome.map(|o| match o {
+        ccube_core::eval::ReflectorEvalOutcome::Pass => "pass".to_string(),
+        ccube_core::eval::ReflectorEvalOutcome::Borderline => "borderline".to_string(),
+        ccube_core::eval::ReflectorEvalOutcome::Fail => "fail".to_string(),
+    });
+
+    Ok(Json(ReflectorRunResponse {
+        trigger: "manual".to_string(),
+        patterns_after: result.patterns_after,
+        rationale: result.rationale,
+        eval_passed: result.eval_result.as_ref().map(|e| e.passed),
+        eval_outcome,
+        committed: result.committed,
+        pending: result.pending,
+        dry_run: result.dry_run,
+        chars_before: result.chars_before,
+        chars_after: result.chars_after,
+        duration_ms,
+    }))
+}
+
+/// GET /agents/reflector/pending — show pending proposal if any.
+async fn get_pending_handler(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<PendingResponse>, ApiError> {
+    let content =
+        reflector::read_pending(&state.data_root.memory_dir).map_err(ApiError::internal)?;
+
+    Ok(Json(PendingResponse {
+        exists: content.is_some(),
+        chars: content.as_ref().map(|c| c.len()),
+        content,
+    }))
+}
+
+/// POST /agents/reflector/accept — accept pending proposal.
+async fn accept_pending_handler(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<PendingActionResponse>, ApiError> {
+    reflector::accept_pending(&state.data_root.memory_dir).map_err(ApiError::internal)?;
+    Ok(Json(PendingActionResponse { status: "accepted" }))
+}
+
+/// POST /agents/reflector/reject — reject pending proposal.
+async fn reject_pending_handler(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<PendingActionResponse>, ApiError> {
+    reflector::reject_pending(&state.data_root.memory_dir).map_err(ApiError::internal)?;
+    Ok(Json(PendingActionResponse { status: "rejected" }))
+}
+
+// ---------- Stats handlers ----------
+
+#[derive(Deserialize)]
+struct WeekQuery {
+    start: Option<String>,
+}
+
+/// Parse a `YYYY-MM-DD` query value, defaulting to today (local time).
+fn parse_date_param(value: Option<&str>) -> Result<chrono::NaiveDate, ApiError> {
+    match value {
+        Some(s) => chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d")
+            .map_err(|_| ApiError::bad_request(format!("invalid date '{s}', expected YYYY-MM-DD"))),
+        None => Ok(chrono::Local::now().date_naive()),
+    }
+}
+
+/// GET /stats/week — per-day focus score and active minutes for the week
+/// (Mon-Sun) containing `?start=YYYY-MM-DD` (default: this week). Days whose
+/// events were pruned come from the stored daily rollups, or are null.
+async fn stats_week_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<WeekQuery>,
+) -> Result<Json<Vec<stats::DayFocus>>, ApiError> {
+    let date = parse_date_param(params.start.as_deref())?;
+    let monday = stats::week_start_monday(date);
+    let sunday = monday + chrono::Duration::days(6);
+    let (from, _) = stats::day_bounds(monday, &chrono::Local);
+    let (_, to) = stats::day_bounds(sunday, &chrono::Local);
+
+    let conn = db::open_events_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
+    let rollups = db::list_daily_stats(&conn, &monday.to_string(), &sunday.to_string())
+        .map_err(ApiError::internal)?;
+    let oldest = db::oldest_event_ts(&conn).map_err(ApiError::internal)?;
+    let events = stats::load_events(&conn, from, to).map_err(ApiError::internal)?;
+    let now = chrono::Utc::now().timestamp_millis();
+
+    Ok(Json(stats::weekly_focus_by_day(
+        &events,
+        &rollups,
+        oldest,
+        monday,
+        &chrono::Local,
+        &state.focus_scoring,
+        now,
+    )))
+}
+
+#[derive(Deserialize)]
+struct DateQuery {
+    date: Option<String>,
+}
+
+/// GET /stats/distraction — the app with the most unfocused time on
+/// `?date=YYYY-MM-DD` (default: today), or null if there was none.
+async fn stats_distraction_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<DateQuery>,
+) -> Result<Json<Option<stats::AppMinutes>>, ApiError> {
+    let date = parse_date_param(params.date.as_deref())?;
+    let conn = db::open_events_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
+    let now = chrono::Utc::now().timestamp_millis();
+    let spans =
+        stats::day_spans(&conn, date, &chrono::Local, now).map_err(ApiError::internal)?;
+
+    Ok(Json(stats::top_distraction(&spans, &state.focus_scoring)))
+}
+
+#[derive(Deserialize)]
+struct DistractionsQuery {
+    date: Option<String>,
+    limit: Option<usize>,
+}
+
+/// GET /stats/distractions — the costliest stretches away from the main task
+/// on `?date=YYYY-MM-DD` (default: today), ranked by impact. `?limit=`
+/// (default 3, max 20).
+async fn stats_distractions_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<DistractionsQuery>,
+) -> Result<Json<Vec<stats::RankedDistraction>>, ApiError> {
+    let date = parse_date_param(params.date.as_deref())?;
+    let limit = params
+        .limit
+        .unwrap_or(stats::DEFAULT_DISTRACTION_LIMIT)
+        .clamp(1, 20);
+    let conn = db::open_events_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
+    let now = chrono::Utc::now().timestamp_millis();
+    let spans =
+        stats::day_spans(&conn, date, &chrono::Local, now).map_err(ApiError::internal)?;
+
+    Ok(Json(stats::ranked_distractions(&spans, limit)))
+}
+
+#[derive(Deserialize)]
+struct ContributionQuery {
+    date: Option<String>,
+    limit: Option<usize>,
+}
+
+/// GET /stats/contribution — on `?date=YYYY-MM-DD` (default: today), the apps
+/// with the most focused time and the biggest time sinks. `?limit=` per list
+/// (default 5, max 20).
+async fn stats_contribution_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<ContributionQuery>,
+) -> Result<Json<stats::AppContributions>, ApiError> {
+    let date = parse_date_param(params.date.as_deref())?;
+    let limit = params.limit.unwrap_or(5).clamp(1, 20);
+    let conn = db::open_events_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
+    let now = chrono::Utc::now().timestamp_millis();
+    let spans =
+        stats::day_spans(&conn, date, &chrono::Local, now).map_err(ApiError::internal)?;
+
+    Ok(Json(stats::app_contributions(&spans, &state.focus_scoring, limit)))
+}
+
+#[derive(Deserialize)]
+struct OnTopicQuery {
+    date: Option<String>,
+    keywords: Option<String>,
+}
+
+/// GET /stats/on-topic — share of `?date=YYYY-MM-DD` (default: today) spent on
+/// windows whose title matches `?keywords=a,b` (default: CCUBE_STUDY_KEYWORDS).
+async fn stats_on_topic_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<OnTopicQuery>,
+) -> Result<Json<stats::TopicFocus>, ApiError> {
+    let date = parse_date_param(params.date.as_deref())?;
+    let keywords = match params.keywords.as_deref() {
+        Some(v) => stats::parse_keywords(v),
+        None => state.study_keywords.clone(),
+    };
+    if keywords.is_empty() {
+        return Err(ApiError::bad_request(
+            "no keywords given and CCUBE_STUDY_KEYWORDS is not set",
+        ));
+    }
+    let conn = db::open_events_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
+    let now = chrono::Utc::now().timestamp_millis();
+    let spans =
+        stats::day_spans(&conn, date, &chrono::Local, now).map_err(ApiError::internal)?;
+
+    Ok(Json(stats::topic_focus(&spans, &keywords)))
+}
+
+#[derive(Deserialize)]
+struct AppsQuery {
+    date: Option<String>,
+    apps: Option<String>,
+}
+
+/// GET /stats/apps — usage of only the apps named in `?apps=a,b` (matched
+/// case-insensitively by substring) on `?date=YYYY-MM-DD` (default: today).
+async fn stats_apps_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<AppsQuery>,
+) -> Result<Json<stats::AppsSummary>, ApiError> {
+    let date = parse_date_param(params.date.as_deref())?;
+    let apps = stats::parse_keywords(params.apps.as_deref().unwrap_or_default());
+    if apps.is_empty() {
+        return Err(ApiError::bad_request(
+            "no apps given, e.g. ?apps=code,slack",
+        ));
+    }
+    let conn = db::open_events_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
+    let now = chrono::Utc::now().timestamp_millis();
+    let spans =
+        stats::day_spans(&conn, date, &chrono::Local, now).map_err(ApiError::internal)?;
+
+    Ok(Json(stats::apps_summary(
+        &spans,
+        &apps,
+        state.session_merge_gap_ms,
+    )))
+}
+
+/// GET /stats/breaks — breaks between focus blocks on `?date=YYYY-MM-DD`
+/// (default: today), restful vs screen, with the focus that followed each.
+async fn stats_breaks_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<DateQuery>,
+) -> Result<Json<stats::BreakAnalysis>, ApiError> {
+    let date = parse_date_param(params.date.as_deref())?;
+    let conn = db::open_events_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
+    let now = chrono::Utc::now().timestamp_millis();
+    let spans =
+        stats::day_spans(&conn, date, &chrono::Local, now).map_err(ApiError::internal)?;
+
+    Ok(Json(stats::break_analysis(&spans, &state.focus_scoring)))
+}
+
+/// GET /stats/hourly-leaders — top app for each hour of `?date=YYYY-MM-DD`
+/// (default: today); hours without activity have a null leader.
+async fn stats_hourly_leaders_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<DateQuery>,
+) -> Result<Json<Vec<stats::HourLeader>>, ApiError> {
+    let date = parse_date_param(params.date.as_deref())?;
+    let conn = db::open_events_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
+    let now = chrono::Utc::now().timestamp_millis();
+    let spans =
+        stats::day_spans(&conn, date, &chrono::Local, now).map_err(ApiError::internal)?;
+
+    Ok(Json(stats::hourly_app_leaders(
+        &spans,
+        date,
+        &chrono::Local,
+    )))
+}
+
+#[derive(Deserialize)]
+struct DaysQuery {
+    days: Option<i64>,
+}
+
+/// GET /stats/unclassified — apps that never matched a focus mode over the
+/// last `?days=N` days (default 7, max 90), most used first.
+async fn stats_unclassified_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<DaysQuery>,
+) -> Result<Json<Vec<stats::AppMinutes>>, ApiError> {
+    let days = params.days.unwrap_or(7).clamp(1, 90);
+    let now = chrono::Utc::now().timestamp_millis();
+    let from = now - days * 86_400_000;
+
+    let conn = db::open_events_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
+    let events = stats::load_events(&conn, from, now).map_err(ApiError::internal)?;
+    let spans = stats::active_spans(&events, from, now, now);
+
+    Ok(Json(stats::unclassified_apps(&spans)))
+}
+
+/// GET /stats/productive-hours — hours of the day ranked by average focus
+/// score over the last `?days=N` days (default 30, max 90); null until some
+/// hour has enough samples.
+async fn stats_productive_hours_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<DaysQuery>,
+) -> Result<Json<Option<Vec<stats::ProductiveHour>>>, ApiError> {
+    let days = params.days.unwrap_or(30).clamp(1, 90);
+    let now = chrono::Utc::now().timestamp_millis();
+    let from = now - days * 86_400_000;
+
+    let conn = db::open_events_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
+    let events = stats::load_events(&conn, from, now).map_err(ApiError::internal)?;
+    let spans = stats::active_spans(&events, from, now, now);
+
+    Ok(Json(stats::productive_hours(
+        &spans,
+        &chrono::Local,
+        &state.focus_scoring,
+    )))
+}
+
+/// GET /stats/schedule — suggested deep-work and admin hours from the
+/// productive hours of the last `?days=N` days (default 30, max 90), drawn up
+/// by the LLM or, when it's unavailable, by the rule-based split; null until
+/// some hour has enough samples.
+async fn stats_schedule_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<DaysQuery>,
+) -> Result<Json<Option<stats::ScheduleRecommendations>>, ApiError> {
+    let Json(hours) = stats_productive_hours_handler(State(state.clone()), Query(params)).await?;
+    let Some(hours) = hours else {
+        return Ok(Json(None));
+    };
+    Ok(Json(Some(
+        ask::suggest_schedule(&hours, state.ask_llm.as_ref()).await,
+    )))
+}
+
+/// GET /stats/review — composite review of `?date=YYYY-MM-DD`
+/// (default: yesterday) for a morning "how did it go?" screen.
+async fn stats_review_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<DateQuery>,
+) -> Result<Json<stats::DailyReview>, ApiError> {
+    let date = match params.date.as_deref() {
+        Some(s) => parse_date_param(Some(s))?,
+        None => chrono::Local::now().date_naive() - chrono::Duration::days(1),
+    };
+    let conn = db::open_events_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
+    let now = chrono::Utc::now().timestamp_millis();
+    let spans =
+        stats::day_spans(&conn, date, &chrono::Local, now).map_err(ApiError::internal)?;
+
+    Ok(Json(stats::daily_review(
+        date,
+        &spans,
+        &state.focus_scoring,
+        Some(state.focus_target_minutes),
+    )))
+}
+
+/// GET /stats/baseline-deviation — last hour's switch rate and focus score
+/// against the past `?days=N` days (default 14, max 90) of active hours.
+async fn stats_baseline_deviation_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<DaysQuery>,
+) -> Result<Json<stats::BaselineDeviation>, ApiError> {
+    let days = params.days.unwrap_or(14).clamp(1, 90);
+    let now = chrono::Utc::now().timestamp_millis();
+    let from = now - days * 86_400_000 - 3_600_000;
+
+    let conn = db::open_events_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
+    let events = stats::load_events(&conn, from, now).map_err(ApiError::internal)?;
+    let spans = stats::active_spans(&events, from, now, now);
+
+    Ok(Json(stats::baseline_deviation(
+        &spans,
+        days,
+        state.switch_counting,
+        &state.focus_scoring,
+        now,
+    )))
+}
+
+#[derive(Deserialize)]
+struct PressureQuery {
+    sensitivity: Option<f64>,
+}
+
+/// GET /stats/switch-pressure — 0-100 meter of app switching in the last five
+/// minutes relative to the 14-day baseline. `?sensitivity=` (default 1.0,
+/// 0.1-5.0) scales how quickly it rises.
+async fn stats_switch_pressure_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<PressureQuery>,
+) -> Result<Json<stats::SwitchPressure>, ApiError> {
+    let sensitivity = params.sensitivity.unwrap_or(1.0);
+    if !(0.1..=5.0).contains(&sensitivity) {
+        return Err(ApiError::bad_request("sensitivity must be between 0.1 and 5.0"));
+    }
+    let now = chrono::Utc::now().timestamp_millis();
+    let from = now - 14 * 86_400_000 - 3_600_000;
+
+    let conn = db::open_events_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
+    let events = stats::load_events(&conn, from, now).map_err(ApiError::internal)?;
+    let spans = stats::active_spans(&events, from, now, now);
+    let baseline = stats::baseline_switch_rate(&spans, 14, state.switch_counting, now);
+
+    Ok(Json(stats::switch_pressure(
+        &spans,
+        baseline,
+        sensitivity,
+        state.switch_counting,
+        now,
+    )))
+}
+
+#[derive(Deserialize)]
+struct StreakQuery {
+    max_break_minutes: Option<i64>,
+}
+
+/// GET /stats/streak — the focus streak running right now, or null.
+/// `?max_break_minutes=` (default 2, max 30) is the longest unfocused stretch
+/// it survives. It also ends after CCUBE_STREAK_IDLE_TIMEOUT_SECS without
+/// activity, when set.
+async fn stats_streak_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<StreakQuery>,
+) -> Result<Json<Option<stats::FocusStreak>>, ApiError> {
+    let max_break_ms = params
+        .max_break_minutes
+        .map(|m| m.clamp(0, 30) * 60_000)
+        .unwrap_or(stats::DEFAULT_STREAK_MAX_BREAK_MS);
+    let now = chrono::Utc::now().timestamp_millis();
+    let from = now - stats::STREAK_LOOKBACK_MS;
+
+    let conn = db::open_events_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
+    let events = stats::load_events(&conn, from, now).map_err(ApiError::internal)?;
+
+    Ok(Json(stats::live_focus_streak(
+        &events,
+        &state.focus_scoring,
+        max_break_ms,
+        state.streak_idle_timeout_ms,
+        now,
+    )))
+}
+
+#[derive(Deserialize)]
+struct ModesQuery {
+    date: Option<String>,
+    include_idle: Option<bool>,
+}
+
+/// GET /stats/modes — minutes per focus mode on `?date=YYYY-MM-DD` (default:
+/// today). `?include_idle=true` adds idle time as an "Idle" entry.
+async fn stats_modes_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<ModesQuery>,
+) -> Result<Json<Vec<stats::ModeMinutes>>, ApiError> {
+    let date = parse_date_param(params.date.as_deref())?;
+    let (from, to) = stats::day_bounds(date, &chrono::Local);
+    let conn = db::open_events_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
+    let events = stats::load_events(&conn, from, to).map_err(ApiError::internal)?;
+    let now = chrono::Utc::now().timestamp_millis();
+
+    Ok(Json(stats::mode_breakdown(
+        &events,
+        from,
+        to,
+        now,
+        params.include_idle.unwrap_or(false),
+    )))
+}
+
+/// GET /stats/modes/{mode} — the apps within one focus mode on `?date=`
+/// (default: today). `uncategorized` is accepted for Unspecified.
+async fn stats_mode_detail_handler(
+    State(state): State<Arc<AppState>>,
+    Path(mode): Path<String>,
+    Query(params): Query<DateQuery>,
+) -> Result<Json<stats::ModeDetail>, ApiError> {
+    let date = parse_date_param(params.date.as_deref())?;
+    let now = chrono::Utc::now().timestamp_millis();
+    let conn = db::open_events_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
+    let spans = stats::day_spans(&conn, date, &chrono::Local, now).map_err(ApiError::internal)?;
+
+    Ok(Json(stats::mode_detail(&spans, &mode)))
+}
+
+#[derive(Deserialize)]
+struct WeeksQuery {
+    weeks: Option<usize>,
+}
+
+/// GET /stats/mode-trend — minutes per focus mode for each of the last
+/// `?weeks=N` Monday-Sunday weeks including this one (default 8, max 52).
+/// Weeks that began before the oldest stored event are null.
+async fn stats_mode_trend_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<WeeksQuery>,
+) -> Result<Json<stats::ModeWeeklyTrend>, ApiError> {
+    let weeks = params.weeks.unwrap_or(8).clamp(1, 52);
+    let today = chrono::Local::now().date_naive();
+    let first_monday = stats::week_start_monday(today) - chrono::Duration::weeks(weeks as i64 - 1);
+    let (from, _) = stats::day_bounds(first_monday, &chrono::Local);
+    let now = chrono::Utc::now().timestamp_millis();
+
+    let conn = db::open_events_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
+    let oldest = db::oldest_event_ts(&conn).map_err(ApiError::internal)?;
+    let events = stats::load_events(&conn, from, now).map_err(ApiError::internal)?;
+
+    Ok(Json(stats::mode_weekly_trend(
+        &events,
+        oldest,
+        today,
+        weeks,
+        &chrono::Local,
+        now,
+    )))
+}
+
+/// GET /stats/projection — projected end-of-day focus score for today, or
+/// null until there's enough activity.
+async fn stats_projection_handler(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Option<stats::FocusProjection>>, ApiError> {
+    let today = chrono::Local::now().date_naive();
+    let conn = db::open_events_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
+    let now = chrono::Utc::now().timestamp_millis();
+    let spans =
+        stats::day_spans(&conn, today, &chrono::Local, now).map_err(ApiError::internal)?;
+
+    Ok(Json(stats::project_focus(&spans, &state.focus_scoring, now)))
+}
+
+#[derive(Deserialize)]
+struct SinceReturnQuery {
+    min_idle_minutes: Option<i64>,
+}
+
+/// GET /stats/since-return — activity since the end of the last idle period of
+/// at least `min_idle_minutes` (default 30) today, or since midnight if there
+/// wasn't one.
+async fn stats_since_return_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<SinceReturnQuery>,
+) -> Result<Json<stats::WindowSummary>, ApiError> {
+    let min_idle_ms = match params.min_idle_minutes {
+        Some(m) if m < 1 => return Err(ApiError::bad_request("min_idle_minutes must be >= 1")),
+        Some(m) => m * 60_000,
+        None => stats::DEFAULT_RETURN_MIN_IDLE_MS,
+    };
+    let today = chrono::Local::now().date_naive();
+    let (midnight, _) = stats::day_bounds(today, &chrono::Local);
+    let conn = db::open_events_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
+    let now = chrono::Utc::now().timestamp_millis();
+    let events = stats::load_events(&conn, midnight, now).map_err(ApiError::internal)?;
+
+    Ok(Json(stats::since_return_summary(
+        &events,
+        min_idle_ms,
+        midnight,
+        &state.focus_scoring,
+        now,
+        &chrono::Local,
+    )))
+}
+
+/// GET /stats/timeframes — focus score over each trailing timeframe (5m, 30m,
+/// 1h), keyed by label; null where the timeframe had no activity.
+async fn stats_timeframes_handler(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<BTreeMap<String, Option<f64>>>, ApiError> {
+    let now = chrono::Utc::now().timestamp_millis();
+    let from = now - stats::FOCUS_TIMEFRAMES_LOOKBACK_MS;
+    let conn = db::open_events_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
+    let events = stats::load_events(&conn, from, now).map_err(ApiError::internal)?;
+    let spans = stats::active_spans(&events, from, now, now);
+
+    Ok(Json(stats::focus_by_timeframe(
+        &spans,
+        &state.focus_scoring,
+        now,
+    )))
+}
+
+/// GET /stats/report-card — letter grade for `?date=YYYY-MM-DD` (default:
+/// today), with a grade and justification per factor.
+async fn stats_report_card_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<DateQuery>,
+) -> Result<Json<report_card::ReportCard>, ApiError> {
+    let date = parse_date_param(params.date.as_deref())?;
+    let now = chrono::Utc::now().timestamp_millis();
+    let conn = db::open_events_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
+    let spans = stats::day_spans(&conn, date, &chrono::Local, now).map_err(ApiError::internal)?;
+
+    Ok(Json(report_card::report_card(
+        date,
+        &spans,
+        &state.focus_scoring,
+        &state.report_thresholds,
+    )))
+}
+
+/// GET /stats/app-sessions — per-app session count and average/median length
+/// on `?date=YYYY-MM-DD` (default: today).
+async fn stats_app_sessions_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<DateQuery>,
+) -> Result<Json<Vec<stats::AppSessionStats>>, ApiError> {
+    let date = parse_date_param(params.date.as_deref())?;
+    let now = chrono::Utc::now().timestamp_millis();
+    let conn = db::open_events_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
+    let spans = stats::day_spans(&conn, date, &chrono::Local, now).map_err(ApiError::internal)?;
+
+    Ok(Json(stats::app_session_stats(
+        &spans,
+        state.session_merge_gap_ms,
+    )))
+}
+
+#[derive(Deserialize)]
+struct TrendQuery {
+    days: Option<i64>,
+    bucket: Option<String>,
+}
+
+/// GET /stats/focus-trend — focused share of active time per `?bucket=day`
+/// (default) or `hour`, over the last `?days=N` days including today
+/// (default 7, max 90).
+async fn stats_focus_trend_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<TrendQuery>,
+) -> Result<Json<Vec<stats::TrendPoint>>, ApiError> {
+    let bucket: stats::TrendBucket = params
+        .bucket
+        .as_deref()
+        .unwrap_or("day")
+        .parse()
+        .map_err(ApiError::bad_request)?;
+    let days = params.days.unwrap_or(7).clamp(1, 90);
+    let first_day = chrono::Local::now().date_naive() - chrono::Duration::days(days - 1);
+    let (from, _) = stats::day_bounds(first_day, &chrono::Local);
+    let now = chrono::Utc::now().timestamp_millis();
+
+    let conn = db::open_events_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
+    let events = stats::load_events(&conn, from, now).map_err(ApiError::internal)?;
+
+    Ok(Json(stats::focus_trend(
+        &events,
+        from,
+        bucket,
+        &chrono::Local,
+        &state.focus_scoring,
+        now,
+    )))
+}
+
+/// GET /stats/daily — stored daily focus rollups for the last `?days=N` days
+/// including today (default 30, max 365). Kept after the raw events are pruned.
+async fn stats_daily_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<DaysQuery>,
+) -> Result<Json<Vec<db::DailyStatsRow>>, ApiError> {
+    let days = params.days.unwrap_or(30).clamp(1, 365);
+    let today = chrono::Local::now().date_naive();
+    let from = today - chrono::Duration::days(days - 1);
+
+    let conn = db::open_events_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
+    let rows = db::list_daily_stats(&conn, &from.to_string(), &today.to_string())
+        .map_err(ApiError::internal)?;
+    Ok(Json(rows))
+}
+
+/// GET /stats/vs-average — today's focus score and active time so far against
+/// the average of the previous `?days=N` days' rollups (default 30, max 365).
+/// `note` flags that today is a partial day.
+async fn stats_vs_average_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<DaysQuery>,
+) -> Result<Json<stats::TodayVsAverage>, ApiError> {
+    let days = params.days.unwrap_or(30).clamp(1, 365);
+    let today = chrono::Local::now().date_naive();
+    let from = today - chrono::Duration::days(days);
+    let yesterday = today - chrono::Duration::days(1);
+    let now = chrono::Utc::now().timestamp_millis();
+
+    let conn = db::open_events_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
+    let rows = db::list_daily_stats(&conn, &from.to_string(), &yesterday.to_string())
+        .map_err(ApiError::internal)?;
+    let spans =
+        stats::day_spans(&conn, today, &chrono::Local, now).map_err(ApiError::internal)?;
+
+    Ok(Json(stats::today_vs_average(
+        today,
+        state.focus_scoring.totals(&spans),
+        &rows,
+        Some(chrono::Local::now().time()),
+    )))
+}
+
+/// GET /stats/target — today's focused minutes against the daily target
+/// (CCUBE_DAILY_FOCUS_TARGET_MINUTES), for a progress ring.
+async fn stats_target_handler(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<stats::TargetProgress>, ApiError> {
+    let today = chrono::Local::now().date_naive();
+    let now = chrono::Utc::now().timestamp_millis();
+    let conn = db::open_events_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
+    let spans =
+        stats::day_spans(&conn, today, &chrono::Local, now).map_err(ApiError::internal)?;
+
+    Ok(Json(stats::target_progress(
+        state.focus_scoring.totals(&spans),
+        state.focus_target_minutes,
+    )))
+}
+
+/// GET /stats/switching-cost — estimated minutes lost to refocusing after app
+/// switches on `?date=YYYY-MM-DD` (default: today), at CCUBE_SWITCH_RECOVERY_SECS
+/// per switch.
+async fn stats_switching_cost_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<DateQuery>,
+) -> Result<Json<stats::SwitchingCost>, ApiError> {
+    let date = parse_date_param(params.date.as_deref())?;
+    let now = chrono::Utc::now().timestamp_millis();
+    let conn = db::open_events_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
+    let spans =
+        stats::day_spans(&conn, date, &chrono::Local, now).map_err(ApiError::internal)?;
+
+    Ok(Json(stats::switching_cost(
+        &spans,
+        state.switch_counting,
+        state.switch_recovery_secs,
+    )))
+}
+
+/// GET /stats/meeting — whether a video-call app (CCUBE_CALL_APPS) has been in
+/// front for at least CCUBE_MEETING_MIN_MINUTES.
+async fn stats_meeting_handler(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<nudge_policy::MeetingStatus>, ApiError> {
+    let now = chrono::Utc::now().timestamp_millis();
+    let conn = db::open_events_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
+    let status = state
+        .meeting_detection
+        .current_status(&conn, now)
+        .map_err(ApiError::internal)?;
+
+    Ok(Json(status))
+}
+
+#[derive(Deserialize)]
+struct YearQuery {
+    year: Option<i32>,
+}
+
+/// GET /stats/calendar — one entry per day of `?year=YYYY` (default this
+/// year, up to today) from the stored daily rollups, for a heatmap.
+async fn stats_calendar_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<YearQuery>,
+) -> Result<Json<Vec<stats::CalendarDay>>, ApiError> {
+    let today = chrono::Local::now().date_naive();
+    let year = params.year.unwrap_or(today.year());
+    if !(1970..=9999).contains(&year) {
+        return Err(ApiError::bad_request("year must be between 1970 and 9999"));
+    }
+
+    let conn = db::open_events_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
+    let rows = db::list_daily_stats(&conn, &format!("{year}-01-01"), &format!("{year}-12-31"))
+        .map_err(ApiError::internal)?;
+    Ok(Json(stats::activity_calendar(year, &rows, today)))
+}
+
+// ---------- Ask handler ----------
+
+#[derive(Deserialize)]
+struct AskRequest {
+    question: String,
+    date: Option<String>,
+}
+
+/// POST /ask — answer a free-form question about `date`'s activity (default:
+/// today) with the LLM. 503 when the LLM can't be reached.
+async fn ask_handler(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<AskRequest>,
+) -> Result<Json<ask::AskOutput>, ApiError> {
+    let question = ask::validate_question(&body.question).map_err(ApiError::bad_request)?;
+    let date = parse_date_param(body.date.as_deref())?;
+    let now = chrono::Utc::now().timestamp_millis();
+    let context = {
+        let conn = db::open_events_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
+        let spans =
+            stats::day_spans(&conn, date, &chrono::Local, now).map_err(ApiError::internal)?;
+        ask::build_context(date, &spans, &state.focus_scoring)
+    };
+
+    // Answers can run to a few hundred tokens, so use the long-timeout client
+    match ask::run(question, &context, state.ask_llm.as_ref()).await {
+        Ok(out) => Ok(Json(out)),
+        Err(ask::AskError::LlmUnavailable(msg)) => Err(ApiError::llm_unavailable(format!(
+            "LLM unavailable, is the server running? ({msg})"
+        ))),
+        Err(e) => Err(ApiError::internal(e)),
+    }
+}
+
+#[derive(Deserialize)]
+struct AskAppsRequest {
+    apps: String,
+    date: Option<String>,
+}
+
+/// POST /ask/apps — the LLM's analysis of the time spent in `apps` (comma-
+/// separated, matched as in /stats/apps) on `date` (default: today), with the
+/// usage numbers it was given. `analysis` is null when the LLM can't be
+/// reached.
+async fn ask_apps_handler(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<AskAppsRequest>,
+) -> Result<Json<ask::AppsAnalysis>, ApiError> {
+    let date = parse_date_param(body.date.as_deref())?;
+    let apps = stats::parse_keywords(&body.apps);
+    if apps.is_empty() {
+        return Err(ApiError::bad_request("no apps given, e.g. \"code,slack\""));
+    }
+    let now = chrono::Utc::now().timestamp_millis();
+    let summary = {
+        let conn = db::open_events_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
+        let spans =
+            stats::day_spans(&conn, date, &chrono::Local, now).map_err(ApiError::internal)?;
+        stats::apps_summary(&spans, &apps, state.session_merge_gap_ms)
+    };
+
+    ask::analyze_apps(date, summary, state.ask_llm.as_ref())
+        .await
+        .map(Json)
+        .map_err(ApiError::internal)
+}
+
+// ---------- LLM diagnostics handlers ----------
+
+#[derive(Deserialize)]
+struct LatencyQuery {
+    limit: Option<i64>,
+}
+
+/// GET /llm/latency — recent LLM call latencies with cold/warm averages.
+async fn llm_latency_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<LatencyQuery>,
+) -> Result<Json<llm::LatencyHistory>, ApiError> {
+    let limit = params.limit.unwrap_or(100).clamp(1, db::LLM_CALLS_MAX_ROWS);
+
+    let conn = db::open_events_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
+    let calls = db::list_llm_calls(&conn, limit).map_err(ApiError::internal)?;
+
+    Ok(Json(llm::latency_history(calls)))
+}
+
+/// GET /llm/usage — LLM calls and tokens for the last `?days=N` days
+/// including today (default 7, max 365), per day and client.
+async fn llm_usage_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<DaysQuery>,
+) -> Result<Json<llm::UsageSummary>, ApiError> {
+    let days = params.days.unwrap_or(7).clamp(1, 365);
+    let today = chrono::Local::now().date_naive();
+    let from = today - chrono::Duration::days(days - 1);
+
+    let conn = db::open_events_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
+    let rows = db::list_llm_usage(&conn, &from.to_string(), &today.to_string())
+        .map_err(ApiError::internal)?;
+    Ok(Json(llm::usage_summary(rows)))
+}
+
+#[derive(Serialize)]
+struct UsageResetResponse {
+    removed: usize,
+}
+
+/// POST /llm/usage/reset — clear the usage counters. The latency history in
+/// llm_calls is kept.
+async fn llm_usage_reset_handler(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<UsageResetResponse>, ApiError> {
+    let conn = db::open_events_db(&state.data_root.data_dir).map_err(ApiError::internal)?;
+    let removed = db::reset_llm_usage(&conn).map_err(ApiError::internal)?;
+    tracing::info!(removed, "LLM usage counters reset");
+    Ok(Json(UsageResetResponse { removed }))
+}
+
+/// GET /llm/models/raw — each LLM endpoint's /models response as-is (or the
+/// error), so connection and model problems can be diagnosed.
+async fn llm_models_raw_handler() -> Result<Json<Vec<llm::ModelsProbe>>, ApiError> {
+    let client = llm::LlamaCppClient::from_env().map_err(ApiError::internal)?;
+    Ok(Json(client.probe_models().await))
+}
@@ -1,654 +1,964 @@
-use ccube_core::agents::{curator, reflector};
-use ccube_core::{agents::detector, briefing, db, eval, memory};
-use chrono::{Datelike, Timelike};
-use serde::Serialize;
-use std::path::Path;
-use std::sync::Arc;
-use tokio_util::sync::CancellationToken;
-
-use crate::http::AppState;
-
-/// Run the periodic scheduler. Includes:
-/// - Detector loop (focus-change trigger + 5-min heartbeat, 30s debounce)
-/// - Curator loop (daily at configurable hour)
-/// - Reflector loop (weekly Sunday 3am or patterns.md > 1600 chars)
-/// - Hourly event prune
-pub async fn run_scheduler(state: Arc<AppState>, cancel: CancellationToken) {
-    tracing::info!("scheduler started");
-
-    let detector_cancel = cancel.clone();
-    let detector_state = state.clone();
-    let detector_handle = tokio::spawn(run_detector_loop(detector_state, detector_cancel));
-
-    let prune_cancel = cancel.clone();
-    let prune_state = state.clone();
-    let prune_handle = tokio::spawn(run_prune_loop(prune_state, prune_cancel));
-
-    let curator_cancel = cancel.clone();
-    let curator_state = state.clone();
-    let curator_handle = tokio::spawn(run_curator_loop(curator_state, curator_cancel));
-
-    let reflector_cancel = cancel.clone();
-    let reflector_state = state.clone();
-    let reflector_handle = tokio::spawn(run_reflector_loop(reflector_state, reflector_cancel));
-
-    let _ = detector_handle.await;
-    let _ = prune_handle.await;
-    let _ = curator_handle.await;
-    let _ = reflector_handle.await;
-}
-
-/// Detector loop: fires on focus change (via Notify) or 5-min heartbeat.
-/// Debounced to 30s minimum between runs.
-async fn run_detector_loop(state: Arc<AppState>, cancel: CancellationToken) {
-    tracing::info!("detector loop started");
-
-    let mut last_run_ms: i64 = 0;
-    const DEBOUNCE_MS: i64 = 30_000;
-    const HEARTBEAT: std::time::Duration = std::time::Duration::from_secs(300);
-
-    loop {
-        // Register the notified future *before* we check / run anything,
-        // so a notify_one() that fires while run_detector() is executing
-        // is not lost.
-        let notified = state.detector_trigger.notified();
-        tokio::pin!(notified);
-
-        // Check if we should run immediately (dirty flag from a previous wakeup
-        // that arrived while we were busy). The first iteration just waits.
-        let trigger = tokio::select! {
-            () = &mut notified => "focus_change",
-            () = tokio::time::sleep(HEARTBEAT) => "heartbeat",
-            () = cancel.cancelled() => {
-                tracing::info!("detector loop shutting down");
-                return;
-            }
-        };
-
-        // Debounce: skip if <30s since last run
-        let now_ms = chrono::Utc::now().timestamp_millis();
-        if now_ms - last_run_ms < DEBOUNCE_MS {
-            tracing::debug!(trigger, "detector skipped (debounce)");
-            continue;
-        }
-
-        last_run_ms = now_ms;
-        run_detector(&state, trigger).await;
-    }
-}
-
-/// Build v2 briefing, run two-step detector, handle result (persist + notify + log).
-async fn run_detector(state: &AppState, trigger: &str) {
-    let start = std::time::Instant::now();
-    let now_ms = chrono::Utc::now().timestamp_millis();
-
-    // Open DB, query events (last hour, build_v2 filters to 5 min window)
-    let conn = match db::open_events_db(&state.data_root.data_dir) {
-        Ok(c) => c,
-        Err(e) => {
-            tracing::error!(error = %e, "detector: failed to open events db");
-            return;
-        }
-    };
-    let events = match db::query_recent_events(&conn, now_ms - 3_600_000) {
-        Ok(rows) => rows,
-        Err(e) => {
-            tracing::error!(error = %e, "detector: failed to query events");
-            return;
-        }
-    };
-
-    // Build v2 briefing from frozen memory
-    let briefing = briefing::build_v2(
-        now_ms,
-        &events,
-        &state.frozen_profile,
-        &state.frozen_patterns,
-        &[], // vault_today: not implemented until later phases
-    );
-
-    // Run v2 two-step detector agent
-    let output = detector::run_v2(&briefing, state.llm.as_ref()).await;
-    let duration_ms = start.elapsed().as_millis() as u64;
-
-    // Persist decision to DB
-    let decision_str = format!("{:?}", output.decision);
-    let nudge_style_str = output.nudge_style.as_ref().map(|s| format!("{:?}", s));
-    let briefing_json = serde_json::to_string(&briefing).unwrap_or_else(|e| {
-        tracing::error!(error = %e, "detector: failed to serialize briefing");
-        String::new()
-    });
-
-    let decision_id = match db::insert_decision(
-        &conn,
-        now_ms,
-        trigger,
-        &decision_str,
-        &output.reasoning,
-        nudge_style_str.as_deref(),
-        output.nudge_message.as_deref(),
-        &briefing_json,
-        &state.frozen_patterns_hash,
-        detector::PROMPT_VERSION_V2,
-        duration_ms as i64,
-    ) {
-        Ok(id) => {
-            tracing::debug!(decision_id = id, "decision persisted");
-            Some(id)
-        }
-        Err(e) => {
-            tracing::error!(error = %e, "failed to persist decision");
-            None
-        }
-    };
-
-    tracing::info!(
-        agent = "detector",
-        trigger,
-        prompt_version = detector::PROMPT_VERSION_V2,
-        decision = ?output.decision,
-        reasoning = %output.reasoning,
-        annotations_count = output.annotations.len(),
-        ?decision_id,
-        duration_ms,
-        "detector decision"
-    );
-
-    // Log to detector.ndjson
-    let log_entry = DetectorLogEntry {
-        ts: now_ms,
-        agent: "detector",
-        trigger,
-        prompt_version: detector::PROMPT_VERSION_V2,
-        decision: &decision_str,
-        reasoning: &output.reasoning,
-        nudge_style: nudge_style_str,
-        nudge_message: output.nudge_message.as_deref(),
-        patterns_cited: &output.patterns_cited,
-        patterns_hash: &state.frozen_patterns_hash,
-        decision_id,
-        duration_ms,
-    };
-
-    let log_path = state.data_root.logs_dir.join("detector.ndjson");
-    if let Ok(line) = serde_json::to_string(&log_entry) {
-        use std::io::Write;
-        if let Ok(mut f) = std::fs::OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(&log_path)
-        {
-            let _ = writeln!(f, "{}", line);
-        }
-    }
-
-    // Send notification on Nudge
-    if output.decision == briefing::DetectorDecision::Nudge
-        && let Some(ref msg) = output.nudge_message
-    {
-        if let Some(id) = decision_id {
-            send_nudge_notification(id, msg);
-        } else {
-            tracing::warn!("nudge triggered but no decision_id available, skipping notification");
-        }
-    }
-}
-
-/// Send a desktop notification for a nudge via PowerShell balloon tip.
-/// Runs in a background thread so it never blocks the async runtime.
-///
-/// The message is passed via the `CCUBE_NUDGE_MSG` environment variable rather
-/// than interpolated into the script, preventing command injection from
-/// LLM-generated output.
-fn send_nudge_notification(decision_id: i64, message: &str) {
-    let msg = message.to_string();
-    let id_str = decision_id.to_string();
-
-    std::thread::spawn(move || {
-        #[cfg(windows)]
-        {
-            use std::os::windows::process::CommandExt;
-            let script = concat!(
-                "Add-Type -AssemblyName System.Windows.Forms;",
-                "$n = New-Object System.Windows.Forms.NotifyIcon;",
-                "$n.Icon = [System.Drawing.SystemIcons]::Information;",
-                "$n.BalloonTipTitle = 'Companion Cube #' + $env:CCUBE_DECISION_ID;",
-                "$n.BalloonTipText = $env:CCUBE_NUDGE_MSG;",
-                "$n.Visible = $true;",
-                "$n.ShowBalloonTip(8000);",
-                "Start-Sleep -Seconds 9;",
-                "$n.Dispose()"
-            );
-            match std::process::Command::new("powershell")
-                .args(["-NoProfile", "-WindowStyle", "Hidden", "-Command", script])
-                .env("CCUBE_NUDGE_MSG", &msg)
-                .env("CCUBE_DECISION_ID", &id_str)
-                .creation_flags(0x08000000) // CREATE_NO_WINDOW
-                .output()
-            {
-                Ok(_) => tracing::debug!("nudge notification sent"),
-                Err(e) => tracing::warn!(error = %e, "failed to send nudge notification"),
-            }
-        }
-        #[cfg(not(windows))]
-        {
-            let title = format!("Companion Cube #{id_str}");
-            match std::process::Command::new("notify-send")
-                .args([&title, &msg])
-                .output()
-            {
-                Ok(_) => tracing::debug!("nudge notification sent"),
-                Err(e) => tracing::warn!(error = %e, "failed to send nudge notification"),
-            }
-        }
-    });
-}
-
-/// Hourly event prune loop.
-async fn run_prune_loop(state: Arc<AppState>, cancel: CancellationToken) {
-    loop {
-        tokio::select! {
-            () = tokio::time::sleep(std::time::Duration::from_secs(3600)) => {
-                run_prune(&state);
-            }
-            () = cancel.cancelled() => {
-                tracing::info!("prune loop shutting down");
-                return;
-            }
-        }
-    }
-}
-
-fn run_prune(state: &AppState) {
-    let now = chrono::Utc::now().timestamp_millis();
-    let cutoff = now - (14 * 24 * 3_600_000);
-
-    match db::open_events_db(&state.data_root.data_dir) {
-        Ok(conn) => {
-            match db::prune_events(&conn, cutoff) {
-                Ok(deleted) => {
-                    if deleted > 0 {
-                        tracing::info!(deleted, "pruned old events");
-                    }
-                }
-                Err(e) => tracing::error!(error = %e, "event prune failed"),
-            }
-            match db::prune_decisions(&conn, cutoff) {
-                Ok(deleted) => {
-                    if deleted > 0 {
-                        tracing::info!(deleted, "pruned old decisions");
-                    }
-                }
-                Err(e) => tracing::error!(error = %e, "decision prune failed"),
-            }
-        }
-        Err(e) => tracing::error!(error = %e, "could not open events db for prune"),
-    }
-}
-
-// ---------------------------------------------------------------------------
-// Detector log entry — one ndjson line per decision
-// ---------------------------------------------------------------------------
-
-#[derive(Serialize)]
-struct DetectorLogEntry<'a> {
-    ts: i64,
-    agent: &'a str,
-    trigger: &'a str,
-    prompt_version: &'a str,
-    decision: &'a str,
-    reasoning: &'a str,
-    nudge_style: Option<String>,
-    nudge_message: Option<&'a str>,
-    patterns_cited: &'a [usize],
-    patterns_hash: &'a str,
-    decision_id: Option<i64>,
-    duration_ms: u64,
-}
-
-// ---------------------------------------------------------------------------
-// Curator loop — daily at configurable hour + NDJSON logging
-// ---------------------------------------------------------------------------
-
-/// Curator loop: checks every 60s whether it's time to run the daily curator.
-async fn run_curator_loop(state: Arc<AppState>, cancel: CancellationToken) {
-    tracing::info!(
-        schedule_hour = state.curator_schedule_hour,
-        "curator loop started"
-    );
-
-    let mut last_run_date: Option<chrono::NaiveDate> = None;
-
-    loop {
-        tokio::select! {
-            () = tokio::time::sleep(std::time::Duration::from_secs(60)) => {}
-            () = cancel.cancelled() => {
-                tracing::info!("curator loop shutting down");
-                return;
-            }
-        }
-
-        let now = chrono::Local::now();
-        let today = now.date_naive();
-        let hour = now.hour();
-
-        // Already ran today? Skip.
-        if last_run_date == Some(today) {
-            continue;
-        }
-
-        // Not the scheduled hour? Skip.
-        if hour != state.curator_schedule_hour {
-            continue;
-        }
-
-        // Any pending corrections?
-        let pending = match db::open_corrections_db(&state.data_root.data_dir) {
-            Ok(conn) => db::count_pending_corrections(&conn).unwrap_or(0),
-            Err(e) => {
-                tracing::error!(error = %e, "curator: failed to open corrections db");
-                continue;
-            }
-        };
-
-        if pending == 0 {
-            tracing::debug!("curator: no pending corrections, skipping daily run");
-            last_run_date = Some(today);
-            continue;
-        }
-
-        // Try to acquire mutex (non-blocking). If a manual run is in progress, skip.
-        let guard = match state.curator_mutex.try_lock() {
-            Ok(g) => g,
-            Err(_) => {
-                tracing::info!("curator: already running (manual?), skipping scheduled run");
-                continue;
-            }
-        };
-
-        tracing::info!(pending, "curator: starting scheduled daily run");
-        let start = std::time::Instant::now();
-
-        match curator::run_curator(
-            &state.data_root.data_dir,
-            &state.data_root.memory_dir,
-            &state.frozen_profile,
-            &state.frozen_patterns,
-            state.curator_llm.as_ref(),
-            state.llm.as_ref(),
-            false, // not dry_run
-        )
-        .await
-        {
-            Ok(result) => {
-                let duration_ms = start.elapsed().as_millis() as u64;
-                tracing::info!(
-                    corrections = result.corrections_processed,
-                    committed = result.committed,
-                    eval_passed = result.eval_result.as_ref().map(|e| e.passed),
-                    duration_ms,
-                    "curator: scheduled run complete"
-                );
-                log_curator_run(
-                    &state.data_root.logs_dir,
-                    "daily_schedule",
-                    &result,
-                    duration_ms,
-                );
-            }
-            Err(e) => {
-                tracing::error!(error = %e, "curator: scheduled run failed");
-            }
-        }
-
-        drop(guard);
-        last_run_date = Some(today);
-    }
-}
-
-/// Write a curator run to `curator.ndjson`. Called from both scheduler and HTTP handler.
-pub(crate) fn log_curator_run(
-    logs_dir: &Path,
-    trigger: &str,
-    result: &curator::CuratorRunResult,
-    duration_ms: u64,
-) {
-    let retained = result
-        .output
-        .correction_verdicts
-        .iter()
-        .filter(|v| v.verdict == "retain")
-        .count();
-    let discarded = result
-        .output
-        .correction_verdicts
-        .iter()
-        .filter(|v| v.verdict == "discard")
-        .count();
-    let deferred = result
-        .output
-        .correction_verdicts
-        .iter()
-        .filter(|v| v.verdict == "defer")
-        .count();
-
-    let entry = CuratorLogEntry {
-        ts: chrono::Utc::now().timestamp_millis(),
-        agent: "curator",
-        trigger,
-        prompt_version: curator::PROMPT_VERSION,
-        corrections_processed: result.corrections_processed,
-        retained,
-        discarded,
-        deferred,
-        eval_passed: result.eval_result.as_ref().map(|e| e.passed),
-        patterns_chars_before: result
-            .candidate_patterns
-            .len()
-            .saturating_sub(result.output.proposed_adds.iter().map(|a| a.text.len() + 1).sum()),
-        patterns_chars_after: result.candidate_patterns.len(),
-        committed: result.committed,
-        dry_run: result.dry_run,
-        duration_ms,
-    };
-
-    let log_path = logs_dir.join("curator.ndjson");
-    if let Ok(line) = serde_json::to_string(&entry) {
-        use std::io::Write;
-        if let Ok(mut f) = std::fs::OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(&log_path)
-        {
-            let _ = writeln!(f, "{}", line);
-        }
-    }
-}
-
-#[derive(Serialize)]
-struct CuratorLogEntry<'a> {
-    ts: i64,
-    agent: &'a str,
-    trigger: &'a str,
-    prompt_version: &'a str,
-    corrections_processed: usize,
-    retained: usize,
-    discarded: usize,
-    deferred: usize,
-    eval_passed: Option<bool>,
-    patterns_chars_before: usize,
-    patterns_chars_after: usize,
-    committed: bool,
-    dry_run: bool,
-    duration_ms: u64,
-}
-
-// ---------------------------------------------------------------------------
-// Reflector loop — weekly (Sunday 3am) or when patterns.md > 1600 chars
-// ---------------------------------------------------------------------------
-
-/// Minimum time between reflector runs (23 hours). Prevents re-triggering on the
-/// size condition right after a run completes within the same day.
-const REFLECTOR_COOLDOWN: std::time::Duration = std::time::Duration::from_secs(23 * 3600);
-
-/// Reflector loop: checks every 60s whether trigger conditions are met.
-///
-/// Triggers:
-/// - **weekly**: Sunday at 3am local time (once per week)
-/// - **size**: `patterns.md` exceeds 1600 chars (once, then cooldown)
-async fn run_reflector_loop(state: Arc<AppState>, cancel: CancellationToken) {
-    tracing::info!("reflector loop started");
-
-    let mut last_run: Option<std::time::Instant> = None;
-
-    loop {
-        tokio::select! {
-            () = tokio::time::sleep(std::time::Duration::from_secs(60)) => {}
-            () = cancel.cancelled() => {
-                tracing::info!("reflector loop shutting down");
-                return;
-            }
-        }
-
-        // Cooldown check
-        if let Some(prev) = last_run
-            && prev.elapsed() < REFLECTOR_COOLDOWN
-        {
-            continue;
-        }
-
-        // Read live patterns from disk (curator may have updated since daemon start)
-        let current_patterns = match memory::read_patterns(&state.data_root.memory_dir) {
-            Ok(p) => p,
-            Err(e) => {
-                tracing::error!(error = %e, "reflector: failed to read patterns.md");
-                continue;
-            }
-        };
-
-        // Determine trigger
-        let now = chrono::Local::now();
-        let is_weekly =
-            now.weekday() == chrono::Weekday::Sun && now.hour() == 3;
-        let is_size = current_patterns.len() > 1600;
-
-        let trigger = if is_weekly {
-            "weekly"
-        } else if is_size {
-            "size"
-        } else {
-            continue;
-        };
-
-        // Try to acquire curator mutex (non-blocking). Skip if curator is running.
-        let guard = match state.curator_mutex.try_lock() {
-            Ok(g) => g,
-            Err(_) => {
-                tracing::info!("reflector: curator mutex held, skipping scheduled run");
-                continue;
-            }
-        };
-
-        tracing::info!(
-            trigger,
-            patterns_len = current_patterns.len(),
-            "reflector: starting scheduled run"
-        );
-        let start = std::time::Instant::now();
-
-        match reflector::run_reflector(
-            &state.data_root.data_dir,
-            &state.data_root.memory_dir,
-            &state.frozen_profile,
-            &current_patterns,
-            state.curator_llm.as_ref(),
-            state.llm.as_ref(), // eval uses detector LLM (faster)
-            false,              // not dry_run
-        )
-        .await
-        {
-            Ok(result) => {
-                let duration_ms = start.elapsed().as_millis() as u64;
-                tracing::info!(
-                    trigger,
-                    committed = result.committed,
-                    pending = result.pending,
-                    chars_before = result.chars_before,
-                    chars_after = result.chars_after,
-                    eval_outcome = ?result.eval_outcome,
-                    duration_ms,
-                    "reflector: scheduled run complete"
-                );
-                log_reflector_run(
-                    &state.data_root.logs_dir,
-                    trigger,
-                    &result,
-                    duration_ms,
-                );
-            }
-            Err(e) => {
-                tracing::error!(error = %e, "reflector: scheduled run failed");
-            }
-        }
-
-        drop(guard);
-        last_run = Some(std::time::Instant::now());
-    }
-}
-
-/// Write a reflector run to `reflector.ndjson`. Called from both scheduler and HTTP handler.
-pub(crate) fn log_reflector_run(
-    logs_dir: &Path,
-    trigger: &str,
-    result: &reflector::ReflectorRunResult,
-    duration_ms: u64,
-) {
-    let eval_outcome_str = result.eval_outcome.map(|o| match o {
-        eval::ReflectorEvalOutcome::Pass => "pass",
-        eval::ReflectorEvalOutcome::Borderline => "borderline",
-        eval::ReflectorEvalOutcome::Fail => "fail",
-    });
-
-    let entry = ReflectorLogEntry {
-        ts: chrono::Utc::now().timestamp_millis(),
-        agent: "reflector",
-        trigger,
-        prompt_version: reflector::PROMPT_VERSION,
-        chars_before: result.chars_before,
-        chars_after: result.chars_after,
-        retained_corrections_count: result.retained_corrections_count,
-        eval_outcome: eval_outcome_str,
-        committed: result.committed,
-        pending: result.pending,
-        dry_run: result.dry_run,
-        duration_ms,
-    };
-
-    let log_path = logs_dir.join("reflector.ndjson");
-    if let Ok(line) = serde_json::to_string(&entry) {
-        use std::io::Write;
-        if let Ok(mut f) = std::fs::OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(&log_path)
-        {
-            let _ = writeln!(f, "{}", line);
-        }
-    }
-}
-
-#[derive(Serialize)]
-struct ReflectorLogEntry<'a> {
-    ts: i64,
-    agent: &'a str,
-    trigger: &'a str,
-    prompt_version: &'a str,
-    chars_before: usize,
-    chars_after: usize,
-    retained_corrections_count: usize,
-    eval_outcome: Option<&'a str>,
-    committed: bool,
-    pending: bool,
-    dry_run: bool,
-    duration_ms: u64,
+use ccube_core::agents::{curator, reflector};
+use ccube_core::{agents::detector, briefing, db, eval, memory, nudge_policy, stats};
+use chrono::{Datelike, Timelike};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicI64, Ordering};
+use tokio_util::sync::CancellationToken;
+
+use crate::http::{AppState, DetectResponse, LastDetection};
+
+/// Run the periodic scheduler. Includes:
+/// - Detector loop (focus-change trigger + 5-min heartbeat, 30s debounce)
+/// - Curator loop (daily at configurable hour)
+/// - Reflector loop (weekly Sunday 3am or patterns.md > 1600 chars)
+/// - Hourly event prune
+pub async fn run_scheduler(state: Arc<AppState>, cancel: CancellationToken) {
+    tracing::info!("scheduler started");
+
+    let detector_cancel = cancel.clone();
+    let detector_state = state.clone();
+    let detector_handle = tokio::spawn(run_detector_loop(detector_state, detector_cancel));
+
+    let prune_cancel = cancel.clone();
+    let prune_state = state.clone();
+    let prune_handle = tokio::spawn(run_prune_loop(prune_state, prune_cancel));
+
+    let curator_cancel = cancel.clone();
+    let curator_state = state.clone();
+    let curator_handle = tokio::spawn(run_curator_loop(curator_state, curator_cancel));
+
+    let reflector_cancel = cancel.clone();
+    let reflector_state = state.clone();
+    let reflector_handle = tokio::spawn(run_reflector_loop(reflector_state, reflector_cancel));
+
+    let _ = detector_handle.await;
+    let _ = prune_handle.await;
+    let _ = curator_handle.await;
+    let _ = reflector_handle.await;
+}
+
+/// Detector loop: fires on focus change (via Notify) or 5-min heartbeat.
+/// Debounced to 30s minimum between runs.
+async fn run_detector_loop(state: Arc<AppState>, cancel: CancellationToken) {
+    tracing::info!("detector loop started");
+
+    let mut last_run_ms: i64 = 0;
+    const DEBOUNCE_MS: i64 = 30_000;
+    const HEARTBEAT: std::time::Duration = std::time::Duration::from_secs(300);
+
+    loop {
+        // Register the notified future *before* we check / run anything,
+        // so a notify_one() that fires while run_detector() is executing
+        // is not lost.
+        let notified = state.detector_trigger.notified();
+        tokio::pin!(notified);
+
+        // Check if we should run immediately (dirty flag from a previous wakeup
+        // that arrived while we were busy). The first iteration just waits.
+        let trigger = tokio::select! {
+            () = &mut notified => "focus_change",
+            () = tokio::time::sleep(HEARTBEAT) => "heartbeat",
+            () = cancel.cancelled() => {
+                tracing::info!("detector loop shutting down");
+                return;
+            }
+        };
+
+        // Debounce: skip if <30s since last run
+        let now_ms = chrono::Utc::now().timestamp_millis();
+        if now_ms - last_run_ms < DEBOUNCE_MS {
+            tracing::debug!(trigger, "detector skipped (debounce)");
+            continue;
+        }
+
+        check_focus_target(&state);
+
+        let today = chrono::Local::now().date_naive();
+        if !state.weekend_policy.allows_detector(today) {
+            tracing::debug!(trigger, "detector skipped (weekend mode off)");
+            continue;
+        }
+
+        last_run_ms = now_ms;
+        run_detector(&state, trigger).await;
+    }
+}
+
+/// Build v2 briefing, run two-step detector, handle result (persist + notify + log).
+async fn run_detector(state: &AppState, trigger: &str) {
+    // Skip if a manual /detect is already running for this window.
+    let mut last = match state.detector_mutex.try_lock() {
+        Ok(g) => g,
+        Err(_) => {
+            tracing::debug!(trigger, "detector skipped (already running)");
+            return;
+        }
+    };
+
+    let start = std::time::Instant::now();
+    let now_ms = chrono::Utc::now().timestamp_millis();
+
+    // Open DB, query events (last hour, build_v2 filters to 5 min window)
+    let conn = match db::open_events_db(&state.data_root.data_dir) {
+        Ok(c) => c,
+        Err(e) => {
+            tracing::error!(error = %e, "detector: failed to open events db");
+            return;
+        }
+    };
+    let events = match db::query_recent_events(&conn, now_ms - 3_600_000) {
+        Ok(rows) => rows,
+        Err(e) => {
+            tracing::error!(error = %e, "detector: failed to query events");
+            return;
+        }
+    };
+
+    // Skip quiet windows: a decision over a few seconds of activity is noise
+    let window_start = now_ms - state.briefing_window_ms; // same window as the briefing
+    if detector::too_quiet(&events, window_start, now_ms, state.detector_min_active_ms) {
+        tracing::debug!(trigger, "detector skipped (insufficient activity)");
+        return;
+    }
+
+    // Build v2 briefing from frozen memory
+    let mut briefing = briefing::build_v2_with_window(
+        now_ms,
+        state.briefing_window_ms,
+        &events,
+        &state.frozen_profile,
+        &state.frozen_patterns,
+        &[], // vault_today: not implemented until later phases
+    );
+    if let Some(max) = state.max_prompt_chars {
+        detector::trim_to_prompt_budget(&mut briefing, max);
+    }
+
+    // Run v2 two-step detector agent
+    let output = detector::run_v2(&briefing, state.llm.as_ref()).await;
+    let duration_ms = start.elapsed().as_millis() as u64;
+
+    // Persist decision to DB
+    let decision_str = format!("{:?}", output.decision);
+    let nudge_style_str = output.nudge_style.as_ref().map(|s| format!("{:?}", s));
+    let briefing_json = serde_json::to_string(&briefing).unwrap_or_else(|e| {
+        tracing::error!(error = %e, "detector: failed to serialize briefing");
+        String::new()
+    });
+
+    let decision_id = match db::insert_decision(
+        &conn,
+        now_ms,
+        trigger,
+        &decision_str,
+        &output.reasoning,
+        nudge_style_str.as_deref(),
+        output.nudge_message.as_deref(),
+        &briefing_json,
+        &state.frozen_patterns_hash,
+        detector::PROMPT_VERSION_V2,
+        duration_ms as i64,
+    ) {
+        Ok(id) => {
+            tracing::debug!(decision_id = id, "decision persisted");
+            // A /detect that waited on this run returns it
+            *last = Some(LastDetection {
+                finished_ms: chrono::Utc::now().timestamp_millis(),
+                response: DetectResponse {
+                    decision_id: id,
+                    output: output.clone(),
+                },
+            });
+            Some(id)
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "failed to persist decision");
+            None
+        }
+    };
+
+    tracing::info!(
+        agent = "detector",
+        trigger,
+        prompt_version = detector::PROMPT_VERSION_V2,
+        decision = ?output.decision,
+        reasoning = %output.reasoning,
+        annotations_count = output.annotations.len(),
+        ?decision_id,
+        duration_ms,
+        "detector decision"
+    );
+
+    // Log to detector.ndjson
+    let log_entry = DetectorLogEntry {
+        ts: now_ms,
+        agent: "detector",
+        trigger,
+        prompt_version: detector::PROMPT_VERSION_V2,
+        decision: &decision_str,
+        reasoning: &output.reasoning,
+        nudge_style: nudge_style_str,
+        nudge_message: output.nudge_message.as_deref(),
+        patterns_cited: &output.patterns_cited,
+        patterns_hash: &state.frozen_patterns_hash,
+        decision_id,
+        duration_ms,
+    };
+
+    let log_path = state.data_root.logs_dir.join("detector.ndjson");
+    if let Ok(line) = serde_json::to_string(&log_entry) {
+        use std::io::Write;
+        if let Ok(mut f) = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&log_path)
+        {
+            let _ = writeln!(f, "{}", line);
+        }
+    }
+
+    // Send notification on Nudge
+    if output.decision == briefing::DetectorDecision::Nudge
+        && let Some(ref msg) = output.nudge_message
+    {
+        if now_ms < state.nudges_snoozed_until.load(Ordering::Relaxed) {
+            tracing::info!(?decision_id, "nudge not shown (snoozed)");
+        } else if !state
+            .weekend_policy
+            .allows_notification(chrono::Local::now().date_naive())
+        {
+            tracing::info!(?decision_id, "nudge not shown (weekend quiet mode)");
+        } else if let Some(mode) = briefing.events.last().map(|e| e.mode.as_str())
+            && !state.mode_nudge_policy.allows_notification(mode)
+        {
+            tracing::info!(?decision_id, mode, "nudge not shown (silent focus mode)");
+        } else if state.flow_protection.suppresses(
+            &stats::active_spans(&events, now_ms - 3_600_000, now_ms, now_ms),
+            &state.focus_scoring,
+            output.nudge_style.as_ref(),
+            now_ms,
+        ) {
+            tracing::info!(?decision_id, "nudge not shown (protecting flow)");
+        } else if state.startup_grace.suppresses(
+            now_ms - state.start_time.elapsed().as_millis() as i64,
+            &events,
+            now_ms,
+        ) {
+            tracing::info!(?decision_id, "nudge not shown (startup/return grace period)");
+        } else if in_meeting(state, now_ms) {
+            tracing::info!(?decision_id, "nudge not shown (in a meeting)");
+        } else if recent_nudge_accuracy(&state.data_root.data_dir, now_ms)
+            .suppresses(output.nudge_style.as_ref())
+        {
+            tracing::info!(
+                ?decision_id,
+                "nudge not shown (recent nudges mostly marked wrong)"
+            );
+        } else if let Some(id) = decision_id {
+            send_nudge_notification(
+                id,
+                state.nudge_titles.title(output.nudge_style.as_ref()),
+                msg,
+                state.data_root.data_dir.clone(),
+                state.nudges_snoozed_until.clone(),
+            );
+        } else {
+            tracing::warn!("nudge triggered but no decision_id available, skipping notification");
+        }
+    }
+}
+
+/// Whether the user is in a call. Reads its own, longer event history than the
+/// detector's hour so a long call is seen from its start. Falls back to "not in
+/// a meeting" if the events db can't be read.
+fn in_meeting(state: &AppState, now_ms: i64) -> bool {
+    match db::open_events_db(&state.data_root.data_dir)
+        .and_then(|conn| state.meeting_detection.current_status(&conn, now_ms))
+    {
+        Ok(status) => status.in_meeting,
+        Err(e) => {
+            tracing::warn!(error = %e, "meeting check failed");
+            false
+        }
+    }
+}
+
+/// Feedback on nudges over the last two weeks. Falls back to "no feedback"
+/// (never suppresses) if the corrections db can't be read.
+fn recent_nudge_accuracy(data_dir: &Path, now_ms: i64) -> nudge_policy::NudgeAccuracy {
+    let since = now_ms - nudge_policy::FEEDBACK_WINDOW_DAYS * 86_400_000;
+    match db::open_corrections_db(data_dir).and_then(|conn| db::list_nudge_feedback(&conn, since)) {
+        Ok(rows) => nudge_policy::NudgeAccuracy::from_feedback(&rows),
+        Err(e) => {
+            tracing::warn!(error = %e, "could not read nudge feedback");
+            nudge_policy::NudgeAccuracy::default()
+        }
+    }
+}
+
+/// Snooze length offered by a nudge notification's snooze action.
+const NOTIFICATION_SNOOZE_MINUTES: i64 = 15;
+
+/// Verdict recorded when the user answers a nudge with "I'm on task".
+const ON_TASK_VERDICT: &str = "on task (from notification)";
+
+/// Send a desktop notification for a nudge via PowerShell balloon tip.
+/// Runs in a background thread so it never blocks the async runtime.
+///
+/// The title and message are passed via environment variables rather than
+/// interpolated into the script, preventing command injection from
+/// LLM-generated output or configured titles. The decision id stays in the
+/// title so the nudge can be answered with `ccube correct`.
+///
+/// Elsewhere notify-send is used with "Snooze" and "I'm on task" actions
+/// (libnotify 0.7.9+); older versions get a plain notification. Balloon tips
+/// have no action buttons, so Windows users snooze with `ccube snooze` and
+/// answer with `ccube correct`.
+#[cfg_attr(windows, allow(unused_variables))]
+fn send_nudge_notification(
+    decision_id: i64,
+    title: &str,
+    message: &str,
+    data_dir: PathBuf,
+    snoozed_until: Arc<AtomicI64>,
+) {
+    let title = format!("{title} (#{decision_id})");
+    let msg = message.to_string();
+
+    std::thread::spawn(move || {
+        #[cfg(windows)]
+        show_balloon(&title, &msg);
+        #[cfg(not(windows))]
+        {
+            let snooze_action = format!("--action=snooze=Snooze {NOTIFICATION_SNOOZE_MINUTES}m");
+            // --wait blocks until the notification closes and prints the chosen action
+            match std::process::Command::new("notify-send")
+                .args([
+                    "--wait",
+                    &snooze_action,
+                    "--action=ontask=I'm on task",
+                    &title,
+                    &msg,
+                ])
+                .output()
+            {
+                Ok(out) if out.status.success() => {
+                    tracing::debug!("nudge notification sent");
+                    let action = String::from_utf8_lossy(&out.stdout);
+                    handle_nudge_action(action.trim(), decision_id, &data_dir, &snoozed_until);
+                }
+                Ok(_) => match std::process::Command::new("notify-send")
+                    .args([&title, &msg])
+                    .output()
+                {
+                    Ok(_) => tracing::debug!("nudge notification sent (no action support)"),
+                    Err(e) => tracing::warn!(error = %e, "failed to send nudge notification"),
+                },
+                Err(e) => tracing::warn!(error = %e, "failed to send nudge notification"),
+            }
+        }
+    });
+}
+
+/// Show a plain desktop notification with no actions, in a background thread.
+fn send_notification(title: &str, message: &str) {
+    let title = title.to_string();
+    let msg = message.to_string();
+
+    std::thread::spawn(move || {
+        #[cfg(windows)]
+        show_balloon(&title, &msg);
+        #[cfg(not(windows))]
+        match std::process::Command::new("notify-send")
+            .args([&title, &msg])
+            .output()
+        {
+            Ok(_) => tracing::debug!(title, "notification sent"),
+            Err(e) => tracing::warn!(error = %e, "failed to send notification"),
+        }
+    });
+}
+
+/// PowerShell balloon tip. Blocks until it has been shown, so call it from a
+/// background thread.
+#[cfg(windows)]
+fn show_balloon(title: &str, msg: &str) {
+    use std::os::windows::process::CommandExt;
+    let script = concat!(
+        "Add-Type -AssemblyName System.Windows.Forms;",
+        "$n = New-Object System.Windows.Forms.NotifyIcon;",
+        "$n.Icon = [System.Drawing.SystemIcons]::Information;",
+        "$n.BalloonTipTitle = $env:CCUBE_NUDGE_TITLE;",
+        "$n.BalloonTipText = $env:CCUBE_NUDGE_MSG;",
+        "$n.Visible = $true;",
+        "$n.ShowBalloonTip(8000);",
+        "Start-Sleep -Seconds 9;",
+        "$n.Dispose()"
+    );
+    match std::process::Command::new("powershell")
+        .args(["-NoProfile", "-WindowStyle", "Hidden", "-Command", script])
+        .env("CCUBE_NUDGE_MSG", msg)
+        .env("CCUBE_NUDGE_TITLE", title)
+        .creation_flags(0x08000000) // CREATE_NO_WINDOW
+        .output()
+    {
+        Ok(_) => tracing::debug!("notification sent"),
+        Err(e) => tracing::warn!(error = %e, "failed to send notification"),
+    }
+}
+
+/// Notify once per day when today's focused time first reaches the target.
+/// The announcement is recorded as an event so a restart doesn't repeat it.
+/// While nudges are snoozed, on a quiet weekend, or in a silent focus mode the
+/// announcement waits until it can be shown.
+fn check_focus_target(state: &AppState) {
+    let today = chrono::Local::now().date_naive();
+    let Ok(mut notified) = state.target_met_notified.lock() else {
+        return;
+    };
+    if *notified == Some(today) {
+        return;
+    }
+
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    let (day_start, _) = stats::day_bounds(today, &chrono::Local);
+    let conn = match db::open_events_db(&state.data_root.data_dir) {
+        Ok(conn) => conn,
+        Err(e) => {
+            tracing::warn!(error = %e, "focus target check failed");
+            return;
+        }
+    };
+    match db::last_event_of_kind(&conn, stats::FOCUS_TARGET_MET_KIND) {
+        Ok(Some(e)) if e.ts >= day_start => {
+            *notified = Some(today);
+            return;
+        }
+        Ok(_) => {}
+        Err(e) => {
+            tracing::warn!(error = %e, "focus target check failed");
+            return;
+        }
+    }
+
+    let spans = match stats::day_spans(&conn, today, &chrono::Local, now_ms) {
+        Ok(spans) => spans,
+        Err(e) => {
+            tracing::warn!(error = %e, "focus target check failed");
+            return;
+        }
+    };
+    let progress = stats::target_progress(
+        state.focus_scoring.totals(&spans),
+        state.focus_target_minutes,
+    );
+    if !progress.met {
+        return;
+    }
+
+    let mode = db::last_event_of_kind(&conn, "app_focus")
+        .ok()
+        .flatten()
+        .and_then(|e| e.mode);
+    if now_ms < state.nudges_snoozed_until.load(Ordering::Relaxed) {
+        tracing::debug!("focus target notice deferred (snoozed)");
+        return;
+    }
+    if !state.weekend_policy.allows_notification(today) {
+        tracing::debug!("focus target notice deferred (weekend quiet mode)");
+        return;
+    }
+    if let Some(mode) = mode.as_deref()
+        && !state.mode_nudge_policy.allows_notification(mode)
+    {
+        tracing::debug!(mode, "focus target notice deferred (silent mode)");
+        return;
+    }
+
+    let kind = stats::FOCUS_TARGET_MET_KIND;
+    if let Err(e) = db::insert_event(&conn, now_ms, kind, None, None, None) {
+        tracing::warn!(error = %e, "failed to record focus target notification");
+    }
+    *notified = Some(today);
+    tracing::info!(focused_minutes = progress.focused_minutes, "daily focus target met");
+    send_notification(
+        "Focus target met",
+        &format!(
+            "{:.0} focused minutes today (target {}).",
+            progress.focused_minutes, progress.target_minutes
+        ),
+    );
+}
+
+/// Act on the button the user picked on a nudge notification. Anything else
+/// (dismissed, timed out) is ignored.
+#[cfg_attr(windows, allow(dead_code))]
+fn handle_nudge_action(action: &str, decision_id: i64, data_dir: &Path, snoozed_until: &AtomicI64) {
+    match action {
+        "snooze" => {
+            let until =
+                chrono::Utc::now().timestamp_millis() + NOTIFICATION_SNOOZE_MINUTES * 60_000;
+            snoozed_until.store(until, Ordering::Relaxed);
+            tracing::info!(decision_id, "nudges snoozed from notification");
+        }
+        "ontask" => match record_on_task(data_dir, decision_id) {
+            Ok(corr_id) => {
+                tracing::info!(decision_id, corr_id, "nudge marked wrong from notification")
+            }
+            Err(e) => tracing::warn!(error = %e, decision_id, "failed to record correction"),
+        },
+        _ => {}
+    }
+}
+
+/// Record an "I'm on task" correction for a nudge, so the curator can learn
+/// from the false positive, and count it as unhelpful nudge feedback.
+#[cfg_attr(windows, allow(dead_code))]
+fn record_on_task(data_dir: &Path, decision_id: i64) -> anyhow::Result<i64> {
+    let events_conn = db::open_events_db(data_dir)?;
+    let decision = db::get_decision(&events_conn, decision_id)?
+        .ok_or_else(|| anyhow::anyhow!("decision #{decision_id} not found"))?;
+    let corr_conn = db::open_corrections_db(data_dir)?;
+    db::upsert_nudge_feedback(
+        &corr_conn,
+        decision.id,
+        false,
+        chrono::Utc::now().timestamp_millis(),
+    )?;
+    db::insert_correction(
+        &corr_conn,
+        decision.id,
+        &decision.decision,
+        ON_TASK_VERDICT,
+        &decision.briefing_json,
+        &decision.patterns_hash,
+    )
+}
+
+/// Hourly event prune loop. Rolls up daily stats first so a day's totals are
+/// stored before its events age out.
+async fn run_prune_loop(state: Arc<AppState>, cancel: CancellationToken) {
+    loop {
+        tokio::select! {
+            () = tokio::time::sleep(std::time::Duration::from_secs(3600)) => {
+                run_prune(&state);
+            }
+            () = cancel.cancelled() => {
+                tracing::info!("prune loop shutting down");
+                return;
+            }
+        }
+    }
+}
+
+fn run_prune(state: &AppState) {
+    let now = chrono::Utc::now().timestamp_millis();
+    let cutoff = now - (state.retention_days * 24 * 3_600_000);
+
+    match db::open_events_db(&state.data_root.data_dir) {
+        Ok(conn) => {
+            // Every day still in retention whose rollup is missing or predates
+            // the day's end, so nothing is lost if the daemon was down
+            match stats::roll_up_stale_days(
+                &conn,
+                cutoff,
+                &chrono::Local,
+                &state.focus_scoring,
+                now,
+            ) {
+                Ok(rolled) => tracing::debug!(rolled, "daily stats rolled up"),
+                Err(e) => {
+                    // Pruning now could drop events whose day was never counted
+                    tracing::error!(error = %e, "daily stats rollup failed, skipping prune");
+                    return;
+                }
+            }
+            match db::prune_events(&conn, cutoff) {
+                Ok(deleted) => {
+                    if deleted > 0 {
+                        tracing::info!(deleted, "pruned old events");
+                    }
+                }
+                Err(e) => tracing::error!(error = %e, "event prune failed"),
+            }
+            match db::prune_decisions(&conn, cutoff) {
+                Ok(deleted) => {
+                    if deleted > 0 {
+                        tracing::info!(deleted, "pruned old decisions");
+                    }
+                }
+                Err(e) => tracing::error!(error = %e, "decision prune failed"),
+            }
+        }
+        Err(e) => tracing::error!(error = %e, "could not open events db for prune"),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Detector log entry — one ndjson line per decision
+// ---------------------------------------------------------------------------
+
+#[derive(Serialize)]
+struct DetectorLogEntry<'a> {
+    ts: i64,
+    agent: &'a str,
+    trigger: &'a str,
+    prompt_version: &'a str,
+    decision: &'a str,
+    reasoning: &'a str,
+    nudge_style: Option<String>,
+    nudge_message: Option<&'a str>,
+    patterns_cited: &'a [usize],
+    patterns_hash: &'a str,
+    decision_id: Option<i64>,
+    duration_ms: u64,
+}
+
+// ---------------------------------------------------------------------------
+// Curator loop — daily at configurable hour + NDJSON logging
+// ---------------------------------------------------------------------------
+
+/// Curator loop: checks every 60s whether it's time to run the daily curator.
+async fn run_curator_loop(state: Arc<AppState>, cancel: CancellationToken) {
+    tracing::info!(
+        schedule_hour = state.curator_schedule_hour,
+        "curator loop started"
+    );
+
+    let mut last_run_date: Option<chrono::NaiveDate> = None;
+
+    loop {
+        tokio::select! {
+            () = tokio::time::sleep(std::time::Duration::from_secs(60)) => {}
+            () = cancel.cancelled() => {
+                tracing::info!("curator loop shutting down");
+                return;
+            }
+        }
+
+        let now = chrono::Local::now();
+        let today = now.date_naive();
+        let hour = now.hour();
+
+        // Already ran today? Skip.
+        if last_run_date == Some(today) {
+            continue;
+        }
+
+        // Not the scheduled hour? Skip.
+        if hour != state.curator_schedule_hour {
+            continue;
+        }
+
+        // Any pending corrections?
+        let pending = match db::open_corrections_db(&state.data_root.data_dir) {
+            Ok(conn) => db::count_pending_corrections(&conn).unwrap_or(0),
+            Err(e) => {
+                tracing::error!(error = %e, "curator: failed to open corrections db");
+                continue;
+            }
+        };
+
+        if pending == 0 {
+            tracing::debug!("curator: no pending corrections, skipping daily run");
+            last_run_date = Some(today);
+            continue;
+        }
+
+        // Try to acquire mutex (non-blocking). If a manual run is in progress, skip.
+        let guard = match state.curator_mutex.try_lock() {
+            Ok(g) => g,
+            Err(_) => {
+                tracing::info!("curator: already running (manual?), skipping scheduled run");
+                continue;
+            }
+        };
+
+        tracing::info!(pending, "curator: starting scheduled daily run");
+        let start = std::time::Instant::now();
+
+        match curator::run_curator(
+            &state.data_root.data_dir,
+            &state.data_root.memory_dir,
+            &state.frozen_profile,
+            &state.frozen_patterns,
+            state.curator_llm.as_ref(),
+            state.llm.as_ref(),
+            false, // not dry_run
+        )
+        .await
+        {
+            Ok(result) => {
+                let duration_ms = start.elapsed().as_millis() as u64;
+                tracing::info!(
+                    corrections = result.corrections_processed,
+                    committed = result.committed,
+                    eval_passed = result.eval_result.as_ref().map(|e| e.passed),
+                    duration_ms,
+                    "curator: scheduled run complete"
+                );
+                log_curator_run(
+                    &state.data_root.logs_dir,
+                    "daily_schedule",
+                    &result,
+                    duration_ms,
+                );
+            }
+            Err(e) => {
+                tracing::error!(error = %e, "curator: scheduled run failed");
+            }
+        }
+
+        drop(guard);
+        last_run_date = Some(today);
+    }
+}
+
+/// Write a curator run to `curator.ndjson`. Called from both scheduler and HTTP handler.
+pub(crate) fn log_curator_run(
+    logs_dir: &Path,
+    trigger: &str,
+    result: &curator::CuratorRunResult,
+    duration_ms: u64,
+) {
+    let retained = result
+        .output
+        .correction_verdicts
+        .iter()
+        .filter(|v| v.verdict == "retain")
+        .count();
+    let discarded = result
+        .output
+        .correction_verdicts
+        .iter()
+        .filter(|v| v.verdict == "discard")
+        .count();
+    let deferred = result
+        .output
+        .correction_verdicts
+        .iter()
+        .filter(|v| v.verdict == "defer")
+        .count();
+
+    let entry = CuratorLogEntry {
+        ts: chrono::Utc::now().timestamp_millis(),
+        agent: "curator",
+        trigger,
+        prompt_version: curator::PROMPT_VERSION,
+        corrections_processed: result.corrections_processed,
+        retained,
+        discarded,
+        deferred,
+        eval_passed: result.eval_result.as_ref().map(|e| e.passed),
+        patterns_chars_before: result
+            .candidate_patterns
+            .len()
+            .saturating_sub(result.output.proposed_adds.iter().map(|a| a.text.len() + 1).sum()),
+        patterns_chars_after: result.candidate_patterns.len(),
+        committed: result.committed,
+        dry_run: result.dry_run,
+        duration_ms,
+    };
+
+    let log_path = logs_dir.join("curator.ndjson");
+    if let Ok(line) = serde_json::to_string(&entry) {
+        use std::io::Write;
+        if let Ok(mut f) = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&log_path)
+        {
+            let _ = writeln!(f, "{}", line);
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct CuratorLogEntry<'a> {
+    ts: i64,
+    agent: &'a str,
+    trigger: &'a str,
+    prompt_version: &'a str,
+    corrections_processed: usize,
+    retained: usize,
+    discarded: usize,
+    deferred: usize,
+    eval_passed: Option<bool>,
+    patterns_chars_before: usize,
+    patterns_chars_after: usize,
+    committed: bool,
+    dry_run: bool,
+    duration_ms: u64,
+}
+
+// ---------------------------------------------------------------------------
+// Reflector loop — weekly (Sunday 3am) or when patterns.md > 1600 chars
+// ---------------------------------------------------------------------------
+
+/// Minimum time between reflector runs (23 hours). Prevents re-triggering on the
+/// size condition right after a run completes within the same day.
+const REFLECTOR_COOLDOWN: std::time::Duration = std::time::Duration::from_secs(23 * 3600);
+
+/// Reflector loop: checks every 60s whether trigger conditions are met.
+///
+/// Triggers:
+/// - **weekly**: Sunday at 3am local time (once per week)
+/// - **size**: `patterns.md` exceeds 1600 chars (once, then cooldown)
+async fn run_reflector_loop(state: Arc<AppState>, cancel: CancellationToken) {
+    tracing::info!("reflector loop started");
+
+    let mut last_run: Option<std::time::Instant> = None;
+
+    loop {
+        tokio::select! {
+            () = tokio::time::sleep(std::time::Duration::from_secs(60)) => {}
+            () = cancel.cancelled() => {
+                tracing::info!("reflector loop shutting down");
+                return;
+            }
+        }
+
+        // Cooldown check
+        if let Some(prev) = last_run
+            && prev.elapsed() < REFLECTOR_COOLDOWN
+        {
+            continue;
+        }
+
+        // Read live patterns from disk (curator may have updated since daemon start)
+        let current_patterns = match memory::read_patterns(&state.data_root.memory_dir) {
+            Ok(p) => p,
+            Err(e) => {
+                tracing::error!(error = %e, "reflector: failed to read patterns.md");
+                continue;
+            }
+        };
+
+        // Determine trigger
+        let now = chrono::Local::now();
+        let is_weekly =
+            now.weekday() == chrono::Weekday::Sun && now.hour() == 3;
+        let is_size = current_patterns.len() > 1600;
+
+        let trigger = if is_weekly {
+            "weekly"
+        } else if is_size {
+            "size"
+        } else {
+            continue;
+        };
+
+        // Try to acquire curator mutex (non-blocking). Skip if curator is running.
+        let guard = match state.curator_mutex.try_lock() {
+            Ok(g) => g,
+            Err(_) => {
+                tracing::info!("reflector: curator mutex held, skipping scheduled run");
+                continue;
+            }
+        };
+
+        tracing::info!(
+            trigger,
+            patterns_len = current_patterns.len(),
+            "reflector: starting scheduled run"
+        );
+        let start = std::time::Instant::now();
+
+        match reflector::run_reflector(
+            &state.data_root.data_dir,
+            &state.data_root.memory_dir,
+            &state.frozen_profile,
+            &current_patterns,
+            state.curator_llm.as_ref(),
+            state.llm.as_ref(), // eval uses detector LLM (faster)
+            false,              // not dry_run
+        )
+        .await
+        {
+            Ok(result) => {
+                let duration_ms = start.elapsed().as_millis() as u64;
+                tracing::info!(
+                    trigger,
+                    committed = result.committed,
+                    pending = result.pending,
+                    chars_before = result.chars_before,
+                    chars_after = result.chars_after,
+                    eval_outcome = ?result.eval_outcome,
+                    duration_ms,
+                    "reflector: scheduled run complete"
+                );
+                log_reflector_run(
+                    &state.data_root.logs_dir,
+                    trigger,
+                    &result,
+                    duration_ms,
+                );
+            }
+            Err(e) => {
+                tracing::error!(error = %e, "reflector: scheduled run failed");
+            }
+        }
+
+        drop(guard);
+        last_run = Some(std::time::Instant::now());
+    }
+}
+
+/// Write a reflector run to `reflector.ndjson`. Called from both scheduler and HTTP handler.
+pub(crate) fn log_reflector_run(
+    logs_dir: &Path,
+    trigger: &str,
+    result: &reflector::ReflectorRunResult,
+    duration_ms: u64,
+) {
+    let eval_outcome_str = result.eval_outcome.map(|o| match o {
+        eval::ReflectorEvalOutcome::Pass => "pass",
+        eval::ReflectorEvalOutcome::Borderline => "borderline",
+        eval::ReflectorEvalOutcome::Fail => "fail",
+    });
+
+    let entry = ReflectorLogEntry {
+        ts: chrono::Utc::now().timestamp_millis(),
+        agent: "reflector",
+        trigger,
+        prompt_version: reflector::PROMPT_VERSION,
+        chars_before: result.chars_before,
+        chars_after: result.chars_after,
+        retained_corrections_count: result.retained_corrections_count,
+        eval_outcome: eval_outcome_str,
+        committed: result.committed,
+        pending: result.pending,
+        dry_run: result.dry_run,
+        duration_ms,
+    };
+
+    let log_path = logs_dir.join("reflector.ndjson");
+    if let Ok(line) = serde_json::to_string(&entry) {
+        use std::io::Write;
+        if let Ok(mut f) = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&log_path)
+        {
+            let _ = writeln!(f, "{}", line);
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ReflectorLogEntry<'a> {
+    ts: i64,
+    agent: &'a str,
+    trigger: &'a str,
+    prompt_version: &'a str,
+    chars_before: usize,
+    chars_after: usize,
+    retained_corrections_count: usize,
+    eval_outcome: Option<&'a str>,
+    committed: bool,
+    pending: bool,
+    dry_run: bool,
+    duration_ms: u64,
 }
\ No newline at end of file